@@ -18,6 +18,12 @@ pub enum Command {
     Setup,
     /// Import the world data
     Import(ImportArgs),
+    /// Export the world data as a vanilla world save
+    Export(ExportArgs),
+    /// Re-compress every stored chunk, e.g. after changing the compression in the config
+    Recompress(RecompressArgs),
+    /// Upgrade every stored chunk to the current chunk format
+    Migrate,
     /// Start the server
     Run,
 }
@@ -37,6 +43,27 @@ pub struct ImportArgs {
     pub max_concurrent_tasks: usize,
 }
 
+#[derive(Debug, Clone, Parser)]
+pub struct ExportArgs {
+    /// Path to the folder to export the world to
+    ///
+    /// Region files are written to the `region`, `DIM-1/region` and `DIM1/region` folders inside it, the same layout as a vanilla world save. Existing region files are overwritten.
+    #[clap(long, required = true)]
+    pub export_path: String,
+}
+
+#[derive(Debug, Clone, Parser)]
+pub struct RecompressArgs {
+    /// Compression algorithm to convert to (brotli, deflate, gzip, zlib, zstd)
+    ///
+    /// Defaults to the one set in the config. Chunks saved afterwards still use the config's algorithm, so you probably want to update it to match.
+    #[clap(long)]
+    pub compression: Option<String>,
+    /// Compression level to convert with, defaults to the one set in the config
+    #[clap(long)]
+    pub level: Option<u32>,
+}
+
 // Wrapper struct for the Level enum
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct LogLevel(Level);
@@ -1,30 +1,36 @@
-//! Launch utilities for server initialization, chunk generation, and world import.
+//! Launch utilities for server initialization, chunk generation, and world import and export.
 
-use crate::cli::ImportArgs;
+use crate::cli::{ExportArgs, ImportArgs, RecompressArgs};
 use crate::errors::BinaryError;
 use ferrumc_config::server_config::get_global_config;
 use ferrumc_general_purpose::paths::get_root_path;
+use ferrumc_state::chunk_packet_cache::ChunkPacketCache;
 use ferrumc_state::player_cache::PlayerCache;
 use ferrumc_state::player_list::PlayerList;
 use ferrumc_state::{GlobalState, ServerState};
+use ferrumc_storage::compressors::{Compressor, CompressorType};
 use ferrumc_threadpool::ThreadPool;
+use ferrumc_world::dimension::Dimension;
+use ferrumc_world::migrations::CHUNK_FORMAT_VERSION;
 use ferrumc_world::pos::ChunkPos;
 use ferrumc_world::World;
 use ferrumc_world_gen::WorldGenerator;
 use std::sync::Arc;
 use std::time::Instant;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 /// Creates the initial server state with all required components.
 pub fn create_state(start_time: Instant) -> Result<ServerState, BinaryError> {
     const SEED: u64 = 0;
+    let world = World::new(&get_global_config().database.db_path)?;
     Ok(ServerState {
-        world: World::new(&get_global_config().database.db_path),
+        player_cache: PlayerCache::new(world.clone()),
+        world,
         terrain_generator: WorldGenerator::new(SEED),
         shut_down: false.into(),
         players: PlayerList::default(),
-        player_cache: PlayerCache::default(),
         thread_pool: ThreadPool::new(),
+        chunk_packets: ChunkPacketCache::new(),
         start_time,
     })
 }
@@ -48,12 +54,15 @@ pub fn generate_spawn_chunks(state: GlobalState) -> Result<(), BinaryError> {
             let pos = ChunkPos::new(x, z);
             let chunk = state_clone
                 .terrain_generator
-                .generate_chunk(pos)
+                .generate_chunk(pos, Dimension::Overworld)
                 .map(Arc::new);
 
             match chunk {
                 Ok(chunk) => {
-                    if let Err(e) = state_clone.world.save_chunk(pos, "overworld", chunk) {
+                    if let Err(e) = state_clone
+                        .world
+                        .save_chunk(pos, Dimension::Overworld, chunk)
+                    {
                         error!("Error saving chunk ({}, {}): {:?}", x, z, e);
                     }
                 }
@@ -73,7 +82,7 @@ pub fn generate_spawn_chunks(state: GlobalState) -> Result<(), BinaryError> {
 pub fn handle_import(import_args: ImportArgs) -> Result<(), BinaryError> {
     info!("Importing world...");
 
-    let mut world = World::new(&get_global_config().database.db_path);
+    let mut world = World::new(&get_global_config().database.db_path)?;
 
     let root_path = get_root_path();
     let mut import_path = root_path.join(&import_args.import_path);
@@ -88,3 +97,57 @@ pub fn handle_import(import_args: ImportArgs) -> Result<(), BinaryError> {
 
     Ok(())
 }
+
+/// Handles exporting the world to a vanilla world save.
+pub fn handle_export(export_args: ExportArgs) -> Result<(), BinaryError> {
+    info!("Exporting world...");
+
+    let world = World::new(&get_global_config().database.db_path)?;
+
+    let export_path = get_root_path().join(&export_args.export_path);
+
+    if let Err(e) = world.export(export_path, ThreadPool::new()) {
+        error!("Could not export world: {}", e.to_string());
+        return Err(BinaryError::Custom("Could not export world.".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Handles re-compressing the stored chunks with a different algorithm or level.
+pub fn handle_recompress(recompress_args: RecompressArgs) -> Result<(), BinaryError> {
+    let config = &get_global_config().database;
+    let algorithm = recompress_args
+        .compression
+        .as_deref()
+        .unwrap_or(&config.compression)
+        .parse::<CompressorType>()?;
+    let level = recompress_args.level.unwrap_or(config.compression_level);
+    let compressor = Compressor::checked(algorithm, level)?;
+    info!("Recompressing chunks with {algorithm} at level {level}...");
+
+    let world = World::new(&config.db_path)?;
+    let start = Instant::now();
+    let count = world.recompress(compressor)?;
+    info!("Recompressed {count} chunks in {:?}", start.elapsed());
+
+    if config.compression != algorithm.name() || config.compression_level != level {
+        warn!(
+            "The config still compresses new chunks with {} at level {}, update it to keep the \
+            database consistent.",
+            config.compression, config.compression_level
+        );
+    }
+    Ok(())
+}
+
+/// Handles upgrading the stored chunks to the current chunk format.
+pub fn handle_migrate() -> Result<(), BinaryError> {
+    info!("Upgrading chunks to format version {CHUNK_FORMAT_VERSION}...");
+
+    let world = World::new(&get_global_config().database.db_path)?;
+    let start = Instant::now();
+    let count = world.migrate()?;
+    info!("Upgraded {count} chunks in {:?}", start.elapsed());
+    Ok(())
+}
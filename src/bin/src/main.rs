@@ -4,6 +4,7 @@ use crate::cli::{CLIArgs, Command};
 use crate::errors::BinaryError;
 use clap::Parser;
 use ferrumc_config::whitelist::create_whitelist;
+use ferrumc_world::dimension::Dimension;
 use ferrumc_world::pos::ChunkPos;
 use std::sync::Arc;
 use std::time::Instant;
@@ -52,6 +53,39 @@ fn main() {
             }
         }
 
+        Some(Command::Export(export_args)) => {
+            info!("Starting export...");
+            if let Err(e) = launch::handle_export(export_args) {
+                error!("Export failed with the following error: {}", e.to_string());
+            } else {
+                info!("Export completed successfully.");
+            }
+        }
+
+        Some(Command::Recompress(recompress_args)) => {
+            info!("Starting recompression...");
+            if let Err(e) = launch::handle_recompress(recompress_args) {
+                error!(
+                    "Recompression failed with the following error: {}",
+                    e.to_string()
+                );
+            } else {
+                info!("Recompression completed successfully.");
+            }
+        }
+
+        Some(Command::Migrate) => {
+            info!("Starting migration...");
+            if let Err(e) = launch::handle_migrate() {
+                error!(
+                    "Migration failed with the following error: {}",
+                    e.to_string()
+                );
+            } else {
+                info!("Migration completed successfully.");
+            }
+        }
+
         Some(Command::Run) | None => {
             info!("Starting server...");
             if let Err(e) = ferrumc_config::setup::setup() {
@@ -75,7 +109,7 @@ fn entry(start_time: Instant) -> Result<(), BinaryError> {
     create_whitelist();
     if !global_state
         .world
-        .chunk_exists(ChunkPos::new(0, 0), "overworld")?
+        .chunk_exists(ChunkPos::new(0, 0), Dimension::Overworld)?
     {
         launch::generate_spawn_chunks(global_state.clone())?;
     }
@@ -16,6 +16,7 @@ mod player_loaded;
 mod set_player_position;
 mod set_player_position_and_rotation;
 mod set_player_rotation;
+mod sign_update;
 mod swing_arm;
 
 pub fn register_packet_handlers(schedule: &mut Schedule) {
@@ -42,6 +43,7 @@ pub fn register_packet_handlers(schedule: &mut Schedule) {
     schedule.add_systems(player_abilities::handle);
     schedule.add_systems(change_game_mode::handle);
     schedule.add_systems(pick_item_from_block::handle);
+    schedule.add_systems(sign_update::handle);
 }
 
 pub mod set_creative_mode_slot;
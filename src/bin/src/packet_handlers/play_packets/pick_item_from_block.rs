@@ -1,5 +1,6 @@
 use bevy_ecs::prelude::{Entity, Query, Res};
 use ferrumc_components::player::abilities::PlayerAbilities;
+use ferrumc_components::player::dimension::DimensionComponent;
 use ferrumc_core::identity::player_identity::PlayerIdentity;
 use ferrumc_inventories::item::ItemID;
 use ferrumc_inventories::slot::InventorySlot;
@@ -19,6 +20,7 @@ pub fn handle(
         Entity,
         &PlayerIdentity,
         &PlayerAbilities,
+        &DimensionComponent,
         &mut Inventory,
         &mut Hotbar,
         &StreamWriter,
@@ -26,7 +28,7 @@ pub fn handle(
 ) {
     for (packet, sender_entity) in events.0.try_iter() {
         // 1. Get player's components
-        let (entity, identity, abilities, mut inventory, mut hotbar, writer) =
+        let (entity, identity, abilities, dimension, mut inventory, mut hotbar, writer) =
             match player_inv_query.get_mut(sender_entity) {
                 Ok(data) => data,
                 Err(e) => {
@@ -44,10 +46,7 @@ pub fn handle(
 
         // 2. Get block from world
         let pos = packet.location.clone().into();
-        let block_state_id = match state.0.world.get_block_and_fetch(
-            pos,
-            "overworld", // TODO: Remove overworld hard coding for the dimension
-        ) {
+        let block_state_id = match state.0.world.get_block_and_fetch(pos, dimension.0) {
             Ok(id) => id,
             Err(e) => {
                 warn!(
@@ -1,20 +1,23 @@
-use std::sync::Arc;
-
-use bevy_ecs::prelude::{Entity, Query, Res};
+use bevy_ecs::prelude::{Commands, Entity, MessageWriter, Query, Res};
+use bevy_math::Vec3;
+use ferrumc_components::player::dimension::DimensionComponent;
+use ferrumc_components::player::gameplay_state::sign_editing::EditingSign;
 use ferrumc_core::collisions::bounds::CollisionBounds;
 use ferrumc_core::transform::position::Position;
+use ferrumc_core::transform::rotation::Rotation;
+use ferrumc_messages::BlocksChanged;
 use ferrumc_net::connection::StreamWriter;
 use ferrumc_net::packets::outgoing::block_change_ack::BlockChangeAck;
-use ferrumc_net::packets::outgoing::block_update::BlockUpdate;
+use ferrumc_net::packets::outgoing::open_sign_editor::OpenSignEditor;
 use ferrumc_net::PlaceBlockReceiver;
-use ferrumc_net_codec::net_types::network_position::NetworkPosition;
-use ferrumc_net_codec::net_types::var_int::VarInt;
 use ferrumc_state::GlobalStateResource;
+use ferrumc_world::placement::PlacementContext;
 use ferrumc_world::pos::BlockPos;
 use tracing::{debug, error, trace};
 
 use ferrumc_inventories::hotbar::Hotbar;
 use ferrumc_inventories::inventory::Inventory;
+use ferrumc_world::block_entity::BlockEntityKind;
 use ferrumc_world::block_state_id::BlockStateId;
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
@@ -39,11 +42,22 @@ static ITEM_TO_BLOCK_MAPPING: Lazy<HashMap<i32, BlockStateId>> = Lazy::new(|| {
 pub fn handle(
     receiver: Res<PlaceBlockReceiver>,
     state: Res<GlobalStateResource>,
-    query: Query<(Entity, &StreamWriter, &Inventory, &Hotbar)>,
-    pos_q: Query<(&Position, &CollisionBounds)>,
+    query: Query<(
+        Entity,
+        &StreamWriter,
+        &Inventory,
+        &Hotbar,
+        &Rotation,
+        &DimensionComponent,
+    )>,
+    pos_q: Query<(&Position, &CollisionBounds, &DimensionComponent)>,
+    mut block_changes: MessageWriter<BlocksChanged>,
+    mut commands: Commands,
 ) {
     'ev_loop: for (event, eid) in receiver.0.try_iter() {
-        let Ok((entity, conn, inventory, hotbar)) = query.get(eid) else {
+        let Ok((entity, conn, inventory, hotbar, rotation, &DimensionComponent(dimension))) =
+            query.get(eid)
+        else {
             debug!("Could not get connection for entity {:?}", eid);
             continue;
         };
@@ -53,6 +67,28 @@ pub fn handle(
         }
         match event.hand.0 {
             0 => {
+                // Levers, buttons and the like are used instead of placing against them
+                let clicked: BlockPos = event.position.clone().into();
+                match state.0.world.use_block(clicked, dimension) {
+                    Ok(Some(positions)) => {
+                        let packet = BlockChangeAck {
+                            sequence: event.sequence,
+                        };
+                        if let Err(err) = conn.send_packet_ref(&packet) {
+                            error!("Failed to send block change ack packet: {:?}", err);
+                        }
+                        block_changes.write(BlocksChanged {
+                            positions,
+                            dimension,
+                        });
+                        continue 'ev_loop;
+                    }
+                    Ok(None) => {}
+                    Err(err) => {
+                        debug!("Failed to use block at {}: {:?}", clicked, err);
+                        continue 'ev_loop;
+                    }
+                }
                 let Ok(slot) = hotbar.get_selected_item(inventory) else {
                     error!("Could not fetch {:?}", eid);
                     continue 'ev_loop;
@@ -71,52 +107,55 @@ pub fn handle(
                         "Placing block with item ID: {}, mapped to block state ID: {}",
                         item_id.0, mapped_block_state_id
                     );
-                    let pos: BlockPos = event.position.into();
-                    let mut chunk = match state.0.world.load_chunk_owned(pos.chunk(), "overworld") {
-                        Ok(chunk) => chunk,
+                    let Some(face) = PlacementContext::face(event.face.0) else {
+                        debug!("Invalid block face: {}", event.face.0);
+                        continue 'ev_loop;
+                    };
+                    let context = PlacementContext {
+                        clicked,
+                        face,
+                        cursor: Vec3::new(event.cursor_x, event.cursor_y, event.cursor_z),
+                        yaw: rotation.yaw,
+                        pitch: rotation.pitch,
+                    };
+                    let placed = match state.0.world.resolve_placement(
+                        *mapped_block_state_id,
+                        &context,
+                        dimension,
+                    ) {
+                        Ok(Some(placed)) => placed,
+                        Ok(None) => {
+                            trace!("Block can't be placed against {}", clicked);
+                            continue 'ev_loop;
+                        }
                         Err(e) => {
-                            debug!("Failed to load chunk: {:?}", e);
+                            debug!("Failed to resolve block placement: {:?}", e);
                             continue 'ev_loop;
                         }
                     };
-                    let Ok(block_clicked) = chunk.get_block(pos.chunk_block_pos()) else {
-                        debug!("Failed to get block at position: {}", pos);
-                        continue 'ev_loop;
-                    };
-                    trace!("Block clicked: {:?}", block_clicked);
-                    // Use the face to determine the offset of the block to place
-                    let offset_pos = pos
-                        + match event.face.0 {
-                            0 => (0, -1, 0),
-                            1 => (0, 1, 0),
-                            2 => (0, 0, -1),
-                            3 => (0, 0, 1),
-                            4 => (-1, 0, 0),
-                            5 => (1, 0, 0),
-                            _ => (0, 0, 0),
-                        };
 
                     // Check if the block collides with any entities
-                    let does_collide = {
-                        pos_q.into_iter().any(|(pos, bounds)| {
-                            bounds.collides(
-                                (pos.x, pos.y, pos.z),
-                                &CollisionBounds {
-                                    x_offset_start: 0.0,
-                                    x_offset_end: 1.0,
-                                    y_offset_start: 0.0,
-                                    y_offset_end: 1.0,
-                                    z_offset_start: 0.0,
-                                    z_offset_end: 1.0,
-                                },
-                                (
-                                    offset_pos.pos.x as f64,
-                                    offset_pos.pos.y as f64,
-                                    offset_pos.pos.z as f64,
-                                ),
-                            )
+                    let does_collide = placed.iter().any(|(placed_pos, _)| {
+                        pos_q.into_iter().any(|(pos, bounds, entity_dimension)| {
+                            entity_dimension.0 == dimension
+                                && bounds.collides(
+                                    (pos.x, pos.y, pos.z),
+                                    &CollisionBounds {
+                                        x_offset_start: 0.0,
+                                        x_offset_end: 1.0,
+                                        y_offset_start: 0.0,
+                                        y_offset_end: 1.0,
+                                        z_offset_start: 0.0,
+                                        z_offset_end: 1.0,
+                                    },
+                                    (
+                                        placed_pos.pos.x as f64,
+                                        placed_pos.pos.y as f64,
+                                        placed_pos.pos.z as f64,
+                                    ),
+                                )
                         })
-                    };
+                    });
                     if does_collide {
                         trace!("Block placement collided with entity");
                         continue 'ev_loop;
@@ -129,42 +168,42 @@ pub fn handle(
                         continue 'ev_loop;
                     }
 
-                    if let Err(err) = chunk.set_block(pos.chunk_block_pos(), *mapped_block_state_id)
-                    {
+                    let result = state.0.world.set_blocks_and_fetch(&placed, dimension);
+                    // Sent either way, so the client drops its own guess if nothing was placed
+                    block_changes.write(BlocksChanged {
+                        positions: placed.iter().map(|(pos, _)| *pos).collect(),
+                        dimension,
+                    });
+                    if let Err(err) = result {
                         error!("Failed to set block: {:?}", err);
                         continue 'ev_loop;
                     }
                     let ack_packet = BlockChangeAck {
                         sequence: event.sequence,
                     };
-
-                    let chunk_packet = BlockUpdate {
-                        location: NetworkPosition {
-                            x: offset_pos.pos.x,
-                            y: offset_pos.pos.y as i16,
-                            z: offset_pos.pos.z,
-                        },
-                        block_state_id: VarInt::from(*mapped_block_state_id),
-                    };
-                    if let Err(err) = conn.send_packet_ref(&chunk_packet) {
-                        error!("Failed to send block update packet: {:?}", err);
-                        continue 'ev_loop;
-                    }
                     if let Err(err) = conn.send_packet_ref(&ack_packet) {
                         error!("Failed to send block change ack packet: {:?}", err);
                         continue 'ev_loop;
                     }
-
-                    if let Err(err) =
-                        state
-                            .0
-                            .world
-                            .save_chunk(pos.chunk(), "overworld", Arc::from(chunk))
-                    {
-                        error!("Failed to save chunk after block placement: {:?}", err);
-                    } else {
-                        trace!("Block placed at {}", offset_pos);
+                    let (offset_pos, placed_block) = placed[0];
+                    if matches!(
+                        BlockEntityKind::for_block(placed_block),
+                        Some(BlockEntityKind::Sign | BlockEntityKind::HangingSign)
+                    ) {
+                        let packet = OpenSignEditor {
+                            location: offset_pos.into(),
+                            is_front_text: true,
+                        };
+                        if let Err(err) = conn.send_packet_ref(&packet) {
+                            error!("Failed to send open sign editor packet: {:?}", err);
+                        } else {
+                            commands.entity(entity).insert(EditingSign {
+                                pos: offset_pos,
+                                dimension,
+                            });
+                        }
                     }
+                    trace!("Block placed at {}", offset_pos);
                 }
             }
             1 => {
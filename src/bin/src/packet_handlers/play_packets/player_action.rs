@@ -1,16 +1,13 @@
-use std::sync::Arc;
-
 use crate::errors::BinaryError;
-use bevy_ecs::prelude::{Entity, MessageWriter, Query, Res};
+use bevy_ecs::prelude::{MessageWriter, Query, Res};
 use ferrumc_components::player::abilities::PlayerAbilities;
+use ferrumc_components::player::dimension::DimensionComponent;
 use ferrumc_messages::player_digging::*;
-use ferrumc_messages::BlockBrokenEvent;
+use ferrumc_messages::{BlockBrokenEvent, BlocksChanged};
 
 use ferrumc_net::connection::StreamWriter;
 use ferrumc_net::packets::outgoing::block_change_ack::BlockChangeAck;
-use ferrumc_net::packets::outgoing::block_update::BlockUpdate;
 use ferrumc_net::PlayerActionReceiver;
-use ferrumc_net_codec::net_types::var_int::VarInt;
 use ferrumc_state::GlobalStateResource;
 use ferrumc_world::{block_state_id::BlockStateId, pos::BlockPos};
 use tracing::{error, trace, warn};
@@ -18,19 +15,20 @@ use tracing::{error, trace, warn};
 pub fn handle(
     receiver: Res<PlayerActionReceiver>,
     state: Res<GlobalStateResource>,
-    broadcast_query: Query<(Entity, &StreamWriter)>,
-    player_query: Query<&PlayerAbilities>,
+    conn_query: Query<&StreamWriter>,
+    player_query: Query<(&PlayerAbilities, &DimensionComponent)>,
     (mut start_dig_events, mut cancel_dig_events, mut finish_dig_events, mut block_break_events): (
         MessageWriter<PlayerStartedDigging>,
         MessageWriter<PlayerCancelledDigging>,
         MessageWriter<PlayerFinishedDigging>,
         MessageWriter<BlockBrokenEvent>,
     ),
+    mut block_changes: MessageWriter<BlocksChanged>,
 ) {
     // https://minecraft.wiki/w/Minecraft_Wiki:Projects/wiki.vg_merge/Protocol?oldid=2773393#Player_Action
     for (event, trigger_eid) in receiver.0.try_iter() {
         // Get the player's abilities to check their gamemode
-        let Ok(abilities) = player_query.get(trigger_eid) else {
+        let Ok((abilities, &DimensionComponent(dimension))) = player_query.get(trigger_eid) else {
             warn!(
                 "PlayerAction: Player {:?} has no PlayerAbilities component",
                 trigger_eid
@@ -44,57 +42,35 @@ pub fn handle(
             // Only instabreak (status 0) is relevant in creative.
             if event.status.0 == 0 {
                 let res: Result<(), BinaryError> = try {
-                    let mut chunk = match state
-                        .0
-                        .clone()
-                        .world
-                        .load_chunk_owned(pos.chunk(), "overworld")
-                    {
-                        Ok(chunk) => chunk,
-                        Err(e) => {
-                            trace!("Chunk not found, generating new chunk: {:?}", e);
-                            state
-                                .0
-                                .clone()
-                                .terrain_generator
-                                .generate_chunk(pos.chunk())
-                                .map_err(BinaryError::WorldGen)?
-                        }
-                    };
-                    chunk
-                        .set_block(pos.chunk_block_pos(), BlockStateId::default())
-                        .map_err(BinaryError::World)?;
-
-                    state
-                        .0
-                        .world
-                        .save_chunk(pos.chunk(), "overworld", Arc::new(chunk))
+                    let world = &state.0.world;
+                    world.get_or_generate_chunk(pos.chunk(), dimension, || {
+                        trace!("Chunk not found, generating new chunk at {}", pos.chunk());
+                        state
+                            .0
+                            .terrain_generator
+                            .generate_chunk(pos.chunk(), dimension)
+                            .map_err(BinaryError::WorldGen)
+                    })?;
+                    world
+                        .set_block_and_fetch(pos, dimension, BlockStateId::default())
                         .map_err(BinaryError::World)?;
 
                     // Send block broken event for un-grounding system
-                    block_break_events.write(BlockBrokenEvent { position: pos });
+                    block_break_events.write(BlockBrokenEvent {
+                        position: pos,
+                        dimension,
+                    });
 
                     // Broadcast the change
-                    for (eid, conn) in &broadcast_query {
-                        if !state.0.players.is_connected(eid) {
-                            continue;
-                        }
+                    block_changes.write(BlocksChanged::single(pos, dimension));
 
-                        let block_update_packet = BlockUpdate {
-                            location: event.location.clone(),
-                            block_state_id: VarInt::from(BlockStateId::default()),
+                    // Send ACK to the creative player
+                    if let Ok(conn) = conn_query.get(trigger_eid) {
+                        let ack_packet = BlockChangeAck {
+                            sequence: event.sequence,
                         };
-                        conn.send_packet_ref(&block_update_packet)
+                        conn.send_packet_ref(&ack_packet)
                             .map_err(BinaryError::Net)?;
-
-                        if eid == trigger_eid {
-                            // Send ACK to the creative player
-                            let ack_packet = BlockChangeAck {
-                                sequence: event.sequence,
-                            };
-                            conn.send_packet_ref(&ack_packet)
-                                .map_err(BinaryError::Net)?;
-                        }
                     }
                 };
                 if res.is_err() {
@@ -0,0 +1,97 @@
+use bevy_ecs::prelude::{Commands, MessageWriter, Query, Res};
+use ferrumc_components::player::dimension::DimensionComponent;
+use ferrumc_components::player::gameplay_state::sign_editing::EditingSign;
+use ferrumc_core::transform::position::Position;
+use ferrumc_messages::BlockEntityChanged;
+use ferrumc_nbt::RawCompound;
+use ferrumc_net::SignUpdateReceiver;
+use ferrumc_state::GlobalStateResource;
+use ferrumc_world::block_entity::BlockEntityKind;
+use ferrumc_world::pos::BlockPos;
+use tracing::{debug, error};
+
+/// How far from the centre of a sign, in blocks, a player can be and still write to it.
+const SIGN_EDIT_REACH: f64 = 8.0;
+/// The longest line vanilla accepts from a sign editor, in characters.
+const MAX_LINE_LENGTH: usize = 384;
+
+pub fn handle(
+    receiver: Res<SignUpdateReceiver>,
+    state: Res<GlobalStateResource>,
+    query: Query<(&DimensionComponent, &Position, Option<&EditingSign>)>,
+    mut changed: MessageWriter<BlockEntityChanged>,
+    mut commands: Commands,
+) {
+    for (event, eid) in receiver.0.try_iter() {
+        let Ok((&DimensionComponent(dimension), player_pos, editing)) = query.get(eid) else {
+            debug!("Could not get dimension for entity {:?}", eid);
+            continue;
+        };
+        let pos: BlockPos = event.location.into();
+        // Players can only write to the sign they were sent the editor for
+        if editing.map(|editing| (editing.pos, editing.dimension)) != Some((pos, dimension)) {
+            debug!("Entity {:?} was not editing the sign at {}", eid, pos);
+            continue;
+        }
+        commands.entity(eid).remove::<EditingSign>();
+        let centre = pos.pos.as_dvec3() + 0.5;
+        if player_pos.coords.distance(centre) > SIGN_EDIT_REACH {
+            debug!(
+                "Entity {:?} is too far away to edit the sign at {}",
+                eid, pos
+            );
+            continue;
+        }
+        let block_entity = match state.0.world.get_block_entity(pos, dimension) {
+            Ok(Some(block_entity)) => block_entity,
+            Ok(None) => {
+                debug!("No block entity to edit at {}", pos);
+                continue;
+            }
+            Err(err) => {
+                error!("Failed to load block entity at {}: {:?}", pos, err);
+                continue;
+            }
+        };
+        if !matches!(
+            block_entity.kind,
+            BlockEntityKind::Sign | BlockEntityKind::HangingSign
+        ) {
+            debug!("Block entity at {} is not a sign", pos);
+            continue;
+        }
+
+        let mut data = block_entity.data();
+        if data.get::<i8>("is_waxed") == Some(1) {
+            debug!("Ignoring edit of waxed sign at {}", pos);
+            continue;
+        }
+        let side = if event.is_front_text {
+            "front_text"
+        } else {
+            "back_text"
+        };
+        let mut text = data.get::<RawCompound>(side).unwrap_or_default();
+        let messages: Vec<String> = [event.line_1, event.line_2, event.line_3, event.line_4]
+            .into_iter()
+            .map(|line| line.chars().take(MAX_LINE_LENGTH).collect())
+            .collect();
+        text.insert("messages", &messages);
+        if text.get::<String>("color").is_none() {
+            text.insert("color", &"black");
+        }
+        if text.get::<i8>("has_glowing_text").is_none() {
+            text.insert("has_glowing_text", &0i8);
+        }
+        data.insert(side, &text);
+
+        if let Err(err) = state.0.world.set_block_entity_data(pos, dimension, data) {
+            error!("Failed to update sign at {}: {:?}", pos, err);
+            continue;
+        }
+        changed.write(BlockEntityChanged {
+            position: pos,
+            dimension,
+        });
+    }
+}
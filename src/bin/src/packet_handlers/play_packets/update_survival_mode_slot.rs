@@ -27,6 +27,7 @@ pub fn handle(
                                 components_to_remove: None,
                                 components_to_add_count: None,
                                 components_to_remove_count: None,
+                                enchantments: None,
                             },
                         )
                         .expect("failed to write to inventory");
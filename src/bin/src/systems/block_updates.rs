@@ -0,0 +1,42 @@
+use bevy_ecs::prelude::{MessageReader, MessageWriter, ParamSet, Res};
+use ferrumc_config::server_config::get_global_config;
+use ferrumc_messages::BlocksChanged;
+use ferrumc_state::GlobalStateResource;
+use tracing::error;
+
+/// Updates the blocks next to every block that changed, then runs the scheduled ticks that are
+/// due this tick, up to the configured budget, and sends out what they changed.
+pub fn handle(
+    mut messages: ParamSet<(MessageReader<BlocksChanged>, MessageWriter<BlocksChanged>)>,
+    state: Res<GlobalStateResource>,
+) {
+    let world = &state.0.world;
+    let mut updated = Vec::new();
+    for event in messages.p0().read() {
+        match world.update_neighbours(event.dimension, &event.positions) {
+            Ok(positions) if !positions.is_empty() => updated.push(BlocksChanged {
+                positions,
+                dimension: event.dimension,
+            }),
+            Ok(_) => {}
+            Err(err) => error!("Failed to update neighbouring blocks: {:?}", err),
+        }
+    }
+
+    let budget = get_global_config().max_scheduled_ticks_per_tick as usize;
+    let changed = world.run_scheduled_ticks(budget);
+
+    let mut writer = messages.p1();
+    writer.write_batch(updated);
+    writer.write_batch(
+        changed
+            .into_iter()
+            .map(|(dimension, positions)| BlocksChanged {
+                positions,
+                dimension,
+            }),
+    );
+    // Block updates and ticks already updated the neighbours of whatever they changed, so skip
+    // reading these back
+    messages.p0().clear();
+}
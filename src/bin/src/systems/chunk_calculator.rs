@@ -1,4 +1,5 @@
 use bevy_ecs::prelude::{MessageReader, Query};
+use ferrumc_components::player::view_distance::ViewDistance;
 use ferrumc_config::server_config::get_global_config;
 use ferrumc_core::chunks::chunk_receiver::ChunkReceiver;
 use ferrumc_core::transform::position::Position;
@@ -6,32 +7,61 @@ use ferrumc_messages::chunk_calc::ChunkCalc;
 
 pub fn handle(
     mut messages: MessageReader<ChunkCalc>,
-    mut query: Query<(&Position, &mut ChunkReceiver)>,
+    mut query: Query<(&Position, &mut ChunkReceiver, &ViewDistance)>,
 ) {
     for message in messages.read() {
-        let (position, mut chunk_receiver) = match query.get_mut(message.0) {
+        let (position, mut chunk_receiver, view_distance) = match query.get_mut(message.0) {
             Ok(data) => data,
             Err(_) => continue, // Skip if the player does not exist
         };
 
         let chunk_receiver = &mut *chunk_receiver;
 
-        let radius = get_global_config().chunk_render_distance as i32;
+        // No point sending chunks the client won't render
+        let radius = (get_global_config().chunk_render_distance as i32).min(view_distance.0 as i32);
         let player_chunk_x = position.x.floor() as i32 >> 4;
         let player_chunk_z = position.z.floor() as i32 >> 4;
 
+        let in_range = |(x, z): (i32, i32)| {
+            (x - player_chunk_x).abs() <= radius && (z - player_chunk_z).abs() <= radius
+        };
+
+        // Unload chunks that are out of range, the packets are sent with the next batch
+        let out_of_range: Vec<_> = chunk_receiver
+            .loaded
+            .iter()
+            .copied()
+            .filter(|coords| !in_range(*coords))
+            .collect();
+        for coords in out_of_range {
+            chunk_receiver.loaded.remove(&coords);
+            chunk_receiver.unloading.insert(coords);
+        }
+
         let mut queued_chunks = Vec::new();
 
         // Add all chunks within the radius to the loading list if not already loaded
         for x in player_chunk_x - radius..=player_chunk_x + radius {
             for z in player_chunk_z - radius..=player_chunk_z + radius {
                 let chunk_coords = (x, z);
-                if !chunk_receiver.loaded.contains(&chunk_coords) {
+                if chunk_receiver.unloading.remove(&chunk_coords) {
+                    // Back in range before the client was told to unload it
+                    chunk_receiver.loaded.insert(chunk_coords);
+                } else if !chunk_receiver.loaded.contains(&chunk_coords) {
                     queued_chunks.push(chunk_coords);
                 }
             }
         }
 
+        // Loaded chunks that are waiting to be sent again aren't in the list above
+        queued_chunks.extend(
+            chunk_receiver
+                .loading
+                .iter()
+                .copied()
+                .filter(|coords| chunk_receiver.loaded.contains(coords)),
+        );
+
         // Sort loading list to prioritize closer chunks
         queued_chunks.sort_by_key(|(x, z)| {
             let dx = x - player_chunk_x;
@@ -39,10 +69,7 @@ pub fn handle(
             dx * dx + dz * dz
         });
 
-        for coords in queued_chunks {
-            chunk_receiver.loading.push_back(coords);
-        }
-
-        // TODO: Handle unloading of distant chunks
+        // Anything still queued from before is either in this list or out of range
+        chunk_receiver.loading = queued_chunks.into();
     }
 }
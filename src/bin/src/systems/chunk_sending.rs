@@ -1,107 +1,188 @@
+use crate::systems::listeners::entity_spawn::spawn_packets;
 use bevy_ecs::prelude::{Entity, Query, Res};
-use bevy_math::IVec3;
+use bevy_math::{IVec2, IVec3, Vec2, Vec3Swizzles};
+use ferrumc_components::player::dimension::DimensionComponent;
 use ferrumc_config::server_config::get_global_config;
 use ferrumc_core::chunks::chunk_receiver::ChunkReceiver;
+use ferrumc_core::identity::entity_identity::EntityIdentity;
+use ferrumc_core::identity::player_identity::PlayerIdentity;
 use ferrumc_core::transform::position::Position;
+use ferrumc_core::transform::rotation::Rotation;
+use ferrumc_core::transform::velocity::Velocity;
+use ferrumc_entities::components::{DroppedItem, EntityMetadata};
 use ferrumc_net::compression::compress_packet;
 use ferrumc_net::connection::StreamWriter;
 use ferrumc_net::packets::outgoing::chunk_and_light_data::ChunkAndLightData;
 use ferrumc_net::packets::outgoing::chunk_batch_finish::ChunkBatchFinish;
 use ferrumc_net::packets::outgoing::chunk_batch_start::ChunkBatchStart;
+use ferrumc_net::packets::outgoing::forget_level_chunk::ForgetLevelChunk;
 use ferrumc_net::packets::outgoing::set_center_chunk::SetCenterChunk;
+use ferrumc_net::packets::outgoing::spawn_entity::SpawnEntityPacket;
 use ferrumc_net_codec::encode::NetEncodeOpts;
+use ferrumc_state::chunk_packet_cache::ChunkPacketKey;
 use ferrumc_state::GlobalStateResource;
+use ferrumc_world::errors::WorldError;
 use ferrumc_world::pos::ChunkPos;
+use std::collections::HashSet;
 use std::sync::atomic::Ordering;
+use tracing::error;
+
+type SpawnableEntityQuery<'a> = (
+    &'a EntityIdentity,
+    &'a EntityMetadata,
+    &'a Position,
+    &'a Rotation,
+    &'a DimensionComponent,
+    Option<&'a Velocity>,
+    Option<&'a DroppedItem>,
+);
+
+type SpawnablePlayerQuery<'a> = (
+    Entity,
+    &'a PlayerIdentity,
+    &'a Position,
+    &'a Rotation,
+    &'a DimensionComponent,
+);
 
 // Just take the needed chunks from the ChunkReceiver and send them
 // calculating which chunks are required is figured out elsewhere
-// TODO: Respect chunks_per_tick limit
 pub fn handle(
-    mut query: Query<(Entity, &StreamWriter, &mut ChunkReceiver, &Position)>,
+    mut query: Query<(
+        Entity,
+        &StreamWriter,
+        &mut ChunkReceiver,
+        &Position,
+        &Rotation,
+        &DimensionComponent,
+    )>,
+    entities: Query<SpawnableEntityQuery>,
+    players: Query<SpawnablePlayerQuery>,
     state: Res<GlobalStateResource>,
 ) {
-    for (eid, conn, mut chunk_receiver, pos) in query.iter_mut() {
+    // The tick-wide budget is split evenly between the players still waiting for chunks, so one
+    // player joining doesn't hold up everyone else
+    let mut budget = get_global_config().max_chunks_per_tick as usize;
+    let mut waiting = query
+        .iter()
+        .filter(|(eid, _, chunk_receiver, ..)| {
+            chunk_receiver.has_pending() && state.0.players.is_connected(*eid)
+        })
+        .count();
+
+    for (eid, conn, mut chunk_receiver, pos, rot, dimension) in query.iter_mut() {
         if !state.0.players.is_connected(eid) {
             continue; // Skip if the player is not connected
         }
 
-        if chunk_receiver.dirty.is_empty() && chunk_receiver.loading.is_empty() {
-            continue;
-        }
-
         let chunk_receiver = &mut *chunk_receiver;
 
-        let mut dirty_chunks = Vec::new();
-
-        // First handle dirty chunks
-        while let Some(coords) = &chunk_receiver.dirty.pop_front() {
-            dirty_chunks.push(*coords);
+        let mut forget_failed = false;
+        for (x, z) in chunk_receiver.unloading.drain() {
+            if let Err(e) = conn.send_packet(ForgetLevelChunk::new(x, z)) {
+                error!("Failed to send ForgetLevelChunk: {:?}", e);
+                forget_failed = true;
+                break;
+            }
         }
-
-        let mut needed_chunks: Vec<(i32, i32)> = Vec::new();
-
-        // Then handle loading chunks
-        while let Some(coords) = chunk_receiver.loading.pop_front() {
-            needed_chunks.push(coords);
+        if forget_failed {
+            // The connection is gone, the player is removed with it
+            if chunk_receiver.has_pending() {
+                waiting -= 1;
+            }
+            continue;
         }
 
-        needed_chunks.extend(dirty_chunks);
-
-        for coords in &needed_chunks {
-            chunk_receiver.loaded.insert(*coords);
+        if !chunk_receiver.has_pending() {
+            continue;
         }
 
-        if needed_chunks.is_empty() {
+        let share = budget.div_ceil(waiting.max(1));
+        waiting -= 1;
+        let quota = chunk_receiver.batch_quota().min(share);
+        if quota == 0 {
             continue;
         }
 
+        let center_chunk: IVec3 = pos.coords.floor().as_ivec3() >> 4;
+        let look = Vec2::new(-rot.yaw.to_radians().sin(), rot.yaw.to_radians().cos());
+        chunk_receiver.loading.make_contiguous().sort_by(|a, b| {
+            send_priority(*a, center_chunk.xz(), look).total_cmp(&send_priority(
+                *b,
+                center_chunk.xz(),
+                look,
+            ))
+        });
+
+        let loading_count = quota.min(chunk_receiver.loading.len());
+        let needed_chunks: Vec<(i32, i32)> =
+            chunk_receiver.loading.drain(..loading_count).collect();
+        // Chunks that are only being sent again already have their entities
+        let new_chunks: HashSet<(i32, i32)> = needed_chunks
+            .iter()
+            .copied()
+            .filter(|coords| chunk_receiver.loaded.insert(*coords))
+            .collect();
+        chunk_receiver.batch_sent(needed_chunks.len());
+        budget -= needed_chunks.len();
+
         let mut batch = state.0.thread_pool.batch();
 
         conn.send_packet(ChunkBatchStart {})
             .expect("Failed to send ChunkBatchStart");
 
-        let center_chunk: IVec3 = pos.coords.floor().as_ivec3() >> 4;
-
-        conn.send_packet(SetCenterChunk {
-            x: center_chunk.x.into(),
-            z: center_chunk.z.into(),
-        })
-        .expect("Failed to send SetCenterChunk");
+        conn.send_packet(SetCenterChunk::new(center_chunk.x, center_chunk.z))
+            .expect("Failed to send SetCenterChunk");
 
         for coordinates in needed_chunks.into_iter().map(|c| ChunkPos::new(c.0, c.1)) {
             let state = state.clone();
+            let dimension = dimension.0;
             let is_compressed = conn.compress.load(Ordering::Relaxed);
             batch.execute({
                 move || {
-                    let chunk = state
+                    // Read before the chunk, so an edit in between leaves the packet under the
+                    // old revision
+                    let key = ChunkPacketKey {
+                        pos: coordinates,
+                        dimension,
+                        revision: state.0.world.chunk_revision(coordinates, dimension),
+                        compressed: is_compressed,
+                    };
+                    state
                         .0
-                        .world
-                        .load_chunk(coordinates, "overworld")
-                        .unwrap_or(
-                            state
-                                .0
-                                .terrain_generator
-                                .generate_chunk(coordinates)
-                                .expect("Could not generate chunk")
-                                .into(),
-                        );
-                    let packet = ChunkAndLightData::from_chunk(coordinates, &chunk)
-                        .expect("Failed to create ChunkAndLightData");
-                    compress_packet(
-                        &packet,
-                        is_compressed,
-                        &NetEncodeOpts::WithLength,
-                        get_global_config().network_compression_threshold as usize,
-                    )
-                    .expect("Failed to compress ChunkAndLightData packet")
+                        .chunk_packets
+                        .get_or_encode(key, || {
+                            // Stored the first time it's sent, so edits in any dimension
+                            // find it
+                            let chunk = state.0.world.get_or_generate_chunk(
+                                coordinates,
+                                dimension,
+                                || {
+                                    Ok::<_, WorldError>(
+                                        state
+                                            .0
+                                            .terrain_generator
+                                            .generate_chunk(coordinates, dimension)
+                                            .expect("Could not generate chunk"),
+                                    )
+                                },
+                            )?;
+                            let packet = ChunkAndLightData::from_chunk(coordinates, &chunk)?;
+                            compress_packet(
+                                &packet,
+                                is_compressed,
+                                &NetEncodeOpts::WithLength,
+                                get_global_config().network_compression_threshold as usize,
+                            )
+                        })
+                        .expect("Failed to encode ChunkAndLightData packet")
                 }
             });
         }
         let packets = batch.wait();
         let packets_len = packets.len();
         for packet in packets {
-            conn.send_raw_packet(packet)
+            conn.send_shared_packet(packet)
                 .expect("Failed to send ChunkAndLightData");
         }
 
@@ -109,5 +190,55 @@ pub fn handle(
             batch_size: packets_len.into(),
         })
         .expect("Failed to send ChunkBatchFinish");
+
+        // Entities spawned while the chunk wasn't loaded were never shown to this player
+        for (identity, metadata, entity_pos, entity_rot, entity_dimension, velocity, item) in
+            entities.iter()
+        {
+            let chunk = (
+                entity_pos.x.floor() as i32 >> 4,
+                entity_pos.z.floor() as i32 >> 4,
+            );
+            if entity_dimension.0 != dimension.0 || !new_chunks.contains(&chunk) {
+                continue;
+            }
+            let (spawn_packet, item_packet) =
+                spawn_packets(identity, metadata, entity_pos, entity_rot, velocity, item);
+            if let Err(e) = conn.send_packet_ref(&spawn_packet) {
+                error!("Failed to send spawn packet: {:?}", e);
+            }
+            if let Some(item_packet) = &item_packet {
+                if let Err(e) = conn.send_packet_ref(item_packet) {
+                    error!("Failed to send item metadata packet: {:?}", e);
+                }
+            }
+        }
+        for (other, identity, player_pos, player_rot, player_dimension) in players.iter() {
+            let chunk = (
+                player_pos.x.floor() as i32 >> 4,
+                player_pos.z.floor() as i32 >> 4,
+            );
+            if other == eid || player_dimension.0 != dimension.0 || !new_chunks.contains(&chunk) {
+                continue;
+            }
+            let spawn_packet = SpawnEntityPacket::for_player(identity, player_pos, player_rot);
+            if let Err(e) = conn.send_packet_ref(&spawn_packet) {
+                error!("Failed to send player spawn packet: {:?}", e);
+            }
+        }
+    }
+}
+
+/// Orders chunks for sending, lowest first. Chunks are sent closest first, and further out the
+/// ones the player is looking towards are preferred over the ones behind them.
+fn send_priority(chunk: (i32, i32), center: IVec2, look: Vec2) -> f32 {
+    let offset = (IVec2::from(chunk) - center).as_vec2();
+    let distance = offset.length();
+    // The chunks right around the player are needed no matter where they look
+    if distance < 2.0 {
+        return distance;
     }
+    // From 1 straight ahead to -1 straight behind
+    let facing = offset.normalize().dot(look);
+    distance * (1.5 - 0.5 * facing)
 }
@@ -3,8 +3,8 @@ use ferrumc_components::{
     active_effects::ActiveEffects,
     health::Health,
     player::{
-        abilities::PlayerAbilities, experience::Experience, gamemode::GameModeComponent,
-        gameplay_state::ender_chest::EnderChest, hunger::Hunger,
+        abilities::PlayerAbilities, dimension::DimensionComponent, experience::Experience,
+        gamemode::GameModeComponent, gameplay_state::ender_chest::EnderChest, hunger::Hunger,
     },
 };
 use ferrumc_core::{
@@ -16,15 +16,16 @@ use ferrumc_messages::player_leave::PlayerLeft;
 use ferrumc_net::connection::StreamWriter;
 use ferrumc_state::{player_cache::OfflinePlayerData, GlobalStateResource};
 use ferrumc_text::TextComponent;
-use tracing::{debug, info, trace, warn};
+use tracing::{debug, error, info, trace, warn};
 
 // This type alias defines all the components of a "full" player
-type PlayerCacheQuery<'a> = (
+pub(crate) type PlayerCacheQuery<'a> = (
     Entity,
     &'a StreamWriter,
     &'a PlayerIdentity,
     &'a PlayerAbilities,
     &'a GameModeComponent,
+    &'a DimensionComponent,
     &'a Position,
     &'a Rotation,
     &'a Inventory,
@@ -59,6 +60,7 @@ pub fn connection_killer(
             player_identity,
             abilities,
             gamemode,
+            dimension,
             pos,
             rot,
             inv,
@@ -106,6 +108,7 @@ pub fn connection_killer(
             let data_to_cache = OfflinePlayerData {
                 abilities: *abilities,
                 gamemode: gamemode.0,
+                dimension: dimension.0,
                 position: *pos,
                 rotation: *rot,
                 inventory: inv.clone(),
@@ -115,10 +118,16 @@ pub fn connection_killer(
                 ender_chest: echest.clone(),
                 active_effects: effects.clone(),
             };
-            state
+            if let Err(e) = state
                 .0
                 .player_cache
-                .insert(player_identity.uuid, data_to_cache);
+                .save(player_identity.uuid, data_to_cache)
+            {
+                error!(
+                    "Failed to save player data for {}: {}",
+                    player_identity.username, e
+                );
+            }
 
             // --- 3. Fire PlayerLeaveEvent ---
             leave_events.write(PlayerLeft(player_identity.clone()));
@@ -0,0 +1,56 @@
+use bevy_ecs::prelude::{Commands, Entity, Query};
+use ferrumc_components::player::dimension::DimensionComponent;
+use ferrumc_core::chunks::chunk_receiver::ChunkReceiver;
+use ferrumc_core::identity::entity_identity::EntityIdentity;
+use ferrumc_core::transform::position::Position;
+use ferrumc_entities::components::DroppedItem;
+use ferrumc_net::connection::StreamWriter;
+use ferrumc_net::packets::outgoing::remove_entities::RemoveEntitiesPacket;
+use ferrumc_net_codec::net_types::length_prefixed_vec::LengthPrefixedVec;
+use ferrumc_net_codec::net_types::var_int::VarInt;
+use tracing::warn;
+
+// Items nobody picks up despawn after a while, so broken blocks don't pile up entities forever
+pub fn handle(
+    mut items: Query<(
+        Entity,
+        &mut DroppedItem,
+        &EntityIdentity,
+        &Position,
+        &DimensionComponent,
+    )>,
+    viewers: Query<(&StreamWriter, &ChunkReceiver, &DimensionComponent)>,
+    mut commands: Commands,
+) {
+    let mut despawned = Vec::new();
+    for (entity, mut item, identity, pos, dimension) in items.iter_mut() {
+        if item.tick() {
+            let chunk = (pos.x.floor() as i32 >> 4, pos.z.floor() as i32 >> 4);
+            despawned.push((identity.entity_id, chunk, dimension.0));
+            commands.entity(entity).despawn();
+        }
+    }
+    if despawned.is_empty() {
+        return;
+    }
+
+    // Only players that have an item's chunk loaded were ever shown it
+    for (conn, chunk_receiver, viewer_dimension) in viewers.iter() {
+        let entity_ids: Vec<VarInt> = despawned
+            .iter()
+            .filter(|(_, chunk, dimension)| {
+                *dimension == viewer_dimension.0 && chunk_receiver.loaded.contains(chunk)
+            })
+            .map(|(entity_id, ..)| VarInt::new(*entity_id))
+            .collect();
+        if entity_ids.is_empty() {
+            continue;
+        }
+        let packet = RemoveEntitiesPacket {
+            entity_ids: LengthPrefixedVec::new(entity_ids),
+        };
+        if let Err(e) = conn.send_packet_ref(&packet) {
+            warn!("Failed to send remove entities packet: {:?}", e);
+        }
+    }
+}
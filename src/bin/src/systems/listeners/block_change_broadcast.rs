@@ -0,0 +1,87 @@
+use bevy_ecs::prelude::{MessageReader, Query, Res};
+use ferrumc_components::player::dimension::DimensionComponent;
+use ferrumc_core::chunks::chunk_receiver::ChunkReceiver;
+use ferrumc_messages::BlocksChanged;
+use ferrumc_net::connection::StreamWriter;
+use ferrumc_net::packets::outgoing::block_update::BlockUpdate;
+use ferrumc_net::packets::outgoing::update_section_blocks::UpdateSectionBlocks;
+use ferrumc_net_codec::encode::NetEncode;
+use ferrumc_net_codec::net_types::var_int::VarInt;
+use ferrumc_state::GlobalStateResource;
+use ferrumc_world::dimension::Dimension;
+use ferrumc_world::pos::{BlockPos, ChunkPos, SectionPos};
+use std::collections::{HashMap, HashSet};
+use tracing::error;
+
+/// Sends changed blocks to every player that has the chunk they're in loaded.
+///
+/// Changes are collected over the whole tick and grouped by section, so a single change goes out
+/// as a Block Update and several changes in one section as a single Update Section Blocks packet.
+pub fn handle(
+    mut events: MessageReader<BlocksChanged>,
+    state: Res<GlobalStateResource>,
+    players: Query<(&StreamWriter, &ChunkReceiver, &DimensionComponent)>,
+) {
+    let mut sections: HashMap<(Dimension, SectionPos), HashSet<BlockPos>> = HashMap::new();
+    for event in events.read() {
+        for pos in &event.positions {
+            sections
+                .entry((event.dimension, pos.section()))
+                .or_default()
+                .insert(*pos);
+        }
+    }
+
+    for ((dimension, section), positions) in sections {
+        let chunk_pos = section.chunk();
+        let chunk = match state.0.world.load_chunk(chunk_pos, dimension) {
+            Ok(chunk) => chunk,
+            Err(err) => {
+                error!("Failed to load changed chunk {}: {:?}", chunk_pos, err);
+                continue;
+            }
+        };
+        let mut blocks = Vec::with_capacity(positions.len());
+        for pos in positions {
+            match chunk.get_block(pos.chunk_block_pos()) {
+                Ok(block) => blocks.push((pos, block)),
+                Err(err) => error!("Failed to get changed block at {}: {:?}", pos, err),
+            }
+        }
+
+        match blocks.as_slice() {
+            [] => {}
+            [(pos, block)] => {
+                let packet = BlockUpdate {
+                    location: (*pos).into(),
+                    block_state_id: VarInt::from(*block),
+                };
+                send_to_viewers(&packet, &players, chunk_pos, dimension);
+            }
+            _ => {
+                let packet = UpdateSectionBlocks::new(section, blocks);
+                send_to_viewers(&packet, &players, chunk_pos, dimension);
+            }
+        }
+    }
+}
+
+fn send_to_viewers(
+    packet: &(impl NetEncode + Send),
+    players: &Query<(&StreamWriter, &ChunkReceiver, &DimensionComponent)>,
+    chunk_pos: ChunkPos,
+    dimension: Dimension,
+) {
+    for (conn, chunk_receiver, player_dimension) in players.iter() {
+        if player_dimension.0 != dimension
+            || !chunk_receiver
+                .loaded
+                .contains(&(chunk_pos.x(), chunk_pos.z()))
+        {
+            continue;
+        }
+        if let Err(err) = conn.send_packet_ref(packet) {
+            error!("Failed to send block changes: {:?}", err);
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use bevy_ecs::prelude::{MessageReader, Query, Res};
+use ferrumc_components::player::dimension::DimensionComponent;
+use ferrumc_core::chunks::chunk_receiver::ChunkReceiver;
+use ferrumc_messages::BlockEntityChanged;
+use ferrumc_net::connection::StreamWriter;
+use ferrumc_net::packets::outgoing::block_entity_data::BlockEntityData;
+use ferrumc_net_codec::net_types::var_int::VarInt;
+use ferrumc_state::GlobalStateResource;
+use tracing::error;
+
+/// Sends changed block entities to every player that has the chunk they're in loaded.
+pub fn handle(
+    mut events: MessageReader<BlockEntityChanged>,
+    state: Res<GlobalStateResource>,
+    players: Query<(&StreamWriter, &ChunkReceiver, &DimensionComponent)>,
+) {
+    for event in events.read() {
+        let block_entity = match state
+            .0
+            .world
+            .get_block_entity(event.position, event.dimension)
+        {
+            Ok(Some(block_entity)) => block_entity,
+            // Removed since, the block update takes care of that
+            Ok(None) => continue,
+            Err(err) => {
+                error!(
+                    "Failed to load block entity at {}: {:?}",
+                    event.position, err
+                );
+                continue;
+            }
+        };
+        let packet = BlockEntityData {
+            location: event.position.into(),
+            block_entity_type: VarInt::new(block_entity.kind.registry_id()),
+            nbt: block_entity.network_data().to_vec(),
+        };
+        let chunk = event.position.chunk();
+        for (conn, chunk_receiver, dimension) in players.iter() {
+            if dimension.0 != event.dimension
+                || !chunk_receiver.loaded.contains(&(chunk.x(), chunk.z()))
+            {
+                continue;
+            }
+            if let Err(err) = conn.send_packet_ref(&packet) {
+                error!("Failed to send block entity data: {:?}", err);
+            }
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use bevy_ecs::prelude::{Query, Res};
+use ferrumc_components::player::dimension::DimensionComponent;
+use ferrumc_core::chunks::chunk_receiver::ChunkReceiver;
+use ferrumc_state::GlobalStateResource;
+
+/// Queues chunks whose light changed because of something next to them to be sent again to every
+/// player that has them loaded.
+pub fn handle(
+    mut players: Query<(&mut ChunkReceiver, &DimensionComponent)>,
+    state: Res<GlobalStateResource>,
+) {
+    let relit = state.0.world.take_relit_chunks();
+    if relit.is_empty() {
+        return;
+    }
+    for (mut chunk_receiver, dimension) in players.iter_mut() {
+        for (pos, relit_dimension) in &relit {
+            if *relit_dimension == dimension.0 {
+                chunk_receiver.resend((pos.x(), pos.z()));
+            }
+        }
+    }
+}
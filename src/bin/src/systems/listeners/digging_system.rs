@@ -1,22 +1,39 @@
 use bevy_ecs::prelude::*;
 use ferrumc_world::pos::BlockPos;
-use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use crate::BinaryError;
 use ferrumc_components::player::abilities::PlayerAbilities;
+use ferrumc_components::player::dimension::DimensionComponent;
 use ferrumc_components::player::gameplay_state::digging::PlayerDigging;
+use ferrumc_core::transform::position::Position;
+use ferrumc_core::transform::velocity::Velocity;
 use ferrumc_data::blocks::types::Block;
+use ferrumc_data::enchantments::Enchantment;
+use ferrumc_data::generated::entities::EntityType as VanillaEntityType;
+use ferrumc_inventories::hotbar::Hotbar;
+use ferrumc_inventories::inventory::Inventory;
+use ferrumc_inventories::item::ItemID;
+use ferrumc_inventories::slot::InventorySlot;
 use ferrumc_messages::player_digging::*;
+use ferrumc_messages::{BlocksChanged, SpawnItemEvent};
 use ferrumc_net::connection::StreamWriter;
 use ferrumc_net::packets::outgoing::{block_change_ack::BlockChangeAck, block_update::BlockUpdate};
 use ferrumc_net_codec::net_types::var_int::VarInt;
 use ferrumc_state::GlobalStateResource;
 use ferrumc_world::block_state_id::BlockStateId;
+use ferrumc_world::dimension::Dimension;
+use ferrumc_world::loot::LootContext;
+use rand::Rng;
 use tracing::{debug, error, trace, warn};
 
 // A query for just the components needed to acknowledge a dig packet
-type DiggingPlayerQuery<'a> = (Entity, &'a StreamWriter, Option<&'a PlayerDigging>);
+type DiggingPlayerQuery<'a> = (
+    Entity,
+    &'a StreamWriter,
+    &'a DimensionComponent,
+    Option<&'a PlayerDigging>,
+);
 
 /// Handles the PlayerStartDiggingEvent.
 /// This system starts the digging timer.
@@ -33,11 +50,15 @@ pub fn handle_start_digging(
         );
 
         // --- 1. Get BlockStateId from the world ---
+        let Ok((_, _, &DimensionComponent(dimension), _)) = player_query.get(event.player) else {
+            warn!(
+                "StartDigging: Player {:?} has no dimension component",
+                event.player
+            );
+            continue;
+        };
         let pos = event.position.clone().into();
-        let block_state_id = match state.0.world.get_block_and_fetch(
-            pos,
-            "overworld", // TODO: remove hardcoded dimension
-        ) {
+        let block_state_id = match state.0.world.get_block_and_fetch(pos, dimension) {
             Ok(id) => id,
             Err(e) => {
                 warn!(
@@ -76,7 +97,7 @@ pub fn handle_start_digging(
 
             // We must still send an ACK to the client.
             // But we do not add the PlayerDigging component.
-            if let Ok((_, writer, _)) = player_query.get_mut(event.player) {
+            if let Ok((_, writer, _, _)) = player_query.get_mut(event.player) {
                 let ack_packet = BlockChangeAck {
                     sequence: event.sequence,
                 };
@@ -110,7 +131,7 @@ pub fn handle_start_digging(
         });
 
         // --- 7. Acknowledge the client ---
-        if let Ok((_, writer, _)) = player_query.get_mut(event.player) {
+        if let Ok((_, writer, _, _)) = player_query.get_mut(event.player) {
             let ack_packet = BlockChangeAck {
                 sequence: event.sequence,
             };
@@ -138,7 +159,7 @@ pub fn handle_cancel_digging(
         commands.entity(event.player).remove::<PlayerDigging>();
 
         // Acknowledge the cancellation.
-        if let Ok((_, writer, _)) = player_query.get_mut(event.player) {
+        if let Ok((_, writer, _, _)) = player_query.get_mut(event.player) {
             let ack_packet = BlockChangeAck {
                 sequence: event.sequence,
             };
@@ -159,11 +180,17 @@ pub fn handle_finish_digging(
     mut events: MessageReader<PlayerFinishedDigging>,
     state: Res<GlobalStateResource>,
     mut player_query: Query<DiggingPlayerQuery>,
-    broadcast_query: Query<(Entity, &StreamWriter)>, // For broadcasting the break
-    mut block_break_writer: MessageWriter<ferrumc_messages::BlockBrokenEvent>,
+    held_items: Query<(&Inventory, &Hotbar)>,
+    (mut block_break_writer, mut block_changes, mut item_spawns): (
+        MessageWriter<ferrumc_messages::BlockBrokenEvent>,
+        MessageWriter<BlocksChanged>,
+        MessageWriter<SpawnItemEvent>,
+    ),
 ) {
     for event in events.read() {
-        let Ok((_player_entity, writer, digging_opt)) = player_query.get_mut(event.player) else {
+        let Ok((_player_entity, writer, &DimensionComponent(dimension), digging_opt)) =
+            player_query.get_mut(event.player)
+        else {
             warn!(
                 "Player {:?} sent FinishDigging but query failed.",
                 event.player
@@ -216,7 +243,7 @@ pub fn handle_finish_digging(
             );
 
             let pos = event.position.clone().into();
-            let real_block_state = match state.0.world.get_block_and_fetch(pos, "overworld") {
+            let real_block_state = match state.0.world.get_block_and_fetch(pos, dimension) {
                 Ok(id) => id,
                 Err(e) => {
                     error!(
@@ -245,13 +272,22 @@ pub fn handle_finish_digging(
                 event.player, event.position
             );
 
+            // The held item decides what the block drops
+            let held = held_items
+                .get(event.player)
+                .ok()
+                .and_then(|(inventory, hotbar)| hotbar.get_selected_item(inventory).ok()?);
+
             // We wrap the block-breaking logic in its own function
             // to handle the errors cleanly (replaces `try` block).
             if let Err(e) = break_block(
                 &state,
-                &broadcast_query,
+                dimension,
                 &event.position,
+                held,
                 &mut block_break_writer,
+                &mut block_changes,
+                &mut item_spawns,
             ) {
                 error!("Error handling finished digging: {:?}", e);
             }
@@ -274,52 +310,190 @@ pub fn handle_finish_digging(
 /// Helper function to contain the block-breaking logic (replaces `try` block)
 fn break_block(
     state: &Res<GlobalStateResource>,
-    broadcast_query: &Query<(Entity, &StreamWriter)>,
+    dimension: Dimension,
     position: &ferrumc_net_codec::net_types::network_position::NetworkPosition,
+    held: Option<&InventorySlot>,
     block_break_writer: &mut MessageWriter<ferrumc_messages::BlockBrokenEvent>,
+    block_changes: &mut MessageWriter<BlocksChanged>,
+    item_spawns: &mut MessageWriter<SpawnItemEvent>,
 ) -> Result<(), BinaryError> {
     let pos: BlockPos = position.clone().into();
-    let mut chunk = match state
-        .0
-        .clone()
-        .world
-        .load_chunk_owned(pos.chunk(), "overworld")
-    {
-        Ok(chunk) => chunk,
-        Err(e) => {
-            trace!("Chunk not found, generating new chunk: {:?}", e);
-            state
-                .0
-                .clone()
-                .terrain_generator
-                .generate_chunk(pos.chunk())
-                .map_err(BinaryError::WorldGen)?
-        }
-    };
-    chunk
-        .set_block(pos.chunk_block_pos(), BlockStateId::default())
-        .map_err(BinaryError::World)?;
-    state
-        .0
-        .world
-        .save_chunk(pos.chunk(), "overworld", Arc::new(chunk))
+    let world = &state.0.world;
+    world.get_or_generate_chunk(pos.chunk(), dimension, || {
+        trace!("Chunk not found, generating new chunk at {}", pos.chunk());
+        state
+            .0
+            .terrain_generator
+            .generate_chunk(pos.chunk(), dimension)
+            .map_err(BinaryError::WorldGen)
+    })?;
+    // Drops come from whatever the break replaced, so a block broken twice at once only drops
+    // once
+    let (broken, _) = world
+        .set_block_and_fetch(pos, dimension, BlockStateId::default())
         .map_err(BinaryError::World)?;
 
-    // Send block broken event for un-grounding system
-    debug!("Sending BlockBrokenEvent for block at {:?}", pos.pos);
-    block_break_writer.write(ferrumc_messages::BlockBrokenEvent { position: pos });
-
-    // Broadcast the block break to all players
-    let block_update_packet = BlockUpdate {
-        location: position.clone(),
-        block_state_id: VarInt::from(BlockStateId::default()),
+    // Drop the block's loot where it was
+    let tool = held.and_then(|slot| slot.item_id?.to_name());
+    let enchantments: Vec<(&str, u32)> = held
+        .and_then(|slot| slot.enchantments.as_ref())
+        .into_iter()
+        .flatten()
+        .filter_map(|(id, level)| {
+            let enchantment = Enchantment::from_id(u16::try_from(id.0).ok()?)?;
+            Some((enchantment.name, u32::try_from(level.0).ok()?))
+        })
+        .collect();
+    let context = LootContext {
+        tool: tool.as_deref(),
+        enchantments: &enchantments,
+        ..Default::default()
     };
-    for (eid, conn) in broadcast_query {
-        if !state.0.players.is_connected(eid) {
+    let mut rng = rand::rng();
+    for drop in broken.drops(&context, &mut rng) {
+        let Some(item_id) = ItemID::from_name(drop.item) else {
+            warn!("Loot table of {} drops unknown item {}", broken, drop.item);
             continue;
-        }
-        conn.send_packet_ref(&block_update_packet)
-            .map_err(BinaryError::Net)?;
+        };
+        // Like vanilla, items pop out somewhere around the middle of the block and are thrown
+        // up and a little to the side
+        let half_height = VanillaEntityType::ITEM.dimension[1] as f64 / 2.0;
+        let position = Position::new(
+            pos.pos.x as f64 + 0.5 + rng.random_range(-0.25..0.25),
+            pos.pos.y as f64 + 0.5 + rng.random_range(-0.25..0.25) - half_height,
+            pos.pos.z as f64 + 0.5 + rng.random_range(-0.25..0.25),
+        );
+        let velocity = Velocity::new(
+            rng.random_range(-0.1..0.1),
+            0.2,
+            rng.random_range(-0.1..0.1),
+        );
+        item_spawns.write(SpawnItemEvent {
+            stack: InventorySlot {
+                count: VarInt::new(drop.count as i32),
+                item_id: Some(item_id),
+                ..Default::default()
+            },
+            position,
+            velocity,
+            dimension,
+        });
     }
+
+    // Send block broken event for un-grounding system
+    debug!("Sending BlockBrokenEvent for block at {:?}", pos.pos);
+    block_break_writer.write(ferrumc_messages::BlockBrokenEvent {
+        position: pos,
+        dimension,
+    });
+
+    // Broadcast the block break to all players that can see it
+    block_changes.write(BlocksChanged::single(pos, dimension));
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs::message::Messages;
+    use bevy_ecs::system::RunSystemOnce;
+    use ferrumc_macros::block;
+    use ferrumc_net_codec::net_types::network_position::NetworkPosition;
+    use ferrumc_state::chunk_packet_cache::ChunkPacketCache;
+    use ferrumc_state::player_cache::PlayerCache;
+    use ferrumc_state::player_list::PlayerList;
+    use ferrumc_state::ServerState;
+    use ferrumc_storage::memory::MemoryBackend;
+    use ferrumc_threadpool::ThreadPool;
+    use ferrumc_world::chunk_format::Chunk;
+    use ferrumc_world::pos::ChunkPos;
+    use ferrumc_world_gen::WorldGenerator;
+    use std::sync::Arc;
+
+    /// Breaks a stone block with the given item and returns the items it dropped.
+    fn break_stone(held: InventorySlot) -> Vec<Option<ItemID>> {
+        let world = ferrumc_world::World::with_backend(MemoryBackend::new()).unwrap();
+        world
+            .save_chunk(
+                ChunkPos::new(0, 0),
+                Dimension::Overworld,
+                Arc::new(Chunk::new(Dimension::Overworld.height())),
+            )
+            .unwrap();
+        world
+            .set_block_and_fetch(
+                BlockPos::of(1, 64, 2),
+                Dimension::Overworld,
+                block!("stone"),
+            )
+            .unwrap();
+        let state = ServerState {
+            player_cache: PlayerCache::new(world.clone()),
+            world,
+            terrain_generator: WorldGenerator::new(0),
+            shut_down: false.into(),
+            players: PlayerList::default(),
+            thread_pool: ThreadPool::new(),
+            chunk_packets: ChunkPacketCache::new(),
+            start_time: Instant::now(),
+        };
+
+        let mut ecs = World::new();
+        ecs.insert_resource(GlobalStateResource(Arc::new(state)));
+        ecs.init_resource::<Messages<ferrumc_messages::BlockBrokenEvent>>();
+        ecs.init_resource::<Messages<BlocksChanged>>();
+        ecs.init_resource::<Messages<SpawnItemEvent>>();
+        ecs.run_system_once(
+            move |state: Res<GlobalStateResource>,
+                  mut block_break_writer: MessageWriter<ferrumc_messages::BlockBrokenEvent>,
+                  mut block_changes: MessageWriter<BlocksChanged>,
+                  mut item_spawns: MessageWriter<SpawnItemEvent>| {
+                break_block(
+                    &state,
+                    Dimension::Overworld,
+                    &NetworkPosition { x: 1, y: 64, z: 2 },
+                    Some(&held),
+                    &mut block_break_writer,
+                    &mut block_changes,
+                    &mut item_spawns,
+                )
+                .unwrap();
+            },
+        )
+        .unwrap();
+
+        ecs.resource::<Messages<SpawnItemEvent>>()
+            .iter_current_update_messages()
+            .map(|spawn| spawn.stack.item_id)
+            .collect()
+    }
+
+    #[test]
+    fn test_silk_touch_changes_drops() {
+        let pickaxe = InventorySlot {
+            count: VarInt::new(1),
+            item_id: ItemID::from_name("minecraft:diamond_pickaxe"),
+            ..Default::default()
+        };
+        assert_eq!(
+            break_stone(pickaxe.clone()),
+            [ItemID::from_name("minecraft:cobblestone")]
+        );
+
+        let silk_touch = InventorySlot {
+            components_to_add_count: Some(VarInt::new(1)),
+            components_to_add: Some(vec![VarInt::new(
+                ferrumc_inventories::slot::ENCHANTMENTS_COMPONENT,
+            )]),
+            enchantments: Some(vec![(
+                VarInt::new(Enchantment::SILK_TOUCH.id as i32),
+                VarInt::new(1),
+            )]),
+            ..pickaxe
+        };
+        assert_eq!(
+            break_stone(silk_touch),
+            [ItemID::from_name("minecraft:stone")]
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use bevy_ecs::prelude::*;
+use ferrumc_components::player::dimension::DimensionComponent;
+use ferrumc_components::player::gamemode::GameModeComponent;
+use ferrumc_core::chunks::chunk_receiver::ChunkReceiver;
+use ferrumc_core::identity::player_identity::PlayerIdentity;
+use ferrumc_core::transform::position::Position;
+use ferrumc_core::transform::rotation::Rotation;
+use ferrumc_messages::chunk_calc::ChunkCalc;
+use ferrumc_messages::PlayerDimensionChanged;
+use ferrumc_net::connection::StreamWriter;
+use ferrumc_net::packets::outgoing::game_event::GameEventPacket;
+use ferrumc_net::packets::outgoing::remove_entities::RemoveEntitiesPacket;
+use ferrumc_net::packets::outgoing::respawn::RespawnPacket;
+use ferrumc_net::packets::outgoing::spawn_entity::SpawnEntityPacket;
+use ferrumc_net::packets::outgoing::synchronize_player_position::SynchronizePlayerPositionPacket;
+use ferrumc_net::packets::outgoing::system_message::SystemMessagePacket;
+use ferrumc_net_codec::net_types::var_int::VarInt;
+use ferrumc_text::{Color, NamedColor, TextComponent, TextComponentBuilder};
+use tracing::{error, info};
+
+type DimensionChangeQuery<'a> = (
+    Entity,
+    &'a PlayerIdentity,
+    &'a mut DimensionComponent,
+    &'a mut Position,
+    &'a Rotation,
+    &'a GameModeComponent,
+    &'a mut ChunkReceiver,
+    &'a StreamWriter,
+);
+
+/// Listens for `PlayerDimensionChanged` and moves the player into the new dimension.
+pub fn handle(
+    mut events: MessageReader<PlayerDimensionChanged>,
+    mut player_query: Query<DimensionChangeQuery>,
+    mut chunk_calc: MessageWriter<ChunkCalc>,
+) {
+    for event in events.read() {
+        // 1. Despawn the player for everyone that could see them in the old dimension
+        let Ok((_, identity, old_dimension, old_position, ..)) = player_query.get(event.player)
+        else {
+            // Player might have disconnected in the same tick
+            continue;
+        };
+        let old_dimension = old_dimension.0;
+        let old_chunk = chunk_of(old_position);
+        let remove_packet = RemoveEntitiesPacket::from_entities([identity.clone()]);
+        for (viewer, viewer_identity, viewer_dimension, _, _, _, chunk_receiver, conn) in
+            player_query.iter()
+        {
+            if viewer == event.player
+                || viewer_dimension.0 != old_dimension
+                || !chunk_receiver.loaded.contains(&old_chunk)
+            {
+                continue;
+            }
+            if let Err(e) = conn.send_packet_ref(&remove_packet) {
+                error!(
+                    "Failed to send remove entities packet to {}: {:?}",
+                    viewer_identity.username, e
+                );
+            }
+        }
+
+        // Get all the player's components
+        let Ok((
+            _,
+            identity,
+            mut dimension,
+            mut position,
+            rotation,
+            gamemode,
+            mut chunk_receiver,
+            writer,
+        )) = player_query.get_mut(event.player)
+        else {
+            // Player might have disconnected in the same tick
+            continue;
+        };
+
+        let new_dimension = event.dimension;
+
+        // --- 2. Update server-side components ---
+        dimension.0 = new_dimension;
+        *position = event.position.unwrap_or_else(|| {
+            let height = new_dimension.height();
+            Position::new(
+                position.x,
+                position
+                    .y
+                    .clamp(height.min_y as f64, (height.max_y() - 2) as f64),
+                position.z,
+            )
+        });
+
+        // The client throws away every chunk it has when it respawns
+        chunk_receiver.loading.clear();
+        chunk_receiver.loaded.clear();
+        chunk_receiver.unloading.clear();
+
+        // --- 3. Send sync packets to client ---
+
+        // 3a. Respawn packet (switches the client's world)
+        let respawn_packet = RespawnPacket::new(new_dimension, gamemode.0 as u8);
+        if let Err(e) = writer.send_packet_ref(&respawn_packet) {
+            error!(
+                "Failed to send respawn packet to {}: {:?}",
+                identity.username, e
+            );
+            continue;
+        }
+
+        // 3b. Position packet (places the player in the new world)
+        let position_packet = SynchronizePlayerPositionPacket::from_position_rotation(
+            &position,
+            rotation,
+            VarInt::new(0),
+        );
+        if let Err(e) = writer.send_packet_ref(&position_packet) {
+            error!(
+                "Failed to send position sync packet to {}: {:?}",
+                identity.username, e
+            );
+        }
+
+        // 3c. Game Event packet (keeps the loading screen up until the chunks arrive)
+        let waiting_packet = GameEventPacket::start_waiting_for_level_chunks();
+        if let Err(e) = writer.send_packet_ref(&waiting_packet) {
+            error!(
+                "Failed to send game event packet to {}: {:?}",
+                identity.username, e
+            );
+        }
+
+        // 4. Queue the chunks around the new position
+        chunk_calc.write(ChunkCalc(event.player));
+
+        // 5. Send confirmation chat message
+        let msg = TextComponentBuilder::new("Moved to ")
+            .extra(
+                TextComponent::from(new_dimension.identifier())
+                    .color(Color::Named(NamedColor::Aqua)),
+            )
+            .build();
+
+        let chat_packet = SystemMessagePacket {
+            message: msg.into(),
+            overlay: false,
+        };
+        if let Err(e) = writer.send_packet_ref(&chat_packet) {
+            error!(
+                "Failed to send dimension confirmation message to {}: {:?}",
+                identity.username, e
+            );
+        }
+
+        info!(
+            "Moved {} to {}",
+            identity.username,
+            new_dimension.identifier()
+        );
+
+        // 6. Spawn the player for everyone that can see them in the new dimension. The entities
+        // around the player are spawned for them as their chunks arrive.
+        let spawn_packet = SpawnEntityPacket::for_player(identity, &position, rotation);
+        let new_chunk = chunk_of(&position);
+        for (viewer, viewer_identity, viewer_dimension, _, _, _, chunk_receiver, conn) in
+            player_query.iter()
+        {
+            if viewer == event.player
+                || viewer_dimension.0 != new_dimension
+                || !chunk_receiver.loaded.contains(&new_chunk)
+            {
+                continue;
+            }
+            if let Err(e) = conn.send_packet_ref(&spawn_packet) {
+                error!(
+                    "Failed to send spawn packet to {}: {:?}",
+                    viewer_identity.username, e
+                );
+            }
+        }
+    }
+}
+
+fn chunk_of(position: &Position) -> (i32, i32) {
+    (
+        position.x.floor() as i32 >> 4,
+        position.z.floor() as i32 >> 4,
+    )
+}
@@ -1,94 +1,121 @@
 use bevy_ecs::prelude::*;
+use ferrumc_components::player::dimension::DimensionComponent;
+use ferrumc_core::chunks::chunk_receiver::ChunkReceiver;
 use ferrumc_core::identity::entity_identity::EntityIdentity;
 use ferrumc_core::transform::position::Position;
 use ferrumc_core::transform::rotation::Rotation;
-use ferrumc_entities::bundles::PigBundle;
-use ferrumc_entities::components::EntityMetadata;
-use ferrumc_entities::markers::entity_types::Pig;
-use ferrumc_entities::markers::{HasCollisions, HasGravity};
-use ferrumc_messages::{EntityType, SpawnEntityCommand, SpawnEntityEvent};
+use ferrumc_core::transform::velocity::Velocity;
+use ferrumc_entities::bundles::{ItemBundle, PigBundle};
+use ferrumc_entities::components::{DroppedItem, EntityMetadata};
+use ferrumc_entities::markers::entity_types::{Item, Pig};
+use ferrumc_entities::markers::{HasCollisions, HasGravity, HasWaterDrag};
+use ferrumc_messages::{EntityType, SpawnEntityCommand, SpawnEntityEvent, SpawnItemEvent};
 use ferrumc_net::connection::StreamWriter;
+use ferrumc_net::packets::outgoing::entity_metadata::{
+    EntityMetadata as MetadataEntry, EntityMetadataPacket,
+};
 use ferrumc_net::packets::outgoing::spawn_entity::SpawnEntityPacket;
+use ferrumc_net_codec::net_types::var_int::VarInt;
 use tracing::{error, warn};
 
-/// Helper function to broadcast entity spawn packets to all connected players.
+/// Builds the packets that show an entity to a player: the spawn itself, and for item entities
+/// the item they hold, since they don't show anything until they're told.
+pub fn spawn_packets(
+    identity: &EntityIdentity,
+    metadata: &EntityMetadata,
+    position: &Position,
+    rotation: &Rotation,
+    velocity: Option<&Velocity>,
+    item: Option<&DroppedItem>,
+) -> (SpawnEntityPacket, Option<EntityMetadataPacket>) {
+    let mut spawn_packet = SpawnEntityPacket::new(
+        identity.entity_id,
+        identity.uuid.as_u128(),
+        metadata.protocol_id() as i32,
+        position,
+        rotation,
+    );
+    if let Some(velocity) = velocity {
+        spawn_packet = spawn_packet.with_velocity(velocity);
+    }
+    let item_packet = item.map(|item| {
+        EntityMetadataPacket::new(
+            VarInt::new(identity.entity_id),
+            [MetadataEntry::item_stack(item.stack.clone())],
+        )
+    });
+    (spawn_packet, item_packet)
+}
+
+/// Helper function to broadcast entity spawn packets to the players that can see it.
 ///
 /// This function queries the entity's components and sends the spawn packet
-/// to all players. It's generic and works for any entity type.
+/// to every player in the same dimension with the entity's chunk loaded. Players
+/// that load the chunk later are sent it along with the chunk. It's generic and
+/// works for any entity type.
 ///
 /// # Arguments
 ///
 /// * `world` - The Bevy world
 /// * `entity` - The entity to broadcast
 fn broadcast_entity_spawn(world: &mut World, entity: Entity) {
-    // Get entity components
-    let metadata = match world.get::<EntityMetadata>(entity) {
-        Some(m) => m,
-        None => {
-            error!("Failed to get entity metadata for {:?}", entity);
-            return;
-        }
-    };
-    let protocol_id = metadata.protocol_id();
-
-    let identity = match world.get::<EntityIdentity>(entity) {
-        Some(i) => i,
-        None => {
-            error!("Failed to get entity identity for {:?}", entity);
-            return;
-        }
+    let mut entity_query = world.query::<(
+        &EntityIdentity,
+        &EntityMetadata,
+        &Position,
+        &Rotation,
+        &DimensionComponent,
+        Option<&Velocity>,
+        Option<&DroppedItem>,
+    )>();
+    let Ok((identity, metadata, position, rotation, dimension, velocity, item)) =
+        entity_query.get(world, entity)
+    else {
+        error!("Failed to get the components to spawn {:?}", entity);
+        return;
     };
-
-    let position = match world.get::<Position>(entity) {
-        Some(p) => p,
-        None => {
-            error!("Failed to get entity position for {:?}", entity);
-            return;
-        }
-    };
-
-    let rotation = match world.get::<Rotation>(entity) {
-        Some(r) => r,
-        None => {
-            error!("Failed to get entity rotation for {:?}", entity);
-            return;
-        }
-    };
-
-    // Create spawn packet
-    let spawn_packet = SpawnEntityPacket::new(
-        identity.entity_id,
-        identity.uuid.as_u128(),
-        protocol_id as i32,
-        position,
-        rotation,
+    let (spawn_packet, item_packet) =
+        spawn_packets(identity, metadata, position, rotation, velocity, item);
+    let dimension = dimension.0;
+    let chunk = (
+        position.x.floor() as i32 >> 4,
+        position.z.floor() as i32 >> 4,
     );
 
-    // Broadcast to all connected players
-    let mut writer_query = world.query::<&StreamWriter>();
-    for writer in writer_query.iter(world) {
+    // Broadcast to the players that have the entity's chunk
+    let mut writer_query = world.query::<(&StreamWriter, &ChunkReceiver, &DimensionComponent)>();
+    for (writer, chunk_receiver, viewer_dimension) in writer_query.iter(world) {
+        if viewer_dimension.0 != dimension || !chunk_receiver.loaded.contains(&chunk) {
+            continue;
+        }
         if let Err(e) = writer.send_packet_ref(&spawn_packet) {
             error!("Failed to send spawn packet: {:?}", e);
         }
+        if let Some(item_packet) = &item_packet {
+            if let Err(e) = writer.send_packet_ref(item_packet) {
+                error!("Failed to send item metadata packet: {:?}", e);
+            }
+        }
     }
 }
 
 /// System that processes spawn commands from messages
 pub fn spawn_command_processor(
     mut spawn_commands: MessageReader<SpawnEntityCommand>,
-    query: Query<(&Position, &Rotation)>,
+    query: Query<(&Position, &Rotation, &DimensionComponent)>,
     mut spawn_events: MessageWriter<SpawnEntityEvent>,
 ) {
     // Process all spawn command messages
     for command in spawn_commands.read() {
         // Get player position and rotation
-        if let Ok((pos, rot)) = query.get(command.player_entity) {
+        if let Ok((pos, rot, dimension)) = query.get(command.player_entity) {
             // Calculate spawn position 2 blocks in front of the player
             let spawn_pos = pos.offset_forward(rot, 2.0);
 
             spawn_events.write(SpawnEntityEvent {
                 entity_type: command.entity_type,
                 position: spawn_pos,
+                dimension: dimension.0,
             });
         } else {
             warn!(
@@ -108,7 +135,7 @@ pub fn handle_spawn_entity(mut events: MessageReader<SpawnEntityEvent>, mut comm
                 // Spawn the pig entity
                 let pig_entity = commands
                     .spawn((
-                        PigBundle::new(event.position),
+                        PigBundle::new(event.position, event.dimension),
                         Pig,
                         HasGravity,
                         HasCollisions,
@@ -123,3 +150,28 @@ pub fn handle_spawn_entity(mut events: MessageReader<SpawnEntityEvent>, mut comm
         }
     }
 }
+
+/// System that listens for `SpawnItemEvent` and spawns the item entity,
+/// then broadcasts the spawn packet along with the item it holds.
+pub fn handle_spawn_item(mut events: MessageReader<SpawnItemEvent>, mut commands: Commands) {
+    for event in events.read() {
+        let item_entity = commands
+            .spawn((
+                ItemBundle::new(
+                    event.stack.clone(),
+                    event.position,
+                    event.velocity,
+                    event.dimension,
+                ),
+                Item,
+                HasGravity,
+                HasWaterDrag,
+                HasCollisions,
+            ))
+            .id();
+
+        commands.queue(move |world: &mut World| {
+            broadcast_entity_spawn(world, item_entity);
+        });
+    }
+}
@@ -1,4 +1,9 @@
+use bevy_ecs::schedule::IntoScheduleConfigs;
+pub mod block_change_broadcast;
+pub mod block_entity_sync;
+pub mod chunk_resend;
 pub mod digging_system;
+pub mod dimension_change;
 pub mod entity_spawn;
 pub mod gamemode_change;
 pub mod player_join_message;
@@ -8,9 +13,14 @@ pub fn register_gameplay_listeners(schedule: &mut bevy_ecs::schedule::Schedule)
     schedule.add_systems(player_leave_message::handle);
     schedule.add_systems(player_join_message::handle);
     schedule.add_systems(gamemode_change::handle);
+    schedule.add_systems(dimension_change::handle);
     schedule.add_systems(entity_spawn::spawn_command_processor);
     schedule.add_systems(entity_spawn::handle_spawn_entity);
+    schedule.add_systems(entity_spawn::handle_spawn_item);
     schedule.add_systems(digging_system::handle_start_digging);
     schedule.add_systems(digging_system::handle_cancel_digging);
     schedule.add_systems(digging_system::handle_finish_digging);
+    schedule.add_systems(chunk_resend::handle);
+    // Block entity data is only accepted by clients that already have the block
+    schedule.add_systems((block_change_broadcast::handle, block_entity_sync::handle).chain());
 }
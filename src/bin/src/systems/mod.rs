@@ -1,6 +1,9 @@
+use bevy_ecs::schedule::IntoScheduleConfigs;
+pub mod block_updates;
 mod chunk_calculator;
 mod chunk_sending;
 pub mod connection_killer;
+mod item_despawn;
 pub mod keep_alive_system;
 pub mod lan_pinger;
 pub mod listeners;
@@ -10,18 +13,30 @@ pub mod new_connections;
 mod particles;
 pub mod physics;
 mod player_swimming;
+mod pressure_plates;
+pub mod random_ticks;
 mod send_entity_updates;
 pub mod shutdown_systems;
 pub mod world_sync;
 
 pub fn register_game_systems(schedule: &mut bevy_ecs::schedule::Schedule) {
     // Tick-bound systems only (run every game tick)
-    schedule.add_systems(new_connections::accept_new_connections);
-    schedule.add_systems(chunk_calculator::handle);
-    schedule.add_systems(chunk_sending::handle);
+    // New players need their chunks calculated before anything can be sent to them
+    schedule.add_systems(
+        (
+            new_connections::accept_new_connections,
+            chunk_calculator::handle,
+            chunk_sending::handle,
+        )
+            .chain(),
+    );
     schedule.add_systems(mq::process);
     schedule.add_systems(player_swimming::detect_player_swimming);
+    schedule.add_systems(pressure_plates::handle);
+    schedule.add_systems(block_updates::handle);
+    schedule.add_systems(random_ticks::handle);
 
+    schedule.add_systems(item_despawn::handle);
     schedule.add_systems(send_entity_updates::handle);
 
     // Should always be last
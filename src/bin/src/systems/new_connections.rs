@@ -5,12 +5,15 @@ use ferrumc_components::{
     health::Health,
     player::{
         abilities::PlayerAbilities,
+        dimension::DimensionComponent,
+        edit_session::EditSession,
         experience::Experience,
         gamemode::{GameMode, GameModeComponent},
         gameplay_state::ender_chest::EnderChest,
         hunger::Hunger,
         player_bundle::PlayerBundle,
         swimming::SwimmingState,
+        view_distance::ViewDistance,
     },
 };
 use ferrumc_core::{
@@ -19,9 +22,11 @@ use ferrumc_core::{
     transform::{grounded::OnGround, position::Position, rotation::Rotation},
 };
 use ferrumc_inventories::{hotbar::Hotbar, inventory::Inventory};
+use ferrumc_messages::chunk_calc::ChunkCalc;
 use ferrumc_messages::player_join::PlayerJoined;
 use ferrumc_net::connection::{DisconnectHandle, NewConnection};
 use ferrumc_state::GlobalStateResource;
+use ferrumc_world::dimension::Dimension;
 use std::time::Instant;
 use tracing::{error, trace};
 
@@ -33,6 +38,7 @@ pub fn accept_new_connections(
     new_connections: Res<NewConnectionRecv>,
     state: Res<GlobalStateResource>,
     mut join_events: MessageWriter<PlayerJoined>,
+    mut chunk_calc: MessageWriter<ChunkCalc>,
 ) {
     if new_connections.0.is_empty() {
         return;
@@ -44,6 +50,7 @@ pub fn accept_new_connections(
         let (
             abilities,
             gamemode,
+            dimension,
             position,
             rotation,
             inventory,
@@ -61,6 +68,7 @@ pub fn accept_new_connections(
                 (
                     data.abilities,
                     data.gamemode,
+                    data.dimension,
                     data.position,
                     data.rotation,
                     data.inventory,
@@ -76,6 +84,7 @@ pub fn accept_new_connections(
                 (
                     PlayerAbilities::default(),
                     GameMode::default(),
+                    Dimension::default(),
                     Position::default(),
                     Rotation::default(),
                     Inventory::default(),
@@ -92,10 +101,12 @@ pub fn accept_new_connections(
             identity: new_connection.player_identity.clone(),
             abilities,
             gamemode: GameModeComponent(gamemode),
+            dimension: DimensionComponent(dimension),
             position,
             rotation,
             on_ground: OnGround::default(),
             chunk_receiver: ChunkReceiver::default(),
+            view_distance: ViewDistance(new_connection.view_distance),
             inventory,
             hotbar: Hotbar::default(),
             ender_chest,
@@ -104,6 +115,7 @@ pub fn accept_new_connections(
             experience,
             active_effects,
             swimming: SwimmingState::default(),
+            edit_session: EditSession::default(),
         };
 
         // --- 3. Spawn the PlayerBundle, then .insert() the network components ---
@@ -137,6 +149,9 @@ pub fn accept_new_connections(
         // Fire PlayerJoinEvent
         join_events.write(PlayerJoined(new_connection.player_identity.clone()));
 
+        // Queue the chunks around the player, the client waits on the loading screen until they arrive
+        chunk_calc.write(ChunkCalc(entity_id));
+
         if let Err(err) = return_sender.send(entity_id) {
             error!(
                 "Failed to send entity ID back to the networking thread: {:?}",
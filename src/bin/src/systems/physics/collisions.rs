@@ -2,6 +2,7 @@ use bevy_ecs::message::MessageWriter;
 use bevy_ecs::prelude::{DetectChanges, Entity, Query, Res, With};
 use bevy_math::bounding::{Aabb3d, BoundingVolume};
 use bevy_math::{IVec3, Vec3A};
+use ferrumc_components::player::dimension::DimensionComponent;
 use ferrumc_core::transform::grounded::OnGround;
 use ferrumc_core::transform::position::Position;
 use ferrumc_core::transform::velocity::Velocity;
@@ -11,23 +12,24 @@ use ferrumc_macros::match_block;
 use ferrumc_messages::entity_update::SendEntityUpdate;
 use ferrumc_state::{GlobalState, GlobalStateResource};
 use ferrumc_world::block_state_id::BlockStateId;
+use ferrumc_world::dimension::Dimension;
 use ferrumc_world::pos::{ChunkBlockPos, ChunkPos};
 
+type CollidingEntityQuery<'a> = (
+    Entity,
+    &'a mut Velocity,
+    &'a mut Position,
+    &'a PhysicalProperties,
+    &'a mut OnGround,
+    &'a DimensionComponent,
+);
+
 pub fn handle(
-    query: Query<
-        (
-            Entity,
-            &mut Velocity,
-            &mut Position,
-            &PhysicalProperties,
-            &mut OnGround,
-        ),
-        With<HasCollisions>,
-    >,
+    query: Query<CollidingEntityQuery, With<HasCollisions>>,
     mut writer: MessageWriter<SendEntityUpdate>,
     state: Res<GlobalStateResource>,
 ) {
-    for (eid, mut vel, mut pos, physical, mut grounded) in query {
+    for (eid, mut vel, mut pos, physical, mut grounded, dimension) in query {
         if pos.is_changed() || vel.is_changed() {
             // Figure out where the entity is going to be next tick
             let next_pos = pos.coords.as_vec3a() + **vel;
@@ -58,10 +60,12 @@ pub fn handle(
                 for y in min_block_pos.y.floor() as i32..=max_block_pos.y.floor() as i32 {
                     for z in min_block_pos.z.floor() as i32..=max_block_pos.z.floor() as i32 {
                         let block_pos = IVec3::new(x, y, z);
-                        if is_solid_block(&state.0, block_pos) {
+                        if is_solid_block(&state.0, dimension.0, block_pos) {
                             collided = true;
                             hit_blocks.push(block_pos);
-                            if is_solid_block(&state.0, IVec3::new(x, y - 1, z)) && vel.y <= 0.0 {
+                            if is_solid_block(&state.0, dimension.0, IVec3::new(x, y - 1, z))
+                                && vel.y <= 0.0
+                            {
                                 grounded.0 = true;
                             }
                         }
@@ -116,17 +120,17 @@ pub fn handle(
     }
 }
 
-pub fn is_solid_block(state: &GlobalState, pos: IVec3) -> bool {
+pub fn is_solid_block(state: &GlobalState, dimension: Dimension, pos: IVec3) -> bool {
     state
         .world
-        .load_chunk(ChunkPos::from(pos.as_dvec3()), "overworld")
-        .unwrap_or(
+        .load_chunk(ChunkPos::from(pos.as_dvec3()), dimension)
+        .unwrap_or_else(|_| {
             state
                 .terrain_generator
-                .generate_chunk(ChunkPos::from(pos.as_dvec3()))
+                .generate_chunk(ChunkPos::from(pos.as_dvec3()), dimension)
                 .expect("Failed to generate chunk")
-                .into(),
-        )
+                .into()
+        })
         .get_block(ChunkBlockPos::from(pos))
         .map(|block_state| {
             !match_block!("air", block_state)
@@ -1,4 +1,5 @@
 use bevy_ecs::prelude::{DetectChanges, Query, Res, With};
+use ferrumc_components::player::dimension::DimensionComponent;
 use ferrumc_core::transform::position::Position;
 use ferrumc_core::transform::velocity::Velocity;
 use ferrumc_entities::markers::HasWaterDrag;
@@ -8,20 +9,24 @@ use ferrumc_world::block_state_id::BlockStateId;
 use ferrumc_world::pos::{ChunkBlockPos, ChunkPos};
 
 pub fn handle(
-    mut query: Query<(&mut Velocity, &mut Position), With<HasWaterDrag>>,
+    mut query: Query<(&mut Velocity, &mut Position, &DimensionComponent), With<HasWaterDrag>>,
     state: Res<GlobalStateResource>,
 ) {
-    for (mut vel, pos) in query.iter_mut() {
+    for (mut vel, pos, dimension) in query.iter_mut() {
         if pos.is_changed() || vel.is_changed() {
             let chunk_pos = ChunkPos::from(pos.coords);
-            let chunk = state.0.world.load_chunk(chunk_pos, "overworld").unwrap_or(
-                state
-                    .0
-                    .terrain_generator
-                    .generate_chunk(chunk_pos)
-                    .expect("Failed to generate chunk")
-                    .into(),
-            );
+            let chunk = state
+                .0
+                .world
+                .load_chunk(chunk_pos, dimension.0)
+                .unwrap_or_else(|_| {
+                    state
+                        .0
+                        .terrain_generator
+                        .generate_chunk(chunk_pos, dimension.0)
+                        .expect("Failed to generate chunk")
+                        .into()
+                });
             let is_in_water = chunk
                 .get_block(ChunkBlockPos::from(pos.coords.as_ivec3()))
                 .map(|block| match_block!("water", block))
@@ -1,6 +1,7 @@
 use bevy_ecs::message::MessageReader;
 use bevy_ecs::prelude::{Query, Res, With};
 use bevy_math::IVec3;
+use ferrumc_components::player::dimension::DimensionComponent;
 use ferrumc_core::transform::grounded::OnGround;
 use ferrumc_core::transform::position::Position;
 use ferrumc_entities::markers::HasCollisions;
@@ -17,7 +18,15 @@ use super::collisions::is_solid_block;
 /// The main purpose is to re-enable gravity for entities that lose their ground support.
 pub fn handle(
     mut events: MessageReader<BlockBrokenEvent>,
-    mut entities: Query<(&Position, &PhysicalProperties, &mut OnGround), With<HasCollisions>>,
+    mut entities: Query<
+        (
+            &Position,
+            &PhysicalProperties,
+            &mut OnGround,
+            &DimensionComponent,
+        ),
+        With<HasCollisions>,
+    >,
     state: Res<GlobalStateResource>,
 ) {
     for event in events.read() {
@@ -28,9 +37,9 @@ pub fn handle(
         );
 
         // Check all entities with collisions
-        for (pos, physical, mut grounded) in entities.iter_mut() {
-            // Skip entities that aren't grounded
-            if !grounded.0 {
+        for (pos, physical, mut grounded, dimension) in entities.iter_mut() {
+            // Skip entities that aren't grounded or are in another dimension
+            if !grounded.0 || dimension.0 != event.dimension {
                 continue;
             }
 
@@ -72,7 +81,7 @@ pub fn handle(
                         }
 
                         let check_pos = IVec3::new(x, feet_y, z);
-                        if is_solid_block(&state.0, check_pos) {
+                        if is_solid_block(&state.0, dimension.0, check_pos) {
                             has_support = true;
                             break;
                         }
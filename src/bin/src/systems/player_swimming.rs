@@ -1,5 +1,6 @@
 use bevy_ecs::prelude::*;
 use bevy_math::DVec3;
+use ferrumc_components::player::dimension::DimensionComponent;
 use ferrumc_components::player::swimming::SwimmingState;
 use ferrumc_core::identity::player_identity::PlayerIdentity;
 use ferrumc_core::transform::position::Position;
@@ -9,6 +10,7 @@ use ferrumc_net::packets::outgoing::entity_metadata::{EntityMetadata, EntityMeta
 use ferrumc_net_codec::net_types::var_int::VarInt;
 use ferrumc_state::GlobalStateResource;
 use ferrumc_world::block_state_id::BlockStateId;
+use ferrumc_world::dimension::Dimension;
 use ferrumc_world::pos::BlockPos;
 use tracing::error;
 
@@ -16,7 +18,11 @@ use tracing::error;
 const PLAYER_EYE_HEIGHT: f64 = 1.62;
 
 /// Check if a player is in water by testing at eye level
-fn is_player_in_water(state: &ferrumc_state::GlobalState, pos: &Position) -> bool {
+fn is_player_in_water(
+    state: &ferrumc_state::GlobalState,
+    dimension: Dimension,
+    pos: &Position,
+) -> bool {
     let eye_pos = DVec3::new(pos.x, pos.y + PLAYER_EYE_HEIGHT, pos.z)
         .floor()
         .as_ivec3();
@@ -25,7 +31,7 @@ fn is_player_in_water(state: &ferrumc_state::GlobalState, pos: &Position) -> boo
 
     state
         .world
-        .get_block_and_fetch(pos, "overworld")
+        .get_block_and_fetch(pos, dimension)
         .map(|current_block| match_block!("water", current_block))
         .unwrap_or(false)
 }
@@ -33,12 +39,17 @@ fn is_player_in_water(state: &ferrumc_state::GlobalState, pos: &Position) -> boo
 /// System that detects when players enter/exit water and updates their swimming state
 /// Also broadcasts the swimming pose to all connected clients
 pub fn detect_player_swimming(
-    mut swimmers: Query<(&PlayerIdentity, &Position, &mut SwimmingState)>,
+    mut swimmers: Query<(
+        &PlayerIdentity,
+        &Position,
+        &DimensionComponent,
+        &mut SwimmingState,
+    )>,
     all_connections: Query<(Entity, &StreamWriter)>,
     state: Res<GlobalStateResource>,
 ) {
-    for (identity, pos, mut swimming_state) in swimmers.iter_mut() {
-        let in_water = is_player_in_water(&state.0, pos);
+    for (identity, pos, dimension, mut swimming_state) in swimmers.iter_mut() {
+        let in_water = is_player_in_water(&state.0, dimension.0, pos);
 
         if in_water && !swimming_state.is_swimming {
             swimming_state.is_swimming = true;
@@ -0,0 +1,79 @@
+use bevy_ecs::prelude::{Changed, Entity, Local, MessageWriter, Or, Query, RemovedComponents, Res};
+use ferrumc_components::player::dimension::DimensionComponent;
+use ferrumc_core::transform::position::Position;
+use ferrumc_messages::BlocksChanged;
+use ferrumc_state::GlobalStateResource;
+use ferrumc_world::dimension::Dimension;
+use ferrumc_world::pos::BlockPos;
+use std::collections::HashMap;
+use tracing::error;
+
+/// The block every player and mob was last seen in, and which of those blocks are pressure
+/// plates.
+#[derive(Default)]
+pub struct StandingIn {
+    blocks: HashMap<Entity, (Dimension, BlockPos)>,
+    plates: HashMap<Entity, (Dimension, BlockPos)>,
+}
+
+type MovedEntityQuery<'a> = (Entity, &'a Position, &'a DimensionComponent);
+type MovedFilter = Or<(Changed<Position>, Changed<DimensionComponent>)>;
+
+/// Presses the pressure plates players and mobs are standing in, and sends out the plates that
+/// went down.
+///
+/// Only entities that moved into another block are looked up in the world, the rest keep
+/// pressing whatever plate they were already in.
+pub fn handle(
+    moved: Query<MovedEntityQuery, MovedFilter>,
+    mut removed: RemovedComponents<Position>,
+    mut standing: Local<StandingIn>,
+    state: Res<GlobalStateResource>,
+    mut block_changes: MessageWriter<BlocksChanged>,
+) {
+    let standing = &mut *standing;
+    for entity in removed.read() {
+        standing.blocks.remove(&entity);
+        standing.plates.remove(&entity);
+    }
+    for (entity, pos, dimension) in moved.iter() {
+        let feet = (
+            dimension.0,
+            BlockPos::of(
+                pos.x.floor() as i32,
+                pos.y.floor() as i32,
+                pos.z.floor() as i32,
+            ),
+        );
+        if standing.blocks.insert(entity, feet) == Some(feet) {
+            continue;
+        }
+        if state.0.world.is_pressure_plate(feet.1, feet.0) {
+            standing.plates.insert(entity, feet);
+        } else {
+            standing.plates.remove(&entity);
+        }
+    }
+
+    let mut plates: HashMap<Dimension, HashMap<BlockPos, usize>> = HashMap::new();
+    for (dimension, feet) in standing.plates.values() {
+        *plates
+            .entry(*dimension)
+            .or_default()
+            .entry(*feet)
+            .or_default() += 1;
+    }
+
+    for (dimension, plates) in plates {
+        match state.0.world.press_pressure_plates(dimension, plates) {
+            Ok(positions) if !positions.is_empty() => {
+                block_changes.write(BlocksChanged {
+                    positions,
+                    dimension,
+                });
+            }
+            Ok(_) => {}
+            Err(err) => error!("Failed to press pressure plates: {:?}", err),
+        }
+    }
+}
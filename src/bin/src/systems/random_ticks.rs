@@ -0,0 +1,47 @@
+use bevy_ecs::prelude::{MessageWriter, Query, Res};
+use ferrumc_components::player::dimension::DimensionComponent;
+use ferrumc_config::server_config::get_global_config;
+use ferrumc_core::chunks::chunk_receiver::ChunkReceiver;
+use ferrumc_messages::BlocksChanged;
+use ferrumc_state::GlobalStateResource;
+use ferrumc_world::pos::ChunkPos;
+use std::collections::HashSet;
+
+/// Random ticks every chunk a player has loaded, and sends out the blocks that changed.
+pub fn handle(
+    players: Query<(&ChunkReceiver, &DimensionComponent)>,
+    state: Res<GlobalStateResource>,
+    mut block_changes: MessageWriter<BlocksChanged>,
+) {
+    let speed = get_global_config().random_tick_speed;
+    if speed == 0 {
+        return;
+    }
+
+    // Players near each other share most of their chunks, which should only be ticked once
+    let mut chunks = HashSet::new();
+    for (chunk_receiver, dimension) in players.iter() {
+        chunks.extend(
+            chunk_receiver
+                .loaded
+                .iter()
+                .map(|(x, z)| (dimension.0, ChunkPos::new(*x, *z))),
+        );
+    }
+    if chunks.is_empty() {
+        return;
+    }
+
+    let changed = state
+        .0
+        .world
+        .random_tick_chunks(&state.0.thread_pool, chunks, speed);
+    block_changes.write_batch(
+        changed
+            .into_iter()
+            .map(|(dimension, positions)| BlocksChanged {
+                positions,
+                dimension,
+            }),
+    );
+}
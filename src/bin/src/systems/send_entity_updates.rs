@@ -1,4 +1,6 @@
 use bevy_ecs::prelude::{MessageReader, Query};
+use ferrumc_components::player::dimension::DimensionComponent;
+use ferrumc_core::chunks::chunk_receiver::ChunkReceiver;
 use ferrumc_core::identity::entity_identity::EntityIdentity;
 use ferrumc_core::transform::grounded::OnGround;
 use ferrumc_core::transform::position::Position;
@@ -20,8 +22,9 @@ pub fn handle(
         &mut LastSyncedPosition,
         &EntityIdentity,
         &OnGround,
+        &DimensionComponent,
     )>,
-    mut conn_query: Query<&StreamWriter>,
+    conn_query: Query<(&StreamWriter, &ChunkReceiver, &DimensionComponent)>,
     mut reader: MessageReader<SendEntityUpdate>,
 ) {
     let mut entities_to_update = vec![];
@@ -30,7 +33,16 @@ pub fn handle(
     }
     entities_to_update.dedup();
     for entity in entities_to_update {
-        if let Ok((pos, vel, rot, mut last_synced, id, grounded)) = query.get_mut(entity) {
+        if let Ok((pos, vel, rot, mut last_synced, id, grounded, dimension)) = query.get_mut(entity)
+        {
+            let chunk = (pos.x.floor() as i32 >> 4, pos.z.floor() as i32 >> 4);
+            // Only players that have the entity's chunk loaded can see it
+            let viewers = conn_query
+                .iter()
+                .filter(|(_, chunk_receiver, viewer_dimension)| {
+                    viewer_dimension.0 == dimension.0 && chunk_receiver.loaded.contains(&chunk)
+                })
+                .map(|(conn, _, _)| conn);
             if last_synced.0.distance(pos.coords) > 8.0 {
                 let packet = TeleportEntityPacket {
                     entity_id: id.entity_id.into(),
@@ -44,8 +56,7 @@ pub fn handle(
                     pitch: rot.pitch,
                     on_ground: grounded.0,
                 };
-                for conn in conn_query.iter_mut() {
-                    // TODO: Only send if the client is tracking this entity
+                for conn in viewers {
                     if let Err(e) = conn.send_packet_ref(&packet) {
                         warn!(
                             "Failed to send teleport packet for entity {:?}: {:?}",
@@ -71,8 +82,7 @@ pub fn handle(
                     pitch: NetAngle::from_degrees(rot.pitch.into()),
                     on_ground: grounded.0,
                 };
-                for conn in conn_query.iter_mut() {
-                    // TODO: Only send if the client is tracking this entity
+                for conn in viewers {
                     if let Err(e) = conn.send_packet_ref(&packet) {
                         warn!(
                             "Failed to send entity update packet for entity {:?}: {:?}",
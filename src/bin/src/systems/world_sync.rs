@@ -1,12 +1,64 @@
-use bevy_ecs::prelude::{Res, ResMut};
+use crate::systems::connection_killer::PlayerCacheQuery;
+use bevy_ecs::prelude::{Query, Res, ResMut};
 use ferrumc_core::chunks::world_sync_tracker::WorldSyncTracker;
+use ferrumc_state::player_cache::OfflinePlayerData;
 use ferrumc_state::GlobalStateResource;
+use tracing::error;
 
-pub fn sync_world(state: Res<GlobalStateResource>, mut last_synced: ResMut<WorldSyncTracker>) {
+pub fn sync_world(
+    state: Res<GlobalStateResource>,
+    mut last_synced: ResMut<WorldSyncTracker>,
+    players: Query<PlayerCacheQuery>,
+) {
     if state.0.shut_down.load(std::sync::atomic::Ordering::Relaxed) {
         return;
     }
 
+    // Save online players so a crash doesn't lose everything since they joined. This happens
+    // here rather than on the thread pool, otherwise it could land after the save made when the
+    // player disconnects and put back older data
+    for (
+        entity,
+        _conn,
+        identity,
+        abilities,
+        gamemode,
+        dimension,
+        pos,
+        rot,
+        inv,
+        health,
+        hunger,
+        exp,
+        echest,
+        effects,
+    ) in players.iter()
+    {
+        // Players on their way out are saved when they're disconnected
+        if !state.0.players.is_connected(entity) {
+            continue;
+        }
+        let data = OfflinePlayerData {
+            abilities: *abilities,
+            gamemode: gamemode.0,
+            dimension: dimension.0,
+            position: *pos,
+            rotation: *rot,
+            inventory: inv.clone(),
+            health: *health,
+            hunger: *hunger,
+            experience: *exp,
+            ender_chest: echest.clone(),
+            active_effects: effects.clone(),
+        };
+        if let Err(e) = state.0.player_cache.persist(identity.uuid, &data) {
+            error!(
+                "Failed to save player data for {}: {}",
+                identity.username, e
+            );
+        }
+    }
+
     // Always schedule a sync; frequency is handled by the schedule period.
     let _handle = state.0.thread_pool.oneshot({
         let state = state.0.clone();
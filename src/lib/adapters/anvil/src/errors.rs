@@ -9,6 +9,8 @@ pub enum AnvilError {
     InvalidTables(PathBuf),
     #[error("Unable to read file {0}: {1}")]
     UnableToReadFile(PathBuf, std::io::Error),
+    #[error("Unable to write file {0}: {1}")]
+    UnableToWriteFile(PathBuf, std::io::Error),
     #[error("Unable to map file {0}: {1}")]
     UnableToMapFile(PathBuf, std::io::Error),
     #[error("Invalid offset or size")]
@@ -19,6 +21,10 @@ pub enum AnvilError {
     MissingChecksum,
     #[error("Cannot decompress data (probably invalid)")]
     DecompressionError,
+    #[error("Cannot compress data")]
+    CompressionError,
+    #[error("Chunk ({0}, {1}) is too large to fit in a region file")]
+    ChunkTooLarge(u32, u32),
 }
 
 impl From<lzzzz::Error> for AnvilError {
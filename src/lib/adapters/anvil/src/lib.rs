@@ -1,4 +1,5 @@
 pub mod errors;
+pub mod writer;
 
 use crate::errors::AnvilError;
 use memmap2::Mmap;
@@ -206,11 +207,10 @@ impl LoadedAnvilFile {
     ///
     /// The x and z coordinates are the chunk coordinates
     ///
-    /// This function will return the decompressed chunk data, or an error if the data reading
-    /// fails for any reason.
+    /// This function will return the decompressed chunk data, `None` if the chunk isn't present
+    /// in the file, or an error if the data reading fails for any reason.
     pub fn get_chunk(&self, x: u32, z: u32) -> Result<Option<Vec<u8>>, AnvilError> {
-        let index = u64::from(4 * ((x & 31) + (z & 31) * 32));
-        let base_index = index as usize * 4;
+        let base_index = 4 * ((x & 31) + (z & 31) * 32) as usize;
         let chunk_data = [
             u32::from(self.table[base_index]),
             u32::from(self.table[base_index + 1]),
@@ -219,6 +219,9 @@ impl LoadedAnvilFile {
         ];
         let location =
             (chunk_data[0] << 24) | (chunk_data[1] << 16) | (chunk_data[2] << 8) | chunk_data[3];
+        if location == 0 {
+            return Ok(None);
+        }
         self.get_chunk_from_location(location)
     }
 }
@@ -0,0 +1,150 @@
+use crate::errors::AnvilError;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use yazi::CompressionLevel;
+
+const SECTOR_SIZE: usize = 4096;
+/// The location table stores the sector count of a chunk in a single byte.
+const MAX_CHUNK_SECTORS: usize = 255;
+const ZLIB_COMPRESSION: u8 = 2;
+
+/// Builds a region file in memory, one chunk at a time
+///
+/// Chunks are stored zlib compressed, which is what vanilla writes by default. Chunks that don't
+/// fit in 255 sectors (~1MB compressed) are rejected; vanilla would move those to an external
+/// `.mcc` file, which we don't support.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::PathBuf;
+/// use ferrumc_anvil::writer::AnvilFileWriter;
+///
+/// let mut writer = AnvilFileWriter::new();
+/// writer.insert_chunk(0, 0, &[10, 0, 0, 0]).unwrap();
+/// writer.write(PathBuf::from("r.0.0.mca")).unwrap();
+/// ```
+pub struct AnvilFileWriter {
+    chunks: Vec<Option<Vec<u8>>>,
+}
+
+impl Default for AnvilFileWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AnvilFileWriter {
+    pub fn new() -> Self {
+        Self {
+            chunks: vec![None; 1024],
+        }
+    }
+
+    /// Compress and store the NBT data of a chunk
+    ///
+    /// The x and z coordinates are the chunk coordinates, only the lowest 5 bits of each are used.
+    /// Inserting a chunk where one is already stored replaces it.
+    pub fn insert_chunk(&mut self, x: u32, z: u32, data: &[u8]) -> Result<(), AnvilError> {
+        let compressed = yazi::compress(data, yazi::Format::Zlib, CompressionLevel::Default)
+            .map_err(|_| AnvilError::CompressionError)?;
+
+        // 4 bytes of length followed by the compression type, the length includes the latter
+        let mut payload = Vec::with_capacity(compressed.len() + 5);
+        payload.extend_from_slice(&(compressed.len() as u32 + 1).to_be_bytes());
+        payload.push(ZLIB_COMPRESSION);
+        payload.extend_from_slice(&compressed);
+
+        if payload.len().div_ceil(SECTOR_SIZE) > MAX_CHUNK_SECTORS {
+            return Err(AnvilError::ChunkTooLarge(x & 31, z & 31));
+        }
+
+        self.chunks[((x & 31) + (z & 31) * 32) as usize] = Some(payload);
+        Ok(())
+    }
+
+    /// Whether no chunks have been inserted yet
+    pub fn is_empty(&self) -> bool {
+        self.chunks.iter().all(Option::is_none)
+    }
+
+    /// Lay out the location and timestamp tables followed by the chunk data, each chunk padded
+    /// to a whole number of sectors
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as u32)
+            .unwrap_or_default();
+
+        let mut header = vec![0u8; SECTOR_SIZE * 2];
+        let mut body = Vec::new();
+        // The first two sectors are taken up by the tables
+        let mut next_sector = 2;
+
+        for (index, payload) in self.chunks.iter().enumerate() {
+            let Some(payload) = payload else {
+                continue;
+            };
+            let sectors = payload.len().div_ceil(SECTOR_SIZE);
+            let location = ((next_sector as u32) << 8) | sectors as u32;
+            header[index * 4..index * 4 + 4].copy_from_slice(&location.to_be_bytes());
+            header[SECTOR_SIZE + index * 4..SECTOR_SIZE + index * 4 + 4]
+                .copy_from_slice(&timestamp.to_be_bytes());
+
+            body.extend_from_slice(payload);
+            body.resize(body.len().next_multiple_of(SECTOR_SIZE), 0);
+            next_sector += sectors;
+        }
+
+        header.extend_from_slice(&body);
+        header
+    }
+
+    /// Write the region file to disk, replacing the file if it exists
+    pub fn write(&self, file_path: PathBuf) -> Result<(), AnvilError> {
+        std::fs::write(&file_path, self.to_bytes())
+            .map_err(|e| AnvilError::UnableToWriteFile(file_path, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load_anvil_file;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_written_chunks_read_back() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("r.0.0.mca");
+
+        let small = vec![7u8; 100];
+        // Big enough to need more than one sector even after compression
+        let large: Vec<u8> = (0..20_000u32).map(|i| (i * 7919 % 251) as u8).collect();
+
+        let mut writer = AnvilFileWriter::new();
+        writer.insert_chunk(0, 0, &small).unwrap();
+        writer.insert_chunk(31, 2, &large).unwrap();
+        writer.insert_chunk(5, 5, &[1, 2, 3]).unwrap();
+        writer.insert_chunk(5, 5, &small).unwrap();
+        writer.write(file_path.clone()).unwrap();
+
+        let loaded_file = load_anvil_file(file_path).unwrap();
+        assert_eq!(loaded_file.get_locations().len(), 3);
+        assert_eq!(loaded_file.get_chunk(0, 0).unwrap(), Some(small.clone()));
+        assert_eq!(loaded_file.get_chunk(31, 2).unwrap(), Some(large));
+        assert_eq!(loaded_file.get_chunk(5, 5).unwrap(), Some(small));
+    }
+
+    #[test]
+    fn test_file_is_sector_aligned() {
+        let mut writer = AnvilFileWriter::new();
+        assert!(writer.is_empty());
+        writer.insert_chunk(3, 4, &[0u8; 10]).unwrap();
+        assert!(!writer.is_empty());
+        let bytes = writer.to_bytes();
+        assert_eq!(bytes.len(), SECTOR_SIZE * 3);
+        let index = (3 + 4 * 32) * 4;
+        assert_eq!(&bytes[index..index + 4], &[0, 0, 2, 1]);
+    }
+}
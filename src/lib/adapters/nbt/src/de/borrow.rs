@@ -160,6 +160,12 @@ impl<'a> NbtTape<'a> {
         self.parse_tag();
     }
 
+    /// A fresh tape over the same data, for re-reading elements (such as lists) that were only
+    /// recorded by position.
+    pub(crate) fn fork(&self) -> NbtTape<'a> {
+        NbtTape::new(self.data)
+    }
+
     fn parse_tag(&mut self) {
         let tag = NbtTag::from(self.read_byte());
         if tag != NbtTag::Compound {
@@ -3,6 +3,7 @@
 pub mod de;
 pub mod errors;
 mod nbt;
+mod raw;
 pub mod ser;
 
 pub type Result<T> = std::result::Result<T, NBTError>;
@@ -11,6 +12,7 @@ pub use de::borrow::{NbtTape, NbtTapeElement};
 pub use de::converter::FromNbt;
 pub use errors::NBTError;
 pub use nbt::NBT;
+pub use raw::RawCompound;
 pub use ser::{NBTSerializable, NBTSerializeOptions};
 
 pub use tokio;
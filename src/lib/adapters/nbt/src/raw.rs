@@ -0,0 +1,237 @@
+use crate::de::borrow::NbtTag;
+use crate::Result;
+use crate::{FromNbt, NBTError, NBTSerializable, NBTSerializeOptions, NbtTape, NbtTapeElement};
+use std::io::Write;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// A compound kept in its serialized form.
+///
+/// Useful for data that has to be stored and passed on intact, but is only partially understood,
+/// such as block entities. Individual tags can still be read with [`RawCompound::get`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawCompound {
+    /// The named tags of the compound followed by its end tag.
+    payload: Vec<u8>,
+}
+
+impl Default for RawCompound {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RawCompound {
+    /// An empty compound.
+    pub fn new() -> Self {
+        Self {
+            payload: vec![NbtTag::End as u8],
+        }
+    }
+
+    /// Copies a compound off a tape, leaving out the tags with the given names.
+    pub fn from_nbt_excluding<'a>(
+        tapes: &NbtTape<'a>,
+        element: &NbtTapeElement<'a>,
+        excluded: &[&str],
+    ) -> Result<Self> {
+        let compound = element.as_compound().ok_or(NBTError::TypeMismatch {
+            expected: "Compound",
+            found: element.nbt_type(),
+        })?;
+        let mut tape = tapes.fork();
+        let mut payload = Vec::new();
+        for (name, value) in compound {
+            if excluded.contains(name) {
+                continue;
+            }
+            payload.push(value.nbt_id());
+            name.serialize(&mut payload, &NBTSerializeOptions::None);
+            value
+                .serialize_as_network(&mut tape, &mut payload, &NBTSerializeOptions::None)
+                .map_err(|_| NBTError::InvalidNBTData)?;
+        }
+        payload.push(NbtTag::End as u8);
+        Ok(Self { payload })
+    }
+
+    /// Takes a compound in the network format, which is the tag id followed by the payload
+    /// without a name. The payload itself isn't validated.
+    pub fn from_network_bytes(bytes: &[u8]) -> Result<Self> {
+        match bytes.split_first() {
+            Some((&id, payload)) if id == NbtTag::Compound as u8 && !payload.is_empty() => {
+                Ok(Self {
+                    payload: payload.to_vec(),
+                })
+            }
+            Some((&id, _)) if id != NbtTag::Compound as u8 => {
+                Err(NBTError::InvalidRootCompound(id))
+            }
+            _ => Err(NBTError::UnexpectedEndOfData),
+        }
+    }
+
+    pub fn to_network_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.payload.len() + 1);
+        self.serialize(&mut buf, &NBTSerializeOptions::Network);
+        buf
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.payload.len() == 1
+    }
+
+    /// Reads a single tag of the compound. Returns `None` if it's missing or of the wrong type.
+    pub fn get<T: for<'a> FromNbt<'a>>(&self, key: &str) -> Option<T> {
+        let mut data = Vec::with_capacity(self.payload.len() + 3);
+        self.serialize(&mut data, &NBTSerializeOptions::WithHeader(""));
+        let mut tape = NbtTape::new(&data);
+        tape.parse();
+        let element = tape.get(key)?;
+        T::from_nbt(&tape, element).ok()
+    }
+
+    /// Sets a tag of the compound, replacing the tag with the same name if there is one.
+    pub fn insert<T: NBTSerializable>(&mut self, key: &str, value: &T) {
+        self.remove(key);
+        self.payload.pop();
+        value.serialize(&mut self.payload, &NBTSerializeOptions::WithHeader(key));
+        self.payload.push(NbtTag::End as u8);
+    }
+
+    /// Removes a tag of the compound, returning whether it was there.
+    pub fn remove(&mut self, key: &str) -> bool {
+        let mut data = Vec::with_capacity(self.payload.len() + 3);
+        self.serialize(&mut data, &NBTSerializeOptions::WithHeader(""));
+        let mut tape = NbtTape::new(&data);
+        tape.parse();
+        let Some((_, root)) = tape.root.as_ref() else {
+            return false;
+        };
+        if root.get(key).is_none() {
+            return false;
+        }
+        let Ok(removed) = Self::from_nbt_excluding(&tape, root, &[key]) else {
+            return false;
+        };
+        *self = removed;
+        true
+    }
+
+    /// The tags without the end tag, so they can be merged into another compound.
+    fn fields(&self) -> &[u8] {
+        &self.payload[..self.payload.len() - 1]
+    }
+}
+
+impl<'a> FromNbt<'a> for RawCompound {
+    fn from_nbt(tapes: &NbtTape<'a>, element: &NbtTapeElement<'a>) -> Result<Self> {
+        Self::from_nbt_excluding(tapes, element, &[])
+    }
+}
+
+impl NBTSerializable for RawCompound {
+    fn serialize<W: Write>(&self, buf: &mut W, options: &NBTSerializeOptions<'_>) {
+        match options {
+            NBTSerializeOptions::None => {}
+            NBTSerializeOptions::WithHeader(name) => {
+                Self::id().serialize(buf, &NBTSerializeOptions::None);
+                name.serialize(buf, &NBTSerializeOptions::None);
+            }
+            NBTSerializeOptions::Network => {
+                Self::id().serialize(buf, &NBTSerializeOptions::None);
+            }
+            NBTSerializeOptions::Flatten => {
+                buf.write_all(self.fields())
+                    .expect("failed to write bytes to writer");
+                return;
+            }
+        }
+        buf.write_all(&self.payload)
+            .expect("failed to write bytes to writer");
+    }
+
+    async fn serialize_async<W: AsyncWrite + Unpin>(
+        &self,
+        buf: &mut W,
+        options: &NBTSerializeOptions<'_>,
+    ) {
+        let mut data = Vec::with_capacity(self.payload.len() + 3);
+        self.serialize(&mut data, options);
+        buf.write_all(&data)
+            .await
+            .expect("failed to write bytes to writer");
+    }
+
+    fn id() -> u8 {
+        NbtTag::Compound as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_raw_compound_keeps_nested_tags() {
+        fn tag(buf: &mut Vec<u8>, tag: NbtTag, name: &str) {
+            buf.push(tag as u8);
+            name.serialize(buf, &NBTSerializeOptions::None);
+        }
+
+        // {Items: [{Slot: 3}, {Slot: 7}], id: "minecraft:chest"}
+        let mut data = Vec::new();
+        tag(&mut data, NbtTag::Compound, "");
+        tag(&mut data, NbtTag::List, "Items");
+        data.push(NbtTag::Compound as u8);
+        2i32.serialize(&mut data, &NBTSerializeOptions::None);
+        for slot in [3, 7] {
+            tag(&mut data, NbtTag::Int, "Slot");
+            slot.serialize(&mut data, &NBTSerializeOptions::None);
+            data.push(NbtTag::End as u8);
+        }
+        tag(&mut data, NbtTag::String, "id");
+        "minecraft:chest".serialize(&mut data, &NBTSerializeOptions::None);
+        data.push(NbtTag::End as u8);
+
+        let mut tape = NbtTape::new(&data);
+        tape.parse();
+        let root = tape.root.as_ref().map(|(_, root)| root).unwrap();
+        let raw = RawCompound::from_nbt_excluding(&tape, root, &["id"]).unwrap();
+
+        assert_eq!(raw.get::<String>("id"), None);
+        let slots: Vec<HashMap<String, i32>> = raw.get("Items").unwrap();
+        assert_eq!(slots[0]["Slot"], 3);
+        assert_eq!(slots[1]["Slot"], 7);
+
+        let round_tripped = RawCompound::from_network_bytes(&raw.to_network_bytes()).unwrap();
+        assert_eq!(raw, round_tripped);
+        assert!(!raw.is_empty());
+        assert!(RawCompound::new().is_empty());
+    }
+
+    #[test]
+    fn test_raw_compound_insert_and_remove() {
+        let mut raw = RawCompound::new();
+        raw.insert("color", &"black");
+        raw.insert("lines", &vec!["a".to_string(), "b".to_string()]);
+        raw.insert("color", &"red");
+
+        let mut nested = RawCompound::new();
+        nested.insert("glowing", &1i8);
+        raw.insert("text", &nested);
+
+        assert_eq!(raw.get::<String>("color").as_deref(), Some("red"));
+        assert_eq!(
+            raw.get::<Vec<String>>("lines"),
+            Some(vec!["a".to_string(), "b".to_string()])
+        );
+        let text: RawCompound = raw.get("text").unwrap();
+        assert_eq!(text.get::<i8>("glowing"), Some(1));
+
+        assert!(raw.remove("lines"));
+        assert!(!raw.remove("lines"));
+        assert_eq!(raw.get::<Vec<String>>("lines"), None);
+        assert_eq!(raw.get::<String>("color").as_deref(), Some("red"));
+    }
+}
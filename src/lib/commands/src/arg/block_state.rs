@@ -0,0 +1,40 @@
+use crate::{
+    arg::{utils::parser_error, CommandArgument, ParserResult},
+    CommandContext,
+};
+
+use super::PrimitiveArgument;
+use ferrumc_world::block_state_id::BlockStateId;
+use ferrumc_world::region_edit::BlockMask;
+use ferrumc_world::vanilla_chunk_format::BlockData;
+
+impl CommandArgument for BlockStateId {
+    fn parse(ctx: &mut CommandContext) -> ParserResult<Self> {
+        let str = ctx.input.read_string();
+
+        BlockStateId::find(&BlockData::from_state_string(&str))
+            .ok_or_else(|| parser_error(&format!("invalid block state: {str}")))
+    }
+
+    fn primitive() -> PrimitiveArgument {
+        PrimitiveArgument::block_state()
+    }
+}
+
+/// Parses a block with some of its properties into a mask of every state of the block that has
+/// them, so `oak_log` matches logs of any axis.
+impl CommandArgument for BlockMask {
+    fn parse(ctx: &mut CommandContext) -> ParserResult<Self> {
+        let str = ctx.input.read_string();
+
+        let states = BlockStateId::matching(&BlockData::from_state_string(&str));
+        if states.is_empty() {
+            return Err(parser_error(&format!("invalid block state: {str}")));
+        }
+        Ok(BlockMask::Only(states.into_iter().collect()))
+    }
+
+    fn primitive() -> PrimitiveArgument {
+        PrimitiveArgument::block_state()
+    }
+}
@@ -0,0 +1,29 @@
+use crate::{
+    arg::{utils::parser_error, CommandArgument, ParserResult},
+    CommandContext, Suggestion,
+};
+
+use super::PrimitiveArgument;
+use ferrumc_world::dimension::Dimension;
+
+impl CommandArgument for Dimension {
+    fn parse(ctx: &mut CommandContext) -> ParserResult<Self> {
+        let str = ctx.input.read_string();
+
+        Dimension::from_identifier(&str.to_lowercase())
+            .ok_or_else(|| parser_error(&format!("invalid dimension: {str}")))
+    }
+
+    fn primitive() -> PrimitiveArgument {
+        PrimitiveArgument::dimension()
+    }
+
+    fn suggest(ctx: &mut CommandContext) -> Vec<Suggestion> {
+        ctx.input.read_string();
+
+        Dimension::ALL
+            .into_iter()
+            .map(|dimension| Suggestion::of(dimension.identifier()))
+            .collect()
+    }
+}
@@ -5,6 +5,8 @@ use primitive::PrimitiveArgument;
 
 use crate::{ctx::CommandContext, Suggestion};
 
+pub mod block_state;
+pub mod dimension;
 pub mod duration;
 pub mod gamemode;
 pub mod primitive;
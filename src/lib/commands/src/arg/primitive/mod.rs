@@ -87,6 +87,20 @@ impl PrimitiveArgument {
             flags: None,
         }
     }
+
+    pub fn block_state() -> PrimitiveArgument {
+        PrimitiveArgument {
+            argument_type: PrimitiveArgumentType::BlockState,
+            flags: None,
+        }
+    }
+
+    pub fn dimension() -> PrimitiveArgument {
+        PrimitiveArgument {
+            argument_type: PrimitiveArgumentType::Dimension,
+            flags: None,
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, NetEncode)]
@@ -10,6 +10,28 @@ pub enum EffectType {
     Regeneration,
 }
 
+impl EffectType {
+    pub const ALL: [EffectType; 3] = [
+        EffectType::Speed,
+        EffectType::Poison,
+        EffectType::Regeneration,
+    ];
+
+    pub const fn identifier(self) -> &'static str {
+        match self {
+            EffectType::Speed => "minecraft:speed",
+            EffectType::Poison => "minecraft:poison",
+            EffectType::Regeneration => "minecraft:regeneration",
+        }
+    }
+
+    pub fn from_identifier(identifier: &str) -> Option<Self> {
+        EffectType::ALL
+            .into_iter()
+            .find(|effect| effect.identifier() == identifier)
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct EffectState {
     pub amplifier: u8,
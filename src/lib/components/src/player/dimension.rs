@@ -0,0 +1,6 @@
+use bevy_ecs::prelude::Component;
+use ferrumc_world::dimension::Dimension;
+
+/// The component storing the dimension a player is currently in.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct DimensionComponent(pub Dimension);
@@ -0,0 +1,21 @@
+use bevy_ecs::prelude::Component;
+use ferrumc_world::pos::BlockPos;
+use ferrumc_world::region_edit::{EditHistory, Region};
+use ferrumc_world::schematic::Schematic;
+
+/// The region editing state of a player: the corners they selected, what they copied and the
+/// edits they can undo.
+#[derive(Component, Debug, Default)]
+pub struct EditSession {
+    pub pos1: Option<BlockPos>,
+    pub pos2: Option<BlockPos>,
+    pub clipboard: Option<Schematic>,
+    pub history: EditHistory,
+}
+
+impl EditSession {
+    /// The region between the two selected corners, once both are set.
+    pub fn selection(&self) -> Option<Region> {
+        Some(Region::new(self.pos1?, self.pos2?))
+    }
+}
@@ -1,3 +1,4 @@
 pub mod cooldowns;
 pub mod digging;
 pub mod ender_chest;
+pub mod sign_editing;
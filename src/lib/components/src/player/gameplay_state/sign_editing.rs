@@ -0,0 +1,11 @@
+use bevy_ecs::prelude::Component;
+use ferrumc_world::dimension::Dimension;
+use ferrumc_world::pos::BlockPos;
+
+/// An "action component" added to a player when they are sent the editor for a sign they
+/// placed. Only that sign may be written to, and only once.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct EditingSign {
+    pub pos: BlockPos,
+    pub dimension: Dimension,
+}
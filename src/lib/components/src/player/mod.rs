@@ -1,5 +1,7 @@
 pub mod abilities;
 pub mod client_information;
+pub mod dimension;
+pub mod edit_session;
 pub mod experience;
 pub mod gamemode;
 pub mod gameplay_state;
@@ -2,8 +2,10 @@ use crate::{
     active_effects::ActiveEffects,
     health::Health,
     player::{
-        abilities::PlayerAbilities, experience::Experience, gamemode::GameModeComponent,
+        abilities::PlayerAbilities, dimension::DimensionComponent, edit_session::EditSession,
+        experience::Experience, gamemode::GameModeComponent,
         gameplay_state::ender_chest::EnderChest, hunger::Hunger, swimming::SwimmingState,
+        view_distance::ViewDistance,
     },
 };
 use bevy_ecs::prelude::Bundle;
@@ -25,10 +27,12 @@ pub struct PlayerBundle {
     pub gamemode: GameModeComponent,
 
     // Position/World
+    pub dimension: DimensionComponent,
     pub position: Position,
     pub rotation: Rotation,
     pub on_ground: OnGround,
     pub chunk_receiver: ChunkReceiver,
+    pub view_distance: ViewDistance,
 
     // Inventory
     pub inventory: Inventory,
@@ -43,4 +47,7 @@ pub struct PlayerBundle {
 
     // Movement State
     pub swimming: SwimmingState,
+
+    // World Editing
+    pub edit_session: EditSession,
 }
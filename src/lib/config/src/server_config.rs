@@ -37,6 +37,11 @@ pub fn set_global_config(config: ServerConfig) {
 /// - `whitelist`: Whether the server whitelist is enabled or not.
 /// - `chunk_render_distance`: The render distance of the chunks. This is the number of chunks that will be
 ///   loaded around the player.
+/// - `max_chunks_per_tick`: The most chunks sent to all players combined in one tick.
+/// - `max_scheduled_ticks_per_tick`: The most scheduled block and fluid ticks run in one tick.
+/// - `random_tick_speed`: How many blocks are randomly ticked per chunk section every tick, like the
+///   `randomTickSpeed` gamerule.
+/// - `max_region_volume`: The most blocks a single region edit command can select, copy or paste.
 #[derive(Debug, Deserialize, Serialize, Default)]
 pub struct ServerConfig {
     pub host: String,
@@ -52,6 +57,10 @@ pub struct ServerConfig {
     pub online_mode: bool,
     pub whitelist: bool,
     pub chunk_render_distance: u32,
+    pub max_chunks_per_tick: u32,
+    pub max_scheduled_ticks_per_tick: u32,
+    pub random_tick_speed: u32,
+    pub max_region_volume: u64,
     pub default_gamemode: String,
 }
 
@@ -60,6 +69,9 @@ pub struct ServerConfig {
 /// Fields:
 /// - `db_path`: The path to the database. This is relative to the server root path.
 /// - `verify_chunk_data`: Whether to verify chunk data when loading it from the database.
+/// - `compression`: The algorithm chunks are compressed with before they're stored (brotli,
+///   deflate, gzip, zlib or zstd).
+/// - `compression_level`: How hard the algorithm tries, see the config file for the ranges.
 /// - `map_size`: The max size of the database's memory map. Basically you need this to be big enough
 ///   to hold everything before it starts writing to disk. This isn't memory use though, it's just
 ///   how much we can map into memory if needed, so you can set this to an insane number if you want,
@@ -70,6 +82,8 @@ pub struct ServerConfig {
 pub struct DatabaseConfig {
     pub db_path: String,
     pub verify_chunk_data: bool,
+    pub compression: String,
+    pub compression_level: u32,
     pub map_size: u64,
     pub cache_ttl: u64,
     pub cache_capacity: u64,
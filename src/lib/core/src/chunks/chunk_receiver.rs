@@ -4,13 +4,25 @@ use typename::TypeName;
 
 pub const VIEW_DISTANCE: i32 = 8;
 
+/// The rate assumed until the client tells us what it can handle, same as vanilla.
+const INITIAL_CHUNKS_PER_TICK: f32 = 9.0;
+/// The bounds for the rate clients can ask for.
+const MIN_CHUNKS_PER_TICK: f32 = 0.01;
+const MAX_CHUNKS_PER_TICK: f32 = 64.0;
+/// How many batches can be on their way at once after the client acknowledged its first one.
+/// Before that, only one is sent so the rate can be measured.
+const MAX_UNACKNOWLEDGED_BATCHES: u32 = 10;
+
 #[derive(TypeName, Component)]
 pub struct ChunkReceiver {
     pub loading: VecDeque<(i32, i32)>,
-    pub dirty: VecDeque<(i32, i32)>,
     pub loaded: HashSet<(i32, i32)>,
     pub unloading: HashSet<(i32, i32)>,
+    /// How many chunks per tick the client asked for in its last Chunk Batch Received.
     pub chunks_per_tick: f32,
+    batch_quota: f32,
+    unacknowledged_batches: u32,
+    max_unacknowledged_batches: u32,
 }
 
 impl Default for ChunkReceiver {
@@ -25,8 +37,91 @@ impl ChunkReceiver {
             loading: VecDeque::new(),
             loaded: HashSet::new(),
             unloading: HashSet::new(),
-            dirty: VecDeque::new(),
-            chunks_per_tick: f32::MAX,
+            chunks_per_tick: INITIAL_CHUNKS_PER_TICK,
+            batch_quota: 0.0,
+            unacknowledged_batches: 0,
+            max_unacknowledged_batches: 1,
+        }
+    }
+
+    /// Whether there are chunks waiting to be sent.
+    pub fn has_pending(&self) -> bool {
+        !self.loading.is_empty()
+    }
+
+    /// Queues a chunk the client already has to be sent again, e.g. after its light changed.
+    /// Chunks the client doesn't have are left alone.
+    pub fn resend(&mut self, chunk: (i32, i32)) {
+        if self.loaded.contains(&chunk) && !self.loading.contains(&chunk) {
+            self.loading.push_back(chunk);
+        }
+    }
+
+    /// Advances the send quota by a tick and returns how many chunks may be sent now. Call
+    /// [`ChunkReceiver::batch_sent`] with the amount actually sent.
+    pub fn batch_quota(&mut self) -> usize {
+        if self.unacknowledged_batches >= self.max_unacknowledged_batches {
+            return 0;
+        }
+        // Unused quota only carries over up to one tick's worth, so a client that asks for less
+        // than a chunk per tick still gets one every few ticks
+        self.batch_quota =
+            (self.batch_quota + self.chunks_per_tick).min(self.chunks_per_tick.max(1.0));
+        self.batch_quota as usize
+    }
+
+    pub fn batch_sent(&mut self, size: usize) {
+        self.batch_quota -= size as f32;
+        self.unacknowledged_batches += 1;
+    }
+
+    /// Handles the client acknowledging a batch, along with the rate it wants from now on.
+    pub fn batch_acknowledged(&mut self, chunks_per_tick: f32) {
+        self.unacknowledged_batches = self.unacknowledged_batches.saturating_sub(1);
+        self.chunks_per_tick = if chunks_per_tick.is_nan() {
+            MIN_CHUNKS_PER_TICK
+        } else {
+            chunks_per_tick.clamp(MIN_CHUNKS_PER_TICK, MAX_CHUNKS_PER_TICK)
+        };
+        if self.unacknowledged_batches == 0 {
+            self.batch_quota = 1.0;
         }
+        self.max_unacknowledged_batches = MAX_UNACKNOWLEDGED_BATCHES;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batches_follow_client_rate() {
+        let mut receiver = ChunkReceiver::new();
+        assert_eq!(receiver.batch_quota(), 9);
+        receiver.batch_sent(9);
+        // Nothing more until the first batch is acknowledged
+        assert_eq!(receiver.batch_quota(), 0);
+
+        receiver.batch_acknowledged(0.5);
+        assert_eq!(receiver.batch_quota(), 1);
+        receiver.batch_sent(1);
+        // Half a chunk per tick is one every other tick
+        assert_eq!(receiver.batch_quota(), 0);
+        assert_eq!(receiver.batch_quota(), 1);
+
+        receiver.batch_acknowledged(f32::MAX);
+        assert_eq!(receiver.chunks_per_tick, MAX_CHUNKS_PER_TICK);
+        receiver.batch_acknowledged(f32::NAN);
+        assert_eq!(receiver.chunks_per_tick, MIN_CHUNKS_PER_TICK);
+    }
+
+    #[test]
+    fn test_only_loaded_chunks_are_resent() {
+        let mut receiver = ChunkReceiver::new();
+        receiver.loaded.insert((0, 0));
+        receiver.resend((0, 0));
+        receiver.resend((0, 0));
+        receiver.resend((1, 0));
+        assert_eq!(receiver.loading, [(0, 0)]);
     }
 }
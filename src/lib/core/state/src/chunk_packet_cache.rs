@@ -0,0 +1,88 @@
+use ferrumc_world::dimension::Dimension;
+use ferrumc_world::pos::ChunkPos;
+use moka::sync::Cache;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How many bytes of packets are kept at most.
+const CAPACITY: u64 = 64 * 1024 * 1024;
+/// Packets nobody asked for in this long are dropped, most of these are for old revisions that
+/// can't be asked for anymore.
+const TIME_TO_IDLE: Duration = Duration::from_secs(30);
+
+/// Identifies one encoding of a chunk. Compressed and uncompressed packets are cached apart since
+/// connections can differ in whether compression is on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ChunkPacketKey {
+    pub pos: ChunkPos,
+    pub dimension: Dimension,
+    /// See [`World::chunk_revision`](ferrumc_world::World::chunk_revision).
+    pub revision: u64,
+    pub compressed: bool,
+}
+
+/// Encoded chunk packets, ready to be sent to any connection. Players that need the same chunk
+/// share one encoding, and an edited chunk gets a new revision so its old packets are never
+/// handed out again.
+pub struct ChunkPacketCache {
+    packets: Cache<ChunkPacketKey, Arc<[u8]>>,
+}
+
+impl Default for ChunkPacketCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChunkPacketCache {
+    pub fn new() -> Self {
+        Self {
+            packets: Cache::builder()
+                .weigher(|_, packet: &Arc<[u8]>| packet.len().try_into().unwrap_or(u32::MAX))
+                .max_capacity(CAPACITY)
+                .time_to_idle(TIME_TO_IDLE)
+                .build(),
+        }
+    }
+
+    /// Returns the cached packet, or encodes and caches it. Concurrent calls for the same key
+    /// wait for the first one to finish encoding instead of doing the work again.
+    pub fn get_or_encode<E: Send + Sync + 'static>(
+        &self,
+        key: ChunkPacketKey,
+        encode: impl FnOnce() -> Result<Vec<u8>, E>,
+    ) -> Result<Arc<[u8]>, Arc<E>> {
+        self.packets.try_get_with(key, || encode().map(Arc::from))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encodes_once_per_revision() {
+        let cache = ChunkPacketCache::new();
+        let mut key = ChunkPacketKey {
+            pos: ChunkPos::new(1, 2),
+            dimension: Dimension::Overworld,
+            revision: 0,
+            compressed: true,
+        };
+        let first = cache
+            .get_or_encode(key, || Ok::<_, ()>(vec![1, 2, 3]))
+            .unwrap();
+        let second = cache
+            .get_or_encode(key, || -> Result<Vec<u8>, ()> { panic!("encoded twice") })
+            .unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        key.revision = 1;
+        let edited = cache.get_or_encode(key, || Ok::<_, ()>(vec![4])).unwrap();
+        assert_eq!(&*edited, &[4]);
+        assert!(cache.get_or_encode(key, || Err(())).is_ok());
+
+        key.revision = 2;
+        assert!(cache.get_or_encode(key, || Err(())).is_err());
+    }
+}
@@ -1,6 +1,8 @@
+pub mod chunk_packet_cache;
 pub mod player_cache;
 pub mod player_list;
 
+use crate::chunk_packet_cache::ChunkPacketCache;
 use crate::player_cache::PlayerCache;
 use crate::player_list::PlayerList;
 use bevy_ecs::prelude::Resource;
@@ -18,6 +20,7 @@ pub struct ServerState {
     pub players: PlayerList, // (UUID, Username)
     pub player_cache: PlayerCache,
     pub thread_pool: ThreadPool,
+    pub chunk_packets: ChunkPacketCache,
     pub start_time: Instant,
 }
 
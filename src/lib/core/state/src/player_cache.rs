@@ -1,5 +1,5 @@
-use dashmap::DashMap;
-use ferrumc_components::active_effects::ActiveEffects;
+use dashmap::{DashMap, DashSet};
+use ferrumc_components::active_effects::{ActiveEffects, EffectState, EffectType};
 use ferrumc_components::health::Health;
 use ferrumc_components::player::abilities::PlayerAbilities;
 use ferrumc_components::player::experience::Experience;
@@ -9,6 +9,14 @@ use ferrumc_components::player::hunger::Hunger;
 use ferrumc_core::transform::position::Position;
 use ferrumc_core::transform::rotation::Rotation;
 use ferrumc_inventories::inventory::Inventory;
+use ferrumc_inventories::item::ItemID;
+use ferrumc_inventories::slot::InventorySlot;
+use ferrumc_net_codec::net_types::var_int::VarInt;
+use ferrumc_world::dimension::Dimension;
+use ferrumc_world::errors::WorldError;
+use ferrumc_world::player_data::{StoredAbilities, StoredEffect, StoredItem, StoredPlayerData};
+use ferrumc_world::World;
+use tracing::error;
 use uuid::Uuid;
 
 /// A struct to hold all component data for an offline player.
@@ -16,6 +24,7 @@ use uuid::Uuid;
 pub struct OfflinePlayerData {
     pub abilities: PlayerAbilities,
     pub gamemode: GameMode,
+    pub dimension: Dimension,
     pub position: Position,
     pub rotation: Rotation,
     pub inventory: Inventory,
@@ -27,25 +36,240 @@ pub struct OfflinePlayerData {
 }
 
 /// The generic struct that holds all offline player data
-#[derive(Debug, Default)]
+///
+/// Data is kept in memory and backed by the world's database, players that aren't in memory are
+/// loaded from the database the first time they're looked up.
 pub struct PlayerCache {
     pub cache: DashMap<Uuid, OfflinePlayerData>,
+    /// Players whose stored data failed to load. They start over as new players, so saving them
+    /// would overwrite their real data with that.
+    unreadable: DashSet<Uuid>,
+    world: World,
 }
 
 // Helper methods
 impl PlayerCache {
+    pub fn new(world: World) -> Self {
+        Self {
+            cache: DashMap::new(),
+            unreadable: DashSet::new(),
+            world,
+        }
+    }
+
     pub fn get_and_remove(&self, uuid: &Uuid) -> Option<OfflinePlayerData> {
-        self.cache.remove(uuid).map(|(_uuid, data)| data)
+        self.cache
+            .remove(uuid)
+            .map(|(_uuid, data)| data)
+            .or_else(|| self.load(uuid))
     }
 
     pub fn get(
         &self,
         uuid: &Uuid,
     ) -> Option<impl std::ops::Deref<Target = OfflinePlayerData> + '_> {
+        if !self.cache.contains_key(uuid) {
+            let data = self.load(uuid)?;
+            self.cache.entry(*uuid).or_insert(data);
+        }
         self.cache.get(uuid)
     }
 
+    /// Caches the data in memory without saving it, see [`PlayerCache::save`].
     pub fn insert(&self, uuid: Uuid, data: OfflinePlayerData) {
         self.cache.insert(uuid, data);
     }
+
+    /// Caches the data and writes it to the database.
+    pub fn save(&self, uuid: Uuid, data: OfflinePlayerData) -> Result<(), WorldError> {
+        let res = self.persist(uuid, &data);
+        self.cache.insert(uuid, data);
+        res
+    }
+
+    /// Writes the data to the database without caching it, for players that are still online.
+    /// Fails without writing anything if the data stored for the player couldn't be read.
+    pub fn persist(&self, uuid: Uuid, data: &OfflinePlayerData) -> Result<(), WorldError> {
+        if self.unreadable.contains(&uuid) {
+            return Err(WorldError::UnreadablePlayerData(uuid.to_string()));
+        }
+        self.world.save_player_data(uuid.as_u128(), &data.into())
+    }
+
+    fn load(&self, uuid: &Uuid) -> Option<OfflinePlayerData> {
+        match self.world.load_player_data(uuid.as_u128()) {
+            Ok(data) => data.map(Into::into),
+            Err(e) => {
+                error!("Failed to load player data for {}: {}", uuid, e);
+                self.unreadable.insert(*uuid);
+                None
+            }
+        }
+    }
+}
+
+fn to_stored_items(inventory: &Inventory) -> Vec<StoredItem> {
+    inventory
+        .slots
+        .iter()
+        .enumerate()
+        .filter_map(|(slot, item)| {
+            let item = item.as_ref()?;
+            let id = item.item_id?;
+            if item.count.0 == 0 {
+                return None;
+            }
+            let components = |components: &Option<Vec<VarInt>>| {
+                components
+                    .iter()
+                    .flatten()
+                    .map(|component| component.0)
+                    .collect()
+            };
+            Some(StoredItem {
+                slot: slot as u16,
+                item: id.0 .0,
+                count: item.count.0,
+                components_to_add: components(&item.components_to_add),
+                components_to_remove: components(&item.components_to_remove),
+                enchantments: item
+                    .enchantments
+                    .iter()
+                    .flatten()
+                    .map(|(id, level)| (id.0, level.0))
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+fn from_stored_items(items: &[StoredItem], size: usize) -> Inventory {
+    let mut inventory = Inventory::new(size);
+    for item in items {
+        let Some(slot) = inventory.slots.get_mut(item.slot as usize) else {
+            continue;
+        };
+        let components =
+            |components: &[i32]| Some(components.iter().copied().map(VarInt::new).collect());
+        *slot = Some(InventorySlot {
+            count: VarInt::new(item.count),
+            item_id: Some(ItemID::new(item.item)),
+            components_to_add_count: Some(VarInt::new(item.components_to_add.len() as i32)),
+            components_to_remove_count: Some(VarInt::new(item.components_to_remove.len() as i32)),
+            components_to_add: components(&item.components_to_add),
+            components_to_remove: components(&item.components_to_remove),
+            enchantments: (!item.enchantments.is_empty()).then(|| {
+                item.enchantments
+                    .iter()
+                    .map(|&(id, level)| (VarInt::new(id), VarInt::new(level)))
+                    .collect()
+            }),
+        });
+    }
+    inventory
+}
+
+impl From<&OfflinePlayerData> for StoredPlayerData {
+    fn from(data: &OfflinePlayerData) -> Self {
+        let abilities = &data.abilities;
+        StoredPlayerData {
+            game_mode: data.gamemode as u8,
+            dimension: data.dimension,
+            position: (data.position.x, data.position.y, data.position.z),
+            rotation: (data.rotation.yaw, data.rotation.pitch),
+            abilities: StoredAbilities {
+                invulnerable: abilities.invulnerable,
+                flying: abilities.flying,
+                may_fly: abilities.may_fly,
+                creative_mode: abilities.creative_mode,
+                may_build: abilities.may_build,
+                flying_speed: abilities.flying_speed,
+                walking_speed: abilities.walking_speed,
+            },
+            health: data.health.current,
+            max_health: data.health.max,
+            food_level: data.hunger.level,
+            saturation: data.hunger.saturation,
+            exhaustion: data.hunger.exhaustion,
+            xp_progress: data.experience.progress,
+            xp_level: data.experience.level,
+            xp_total: data.experience.total_xp,
+            inventory: to_stored_items(&data.inventory),
+            ender_chest: to_stored_items(&data.ender_chest.0),
+            effects: data
+                .active_effects
+                .effects
+                .iter()
+                .map(|(effect, state)| StoredEffect {
+                    id: effect.identifier().to_string(),
+                    amplifier: state.amplifier,
+                    duration_ticks: state.duration_ticks,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<StoredPlayerData> for OfflinePlayerData {
+    fn from(data: StoredPlayerData) -> Self {
+        let gamemode = match data.game_mode {
+            1 => GameMode::Creative,
+            2 => GameMode::Adventure,
+            3 => GameMode::Spectator,
+            _ => GameMode::Survival,
+        };
+        let abilities = data.abilities;
+        let (x, y, z) = data.position;
+        let (yaw, pitch) = data.rotation;
+        OfflinePlayerData {
+            abilities: PlayerAbilities {
+                invulnerable: abilities.invulnerable,
+                flying: abilities.flying,
+                may_fly: abilities.may_fly,
+                creative_mode: abilities.creative_mode,
+                may_build: abilities.may_build,
+                flying_speed: abilities.flying_speed,
+                walking_speed: abilities.walking_speed,
+            },
+            gamemode,
+            dimension: data.dimension,
+            position: Position::new(x, y, z),
+            rotation: Rotation::new(yaw, pitch),
+            inventory: from_stored_items(&data.inventory, Inventory::DEFAULT_PLAYER_SIZE),
+            health: Health {
+                current: data.health,
+                max: data.max_health,
+            },
+            hunger: Hunger {
+                level: data.food_level,
+                saturation: data.saturation,
+                exhaustion: data.exhaustion,
+            },
+            experience: Experience {
+                progress: data.xp_progress,
+                level: data.xp_level,
+                total_xp: data.xp_total,
+            },
+            ender_chest: EnderChest(from_stored_items(
+                &data.ender_chest,
+                EnderChest::ENDERCHEST_SIZE,
+            )),
+            active_effects: ActiveEffects {
+                effects: data
+                    .effects
+                    .iter()
+                    .filter_map(|effect| {
+                        let effect_type = EffectType::from_identifier(&effect.id)?;
+                        Some((
+                            effect_type,
+                            EffectState {
+                                amplifier: effect.amplifier,
+                                duration_ticks: effect.duration_ticks,
+                            },
+                        ))
+                    })
+                    .collect(),
+            },
+        }
+    }
 }
@@ -92,10 +92,13 @@ pub(crate) fn build() -> TokenStream {
             .expect("Failed to parse biome.json");
 
     let mut constants = TokenStream::new();
+    let mut type_from_id_arms = TokenStream::new();
     let mut type_from_name = TokenStream::new();
 
-    for (name, biome) in biomes.iter() {
+    // Biomes are synced to clients sorted by name, so their position is their network id
+    for (id, (name, biome)) in biomes.iter().enumerate() {
         let const_ident = format_ident!("{}", name.to_shouty_snake_case());
+        let id_lit = LitInt::new(&id.to_string(), Span::call_site());
 
         let has_precipitation = LitBool::new(biome.has_precipitation, Span::call_site());
         let temperature = LitFloat::new(&format!("{:.1}", biome.temperature), Span::call_site());
@@ -135,6 +138,7 @@ pub(crate) fn build() -> TokenStream {
 
         constants.extend(quote! {
             pub const #const_ident: Biome = Biome {
+                id: #id_lit,
                 name: #name,
                 has_precipitation: #has_precipitation,
                 temperature: #temperature,
@@ -149,6 +153,10 @@ pub(crate) fn build() -> TokenStream {
             };
         });
 
+        type_from_id_arms.extend(quote! {
+            #id_lit => Some(&Self::#const_ident),
+        });
+
         type_from_name.extend(quote! {
             #name => Some(&Self::#const_ident),
         });
@@ -158,6 +166,7 @@ pub(crate) fn build() -> TokenStream {
 
         #[derive(Debug, Clone, Copy, PartialEq)]
         pub struct Biome {
+            pub id: u16,
             pub name: &'static str,
             pub has_precipitation: bool,
             pub temperature: f64,
@@ -174,6 +183,14 @@ pub(crate) fn build() -> TokenStream {
         impl Biome {
             #constants
 
+            #[doc = r" Try to get a `Biome` by its network id."]
+            pub const fn from_id(id: u16) -> Option<&'static Self> {
+                match id {
+                    #type_from_id_arms
+                    _ => None
+                }
+            }
+
             #[doc = r" Try to parse a `Biome` from a resource location string."]
             pub fn from_name(name: &str) -> Option<&'static Self> {
                 let name = name.strip_prefix("minecraft:").unwrap_or(name);
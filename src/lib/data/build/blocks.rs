@@ -1,5 +1,9 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use crate::loot;
 
 #[derive(Debug, serde::Deserialize)]
 #[allow(dead_code)]
@@ -63,6 +67,7 @@ fn format_float(f: f32) -> String {
 
 pub fn build() -> Result<(), Box<dyn std::error::Error>> {
     println!("cargo:rerun-if-changed=../../../assets/extracted/blocks.json");
+    println!("cargo:rerun-if-changed=../../../assets/data/block_loot_tables.json");
 
     let out_dir = std::env::var("OUT_DIR")?;
     let blocks_dir = PathBuf::from(out_dir.clone()).join("blocks");
@@ -72,6 +77,10 @@ pub fn build() -> Result<(), Box<dyn std::error::Error>> {
 
     let json_content = fs::read_to_string("../../../assets/extracted/blocks.json")?;
     let data: BlockData = serde_json::from_str(&json_content)?;
+    // Vanilla's block loot tables, by block ID. Blocks that never drop anything have none
+    let loot_tables: HashMap<String, serde_json::Value> = serde_json::from_str(
+        &fs::read_to_string("../../../assets/data/block_loot_tables.json")?,
+    )?;
 
     // Create types.rs
     let mut types_content = String::new();
@@ -90,6 +99,8 @@ pub fn build() -> Result<(), Box<dyn std::error::Error>> {
     types_content.push_str("    pub jump_velocity_multiplier: f32,\n");
     types_content.push_str("    pub luminance: u32,\n");
     types_content.push_str("    pub item_id: u32,\n");
+    types_content.push_str("    pub default_state_id: u32,\n");
+    types_content.push_str("    pub loot_table: Option<&'static super::loot::LootTable>,\n");
     types_content.push_str("}\n\n");
 
     types_content.push_str("#[derive(Debug, Clone, Copy)]\n");
@@ -112,6 +123,7 @@ pub fn build() -> Result<(), Box<dyn std::error::Error>> {
     types_content.push_str("}\n\n");
 
     fs::write(blocks_dir.join("types.rs"), types_content)?;
+    fs::write(blocks_dir.join("loot.rs"), loot::LOOT_TYPES)?;
 
     // Create shapes.rs
     let mut shapes_content = String::new();
@@ -130,9 +142,14 @@ pub fn build() -> Result<(), Box<dyn std::error::Error>> {
     for block in &data.blocks {
         let sanitized_name = sanitize_name(&block.name);
         let file_name = format!("{}.rs", sanitized_name);
+        let loot_table = loot_tables.get(&format!("minecraft:{}", sanitized_name));
 
         let mut content = String::new();
-        content.push_str("use super::types::{Block, BlockState};\n\n");
+        content.push_str("use super::types::{Block, BlockState};\n");
+        if loot_table.is_some() {
+            content.push_str("use super::loot::*;\n");
+        }
+        content.push('\n');
 
         // Block constant
         content.push_str(&format!(
@@ -170,6 +187,17 @@ pub fn build() -> Result<(), Box<dyn std::error::Error>> {
         let first_state = &block.states[0];
         content.push_str(&format!("    luminance: {},\n", first_state.luminance));
         content.push_str(&format!("    item_id: {},\n", block.item_id));
+        content.push_str(&format!(
+            "    default_state_id: {},\n",
+            block.default_state_id
+        ));
+        match loot_table {
+            Some(table) => content.push_str(&format!(
+                "    loot_table: Some(&{}),\n",
+                loot::table(table).map_err(|e| format!("{}: {}", block.name, e))?
+            )),
+            None => content.push_str("    loot_table: None,\n"),
+        }
         content.push_str("};\n\n");
 
         // States
@@ -218,10 +246,14 @@ pub fn build() -> Result<(), Box<dyn std::error::Error>> {
         fs::write(blocks_dir.join(&file_name), content)?;
     }
 
+    build_families(&blocks_dir)?;
+
     // Create mod.rs
     let mut mod_content = String::new();
     mod_content.push_str("pub mod types;\n");
-    mod_content.push_str("pub mod shapes;\n\n");
+    mod_content.push_str("pub mod shapes;\n");
+    mod_content.push_str("pub mod families;\n");
+    mod_content.push_str("pub mod loot;\n\n");
 
     // Add individual block modules
     for block in &data.blocks {
@@ -258,6 +290,11 @@ pub fn build() -> Result<(), Box<dyn std::error::Error>> {
     // Re-export types and lookup functions
     mod_content.push_str("// Re-export types and lookup functions\n");
     mod_content.push_str("pub use types::{Block, BlockState, Shape};\n");
+    mod_content.push_str("pub use families::{BlockFamily, BlockProperty};\n");
+    mod_content.push_str(
+        "pub use loot::{BonusFormula, LootCondition, LootEntry, LootEntryKind, LootFunction, \
+         LootFunctionKind, LootPool, LootTable, NumberProvider};\n",
+    );
     mod_content.push_str("pub use shapes::SHAPES;\n\n");
 
     // Lookup functions
@@ -299,3 +336,173 @@ pub mod blocks;"#,
 
     Ok(())
 }
+
+#[derive(Debug, serde::Deserialize)]
+pub struct StateEntry {
+    name: String,
+    #[serde(default)]
+    default: bool,
+    properties: Option<BTreeMap<String, String>>,
+}
+
+const FAMILY_TYPES: &str = r#"/// A block and all of its states, which have consecutive IDs.
+#[derive(Debug)]
+pub struct BlockFamily {
+    pub name: &'static str,
+    pub first_state: u32,
+    pub state_count: u32,
+    pub default_state: u32,
+    /// Slowest changing first, the order vanilla declares them in.
+    pub properties: &'static [BlockProperty],
+}
+
+/// A property of a block's states, like `facing` or `powered`.
+#[derive(Debug)]
+pub struct BlockProperty {
+    pub name: &'static str,
+    /// Every value the property can have, in the order vanilla lists them.
+    pub values: &'static [&'static str],
+    /// How far apart the IDs of two states are when they differ by one step of this property.
+    pub stride: u32,
+}
+
+impl BlockFamily {
+    /// The block a state ID belongs to.
+    pub fn of_state(state: u32) -> Option<&'static BlockFamily> {
+        STATE_FAMILIES
+            .get(state as usize)
+            .map(|family| &BLOCK_FAMILIES[*family as usize])
+    }
+
+    /// Looks a block up by name, with or without the `minecraft:` prefix.
+    pub fn by_name(name: &str) -> Option<&'static BlockFamily> {
+        let name = name.strip_prefix("minecraft:").unwrap_or(name);
+        family_index(name).map(|family| &BLOCK_FAMILIES[family])
+    }
+
+    pub fn property(&self, name: &str) -> Option<&'static BlockProperty> {
+        self.properties.iter().find(|property| property.name == name)
+    }
+
+    /// Every state ID of the block.
+    pub fn states(&self) -> std::ops::Range<u32> {
+        self.first_state..self.first_state + self.state_count
+    }
+
+    /// The index into `property.values` of the value a state of this block has.
+    pub fn value_index(&self, state: u32, property: &BlockProperty) -> usize {
+        ((state - self.first_state) / property.stride) as usize % property.values.len()
+    }
+}
+
+"#;
+
+/// Groups the block states in `assets/data/blockstates.json` by block, and works out each
+/// property's stride from the order vanilla hands out state IDs in.
+fn build_families(blocks_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=../../../assets/data/blockstates.json");
+
+    let json_content = fs::read_to_string("../../../assets/data/blockstates.json")?;
+    let entries: HashMap<String, StateEntry> = serde_json::from_str(&json_content)?;
+    let mut states: Vec<(u32, StateEntry)> = entries
+        .into_iter()
+        .map(|(id, entry)| Ok((id.parse()?, entry)))
+        .collect::<Result<_, std::num::ParseIntError>>()?;
+    states.sort_by_key(|(id, _)| *id);
+
+    let mut families: Vec<&[(u32, StateEntry)]> = Vec::new();
+    let mut rest = states.as_slice();
+    while let Some((_, first)) = rest.first() {
+        let len = rest
+            .iter()
+            .position(|(_, entry)| entry.name != first.name)
+            .unwrap_or(rest.len());
+        let (family, tail) = rest.split_at(len);
+        families.push(family);
+        rest = tail;
+    }
+
+    let mut content = String::from(FAMILY_TYPES);
+    content.push_str("pub const BLOCK_FAMILIES: &[BlockFamily] = &[\n");
+    let mut name_arms = String::new();
+    let mut state_families = String::new();
+    for (index, family) in families.iter().enumerate() {
+        let (first_state, base) = &family[0];
+        let props = |entry: &StateEntry| entry.properties.clone().unwrap_or_default();
+        let base_props = props(base);
+
+        let mut properties = Vec::new();
+        for (key, base_value) in &base_props {
+            let mut values: Vec<String> = Vec::new();
+            for (_, entry) in family.iter() {
+                let value = &props(entry)[key];
+                if !values.contains(value) {
+                    values.push(value.clone());
+                }
+            }
+            let stride = family
+                .iter()
+                .position(|(_, entry)| &props(entry)[key] != base_value)
+                .unwrap_or(1) as u32;
+            properties.push((key.clone(), values, stride));
+        }
+        properties.sort_by_key(|(_, _, stride)| std::cmp::Reverse(*stride));
+
+        // Every state's ID has to follow from its values, or the strides are wrong
+        for (id, entry) in family.iter() {
+            let entry_props = props(entry);
+            let offset: u32 = properties
+                .iter()
+                .map(|(key, values, stride)| {
+                    values.iter().position(|v| *v == entry_props[key]).unwrap() as u32 * stride
+                })
+                .sum();
+            if first_state + offset != *id {
+                return Err(
+                    format!("state {id} of {} doesn't follow its properties", base.name).into(),
+                );
+            }
+        }
+
+        let default_state = family
+            .iter()
+            .find(|(_, entry)| entry.default)
+            .map_or(*first_state, |(id, _)| *id);
+        writeln!(content, "    BlockFamily {{")?;
+        writeln!(content, "        name: \"{}\",", base.name)?;
+        writeln!(content, "        first_state: {first_state},")?;
+        writeln!(content, "        state_count: {},", family.len())?;
+        writeln!(content, "        default_state: {default_state},")?;
+        writeln!(content, "        properties: &[")?;
+        for (key, values, stride) in &properties {
+            let values: Vec<String> = values.iter().map(|v| format!("\"{v}\"")).collect();
+            writeln!(
+                content,
+                "            BlockProperty {{ name: \"{key}\", values: &[{}], stride: {stride} }},",
+                values.join(", ")
+            )?;
+        }
+        writeln!(content, "        ],")?;
+        writeln!(content, "    }},")?;
+
+        let short_name = base.name.strip_prefix("minecraft:").unwrap_or(&base.name);
+        writeln!(name_arms, "        \"{short_name}\" => Some({index}),")?;
+        for _ in family.iter() {
+            write!(state_families, "{index},")?;
+        }
+    }
+    content.push_str("];\n\n");
+    writeln!(
+        content,
+        "pub const STATE_FAMILIES: &[u16] = &[{state_families}];\n"
+    )?;
+    writeln!(content, "fn family_index(name: &str) -> Option<usize> {{")?;
+    writeln!(content, "    match name {{")?;
+    content.push_str(&name_arms);
+    writeln!(content, "        _ => None,")?;
+    writeln!(content, "    }}")?;
+    writeln!(content, "}}")?;
+
+    fs::write(blocks_dir.join("families.rs"), content)?;
+    Ok(())
+}
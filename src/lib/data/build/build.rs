@@ -11,6 +11,7 @@ mod enchantments;
 mod entities;
 mod fluids;
 mod items;
+mod loot;
 mod particles;
 mod potions;
 mod recipes;
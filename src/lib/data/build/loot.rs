@@ -0,0 +1,403 @@
+//! Turns the block loot tables in `assets/data/block_loot_tables.json`, which are in vanilla's
+//! JSON format, into Rust expressions for the generated block constants.
+
+use serde_json::Value;
+
+pub const LOOT_TYPES: &str = r#"/// What a block drops when it's broken, as vanilla's loot table for it describes.
+#[derive(Debug)]
+pub struct LootTable {
+    pub pools: &'static [LootPool],
+    /// Applied to everything the pools drop.
+    pub functions: &'static [LootFunction],
+}
+
+/// Picks from its entries a number of times, if its conditions pass.
+#[derive(Debug)]
+pub struct LootPool {
+    pub rolls: NumberProvider,
+    /// Extra rolls per point of luck.
+    pub bonus_rolls: f32,
+    pub entries: &'static [LootEntry],
+    pub conditions: &'static [LootCondition],
+    /// Applied to everything the entries drop.
+    pub functions: &'static [LootFunction],
+}
+
+#[derive(Debug)]
+pub struct LootEntry {
+    pub kind: LootEntryKind,
+    /// How likely the entry is to be picked over the others in its pool.
+    pub weight: u32,
+    /// How much luck changes the weight.
+    pub quality: i32,
+    pub conditions: &'static [LootCondition],
+    pub functions: &'static [LootFunction],
+}
+
+#[derive(Debug)]
+pub enum LootEntryKind {
+    Item(&'static str),
+    /// Every item in the tag, or with `expand` each of them as an entry of its own.
+    Tag { name: &'static str, expand: bool },
+    /// The first child whose conditions pass.
+    Alternatives(&'static [LootEntry]),
+    /// Every child whose conditions pass.
+    Group(&'static [LootEntry]),
+    /// The children in order, up to the first whose conditions fail.
+    Sequence(&'static [LootEntry]),
+    /// Drops nothing. Entries that drop a block entity's contents or another loot table are
+    /// generated as this too, as neither is supported.
+    Empty,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum NumberProvider {
+    Constant(f32),
+    Uniform { min: f32, max: f32 },
+    Binomial { n: f32, p: f32 },
+}
+
+#[derive(Debug)]
+pub enum LootCondition {
+    /// Passes with a chance of one over the explosion's radius, or always if nothing exploded.
+    SurvivesExplosion,
+    /// The tool is one of the items, or any item if there are none, and is enchanted with at
+    /// least these levels. Items starting with `#` are item tags.
+    MatchTool {
+        items: &'static [&'static str],
+        enchantments: &'static [(&'static str, u32)],
+    },
+    /// Passes with the chance for the tool's level of the enchantment, or the last chance if the
+    /// level is past the end.
+    TableBonus {
+        enchantment: &'static str,
+        chances: &'static [f32],
+    },
+    RandomChance(f32),
+    /// The broken block's properties have these values.
+    BlockStateProperty(&'static [(&'static str, &'static str)]),
+    Inverted(&'static LootCondition),
+    AnyOf(&'static [LootCondition]),
+    AllOf(&'static [LootCondition]),
+    /// A condition on something breaking a block doesn't have, like the weather or the entity
+    /// that killed a mob. Never passes.
+    Unsupported,
+}
+
+#[derive(Debug)]
+pub struct LootFunction {
+    pub kind: LootFunctionKind,
+    pub conditions: &'static [LootCondition],
+}
+
+#[derive(Debug)]
+pub enum LootFunctionKind {
+    SetCount {
+        count: NumberProvider,
+        /// Whether the count is added to the stack's count rather than replacing it.
+        add: bool,
+    },
+    ApplyBonus {
+        enchantment: &'static str,
+        formula: BonusFormula,
+    },
+    LimitCount { min: Option<i32>, max: Option<i32> },
+    /// Each item survives with a chance of one over the explosion's radius.
+    ExplosionDecay,
+    /// A function that changes something other than the count, like the item's components.
+    Other,
+}
+
+/// How [`LootFunctionKind::ApplyBonus`] raises the count with the enchantment's level.
+#[derive(Debug, Clone, Copy)]
+pub enum BonusFormula {
+    /// Multiplies the count by a random number from 1 to one more than the level.
+    OreDrops,
+    /// Adds a random number from 0 to the level times the multiplier.
+    UniformBonusCount { bonus_multiplier: i32 },
+    /// Adds one for every success out of the level plus `extra` tries.
+    BinomialWithBonusCount { extra: i32, probability: f32 },
+}
+"#;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// The expression for a loot table, e.g. `LootTable { pools: &[...], functions: &[] }`.
+pub fn table(table: &Value) -> Result<String> {
+    Ok(format!(
+        "LootTable {{ pools: {}, functions: {} }}",
+        list(table.get("pools"), pool)?,
+        list(table.get("functions"), function)?
+    ))
+}
+
+fn list(values: Option<&Value>, item: fn(&Value) -> Result<String>) -> Result<String> {
+    let items = values
+        .and_then(Value::as_array)
+        .map(|values| values.iter().map(item).collect::<Result<Vec<_>>>())
+        .transpose()?
+        .unwrap_or_default();
+    Ok(format!("&[{}]", items.join(", ")))
+}
+
+fn strings(values: impl IntoIterator<Item = String>) -> String {
+    let values: Vec<_> = values.into_iter().map(|v| format!("{:?}", v)).collect();
+    format!("&[{}]", values.join(", "))
+}
+
+/// A string or a list of strings, which vanilla uses interchangeably for one or more IDs.
+fn ids(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::String(id)) => vec![id.clone()],
+        Some(Value::Array(ids)) => ids
+            .iter()
+            .filter_map(|id| id.as_str().map(str::to_string))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+fn kind(value: &Value, key: &str) -> String {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .trim_start_matches("minecraft:")
+        .to_string()
+}
+
+fn float(value: Option<&Value>) -> Result<f32> {
+    match value {
+        Some(Value::Number(n)) => Ok(n.as_f64().unwrap_or_default() as f32),
+        Some(v) if kind(v, "type") == "constant" => float(v.get("value")),
+        other => Err(format!("Expected a constant number, got {:?}", other).into()),
+    }
+}
+
+fn pool(pool: &Value) -> Result<String> {
+    Ok(format!(
+        "LootPool {{ rolls: {}, bonus_rolls: {:?}, entries: {}, conditions: {}, functions: {} }}",
+        number(pool.get("rolls").unwrap_or(&Value::from(1.0)))?,
+        float(pool.get("bonus_rolls")).unwrap_or(0.0),
+        list(pool.get("entries"), entry)?,
+        list(pool.get("conditions"), condition)?,
+        list(pool.get("functions"), function)?
+    ))
+}
+
+fn entry(entry: &Value) -> Result<String> {
+    let name = entry
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let kind = match kind(entry, "type").as_str() {
+        "item" => format!("LootEntryKind::Item({:?})", name),
+        "tag" => format!(
+            "LootEntryKind::Tag {{ name: {:?}, expand: {} }}",
+            name,
+            entry
+                .get("expand")
+                .and_then(Value::as_bool)
+                .unwrap_or(false)
+        ),
+        "alternatives" => format!(
+            "LootEntryKind::Alternatives({})",
+            list(entry.get("children"), self::entry)?
+        ),
+        "group" => format!(
+            "LootEntryKind::Group({})",
+            list(entry.get("children"), self::entry)?
+        ),
+        "sequence" => format!(
+            "LootEntryKind::Sequence({})",
+            list(entry.get("children"), self::entry)?
+        ),
+        _ => "LootEntryKind::Empty".to_string(),
+    };
+    Ok(format!(
+        "LootEntry {{ kind: {}, weight: {}, quality: {}, conditions: {}, functions: {} }}",
+        kind,
+        entry.get("weight").and_then(Value::as_u64).unwrap_or(1),
+        entry.get("quality").and_then(Value::as_i64).unwrap_or(0),
+        list(entry.get("conditions"), condition)?,
+        list(entry.get("functions"), function)?
+    ))
+}
+
+fn number(number: &Value) -> Result<String> {
+    if number.is_number() {
+        return Ok(format!(
+            "NumberProvider::Constant({:?})",
+            float(Some(number))?
+        ));
+    }
+    match kind(number, "type").as_str() {
+        "constant" => Ok(format!(
+            "NumberProvider::Constant({:?})",
+            float(number.get("value"))?
+        )),
+        "uniform" => Ok(format!(
+            "NumberProvider::Uniform {{ min: {:?}, max: {:?} }}",
+            float(number.get("min"))?,
+            float(number.get("max"))?
+        )),
+        "binomial" => Ok(format!(
+            "NumberProvider::Binomial {{ n: {:?}, p: {:?} }}",
+            float(number.get("n"))?,
+            float(number.get("p"))?
+        )),
+        other => Err(format!("Unsupported number provider {:?}", other).into()),
+    }
+}
+
+fn condition(condition: &Value) -> Result<String> {
+    Ok(match kind(condition, "condition").as_str() {
+        "survives_explosion" => "LootCondition::SurvivesExplosion".to_string(),
+        "match_tool" => {
+            let predicate = condition.get("predicate");
+            let items = ids(predicate.and_then(|p| p.get("items")));
+            // A list of enchantment predicates, or just one
+            let enchantments = match predicate
+                .and_then(|p| p.get("predicates"))
+                .and_then(|p| p.get("minecraft:enchantments"))
+            {
+                Some(Value::Array(predicates)) => predicates.iter().collect(),
+                Some(predicate) => vec![predicate],
+                None => vec![],
+            };
+            let mut levels = vec![];
+            for enchantment in enchantments {
+                let level = match enchantment.get("levels") {
+                    Some(Value::Number(level)) => level.as_u64(),
+                    Some(levels) => levels.get("min").and_then(Value::as_u64),
+                    None => None,
+                };
+                for id in ids(enchantment.get("enchantments")) {
+                    levels.push(format!("({:?}, {})", id, level.unwrap_or(1)));
+                }
+            }
+            format!(
+                "LootCondition::MatchTool {{ items: {}, enchantments: &[{}] }}",
+                strings(items),
+                levels.join(", ")
+            )
+        }
+        "table_bonus" => {
+            let chances = condition
+                .get("chances")
+                .and_then(Value::as_array)
+                .map(|chances| {
+                    chances
+                        .iter()
+                        .map(|chance| float(Some(chance)).map(|c| format!("{:?}", c)))
+                        .collect::<Result<Vec<_>>>()
+                })
+                .transpose()?
+                .unwrap_or_default();
+            format!(
+                "LootCondition::TableBonus {{ enchantment: {:?}, chances: &[{}] }}",
+                condition
+                    .get("enchantment")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default(),
+                chances.join(", ")
+            )
+        }
+        "random_chance" => match float(condition.get("chance")) {
+            Ok(chance) => format!("LootCondition::RandomChance({:?})", chance),
+            Err(_) => "LootCondition::Unsupported".to_string(),
+        },
+        "block_state_property" => {
+            let properties = condition.get("properties").and_then(Value::as_object);
+            let exact: Option<Vec<_>> = properties
+                .map(|properties| {
+                    properties
+                        .iter()
+                        .map(|(key, value)| {
+                            value
+                                .as_str()
+                                .map(|value| format!("({:?}, {:?})", key, value))
+                        })
+                        .collect()
+                })
+                .unwrap_or(Some(vec![]));
+            // Ranges of values aren't used by any block's table
+            match exact {
+                Some(exact) => {
+                    format!("LootCondition::BlockStateProperty(&[{}])", exact.join(", "))
+                }
+                None => "LootCondition::Unsupported".to_string(),
+            }
+        }
+        "inverted" => match condition.get("term") {
+            Some(term) => format!("LootCondition::Inverted(&{})", self::condition(term)?),
+            None => "LootCondition::Unsupported".to_string(),
+        },
+        "any_of" => format!(
+            "LootCondition::AnyOf({})",
+            list(condition.get("terms"), self::condition)?
+        ),
+        "all_of" => format!(
+            "LootCondition::AllOf({})",
+            list(condition.get("terms"), self::condition)?
+        ),
+        _ => "LootCondition::Unsupported".to_string(),
+    })
+}
+
+fn function(function: &Value) -> Result<String> {
+    let kind = match kind(function, "function").as_str() {
+        "set_count" => format!(
+            "LootFunctionKind::SetCount {{ count: {}, add: {} }}",
+            number(function.get("count").unwrap_or(&Value::from(1.0)))?,
+            function
+                .get("add")
+                .and_then(Value::as_bool)
+                .unwrap_or(false)
+        ),
+        "apply_bonus" => {
+            let parameters = function.get("parameters");
+            let parameter = |key| float(parameters.and_then(|p| p.get(key)));
+            let formula = match kind(function, "formula").as_str() {
+                "ore_drops" => "BonusFormula::OreDrops".to_string(),
+                "uniform_bonus_count" => format!(
+                    "BonusFormula::UniformBonusCount {{ bonus_multiplier: {} }}",
+                    parameter("bonusMultiplier")? as i32
+                ),
+                "binomial_with_bonus_count" => format!(
+                    "BonusFormula::BinomialWithBonusCount {{ extra: {}, probability: {:?} }}",
+                    parameter("extra")? as i32,
+                    parameter("probability")?
+                ),
+                other => return Err(format!("Unsupported bonus formula {:?}", other).into()),
+            };
+            format!(
+                "LootFunctionKind::ApplyBonus {{ enchantment: {:?}, formula: {} }}",
+                function
+                    .get("enchantment")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default(),
+                formula
+            )
+        }
+        "limit_count" => {
+            let limit = function.get("limit");
+            let bound = |key| match limit.and_then(|l| l.get(key)) {
+                Some(value) => float(Some(value)).map(|v| format!("Some({})", v as i32)),
+                None => Ok("None".to_string()),
+            };
+            format!(
+                "LootFunctionKind::LimitCount {{ min: {}, max: {} }}",
+                bound("min")?,
+                bound("max")?
+            )
+        }
+        "explosion_decay" => "LootFunctionKind::ExplosionDecay".to_string(),
+        _ => "LootFunctionKind::Other".to_string(),
+    };
+    Ok(format!(
+        "LootFunction {{ kind: {}, conditions: {} }}",
+        kind,
+        list(function.get("conditions"), condition)?
+    ))
+}
@@ -1,5 +1,6 @@
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Biome {
+    pub id: u16,
     pub name: &'static str,
     pub has_precipitation: bool,
     pub temperature: f64,
@@ -14,6 +15,7 @@ pub struct Biome {
 }
 impl Biome {
     pub const BADLANDS: Biome = Biome {
+        id: 0,
         name: "badlands",
         has_precipitation: false,
         temperature: 2.0,
@@ -27,6 +29,7 @@ impl Biome {
         creature_spawn_probability: Some(0.03),
     };
     pub const BAMBOO_JUNGLE: Biome = Biome {
+        id: 1,
         name: "bamboo_jungle",
         has_precipitation: true,
         temperature: 0.9,
@@ -40,6 +43,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const BASALT_DELTAS: Biome = Biome {
+        id: 2,
         name: "basalt_deltas",
         has_precipitation: false,
         temperature: 2.0,
@@ -53,6 +57,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const BEACH: Biome = Biome {
+        id: 3,
         name: "beach",
         has_precipitation: true,
         temperature: 0.8,
@@ -66,6 +71,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const BIRCH_FOREST: Biome = Biome {
+        id: 4,
         name: "birch_forest",
         has_precipitation: true,
         temperature: 0.6,
@@ -79,6 +85,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const CHERRY_GROVE: Biome = Biome {
+        id: 5,
         name: "cherry_grove",
         has_precipitation: true,
         temperature: 0.5,
@@ -92,6 +99,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const COLD_OCEAN: Biome = Biome {
+        id: 6,
         name: "cold_ocean",
         has_precipitation: true,
         temperature: 0.5,
@@ -105,6 +113,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const CRIMSON_FOREST: Biome = Biome {
+        id: 7,
         name: "crimson_forest",
         has_precipitation: false,
         temperature: 2.0,
@@ -118,6 +127,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const DARK_FOREST: Biome = Biome {
+        id: 8,
         name: "dark_forest",
         has_precipitation: true,
         temperature: 0.7,
@@ -131,6 +141,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const DEEP_COLD_OCEAN: Biome = Biome {
+        id: 9,
         name: "deep_cold_ocean",
         has_precipitation: true,
         temperature: 0.5,
@@ -144,6 +155,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const DEEP_DARK: Biome = Biome {
+        id: 10,
         name: "deep_dark",
         has_precipitation: true,
         temperature: 0.8,
@@ -157,6 +169,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const DEEP_FROZEN_OCEAN: Biome = Biome {
+        id: 11,
         name: "deep_frozen_ocean",
         has_precipitation: true,
         temperature: 0.5,
@@ -170,6 +183,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const DEEP_LUKEWARM_OCEAN: Biome = Biome {
+        id: 12,
         name: "deep_lukewarm_ocean",
         has_precipitation: true,
         temperature: 0.5,
@@ -183,6 +197,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const DEEP_OCEAN: Biome = Biome {
+        id: 13,
         name: "deep_ocean",
         has_precipitation: true,
         temperature: 0.5,
@@ -196,6 +211,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const DESERT: Biome = Biome {
+        id: 14,
         name: "desert",
         has_precipitation: false,
         temperature: 2.0,
@@ -209,6 +225,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const DRIPSTONE_CAVES: Biome = Biome {
+        id: 15,
         name: "dripstone_caves",
         has_precipitation: true,
         temperature: 0.8,
@@ -222,6 +239,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const END_BARRENS: Biome = Biome {
+        id: 16,
         name: "end_barrens",
         has_precipitation: false,
         temperature: 0.5,
@@ -235,6 +253,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const END_HIGHLANDS: Biome = Biome {
+        id: 17,
         name: "end_highlands",
         has_precipitation: false,
         temperature: 0.5,
@@ -248,6 +267,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const END_MIDLANDS: Biome = Biome {
+        id: 18,
         name: "end_midlands",
         has_precipitation: false,
         temperature: 0.5,
@@ -261,6 +281,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const ERODED_BADLANDS: Biome = Biome {
+        id: 19,
         name: "eroded_badlands",
         has_precipitation: false,
         temperature: 2.0,
@@ -274,6 +295,7 @@ impl Biome {
         creature_spawn_probability: Some(0.03),
     };
     pub const FLOWER_FOREST: Biome = Biome {
+        id: 20,
         name: "flower_forest",
         has_precipitation: true,
         temperature: 0.7,
@@ -287,6 +309,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const FOREST: Biome = Biome {
+        id: 21,
         name: "forest",
         has_precipitation: true,
         temperature: 0.7,
@@ -300,6 +323,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const FROZEN_OCEAN: Biome = Biome {
+        id: 22,
         name: "frozen_ocean",
         has_precipitation: true,
         temperature: 0.0,
@@ -313,6 +337,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const FROZEN_PEAKS: Biome = Biome {
+        id: 23,
         name: "frozen_peaks",
         has_precipitation: true,
         temperature: -0.7,
@@ -326,6 +351,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const FROZEN_RIVER: Biome = Biome {
+        id: 24,
         name: "frozen_river",
         has_precipitation: true,
         temperature: 0.0,
@@ -339,6 +365,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const GROVE: Biome = Biome {
+        id: 25,
         name: "grove",
         has_precipitation: true,
         temperature: -0.2,
@@ -352,6 +379,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const ICE_SPIKES: Biome = Biome {
+        id: 26,
         name: "ice_spikes",
         has_precipitation: true,
         temperature: 0.0,
@@ -365,6 +393,7 @@ impl Biome {
         creature_spawn_probability: Some(0.07),
     };
     pub const JAGGED_PEAKS: Biome = Biome {
+        id: 27,
         name: "jagged_peaks",
         has_precipitation: true,
         temperature: -0.7,
@@ -378,6 +407,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const JUNGLE: Biome = Biome {
+        id: 28,
         name: "jungle",
         has_precipitation: true,
         temperature: 0.9,
@@ -391,6 +421,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const LUKEWARM_OCEAN: Biome = Biome {
+        id: 29,
         name: "lukewarm_ocean",
         has_precipitation: true,
         temperature: 0.5,
@@ -404,6 +435,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const LUSH_CAVES: Biome = Biome {
+        id: 30,
         name: "lush_caves",
         has_precipitation: true,
         temperature: 0.5,
@@ -417,6 +449,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const MANGROVE_SWAMP: Biome = Biome {
+        id: 31,
         name: "mangrove_swamp",
         has_precipitation: true,
         temperature: 0.8,
@@ -430,6 +463,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const MEADOW: Biome = Biome {
+        id: 32,
         name: "meadow",
         has_precipitation: true,
         temperature: 0.5,
@@ -443,6 +477,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const MUSHROOM_FIELDS: Biome = Biome {
+        id: 33,
         name: "mushroom_fields",
         has_precipitation: true,
         temperature: 0.9,
@@ -456,6 +491,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const NETHER_WASTES: Biome = Biome {
+        id: 34,
         name: "nether_wastes",
         has_precipitation: false,
         temperature: 2.0,
@@ -469,6 +505,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const OCEAN: Biome = Biome {
+        id: 35,
         name: "ocean",
         has_precipitation: true,
         temperature: 0.5,
@@ -482,6 +519,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const OLD_GROWTH_BIRCH_FOREST: Biome = Biome {
+        id: 36,
         name: "old_growth_birch_forest",
         has_precipitation: true,
         temperature: 0.6,
@@ -495,6 +533,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const OLD_GROWTH_PINE_TAIGA: Biome = Biome {
+        id: 37,
         name: "old_growth_pine_taiga",
         has_precipitation: true,
         temperature: 0.3,
@@ -508,6 +547,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const OLD_GROWTH_SPRUCE_TAIGA: Biome = Biome {
+        id: 38,
         name: "old_growth_spruce_taiga",
         has_precipitation: true,
         temperature: 0.2,
@@ -521,6 +561,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const PALE_GARDEN: Biome = Biome {
+        id: 39,
         name: "pale_garden",
         has_precipitation: true,
         temperature: 0.7,
@@ -534,6 +575,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const PLAINS: Biome = Biome {
+        id: 40,
         name: "plains",
         has_precipitation: true,
         temperature: 0.8,
@@ -547,6 +589,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const RIVER: Biome = Biome {
+        id: 41,
         name: "river",
         has_precipitation: true,
         temperature: 0.5,
@@ -560,6 +603,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const SAVANNA: Biome = Biome {
+        id: 42,
         name: "savanna",
         has_precipitation: false,
         temperature: 2.0,
@@ -573,6 +617,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const SAVANNA_PLATEAU: Biome = Biome {
+        id: 43,
         name: "savanna_plateau",
         has_precipitation: false,
         temperature: 2.0,
@@ -586,6 +631,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const SMALL_END_ISLANDS: Biome = Biome {
+        id: 44,
         name: "small_end_islands",
         has_precipitation: false,
         temperature: 0.5,
@@ -599,6 +645,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const SNOWY_BEACH: Biome = Biome {
+        id: 45,
         name: "snowy_beach",
         has_precipitation: true,
         temperature: 0.1,
@@ -612,6 +659,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const SNOWY_PLAINS: Biome = Biome {
+        id: 46,
         name: "snowy_plains",
         has_precipitation: true,
         temperature: 0.0,
@@ -625,6 +673,7 @@ impl Biome {
         creature_spawn_probability: Some(0.07),
     };
     pub const SNOWY_SLOPES: Biome = Biome {
+        id: 47,
         name: "snowy_slopes",
         has_precipitation: true,
         temperature: -0.3,
@@ -638,6 +687,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const SNOWY_TAIGA: Biome = Biome {
+        id: 48,
         name: "snowy_taiga",
         has_precipitation: true,
         temperature: -0.5,
@@ -651,6 +701,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const SOUL_SAND_VALLEY: Biome = Biome {
+        id: 49,
         name: "soul_sand_valley",
         has_precipitation: false,
         temperature: 2.0,
@@ -664,6 +715,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const SPARSE_JUNGLE: Biome = Biome {
+        id: 50,
         name: "sparse_jungle",
         has_precipitation: true,
         temperature: 0.9,
@@ -677,6 +729,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const STONY_PEAKS: Biome = Biome {
+        id: 51,
         name: "stony_peaks",
         has_precipitation: true,
         temperature: 1.0,
@@ -690,6 +743,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const STONY_SHORE: Biome = Biome {
+        id: 52,
         name: "stony_shore",
         has_precipitation: true,
         temperature: 0.2,
@@ -703,6 +757,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const SUNFLOWER_PLAINS: Biome = Biome {
+        id: 53,
         name: "sunflower_plains",
         has_precipitation: true,
         temperature: 0.8,
@@ -716,6 +771,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const SWAMP: Biome = Biome {
+        id: 54,
         name: "swamp",
         has_precipitation: true,
         temperature: 0.8,
@@ -729,6 +785,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const TAIGA: Biome = Biome {
+        id: 55,
         name: "taiga",
         has_precipitation: true,
         temperature: 0.2,
@@ -742,6 +799,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const THE_END: Biome = Biome {
+        id: 56,
         name: "the_end",
         has_precipitation: false,
         temperature: 0.5,
@@ -755,6 +813,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const THE_VOID: Biome = Biome {
+        id: 57,
         name: "the_void",
         has_precipitation: false,
         temperature: 0.5,
@@ -768,6 +827,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const WARM_OCEAN: Biome = Biome {
+        id: 58,
         name: "warm_ocean",
         has_precipitation: true,
         temperature: 0.5,
@@ -781,6 +841,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const WARPED_FOREST: Biome = Biome {
+        id: 59,
         name: "warped_forest",
         has_precipitation: false,
         temperature: 2.0,
@@ -794,6 +855,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const WINDSWEPT_FOREST: Biome = Biome {
+        id: 60,
         name: "windswept_forest",
         has_precipitation: true,
         temperature: 0.2,
@@ -807,6 +869,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const WINDSWEPT_GRAVELLY_HILLS: Biome = Biome {
+        id: 61,
         name: "windswept_gravelly_hills",
         has_precipitation: true,
         temperature: 0.2,
@@ -820,6 +883,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const WINDSWEPT_HILLS: Biome = Biome {
+        id: 62,
         name: "windswept_hills",
         has_precipitation: true,
         temperature: 0.2,
@@ -833,6 +897,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const WINDSWEPT_SAVANNA: Biome = Biome {
+        id: 63,
         name: "windswept_savanna",
         has_precipitation: false,
         temperature: 2.0,
@@ -846,6 +911,7 @@ impl Biome {
         creature_spawn_probability: None,
     };
     pub const WOODED_BADLANDS: Biome = Biome {
+        id: 64,
         name: "wooded_badlands",
         has_precipitation: false,
         temperature: 2.0,
@@ -858,6 +924,77 @@ impl Biome {
         grass_color: Some(9470285),
         creature_spawn_probability: Some(0.04),
     };
+    #[doc = r" Try to get a `Biome` by its network id."]
+    pub const fn from_id(id: u16) -> Option<&'static Self> {
+        match id {
+            0 => Some(&Self::BADLANDS),
+            1 => Some(&Self::BAMBOO_JUNGLE),
+            2 => Some(&Self::BASALT_DELTAS),
+            3 => Some(&Self::BEACH),
+            4 => Some(&Self::BIRCH_FOREST),
+            5 => Some(&Self::CHERRY_GROVE),
+            6 => Some(&Self::COLD_OCEAN),
+            7 => Some(&Self::CRIMSON_FOREST),
+            8 => Some(&Self::DARK_FOREST),
+            9 => Some(&Self::DEEP_COLD_OCEAN),
+            10 => Some(&Self::DEEP_DARK),
+            11 => Some(&Self::DEEP_FROZEN_OCEAN),
+            12 => Some(&Self::DEEP_LUKEWARM_OCEAN),
+            13 => Some(&Self::DEEP_OCEAN),
+            14 => Some(&Self::DESERT),
+            15 => Some(&Self::DRIPSTONE_CAVES),
+            16 => Some(&Self::END_BARRENS),
+            17 => Some(&Self::END_HIGHLANDS),
+            18 => Some(&Self::END_MIDLANDS),
+            19 => Some(&Self::ERODED_BADLANDS),
+            20 => Some(&Self::FLOWER_FOREST),
+            21 => Some(&Self::FOREST),
+            22 => Some(&Self::FROZEN_OCEAN),
+            23 => Some(&Self::FROZEN_PEAKS),
+            24 => Some(&Self::FROZEN_RIVER),
+            25 => Some(&Self::GROVE),
+            26 => Some(&Self::ICE_SPIKES),
+            27 => Some(&Self::JAGGED_PEAKS),
+            28 => Some(&Self::JUNGLE),
+            29 => Some(&Self::LUKEWARM_OCEAN),
+            30 => Some(&Self::LUSH_CAVES),
+            31 => Some(&Self::MANGROVE_SWAMP),
+            32 => Some(&Self::MEADOW),
+            33 => Some(&Self::MUSHROOM_FIELDS),
+            34 => Some(&Self::NETHER_WASTES),
+            35 => Some(&Self::OCEAN),
+            36 => Some(&Self::OLD_GROWTH_BIRCH_FOREST),
+            37 => Some(&Self::OLD_GROWTH_PINE_TAIGA),
+            38 => Some(&Self::OLD_GROWTH_SPRUCE_TAIGA),
+            39 => Some(&Self::PALE_GARDEN),
+            40 => Some(&Self::PLAINS),
+            41 => Some(&Self::RIVER),
+            42 => Some(&Self::SAVANNA),
+            43 => Some(&Self::SAVANNA_PLATEAU),
+            44 => Some(&Self::SMALL_END_ISLANDS),
+            45 => Some(&Self::SNOWY_BEACH),
+            46 => Some(&Self::SNOWY_PLAINS),
+            47 => Some(&Self::SNOWY_SLOPES),
+            48 => Some(&Self::SNOWY_TAIGA),
+            49 => Some(&Self::SOUL_SAND_VALLEY),
+            50 => Some(&Self::SPARSE_JUNGLE),
+            51 => Some(&Self::STONY_PEAKS),
+            52 => Some(&Self::STONY_SHORE),
+            53 => Some(&Self::SUNFLOWER_PLAINS),
+            54 => Some(&Self::SWAMP),
+            55 => Some(&Self::TAIGA),
+            56 => Some(&Self::THE_END),
+            57 => Some(&Self::THE_VOID),
+            58 => Some(&Self::WARM_OCEAN),
+            59 => Some(&Self::WARPED_FOREST),
+            60 => Some(&Self::WINDSWEPT_FOREST),
+            61 => Some(&Self::WINDSWEPT_GRAVELLY_HILLS),
+            62 => Some(&Self::WINDSWEPT_HILLS),
+            63 => Some(&Self::WINDSWEPT_SAVANNA),
+            64 => Some(&Self::WOODED_BADLANDS),
+            _ => None,
+        }
+    }
     #[doc = r" Try to parse a `Biome` from a resource location string."]
     pub fn from_name(name: &str) -> Option<&'static Self> {
         let name = name.strip_prefix("minecraft:").unwrap_or(name);
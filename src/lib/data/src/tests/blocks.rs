@@ -245,3 +245,38 @@ fn test_blocks_consistency() {
     assert_eq!(direct_stone.hardness, id_lookup.hardness);
     assert_eq!(direct_stone.hardness, name_lookup.hardness);
 }
+
+#[test]
+fn test_block_families() {
+    let stairs = blocks::BlockFamily::by_name("minecraft:oak_stairs").unwrap();
+    assert_eq!(stairs.name, "minecraft:oak_stairs");
+    assert_eq!(stairs.state_count, 80);
+    assert_eq!(
+        blocks::BlockFamily::by_name("oak_stairs")
+            .unwrap()
+            .first_state,
+        stairs.first_state
+    );
+    for state in stairs.states() {
+        assert_eq!(
+            blocks::BlockFamily::of_state(state).unwrap().name,
+            stairs.name
+        );
+    }
+
+    // Properties are listed slowest changing first, and the last one changes every state
+    let names: Vec<_> = stairs.properties.iter().map(|p| p.name).collect();
+    assert_eq!(names, ["facing", "half", "shape", "waterlogged"]);
+    let waterlogged = stairs.property("waterlogged").unwrap();
+    assert_eq!(waterlogged.values, ["true", "false"]);
+    assert_eq!(waterlogged.stride, 1);
+    let facing = stairs.property("facing").unwrap();
+    assert_eq!(facing.values, ["north", "south", "west", "east"]);
+    assert_eq!(stairs.value_index(stairs.first_state + 20, facing), 1);
+
+    let stone = blocks::BlockFamily::by_name("stone").unwrap();
+    assert!(stone.properties.is_empty());
+    assert_eq!(stone.default_state, stone.first_state);
+    assert!(blocks::BlockFamily::by_name("nonexistent_block").is_none());
+    assert!(blocks::BlockFamily::of_state(u32::MAX).is_none());
+}
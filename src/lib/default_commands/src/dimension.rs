@@ -0,0 +1,29 @@
+use bevy_ecs::prelude::*;
+use ferrumc_commands::Sender;
+use ferrumc_macros::command;
+use ferrumc_messages::PlayerDimensionChanged;
+use ferrumc_world::dimension::Dimension;
+
+/// Moves the sender into another dimension.
+#[command("dimension")]
+fn dimension_command(
+    #[sender] sender: Sender,
+    #[arg] dimension: Dimension,
+    mut dimension_events: MessageWriter<PlayerDimensionChanged>,
+) {
+    // 1. Ensure the sender is a player
+    let player_entity = match sender {
+        Sender::Server => {
+            sender.send_message("Error: The server can't change dimension.".into(), false);
+            return;
+        }
+        Sender::Player(entity) => entity,
+    };
+
+    // 2. Fire the event
+    dimension_events.write(PlayerDimensionChanged {
+        player: player_entity,
+        dimension,
+        position: None,
+    });
+}
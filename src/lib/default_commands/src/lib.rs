@@ -1,7 +1,9 @@
+pub mod dimension;
 pub mod echo;
 pub mod fly;
 pub mod gamemode;
 pub mod nested;
+pub mod region;
 pub mod spawn;
 pub mod tps;
 
@@ -0,0 +1,351 @@
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::SystemParam;
+use bevy_math::IVec3;
+use ferrumc_commands::arg::primitive::int::Integer;
+use ferrumc_commands::Sender;
+use ferrumc_components::player::dimension::DimensionComponent;
+use ferrumc_components::player::edit_session::EditSession;
+use ferrumc_config::server_config::get_global_config;
+use ferrumc_core::transform::position::Position;
+use ferrumc_macros::command;
+use ferrumc_messages::{BlockEntityChanged, BlocksChanged};
+use ferrumc_state::GlobalStateResource;
+use ferrumc_world::block_state_id::BlockStateId;
+use ferrumc_world::errors::WorldError;
+use ferrumc_world::pos::BlockPos;
+use ferrumc_world::region_edit::{BlockMask, RegionEdit};
+use ferrumc_world::schematic::{Mirror, Rotation};
+
+type EditQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static Position,
+        &'static DimensionComponent,
+        &'static mut EditSession,
+    ),
+>;
+
+/// Sends the blocks an edit changed to the players that can see them.
+#[derive(SystemParam)]
+struct EditBroadcast<'w> {
+    blocks: MessageWriter<'w, BlocksChanged>,
+    block_entities: MessageWriter<'w, BlockEntityChanged>,
+}
+
+impl EditBroadcast<'_> {
+    fn send(&mut self, edit: &RegionEdit) {
+        let positions = edit.changed_blocks();
+        if !positions.is_empty() {
+            self.blocks.write(BlocksChanged {
+                positions,
+                dimension: edit.dimension(),
+            });
+        }
+        for position in edit.changed_block_entities() {
+            self.block_entities.write(BlockEntityChanged {
+                position,
+                dimension: edit.dimension(),
+            });
+        }
+    }
+}
+
+fn player_entity(sender: &Sender) -> Option<Entity> {
+    match sender {
+        Sender::Server => {
+            sender.send_message("Error: The server can't edit regions.".into(), false);
+            None
+        }
+        Sender::Player(entity) => Some(*entity),
+    }
+}
+
+fn block_pos(position: &Position) -> BlockPos {
+    BlockPos {
+        pos: position.coords.floor().as_ivec3(),
+    }
+}
+
+/// Whether a command working on `volume` blocks can go ahead. Larger ones are refused before any
+/// work starts, since the tick waits for the edit and its history is kept in memory.
+fn within_limit(sender: &Sender, volume: u64) -> bool {
+    let limit = get_global_config().max_region_volume;
+    if volume > limit {
+        sender.send_message(
+            format!("Error: {volume} blocks is more than the limit of {limit}.").into(),
+            false,
+        );
+        return false;
+    }
+    true
+}
+
+/// Broadcasts and records a finished edit, or tells the sender why it failed.
+fn finish_edit(
+    sender: &Sender,
+    session: &mut EditSession,
+    broadcast: &mut EditBroadcast,
+    result: Result<RegionEdit, WorldError>,
+) {
+    match result {
+        Ok(edit) => {
+            broadcast.send(&edit);
+            sender.send_message(
+                format!("{} blocks changed.", edit.changed_blocks().len()).into(),
+                false,
+            );
+            session.history.record(edit);
+        }
+        Err(err) => sender.send_message(format!("Error: {err}").into(), false),
+    }
+}
+
+fn set_corner(sender: Sender, query: &mut EditQuery, second: bool) {
+    let Some(entity) = player_entity(&sender) else {
+        return;
+    };
+    let Ok((position, _, mut session)) = query.get_mut(entity) else {
+        return;
+    };
+    let pos = block_pos(position);
+    if second {
+        session.pos2 = Some(pos);
+    } else {
+        session.pos1 = Some(pos);
+    }
+    let which = if second { "Second" } else { "First" };
+    sender.send_message(format!("{which} position set to {pos}.").into(), false);
+}
+
+/// Sets the first corner of the selection to the sender's position.
+#[command("region pos1")]
+fn region_pos1_command(#[sender] sender: Sender, mut query: EditQuery) {
+    set_corner(sender, &mut query, false);
+}
+
+/// Sets the second corner of the selection to the sender's position.
+#[command("region pos2")]
+fn region_pos2_command(#[sender] sender: Sender, mut query: EditQuery) {
+    set_corner(sender, &mut query, true);
+}
+
+/// Fills the selection with a block.
+#[command("region set")]
+fn region_set_command(
+    #[sender] sender: Sender,
+    #[arg] block: BlockStateId,
+    state: Res<GlobalStateResource>,
+    mut query: EditQuery,
+    mut broadcast: EditBroadcast,
+) {
+    let Some(entity) = player_entity(&sender) else {
+        return;
+    };
+    let Ok((_, dimension, mut session)) = query.get_mut(entity) else {
+        return;
+    };
+    let Some(region) = session.selection() else {
+        sender.send_message("Error: Select a region first.".into(), false);
+        return;
+    };
+    if !within_limit(&sender, region.volume()) {
+        return;
+    }
+    let result = state.0.world.fill_region(
+        region,
+        dimension.0,
+        block,
+        &BlockMask::Any,
+        &state.0.thread_pool,
+    );
+    finish_edit(&sender, &mut session, &mut broadcast, result);
+}
+
+/// Replaces every state of a block in the selection with another block.
+#[command("region replace")]
+fn region_replace_command(
+    #[sender] sender: Sender,
+    #[arg] from: BlockMask,
+    #[arg] to: BlockStateId,
+    state: Res<GlobalStateResource>,
+    mut query: EditQuery,
+    mut broadcast: EditBroadcast,
+) {
+    let Some(entity) = player_entity(&sender) else {
+        return;
+    };
+    let Ok((_, dimension, mut session)) = query.get_mut(entity) else {
+        return;
+    };
+    let Some(region) = session.selection() else {
+        sender.send_message("Error: Select a region first.".into(), false);
+        return;
+    };
+    if !within_limit(&sender, region.volume()) {
+        return;
+    }
+    let result = state
+        .0
+        .world
+        .fill_region(region, dimension.0, to, &from, &state.0.thread_pool);
+    finish_edit(&sender, &mut session, &mut broadcast, result);
+}
+
+/// Copies the selection to the sender's clipboard, relative to where they stand.
+#[command("region copy")]
+fn region_copy_command(
+    #[sender] sender: Sender,
+    state: Res<GlobalStateResource>,
+    mut query: EditQuery,
+) {
+    let Some(entity) = player_entity(&sender) else {
+        return;
+    };
+    let Ok((position, dimension, mut session)) = query.get_mut(entity) else {
+        return;
+    };
+    let Some(region) = session.selection() else {
+        sender.send_message("Error: Select a region first.".into(), false);
+        return;
+    };
+    if !within_limit(&sender, region.volume()) {
+        return;
+    }
+    match state
+        .0
+        .world
+        .copy_schematic(region.min(), region.max(), dimension.0)
+    {
+        Ok(mut schematic) => {
+            schematic.offset = region.min().pos - block_pos(position).pos;
+            session.clipboard = Some(schematic);
+            sender.send_message(format!("Copied {} blocks.", region.volume()).into(), false);
+        }
+        Err(err) => sender.send_message(format!("Error: {err}").into(), false),
+    }
+}
+
+/// Pastes the sender's clipboard, placed the same way relative to them as when it was copied.
+#[command("region paste")]
+fn region_paste_command(
+    #[sender] sender: Sender,
+    state: Res<GlobalStateResource>,
+    mut query: EditQuery,
+    mut broadcast: EditBroadcast,
+) {
+    let Some(entity) = player_entity(&sender) else {
+        return;
+    };
+    let Ok((position, dimension, mut session)) = query.get_mut(entity) else {
+        return;
+    };
+    let Some(clipboard) = &session.clipboard else {
+        sender.send_message("Error: Copy a region first.".into(), false);
+        return;
+    };
+    let volume = [clipboard.width(), clipboard.height(), clipboard.length()]
+        .map(u64::from)
+        .iter()
+        .product();
+    if !within_limit(&sender, volume) {
+        return;
+    }
+    let origin = BlockPos {
+        pos: block_pos(position).pos + clipboard.offset,
+    };
+    let result = state.0.world.paste_region(
+        clipboard,
+        origin,
+        dimension.0,
+        Rotation::None,
+        Mirror::None,
+        &state.0.thread_pool,
+    );
+    finish_edit(&sender, &mut session, &mut broadcast, result);
+}
+
+/// Moves the selection, and the blocks in it, by an offset.
+#[command("region move")]
+fn region_move_command(
+    #[sender] sender: Sender,
+    #[arg] x: Integer,
+    #[arg] y: Integer,
+    #[arg] z: Integer,
+    state: Res<GlobalStateResource>,
+    mut query: EditQuery,
+    mut broadcast: EditBroadcast,
+) {
+    let Some(entity) = player_entity(&sender) else {
+        return;
+    };
+    let Ok((_, dimension, mut session)) = query.get_mut(entity) else {
+        return;
+    };
+    let Some(region) = session.selection() else {
+        sender.send_message("Error: Select a region first.".into(), false);
+        return;
+    };
+    if !within_limit(&sender, region.volume()) {
+        return;
+    }
+    let offset = IVec3::new(*x, *y, *z);
+    let result = state
+        .0
+        .world
+        .move_region(region, offset, dimension.0, &state.0.thread_pool);
+    if result.is_ok() {
+        let moved = region.offset(offset);
+        session.pos1 = Some(moved.min());
+        session.pos2 = Some(moved.max());
+    }
+    finish_edit(&sender, &mut session, &mut broadcast, result);
+}
+
+/// Undoes the sender's last region edit.
+#[command("region undo")]
+fn region_undo_command(
+    #[sender] sender: Sender,
+    state: Res<GlobalStateResource>,
+    mut query: EditQuery,
+    mut broadcast: EditBroadcast,
+) {
+    let Some(entity) = player_entity(&sender) else {
+        return;
+    };
+    let Ok((_, _, mut session)) = query.get_mut(entity) else {
+        return;
+    };
+    match session.history.undo(&state.0.world, &state.0.thread_pool) {
+        Ok(Some(edit)) => {
+            broadcast.send(&edit);
+            sender.send_message("Undid the last edit.".into(), false);
+        }
+        Ok(None) => sender.send_message("Nothing to undo.".into(), false),
+        Err(err) => sender.send_message(format!("Error: {err}").into(), false),
+    }
+}
+
+/// Redoes the sender's last undone region edit.
+#[command("region redo")]
+fn region_redo_command(
+    #[sender] sender: Sender,
+    state: Res<GlobalStateResource>,
+    mut query: EditQuery,
+    mut broadcast: EditBroadcast,
+) {
+    let Some(entity) = player_entity(&sender) else {
+        return;
+    };
+    let Ok((_, _, mut session)) = query.get_mut(entity) else {
+        return;
+    };
+    match session.history.redo(&state.0.world, &state.0.thread_pool) {
+        Ok(Some(edit)) => {
+            broadcast.send(&edit);
+            sender.send_message("Redid the last undone edit.".into(), false);
+        }
+        Ok(None) => sender.send_message("Nothing to redo.".into(), false),
+        Err(err) => sender.send_message(format!("Error: {err}").into(), false),
+    }
+}
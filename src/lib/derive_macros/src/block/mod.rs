@@ -2,6 +2,7 @@ use proc_macro::TokenStream;
 use quote::quote;
 use simd_json::prelude::{ValueAsObject, ValueAsScalar, ValueObjectAccess};
 use simd_json::{OwnedValue, StaticNode};
+use syn::ext::IdentExt;
 use syn::parse::{Parse, ParseStream};
 use syn::punctuated::Punctuated;
 use syn::{braced, Expr, Ident, Lit, LitStr, Result, Token};
@@ -110,7 +111,8 @@ pub fn block(input: TokenStream) -> TokenStream {
         .iter()
         .map(|kv| {
             Ok((
-                kv.key.to_string(),
+                // Raw identifiers allow keyword properties, like `r#type` for slabs and chests
+                kv.key.unraw().to_string(),
                 match &kv.value {
                     Expr::Lit(v) => match &v.lit {
                         Lit::Str(v) => v.value(),
@@ -0,0 +1,83 @@
+use bevy_ecs::prelude::Bundle;
+use ferrumc_components::player::dimension::DimensionComponent;
+use ferrumc_core::identity::entity_identity::EntityIdentity;
+use ferrumc_core::transform::{
+    grounded::OnGround, position::Position, rotation::Rotation, velocity::Velocity,
+};
+use ferrumc_data::generated::entities::EntityType as VanillaEntityType;
+use ferrumc_inventories::slot::InventorySlot;
+use ferrumc_world::dimension::Dimension;
+
+use crate::components::{DroppedItem, EntityMetadata, LastSyncedPosition, PhysicalProperties};
+
+/// Complete bundle to spawn an item entity in Bevy ECS, like the loot
+/// dropped by a broken block.
+///
+/// Items aren't mobs, so unlike [`PigBundle`](super::PigBundle) there
+/// are no combat or spawn properties.
+#[derive(Bundle)]
+pub struct ItemBundle {
+    pub identity: EntityIdentity,
+    pub metadata: EntityMetadata,
+    pub physical: PhysicalProperties,
+    pub item: DroppedItem,
+    pub position: Position,
+    pub dimension: DimensionComponent,
+    pub rotation: Rotation,
+    pub velocity: Velocity,
+    pub on_ground: OnGround,
+    pub last_synced_position: LastSyncedPosition,
+}
+
+impl ItemBundle {
+    pub fn new(
+        stack: InventorySlot,
+        position: Position,
+        velocity: Velocity,
+        dimension: Dimension,
+    ) -> Self {
+        let metadata = EntityMetadata::from_vanilla(&VanillaEntityType::ITEM);
+        let physical = PhysicalProperties::from_metadata(&metadata);
+
+        Self {
+            identity: EntityIdentity::new(),
+            metadata,
+            physical,
+            item: DroppedItem::new(stack),
+            dimension: DimensionComponent(dimension),
+            rotation: Rotation::default(),
+            velocity,
+            on_ground: OnGround(false),
+            last_synced_position: LastSyncedPosition::from_position(&position),
+            position,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ferrumc_inventories::item::ItemID;
+
+    #[test]
+    fn test_item_bundle_creation() {
+        let stack = InventorySlot {
+            count: 3.into(),
+            item_id: Some(ItemID::new(1)),
+            ..Default::default()
+        };
+        let item = ItemBundle::new(
+            stack.clone(),
+            Position::new(0.5, 64.0, 0.5),
+            Velocity::new(0.0, 0.2, 0.0),
+            Dimension::Overworld,
+        );
+
+        assert_eq!(item.metadata.resource_name(), "item");
+        assert!(!item.metadata.is_mob());
+        assert!((item.physical.bounding_box.width() - 0.25).abs() < 1e-6);
+        assert_eq!(item.item.stack, stack);
+        assert!((item.velocity.y - 0.2).abs() < 1e-6);
+        assert_eq!(item.dimension.0, Dimension::Overworld);
+    }
+}
@@ -1,5 +1,7 @@
 // Entity bundles for spawning in Bevy ECS
+pub mod item;
 pub mod pig;
 
 // Re-exports
+pub use item::ItemBundle;
 pub use pig::PigBundle;
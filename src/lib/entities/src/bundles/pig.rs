@@ -1,9 +1,11 @@
 use bevy_ecs::prelude::Bundle;
+use ferrumc_components::player::dimension::DimensionComponent;
 use ferrumc_core::identity::entity_identity::EntityIdentity;
 use ferrumc_core::transform::{
     grounded::OnGround, position::Position, rotation::Rotation, velocity::Velocity,
 };
 use ferrumc_data::generated::entities::EntityType as VanillaEntityType;
+use ferrumc_world::dimension::Dimension;
 
 use crate::components::{
     CombatProperties, EntityMetadata, LastSyncedPosition, PhysicalProperties, SpawnProperties,
@@ -22,6 +24,7 @@ pub struct PigBundle {
     pub combat: CombatProperties,
     pub spawn: SpawnProperties,
     pub position: Position,
+    pub dimension: DimensionComponent,
     pub rotation: Rotation,
     pub velocity: Velocity,
     pub on_ground: OnGround,
@@ -29,7 +32,7 @@ pub struct PigBundle {
 }
 
 impl PigBundle {
-    pub fn new(position: Position) -> Self {
+    pub fn new(position: Position, dimension: Dimension) -> Self {
         let metadata = EntityMetadata::from_vanilla(&VanillaEntityType::PIG);
         let physical = PhysicalProperties::from_metadata(&metadata);
         let combat = CombatProperties::from_metadata(&metadata);
@@ -41,6 +44,7 @@ impl PigBundle {
             physical,
             combat,
             spawn,
+            dimension: DimensionComponent(dimension),
             rotation: Rotation::default(),
             velocity: Velocity::zero(),
             on_ground: OnGround(false),
@@ -49,8 +53,8 @@ impl PigBundle {
         }
     }
 
-    pub fn with_rotation(position: Position, rotation: Rotation) -> Self {
-        let mut bundle = Self::new(position);
+    pub fn with_rotation(position: Position, dimension: Dimension, rotation: Rotation) -> Self {
+        let mut bundle = Self::new(position, dimension);
         bundle.rotation = rotation;
         bundle
     }
@@ -66,7 +70,7 @@ mod tests {
         const EPSILON_F64: f64 = 1e-6;
 
         let position = Position::new(0.0, 64.0, 0.0);
-        let pig = PigBundle::new(position);
+        let pig = PigBundle::new(position, Dimension::Overworld);
 
         // Verify vanilla metadata
         assert_eq!(pig.metadata.protocol_id(), 95);
@@ -97,9 +101,10 @@ mod tests {
             yaw: 90.0,
             pitch: 0.0,
         };
-        let pig = PigBundle::with_rotation(position, rotation);
+        let pig = PigBundle::with_rotation(position, Dimension::Nether, rotation);
 
         assert_eq!(pig.rotation.yaw, 90.0);
         assert_eq!(pig.rotation.pitch, 0.0);
+        assert_eq!(pig.dimension.0, Dimension::Nether);
     }
 }
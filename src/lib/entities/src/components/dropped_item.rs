@@ -0,0 +1,42 @@
+use bevy_ecs::prelude::Component;
+use ferrumc_inventories::slot::InventorySlot;
+
+/// How many ticks an item lies in the world before it despawns, 5 minutes like vanilla.
+pub const DESPAWN_AGE: u32 = 6000;
+
+/// The stack of items an item entity lying in the world holds.
+///
+/// Clients are sent the stack in the entity's metadata so they know
+/// which item to render and how many are in it.
+#[derive(Component, Debug, Clone)]
+pub struct DroppedItem {
+    pub stack: InventorySlot,
+    /// Ticks since the item was dropped.
+    pub age: u32,
+}
+
+impl DroppedItem {
+    pub fn new(stack: InventorySlot) -> Self {
+        Self { stack, age: 0 }
+    }
+
+    /// Ages the item by a tick. Returns whether it's old enough to despawn.
+    pub fn tick(&mut self) -> bool {
+        self.age = self.age.saturating_add(1);
+        self.age >= DESPAWN_AGE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_despawns_after_despawn_age() {
+        let mut item = DroppedItem::new(InventorySlot::default());
+        for _ in 1..DESPAWN_AGE {
+            assert!(!item.tick());
+        }
+        assert!(item.tick());
+    }
+}
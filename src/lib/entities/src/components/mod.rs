@@ -1,5 +1,6 @@
 // Core entity components based on ferrumc-data
 pub mod combat;
+pub mod dropped_item;
 pub mod last_synced_position;
 pub mod metadata;
 pub mod physical;
@@ -7,6 +8,7 @@ pub mod spawn;
 
 // Re-exports
 pub use combat::CombatProperties;
+pub use dropped_item::DroppedItem;
 pub use last_synced_position::LastSyncedPosition;
 pub use metadata::EntityMetadata;
 pub use physical::PhysicalProperties;
@@ -8,6 +8,9 @@ use std::fmt::Display;
 use std::io::{Read, Write};
 use tokio::io::{AsyncRead, AsyncWrite};
 
+/// The id of the `minecraft:enchantments` data component.
+pub const ENCHANTMENTS_COMPONENT: i32 = 10;
+
 #[derive(Debug, Clone, Hash, Default, PartialEq)]
 pub struct InventorySlot {
     pub count: VarInt,
@@ -16,6 +19,9 @@ pub struct InventorySlot {
     pub components_to_remove_count: Option<VarInt>,
     pub components_to_add: Option<Vec<VarInt>>,
     pub components_to_remove: Option<Vec<VarInt>>,
+    /// The data of the enchantments component, if it's in `components_to_add`: the registry id
+    /// of each enchantment and its level.
+    pub enchantments: Option<Vec<(VarInt, VarInt)>>,
     // https://minecraft.wiki/w/Java_Edition_protocol/Slot_data
 }
 
@@ -28,6 +34,7 @@ impl InventorySlot {
             components_to_add: None,
             components_to_remove: None,
             components_to_remove_count: None,
+            enchantments: None,
         }
     }
 }
@@ -58,10 +65,24 @@ impl NetDecode for InventorySlot {
             let components_to_add_count = VarInt::decode(reader, opts)?;
             let components_to_remove_count = VarInt::decode(reader, opts)?;
 
+            let mut enchantments = None;
             let components_to_add = {
                 let mut components = Vec::with_capacity(components_to_add_count.0 as usize);
                 for _ in 0..components_to_add_count.0 {
-                    components.push(VarInt::decode(reader, opts)?);
+                    let component = VarInt::decode(reader, opts)?;
+                    // The data of other components isn't decoded yet
+                    if component.0 == ENCHANTMENTS_COMPONENT {
+                        let count = VarInt::decode(reader, opts)?;
+                        let mut levels = Vec::with_capacity(count.0.clamp(0, 64) as usize);
+                        for _ in 0..count.0 {
+                            levels.push((
+                                VarInt::decode(reader, opts)?,
+                                VarInt::decode(reader, opts)?,
+                            ));
+                        }
+                        enchantments = Some(levels);
+                    }
+                    components.push(component);
                 }
                 Some(components)
             };
@@ -80,6 +101,7 @@ impl NetDecode for InventorySlot {
                 components_to_remove_count: Some(components_to_remove_count),
                 components_to_add,
                 components_to_remove,
+                enchantments,
             })
         }
     }
@@ -132,6 +154,14 @@ impl NetEncode for InventorySlot {
         {
             for component in components {
                 component.encode(writer, opts)?;
+                if component.0 == ENCHANTMENTS_COMPONENT {
+                    let enchantments = self.enchantments.as_deref().unwrap_or_default();
+                    VarInt::new(enchantments.len() as i32).encode(writer, opts)?;
+                    for (id, level) in enchantments {
+                        id.encode(writer, opts)?;
+                        level.encode(writer, opts)?;
+                    }
+                }
             }
         }
 
@@ -201,6 +231,7 @@ mod tests {
             components_to_remove_count: Some(VarInt::new(0)),
             components_to_add: Some(vec![]),
             components_to_remove: Some(vec![]),
+            enchantments: None,
         };
 
         let decoded_simple = run_roundtrip_test(&simple_slot);
@@ -214,6 +245,7 @@ mod tests {
             components_to_remove_count: Some(VarInt::new(1)),
             components_to_add: Some(vec![VarInt::new(10), VarInt::new(11)]),
             components_to_remove: Some(vec![VarInt::new(20)]),
+            enchantments: Some(vec![]),
         };
         let decoded_complex = run_roundtrip_test(&complex_slot);
         assert_eq!(
@@ -221,4 +253,21 @@ mod tests {
             "Complex slot roundtrip failed"
         );
     }
+
+    #[test]
+    fn test_slot_enchantments_roundtrip() {
+        let pickaxe = InventorySlot {
+            count: VarInt::new(1),
+            item_id: Some(ItemID::new(872)),
+            components_to_add_count: Some(VarInt::new(1)),
+            components_to_remove_count: Some(VarInt::new(0)),
+            components_to_add: Some(vec![VarInt::new(ENCHANTMENTS_COMPONENT)]),
+            components_to_remove: Some(vec![]),
+            enchantments: Some(vec![
+                (VarInt::new(8), VarInt::new(5)),
+                (VarInt::new(33), VarInt::new(1)),
+            ]),
+        };
+        assert_eq!(pickaxe, run_roundtrip_test(&pickaxe));
+    }
 }
@@ -0,0 +1,21 @@
+use bevy_ecs::prelude::Message;
+use ferrumc_world::dimension::Dimension;
+use ferrumc_world::pos::BlockPos;
+
+/// Message sent when blocks in the world change, so players that have them loaded are sent the
+/// new states. The states are read from the world when the changes are sent, so only the
+/// positions are needed.
+#[derive(Message)]
+pub struct BlocksChanged {
+    pub positions: Vec<BlockPos>,
+    pub dimension: Dimension,
+}
+
+impl BlocksChanged {
+    pub fn single(position: BlockPos, dimension: Dimension) -> Self {
+        Self {
+            positions: vec![position],
+            dimension,
+        }
+    }
+}
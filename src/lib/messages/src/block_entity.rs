@@ -0,0 +1,11 @@
+use bevy_ecs::prelude::Message;
+use ferrumc_world::dimension::Dimension;
+use ferrumc_world::pos::BlockPos;
+
+/// Message sent when the data of a block entity changes, like the text on a sign, so players
+/// that can see it get the new data.
+#[derive(Message)]
+pub struct BlockEntityChanged {
+    pub position: BlockPos,
+    pub dimension: Dimension,
+}
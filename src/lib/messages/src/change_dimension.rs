@@ -0,0 +1,14 @@
+use bevy_ecs::prelude::{Entity, Message};
+use ferrumc_core::transform::position::Position;
+use ferrumc_world::dimension::Dimension;
+
+/// Fired when a player should be moved into another dimension.
+/// This can be triggered by a command or, eventually, by a portal.
+#[derive(Message)]
+pub struct PlayerDimensionChanged {
+    pub player: Entity,
+    pub dimension: Dimension,
+    /// Where the player should arrive. When `None` the player keeps their coordinates, clamped
+    /// to the height of the new dimension.
+    pub position: Option<Position>,
+}
@@ -1,5 +1,8 @@
 use bevy_ecs::prelude::{Entity, Message};
 use ferrumc_core::transform::position::Position;
+use ferrumc_core::transform::velocity::Velocity;
+use ferrumc_inventories::slot::InventorySlot;
+use ferrumc_world::dimension::Dimension;
 
 /// Type of entity to spawn
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
@@ -29,4 +32,18 @@ pub struct SpawnEntityCommand {
 pub struct SpawnEntityEvent {
     pub entity_type: EntityType,
     pub position: Position,
+    pub dimension: Dimension,
+}
+
+/// Event fired when a stack of items should be dropped into the world as an
+/// item entity, like the loot of a broken block.
+///
+/// Unlike `SpawnEntityEvent` the item starts out moving, since dropped items
+/// are thrown a little way from where they come from.
+#[derive(Message)]
+pub struct SpawnItemEvent {
+    pub stack: InventorySlot,
+    pub position: Position,
+    pub velocity: Velocity,
+    pub dimension: Dimension,
 }
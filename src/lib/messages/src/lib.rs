@@ -16,16 +16,24 @@ pub use player_join::*;
 pub mod player_leave;
 pub use player_leave::*;
 
+pub mod change_dimension;
 pub mod change_gamemode;
 pub mod chunk_calc;
 
+pub use change_dimension::*;
 pub use change_gamemode::*;
 
 pub mod entity_spawn;
 pub mod entity_update;
 pub mod particle;
 
-pub use entity_spawn::{EntityType, SpawnEntityCommand, SpawnEntityEvent};
+pub use entity_spawn::{EntityType, SpawnEntityCommand, SpawnEntityEvent, SpawnItemEvent};
 
 pub mod block_break;
 pub use block_break::BlockBrokenEvent;
+
+pub mod block_entity;
+pub use block_entity::BlockEntityChanged;
+
+pub mod block_change;
+pub use block_change::BlocksChanged;
@@ -1,5 +1,6 @@
 use criterion::measurement::WallTime;
 use ferrumc_net_codec::encode::{NetEncode, NetEncodeOpts};
+use ferrumc_world::dimension::Dimension;
 use ferrumc_world::pos::ChunkPos;
 use std::hint::black_box;
 
@@ -9,7 +10,7 @@ pub fn bench_packets(c: &mut criterion::BenchmarkGroup<WallTime>) {
 
 fn bench_chunk_packet(c: &mut criterion::BenchmarkGroup<WallTime>) {
     let chunk = ferrumc_world_gen::WorldGenerator::new(0)
-        .generate_chunk(ChunkPos::new(0, 0))
+        .generate_chunk(ChunkPos::new(0, 0), Dimension::Overworld)
         .unwrap();
     let chunk_packet = black_box(
         ferrumc_net::packets::outgoing::chunk_and_light_data::ChunkAndLightData::from_chunk(
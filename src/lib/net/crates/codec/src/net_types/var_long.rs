@@ -0,0 +1,160 @@
+use crate::decode::errors::NetDecodeError;
+use crate::decode::{NetDecode, NetDecodeOpts};
+use crate::encode::errors::NetEncodeError;
+use crate::encode::{NetEncode, NetEncodeOpts};
+use crate::net_types::NetTypesError;
+use std::fmt::Display;
+use std::io::{Read, Write};
+use tokio::io::AsyncRead;
+use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncReadExt, AsyncWrite};
+
+/// A variable length `i64`, encoded the same way as [`VarInt`](super::var_int::VarInt) but with
+/// up to 10 bytes.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Copy, Hash, Default)]
+pub struct VarLong(pub i64);
+
+impl From<i64> for VarLong {
+    fn from(value: i64) -> Self {
+        Self::new(value)
+    }
+}
+
+const SEGMENT_BITS: i64 = 0x7F;
+const CONTINUE_BIT: i64 = 0x80;
+
+impl Display for VarLong {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl VarLong {
+    pub const fn new(value: i64) -> Self {
+        Self(value)
+    }
+
+    pub fn read<R: Read>(cursor: &mut R) -> Result<Self, NetTypesError> {
+        let mut val = 0;
+        for i in 0..10 {
+            let byte = {
+                let mut buf = [0u8; 1];
+                cursor.read_exact(&mut buf)?;
+                buf[0]
+            } as i64;
+
+            val |= (byte & SEGMENT_BITS) << (7 * i);
+            if byte & CONTINUE_BIT == 0 {
+                return Ok(Self::new(val));
+            }
+        }
+
+        Err(NetTypesError::InvalidVarLong)
+    }
+
+    pub async fn read_async<R: AsyncRead + Unpin>(cursor: &mut R) -> Result<Self, NetTypesError> {
+        let mut val = 0;
+        for i in 0..10 {
+            let byte = {
+                let mut buf = [0u8; 1];
+                cursor.read_exact(&mut buf).await?;
+                buf[0]
+            } as i64;
+
+            val |= (byte & SEGMENT_BITS) << (7 * i);
+            if byte & CONTINUE_BIT == 0 {
+                return Ok(Self::new(val));
+            }
+        }
+
+        Err(NetTypesError::InvalidVarLong)
+    }
+
+    pub fn write<W: Write>(&self, cursor: &mut W) -> Result<(), NetTypesError> {
+        let VarLong(mut val) = self;
+        loop {
+            if (val & !SEGMENT_BITS) == 0 {
+                cursor.write_all(&[val as u8])?;
+                return Ok(());
+            }
+
+            cursor.write_all(&[((val & SEGMENT_BITS) | CONTINUE_BIT) as u8])?;
+            val = ((val as u64) >> 7) as i64;
+        }
+    }
+
+    pub async fn write_async<W: AsyncWrite + Unpin>(
+        &self,
+        cursor: &mut W,
+    ) -> Result<(), NetTypesError> {
+        let VarLong(mut val) = self;
+        loop {
+            if (val & !SEGMENT_BITS) == 0 {
+                cursor.write_all(&[val as u8]).await?;
+                return Ok(());
+            }
+
+            cursor
+                .write_all(&[((val & SEGMENT_BITS) | CONTINUE_BIT) as u8])
+                .await?;
+            val = ((val as u64) >> 7) as i64;
+        }
+    }
+}
+
+impl NetDecode for VarLong {
+    fn decode<R: Read>(reader: &mut R, _opts: &NetDecodeOpts) -> Result<Self, NetDecodeError> {
+        VarLong::read(reader).map_err(|e| NetDecodeError::ExternalError(e.into()))
+    }
+    async fn decode_async<R: AsyncRead + Unpin>(
+        reader: &mut R,
+        _opts: &NetDecodeOpts,
+    ) -> Result<Self, NetDecodeError> {
+        VarLong::read_async(reader)
+            .await
+            .map_err(|e| NetDecodeError::ExternalError(e.into()))
+    }
+}
+
+impl NetEncode for VarLong {
+    fn encode<W: Write>(
+        &self,
+        writer: &mut W,
+        _opts: &NetEncodeOpts,
+    ) -> Result<(), NetEncodeError> {
+        self.write(writer)
+            .map_err(|e| NetEncodeError::ExternalError(e.into()))
+    }
+
+    async fn encode_async<W: AsyncWrite + Unpin>(
+        &self,
+        writer: &mut W,
+        _opts: &NetEncodeOpts,
+    ) -> Result<(), NetEncodeError> {
+        self.write_async(writer)
+            .await
+            .map_err(|e| NetEncodeError::ExternalError(e.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        for (value, len) in [
+            (0, 1),
+            (127, 1),
+            (128, 2),
+            (i64::MAX, 9),
+            (-1, 10),
+            (i64::MIN, 10),
+        ] {
+            let mut buf = Vec::new();
+            VarLong::new(value).write(&mut buf).unwrap();
+            assert_eq!(buf.len(), len);
+            assert_eq!(VarLong::read(&mut buf.as_slice()).unwrap().0, value);
+        }
+    }
+}
@@ -22,6 +22,10 @@ impl<Writer> EncryptedWriter<Writer> {
         self.cipher = Some(Encryptor::new_from_slices(key, key).unwrap());
     }
 
+    pub fn is_encrypted(&self) -> bool {
+        self.cipher.is_some()
+    }
+
     pub fn encrypt_buf(&mut self, buf: &mut [u8]) {
         if let Some(enc) = self.cipher.as_mut() {
             for b in buf.chunks_mut(1) {
@@ -23,9 +23,11 @@ use tracing::{error, trace};
 ///
 /// - `player_identity`: Populated when login is successful and a player is identified.
 /// - `compression`: Indicates whether network compression should be enabled for this connection.
+/// - `view_distance`: The view distance the client asked for, in chunks.
 pub(crate) struct LoginResult {
     pub player_identity: Option<PlayerIdentity>,
     pub compression: bool,
+    pub view_distance: u8,
 }
 
 /// Protocol version supported by this server implementation (Minecraft 1.21.8).
@@ -81,22 +81,35 @@ impl StreamWriter {
                     break;
                 };
 
-                match cmd {
+                let result = match cmd {
                     WriterCommand::SendPacket(mut bytes) => {
-                        // This handles ONLY if there was a writing error to the client.
                         writer.encrypt_buf(&mut bytes);
-                        if let Err(e) = writer.write_all(&bytes).await {
-                            error!("Failed to write to client: {:?}", e);
-                            running_clone.store(false, Ordering::Relaxed);
-                            if let Some(entity_id) = *entity_clone.lock().unwrap() {
-                                state_clone.players.disconnect(entity_id, None);
-                            }
-                            break;
+                        writer.write_all(&bytes).await
+                    }
+                    WriterCommand::SendShared(bytes) => {
+                        // Other connections use the same bytes, so they're only copied when they
+                        // need encrypting
+                        if writer.is_encrypted() {
+                            let mut bytes = bytes.to_vec();
+                            writer.encrypt_buf(&mut bytes);
+                            writer.write_all(&bytes).await
+                        } else {
+                            writer.write_all(&bytes).await
                         }
                     }
                     WriterCommand::CipherKey(new_key) => {
                         writer.update_cipher(&new_key);
+                        Ok(())
+                    }
+                };
+                // This handles ONLY if there was a writing error to the client.
+                if let Err(e) = result {
+                    error!("Failed to write to client: {:?}", e);
+                    running_clone.store(false, Ordering::Relaxed);
+                    if let Some(entity_id) = *entity_clone.lock().unwrap() {
+                        state_clone.players.disconnect(entity_id, None);
                     }
+                    break;
                 }
             }
 
@@ -206,6 +219,21 @@ impl StreamWriter {
         Ok(())
     }
 
+    /// Sends pre-encoded raw bytes that are shared with other connections, like cached chunk
+    /// packets. They're only copied if the connection is encrypted.
+    pub fn send_shared_packet(&self, raw_bytes: Arc<[u8]>) -> Result<(), NetError> {
+        if !self.running.load(Ordering::Relaxed) {
+            #[cfg(debug_assertions)]
+            warn!("Attempted to send shared bytes on closed connection");
+            return Err(NetError::ConnectionDropped);
+        }
+
+        self.sender
+            .send(WriterCommand::SendShared(raw_bytes))
+            .map_err(std::io::Error::other)?;
+        Ok(())
+    }
+
     /// Sends a message to the outgoing packet writer to update its encryption keys
     pub fn update_encryption_cipher(&self, new_key: &[u8]) -> Result<(), NetError> {
         if !self.running.load(Ordering::Relaxed) {
@@ -226,6 +254,7 @@ impl StreamWriter {
 pub struct NewConnection {
     pub stream: StreamWriter,
     pub player_identity: PlayerIdentity,
+    pub view_distance: u8,
     pub entity_return: oneshot::Sender<Entity>,
     pub disconnect_handle: oneshot::Sender<()>,
 }
@@ -336,6 +365,7 @@ pub async fn handle_connection(
         .send(NewConnection {
             stream,
             player_identity: login_result.player_identity.unwrap_or_default(),
+            view_distance: login_result.view_distance,
             entity_return,
             disconnect_handle: disconnect_return,
         })
@@ -436,5 +466,6 @@ pub async fn handle_connection(
 
 enum WriterCommand {
     SendPacket(Vec<u8>),
+    SendShared(Arc<[u8]>),
     CipherKey(Vec<u8>),
 }
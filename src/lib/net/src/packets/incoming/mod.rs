@@ -16,6 +16,7 @@ pub mod player_command;
 pub mod set_player_position;
 pub mod set_player_position_and_rotation;
 pub mod set_player_rotation;
+pub mod sign_update;
 
 pub mod chat_message;
 pub mod command;
@@ -0,0 +1,13 @@
+use ferrumc_macros::{packet, NetDecode};
+use ferrumc_net_codec::net_types::network_position::NetworkPosition;
+
+#[derive(NetDecode, Debug)]
+#[packet(packet_id = "sign_update", state = "play")]
+pub struct SignUpdate {
+    pub location: NetworkPosition,
+    pub is_front_text: bool,
+    pub line_1: String,
+    pub line_2: String,
+    pub line_3: String,
+    pub line_4: String,
+}
@@ -0,0 +1,12 @@
+use ferrumc_macros::{packet, NetEncode};
+use ferrumc_net_codec::net_types::network_position::NetworkPosition;
+use ferrumc_net_codec::net_types::var_int::VarInt;
+
+#[derive(NetEncode)]
+#[packet(packet_id = "block_entity_data", state = "play")]
+pub struct BlockEntityData {
+    pub location: NetworkPosition,
+    pub block_entity_type: VarInt,
+    /// The block entity's tags as a network NBT compound
+    pub nbt: Vec<u8>,
+}
@@ -5,10 +5,11 @@ use ferrumc_net_codec::net_types::bitset::BitSet;
 use ferrumc_net_codec::net_types::byte_array::ByteArray;
 use ferrumc_net_codec::net_types::length_prefixed_vec::LengthPrefixedVec;
 use ferrumc_net_codec::net_types::var_int::VarInt;
-use ferrumc_world::chunk_format::{Chunk, PaletteType};
+use ferrumc_world::biomes::DIRECT_BITS_PER_BIOME;
+use ferrumc_world::chunk_format::{BiomeStates, Chunk, PaletteType};
+use ferrumc_world::heightmaps::HeightmapType;
 use ferrumc_world::pos::ChunkPos;
 use std::io::Cursor;
-use std::ops::Not;
 use tracing::warn;
 
 const SECTIONS: usize = 24; // Number of sections, adjust for your Y range (-64 to 319)
@@ -74,30 +75,7 @@ impl ChunkAndLightData {
 
     pub fn from_chunk(pos: ChunkPos, chunk: &Chunk) -> Result<Self, NetError> {
         let mut raw_data = Cursor::new(Vec::new());
-        let mut sky_light_data = Vec::new();
-        let mut block_light_data = Vec::new();
         for section in &chunk.sections {
-            let section_sky_light_data = if section.sky_light.len() != 2048 {
-                warn!(
-                    "Sky light data for section at {} is not 2048 bytes long",
-                    pos
-                );
-                vec![255; 2048]
-            } else {
-                section.sky_light.clone()
-            };
-            sky_light_data.push(section_sky_light_data);
-            let section_block_light_data = if section.block_light.len() != 2048 {
-                warn!(
-                    "Block light data for section at {} is not 2048 bytes long",
-                    pos
-                );
-                vec![255; 2048]
-            } else {
-                section.block_light.clone()
-            };
-            block_light_data.push(section_block_light_data);
-
             raw_data.write_u16::<BigEndian>(section.block_states.non_air_blocks)?;
 
             match &section.block_states.block_data {
@@ -128,59 +106,83 @@ impl ChunkAndLightData {
                 }
             }
 
-            // Empty biome data for now
-            raw_data.write_u8(0)?;
-            // Forest biome id
-            raw_data.write_u8(21)?;
+            write_biomes(&section.biome_states, &mut raw_data)?;
         }
-        let mut sky_light_mask = BitSet::new(SECTIONS + 2);
-        let mut block_light_mask = BitSet::new(SECTIONS + 2);
+        // The light masks have one bit per section plus one for the section below the world
+        // (bit 0) and one for the section above it (the last bit).
+        let light_sections = chunk.sections.len() + 2;
+        let mut sky_light_mask = BitSet::new(light_sections);
+        let mut block_light_mask = BitSet::new(light_sections);
+        let mut empty_sky_light_mask = BitSet::new(light_sections);
+        let mut empty_block_light_mask = BitSet::new(light_sections);
+        let mut sky_light_arrays = Vec::new();
+        let mut block_light_arrays = Vec::new();
+
+        // Nothing is ever lit below the world.
+        empty_sky_light_mask.set(0, true);
+        empty_block_light_mask.set(0, true);
 
-        // Populate masks based on light data
         for (i, section) in chunk.sections.iter().enumerate() {
-            if !section.sky_light.is_empty() && section.sky_light.len() == 2048 {
-                sky_light_mask.set(i, true);
+            let bit = i + 1;
+            if section.sky_light.len() != 2048 {
+                warn!(
+                    "Sky light data for section at {} is not 2048 bytes long",
+                    pos
+                );
+                empty_sky_light_mask.set(bit, true);
+            } else if section.sky_light.iter().all(|&b| b == 0) {
+                empty_sky_light_mask.set(bit, true);
+            } else {
+                sky_light_mask.set(bit, true);
+                sky_light_arrays.push(ByteArray::new(section.sky_light.clone()));
             }
-            if !section.block_light.is_empty() && section.block_light.len() == 2048 {
-                block_light_mask.set(i, true);
+            if section.block_light.len() != 2048 {
+                warn!(
+                    "Block light data for section at {} is not 2048 bytes long",
+                    pos
+                );
+                empty_block_light_mask.set(bit, true);
+            } else if section.block_light.iter().all(|&b| b == 0) {
+                empty_block_light_mask.set(bit, true);
+            } else {
+                block_light_mask.set(bit, true);
+                block_light_arrays.push(ByteArray::new(section.block_light.clone()));
             }
         }
 
-        // Invert masks to create empty masks
-        let empty_sky_light_mask = sky_light_mask.clone().not();
-        let empty_block_light_mask = block_light_mask.clone().not();
+        // Everything above the world sees the sky.
+        sky_light_mask.set(light_sections - 1, true);
+        sky_light_arrays.push(ByteArray::new(vec![0xFF; 2048]));
+        empty_block_light_mask.set(light_sections - 1, true);
 
-        // Align light arrays with masks
-        let sky_light_arrays = chunk
-            .sections
-            .iter()
-            .filter(|section| !section.sky_light.is_empty())
-            .map(|section| ByteArray::new(section.sky_light.clone()))
+        let heightmaps = HeightmapType::CLIENT
+            .into_iter()
+            .map(|kind| NetHeightmap {
+                id: VarInt::new(kind.protocol_id()),
+                data: LengthPrefixedVec::new(chunk.heightmaps.get(kind).clone()),
+            })
             .collect();
 
-        let block_light_arrays = chunk
-            .sections
+        let block_entities = chunk
+            .block_entities
             .iter()
-            .filter(|section| !section.block_light.is_empty())
-            .map(|section| ByteArray::new(section.block_light.clone()))
+            .map(|block_entity| {
+                let block_pos = block_entity.pos();
+                BlockEntity {
+                    xz: (block_pos.x() << 4) | block_pos.z(),
+                    y: block_pos.y() as u16,
+                    entity_type: VarInt::new(block_entity.kind.registry_id()),
+                    nbt: block_entity.network_data().to_vec(),
+                }
+            })
             .collect();
-        let heightmaps = vec![
-            NetHeightmap {
-                id: VarInt::new(1), // Placeholder for heightmap ID
-                data: LengthPrefixedVec::new(chunk.heightmaps.world_surface.clone()),
-            },
-            NetHeightmap {
-                id: VarInt::new(4), // Placeholder for heightmap ID
-                data: LengthPrefixedVec::new(chunk.heightmaps.motion_blocking.clone()),
-            },
-        ];
 
         Ok(ChunkAndLightData {
             chunk_x: pos.x(),
             chunk_z: pos.z(),
             heightmaps: LengthPrefixedVec::new(heightmaps),
             data: ByteArray::new(raw_data.into_inner()),
-            block_entities: LengthPrefixedVec::new(Vec::new()),
+            block_entities: LengthPrefixedVec::new(block_entities),
             sky_light_mask,
             block_light_mask,
             empty_sky_light_mask,
@@ -190,3 +192,34 @@ impl ChunkAndLightData {
         })
     }
 }
+
+/// Writes a section's biomes as a paletted container. Sections with more biomes than fit in the
+/// client's 3-bit palette are sent as raw biome ids instead.
+fn write_biomes(biomes: &BiomeStates, out: &mut Cursor<Vec<u8>>) -> Result<(), NetError> {
+    if biomes.palette.len() <= 1 || biomes.data.is_empty() {
+        out.write_u8(0)?;
+        biomes.get_id(0)?.write(out)?;
+    } else if biomes.bits_per_biome <= 3 {
+        out.write_u8(biomes.bits_per_biome)?;
+        VarInt::new(biomes.palette.len() as i32).write(out)?;
+        for entry in &biomes.palette {
+            entry.write(out)?;
+        }
+        for long in &biomes.data {
+            out.write_i64::<BigEndian>(*long)?;
+        }
+    } else {
+        let bits = DIRECT_BITS_PER_BIOME as usize;
+        let per_long = 64 / bits;
+        let mut data = vec![0u64; 64usize.div_ceil(per_long)];
+        for index in 0..64 {
+            let id = biomes.get_id(index)?.0 as u64;
+            data[index / per_long] |= id << ((index % per_long) * bits);
+        }
+        out.write_u8(DIRECT_BITS_PER_BIOME)?;
+        for long in data {
+            out.write_u64::<BigEndian>(long)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,18 @@
+use ferrumc_macros::{packet, NetEncode};
+
+/// Tells the client to unload a chunk. The coordinates are sent z first.
+#[derive(NetEncode)]
+#[packet(packet_id = "forget_level_chunk", state = "play")]
+pub struct ForgetLevelChunk {
+    pub chunk_z: i32,
+    pub chunk_x: i32,
+}
+
+impl ForgetLevelChunk {
+    pub fn new(x: i32, z: i32) -> Self {
+        Self {
+            chunk_z: z,
+            chunk_x: x,
+        }
+    }
+}
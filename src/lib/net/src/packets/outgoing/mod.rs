@@ -4,6 +4,7 @@ pub mod chunk_batch_start;
 pub mod client_bound_known_packs;
 pub mod disconnect;
 pub mod finish_configuration;
+pub mod forget_level_chunk;
 pub mod game_event;
 pub mod keep_alive;
 pub mod login_disconnect;
@@ -11,6 +12,7 @@ pub mod login_play;
 pub mod login_success;
 pub mod ping_response;
 pub mod registry_data;
+pub mod respawn;
 pub mod set_center_chunk;
 pub mod set_default_spawn_position;
 pub mod set_held_slot;
@@ -38,7 +40,10 @@ pub mod update_entity_rotation;
 
 pub mod block_change_ack;
 
+pub mod block_entity_data;
 pub mod block_update;
+pub mod open_sign_editor;
+pub mod update_section_blocks;
 
 pub mod command_suggestions;
 pub mod commands;
@@ -0,0 +1,9 @@
+use ferrumc_macros::{packet, NetEncode};
+use ferrumc_net_codec::net_types::network_position::NetworkPosition;
+
+#[derive(NetEncode)]
+#[packet(packet_id = "open_sign_editor", state = "play")]
+pub struct OpenSignEditor {
+    pub location: NetworkPosition,
+    pub is_front_text: bool,
+}
@@ -0,0 +1,57 @@
+use ferrumc_macros::{packet, NetEncode};
+use ferrumc_net_codec::net_types::network_position::NetworkPosition;
+use ferrumc_net_codec::net_types::prefixed_optional::PrefixedOptional;
+use ferrumc_net_codec::net_types::var_int::VarInt;
+use ferrumc_world::dimension::Dimension;
+
+/// Moves the client into another dimension (or reloads the current one).
+///
+/// The client drops every chunk it has loaded and shows the loading screen until it receives
+/// the chunks around its new position.
+#[derive(NetEncode)]
+#[packet(packet_id = "respawn", state = "play")]
+pub struct RespawnPacket<'a> {
+    pub dimension_type: VarInt,
+    pub dimension_name: &'a str,
+    pub seed_hash: i64,
+    pub gamemode: u8,
+    pub previous_gamemode: i8,
+    pub is_debug: bool,
+    pub is_flat: bool,
+    pub death_location: PrefixedOptional<DeathLocation<'a>>,
+    pub portal_cooldown: VarInt,
+    pub sea_level: VarInt,
+    pub data_kept: u8,
+}
+
+/// Where the player last died, shown by recovery compasses.
+#[derive(NetEncode)]
+pub struct DeathLocation<'a> {
+    pub dimension_name: &'a str,
+    pub location: NetworkPosition,
+}
+
+impl RespawnPacket<'_> {
+    /// Keep the player's attributes when respawning.
+    pub const KEEP_ATTRIBUTES: u8 = 0x01;
+    /// Keep the player's entity metadata (health, effects, etc.) when respawning.
+    pub const KEEP_METADATA: u8 = 0x02;
+
+    /// A respawn into `dimension` that keeps all of the player's data, as when travelling
+    /// through a portal.
+    pub fn new(dimension: Dimension, gamemode: u8) -> Self {
+        Self {
+            dimension_type: VarInt::new(dimension.registry_id()),
+            dimension_name: dimension.identifier(),
+            seed_hash: 0,
+            gamemode,
+            previous_gamemode: -1,
+            is_debug: false,
+            is_flat: false,
+            death_location: PrefixedOptional::None,
+            portal_cooldown: VarInt::from(0),
+            sea_level: VarInt::from(dimension.sea_level()),
+            data_kept: Self::KEEP_ATTRIBUTES | Self::KEEP_METADATA,
+        }
+    }
+}
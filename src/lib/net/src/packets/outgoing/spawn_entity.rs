@@ -5,6 +5,7 @@ use ferrumc_core::identity::entity_identity::EntityIdentity;
 use ferrumc_core::identity::player_identity::PlayerIdentity;
 use ferrumc_core::transform::position::Position;
 use ferrumc_core::transform::rotation::Rotation;
+use ferrumc_core::transform::velocity::Velocity;
 use ferrumc_macros::{get_registry_entry, packet, NetEncode};
 use ferrumc_net_codec::net_types::angle::NetAngle;
 use ferrumc_net_codec::net_types::var_int::VarInt;
@@ -61,6 +62,27 @@ impl SpawnEntityPacket {
         }
     }
 
+    /// Sets the velocity the entity starts moving at, in blocks per tick.
+    pub fn with_velocity(mut self, velocity: &Velocity) -> Self {
+        // Sent in units of 1/8000 of a block per tick
+        let encode = |v: f32| (v.clamp(-3.9, 3.9) * 8000.0) as i16;
+        self.velocity_x = encode(velocity.x);
+        self.velocity_y = encode(velocity.y);
+        self.velocity_z = encode(velocity.z);
+        self
+    }
+
+    /// Creates a spawn entity packet for a player at the given position.
+    pub fn for_player(identity: &PlayerIdentity, position: &Position, rotation: &Rotation) -> Self {
+        Self::new(
+            identity.short_uuid,
+            identity.uuid.as_u128(),
+            PLAYER_ID as i32,
+            position,
+            rotation,
+        )
+    }
+
     pub fn player(
         entity_id: Entity,
         query: Query<(&PlayerIdentity, &Position, &Rotation)>,
@@ -0,0 +1,52 @@
+use ferrumc_macros::{packet, NetEncode};
+use ferrumc_net_codec::net_types::length_prefixed_vec::LengthPrefixedVec;
+use ferrumc_net_codec::net_types::var_long::VarLong;
+use ferrumc_world::block_state_id::BlockStateId;
+use ferrumc_world::pos::{BlockPos, SectionPos};
+
+/// Changes several blocks in one section at once. Use [`BlockUpdate`](super::block_update::BlockUpdate)
+/// for single blocks.
+#[derive(NetEncode)]
+#[packet(packet_id = "section_blocks_update", state = "play")]
+pub struct UpdateSectionBlocks {
+    pub section: i64,
+    /// The block state id shifted left by 12, followed by the position in the section as
+    /// `x << 8 | z << 4 | y`.
+    pub blocks: LengthPrefixedVec<VarLong>,
+}
+
+impl UpdateSectionBlocks {
+    /// All the blocks have to be in `section`.
+    pub fn new(
+        section: SectionPos,
+        blocks: impl IntoIterator<Item = (BlockPos, BlockStateId)>,
+    ) -> Self {
+        let blocks = blocks
+            .into_iter()
+            .map(|(pos, block)| {
+                let local = (pos.pos & 15).as_i64vec3();
+                VarLong::new((block.raw() as i64) << 12 | local.x << 8 | local.z << 4 | local.y)
+            })
+            .collect();
+        Self {
+            section: section.pack(),
+            blocks: LengthPrefixedVec::new(blocks),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packs_positions() {
+        let pos = BlockPos::of(-15, -60, 35);
+        let packet = UpdateSectionBlocks::new(pos.section(), [(pos, BlockStateId::new(9))]);
+        assert_eq!(packet.section, 0x3FFFFF << 42 | 2 << 20 | (-4i64 & 0xFFFFF));
+        assert_eq!(
+            packet.blocks.data,
+            vec![VarLong::new(9 << 12 | 1 << 8 | 3 << 4 | 4)]
+        );
+    }
+}
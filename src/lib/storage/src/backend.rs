@@ -0,0 +1,43 @@
+use crate::errors::StorageError;
+
+/// A key-value store the world is persisted in.
+///
+/// Data is split into named tables, each mapping `u128` keys to raw bytes. Tables have to be
+/// created before they can be read from, but the methods that write in bulk create them as
+/// needed.
+pub trait StorageBackend: Send + Sync {
+    /// Inserts a new key, failing if it already exists.
+    fn insert(&self, table: String, key: u128, value: Vec<u8>) -> Result<(), StorageError>;
+
+    fn get(&self, table: String, key: u128) -> Result<Option<Vec<u8>>, StorageError>;
+
+    /// Looks up several keys at once, the results are in the same order as the keys.
+    fn batch_get(
+        &self,
+        table: String,
+        keys: Vec<u128>,
+    ) -> Result<Vec<Option<Vec<u8>>>, StorageError>;
+
+    /// Inserts or replaces a key. Returns whether the value was written.
+    fn upsert(&self, table: String, key: u128, value: Vec<u8>) -> Result<bool, StorageError>;
+
+    fn batch_upsert(&self, table: String, data: Vec<(u128, Vec<u8>)>) -> Result<(), StorageError>;
+
+    fn exists(&self, table: String, key: u128) -> Result<bool, StorageError>;
+
+    /// Deletes a key, failing if it doesn't exist.
+    fn delete(&self, table: String, key: u128) -> Result<(), StorageError>;
+
+    /// Every key in the table, in ascending order.
+    fn keys(&self, table: String) -> Result<Vec<u128>, StorageError>;
+
+    fn table_exists(&self, table: String) -> Result<bool, StorageError>;
+
+    fn create_table(&self, table: String) -> Result<(), StorageError>;
+
+    /// Makes sure everything written so far is durable.
+    fn flush(&self) -> Result<(), StorageError>;
+
+    /// A short description of the backend for logging.
+    fn details(&self) -> String;
+}
@@ -1,3 +1,4 @@
+use ferrumc_storage::backend::StorageBackend;
 use ferrumc_storage::lmdb::LmdbBackend;
 use rand::Rng;
 use std::collections::HashSet;
@@ -4,6 +4,8 @@ use crate::compressors::gzip::{compress_gzip, decompress_gzip};
 use crate::compressors::zlib::{compress_zlib, decompress_zlib};
 use crate::compressors::zstd::{compress_zstd, decompress_zstd};
 use crate::errors::StorageError;
+use std::fmt::Display;
+use std::str::FromStr;
 
 pub mod brotli;
 pub mod deflate;
@@ -11,7 +13,7 @@ pub mod gzip;
 pub mod zlib;
 pub mod zstd;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum CompressorType {
     Gzip,
     Zstd,
@@ -20,7 +22,72 @@ pub enum CompressorType {
     Zlib,
 }
 
-#[derive(Clone, Copy)]
+impl CompressorType {
+    pub const ALL: [CompressorType; 5] = [
+        CompressorType::Gzip,
+        CompressorType::Zstd,
+        CompressorType::Brotli,
+        CompressorType::Deflate,
+        CompressorType::Zlib,
+    ];
+
+    /// The name used for the algorithm in the config.
+    pub const fn name(self) -> &'static str {
+        match self {
+            CompressorType::Gzip => "gzip",
+            CompressorType::Zstd => "zstd",
+            CompressorType::Brotli => "brotli",
+            CompressorType::Deflate => "deflate",
+            CompressorType::Zlib => "zlib",
+        }
+    }
+
+    /// A stable identifier for the algorithm, for tagging stored data with how it was compressed.
+    /// These must never change or existing data won't decompress.
+    pub const fn id(self) -> u8 {
+        match self {
+            CompressorType::Gzip => 1,
+            CompressorType::Zstd => 2,
+            CompressorType::Brotli => 3,
+            CompressorType::Deflate => 4,
+            CompressorType::Zlib => 5,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        CompressorType::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.id() == id)
+    }
+
+    /// The highest compression level the algorithm accepts.
+    pub const fn max_level(self) -> u32 {
+        match self {
+            CompressorType::Zstd => 22,
+            CompressorType::Brotli => 11,
+            CompressorType::Gzip | CompressorType::Deflate | CompressorType::Zlib => 9,
+        }
+    }
+}
+
+impl Display for CompressorType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for CompressorType {
+    type Err = StorageError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CompressorType::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| StorageError::InvalidCompressor(s.to_string()))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Compressor {
     pub algorithm: CompressorType,
     pub level: u32,
@@ -31,6 +98,18 @@ impl Compressor {
         Self { algorithm, level }
     }
 
+    /// Like [`Compressor::create`], but fails if the level is out of range for the algorithm
+    /// instead of leaving it to the algorithm to clamp or reject.
+    pub fn checked(algorithm: CompressorType, level: u32) -> Result<Self, StorageError> {
+        if level > algorithm.max_level() {
+            return Err(StorageError::InvalidCompressor(format!(
+                "level {level} is out of range for {algorithm} (0-{})",
+                algorithm.max_level()
+            )));
+        }
+        Ok(Self::create(algorithm, level))
+    }
+
     pub fn compress(&self, data: &[u8]) -> Result<Vec<u8>, StorageError> {
         match self.algorithm {
             CompressorType::Gzip => compress_gzip(self.level, data),
@@ -51,3 +130,27 @@ impl Compressor {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_ids() {
+        for algorithm in CompressorType::ALL {
+            assert_eq!(
+                algorithm.name().parse::<CompressorType>().unwrap(),
+                algorithm
+            );
+            assert_eq!(CompressorType::from_id(algorithm.id()), Some(algorithm));
+        }
+        assert_eq!(
+            "ZSTD".parse::<CompressorType>().unwrap(),
+            CompressorType::Zstd
+        );
+        assert!("lz4".parse::<CompressorType>().is_err());
+        assert_eq!(CompressorType::from_id(0), None);
+        assert!(Compressor::checked(CompressorType::Zstd, 22).is_ok());
+        assert!(Compressor::checked(CompressorType::Gzip, 10).is_err());
+    }
+}
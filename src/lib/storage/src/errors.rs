@@ -9,6 +9,8 @@ pub enum StorageError {
     CompressionError(String),
     #[error("Decompression error: {0}")]
     DecompressionError(String),
+    #[error("Invalid compressor: {0}")]
+    InvalidCompressor(String),
     #[error("Invalid path")]
     InvalidPath,
     #[error("Failed to write to database: {0}")]
@@ -1,2 +1,5 @@
+pub mod backend;
+pub mod compressors;
 pub mod errors;
 pub mod lmdb;
+pub mod memory;
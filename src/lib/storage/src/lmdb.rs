@@ -1,7 +1,8 @@
+use crate::backend::StorageBackend;
 use crate::errors::StorageError;
 use heed;
 use heed::byteorder::BigEndian;
-use heed::types::{Bytes, U128};
+use heed::types::{Bytes, DecodeIgnore, U128};
 use heed::{Database, Env, EnvOpenOptions, WithoutTls};
 use parking_lot::Mutex;
 use std::collections::HashMap;
@@ -60,7 +61,51 @@ impl LmdbBackend {
         }
     }
 
-    pub fn insert(&self, table: String, key: u128, value: Vec<u8>) -> Result<(), StorageError> {
+    pub fn update(&self, table: String, key: u128, value: Vec<u8>) -> Result<(), StorageError> {
+        let env = self.env.lock();
+        let mut rw_txn = env.write_txn()?;
+        let db: Database<U128<BigEndian>, Bytes> = env
+            .open_database(&rw_txn, Some(&table))?
+            .ok_or(StorageError::TableError("Table not found".to_string()))?;
+        if db.get(&rw_txn, &key)?.is_none() {
+            return Err(StorageError::KeyNotFound(key as u64));
+        }
+        db.put(&mut rw_txn, &key, &value)?;
+        rw_txn.commit()?;
+        Ok(())
+    }
+
+    pub fn batch_insert(
+        &self,
+        table: String,
+        data: Vec<(u128, Vec<u8>)>,
+    ) -> Result<(), StorageError> {
+        let env = self.env.lock();
+        let mut rw_txn = env.write_txn()?;
+        let db = env.create_database::<U128<BigEndian>, Bytes>(&mut rw_txn, Some(&table))?;
+
+        let keymap: HashMap<u128, &Vec<u8>> = data.iter().map(|(k, v)| (*k, v)).collect();
+        let mut sorted_keys: Vec<u128> = keymap.keys().cloned().collect();
+        sorted_keys.sort();
+
+        for key in sorted_keys {
+            if db.get(&rw_txn, &key)?.is_some() {
+                return Err(StorageError::KeyExists(key as u64));
+            }
+            db.put(&mut rw_txn, &key, keymap[&key])?;
+        }
+        rw_txn.commit()?;
+        Ok(())
+    }
+
+    pub fn close(&self) -> Result<(), StorageError> {
+        self.flush()?;
+        Ok(())
+    }
+}
+
+impl StorageBackend for LmdbBackend {
+    fn insert(&self, table: String, key: u128, value: Vec<u8>) -> Result<(), StorageError> {
         let env = self.env.lock();
         let mut rw_txn = env.write_txn()?;
         let db: Database<U128<BigEndian>, Bytes> =
@@ -73,7 +118,7 @@ impl LmdbBackend {
         Ok(())
     }
 
-    pub fn get(&self, table: String, key: u128) -> Result<Option<Vec<u8>>, StorageError> {
+    fn get(&self, table: String, key: u128) -> Result<Option<Vec<u8>>, StorageError> {
         let env = self.env.lock();
         let ro_txn = env.read_txn()?;
         let db: Database<U128<BigEndian>, Bytes> = env
@@ -87,7 +132,7 @@ impl LmdbBackend {
         }
     }
 
-    pub fn delete(&self, table: String, key: u128) -> Result<(), StorageError> {
+    fn delete(&self, table: String, key: u128) -> Result<(), StorageError> {
         let env = self.env.lock();
         let mut rw_txn = env.write_txn()?;
         let db: Database<U128<BigEndian>, Bytes> = env
@@ -101,21 +146,7 @@ impl LmdbBackend {
         Ok(())
     }
 
-    pub fn update(&self, table: String, key: u128, value: Vec<u8>) -> Result<(), StorageError> {
-        let env = self.env.lock();
-        let mut rw_txn = env.write_txn()?;
-        let db: Database<U128<BigEndian>, Bytes> = env
-            .open_database(&rw_txn, Some(&table))?
-            .ok_or(StorageError::TableError("Table not found".to_string()))?;
-        if db.get(&rw_txn, &key)?.is_none() {
-            return Err(StorageError::KeyNotFound(key as u64));
-        }
-        db.put(&mut rw_txn, &key, &value)?;
-        rw_txn.commit()?;
-        Ok(())
-    }
-
-    pub fn upsert(&self, table: String, key: u128, value: Vec<u8>) -> Result<bool, StorageError> {
+    fn upsert(&self, table: String, key: u128, value: Vec<u8>) -> Result<bool, StorageError> {
         let env = self.env.lock();
         let mut rw_txn = env.write_txn()?;
         let db: Database<U128<BigEndian>, Bytes> = env
@@ -126,11 +157,7 @@ impl LmdbBackend {
         Ok(true)
     }
 
-    pub fn batch_upsert(
-        &self,
-        table: String,
-        data: Vec<(u128, Vec<u8>)>,
-    ) -> Result<(), StorageError> {
+    fn batch_upsert(&self, table: String, data: Vec<(u128, Vec<u8>)>) -> Result<(), StorageError> {
         let env = self.env.lock();
         let mut rw_txn = env.write_txn()?;
 
@@ -161,7 +188,7 @@ impl LmdbBackend {
         Ok(())
     }
 
-    pub fn exists(&self, table: String, key: u128) -> Result<bool, StorageError> {
+    fn exists(&self, table: String, key: u128) -> Result<bool, StorageError> {
         let env = self.env.lock();
         let ro_txn = env.read_txn()?;
         let db: Database<U128<BigEndian>, Bytes> = env
@@ -170,41 +197,18 @@ impl LmdbBackend {
         Ok(db.get(&ro_txn, &key)?.is_some())
     }
 
-    pub fn table_exists(&self, table: String) -> Result<bool, StorageError> {
+    fn table_exists(&self, table: String) -> Result<bool, StorageError> {
         let env = self.env.lock();
         let ro_txn = env.read_txn()?;
         let db = env.open_database::<U128<BigEndian>, Bytes>(&ro_txn, Some(&table))?;
         Ok(db.is_some())
     }
 
-    pub fn details(&self) -> String {
+    fn details(&self) -> String {
         format!("LMDB (heed 0.20.5): {:?}", self.env.lock().info())
     }
 
-    pub fn batch_insert(
-        &self,
-        table: String,
-        data: Vec<(u128, Vec<u8>)>,
-    ) -> Result<(), StorageError> {
-        let env = self.env.lock();
-        let mut rw_txn = env.write_txn()?;
-        let db = env.create_database::<U128<BigEndian>, Bytes>(&mut rw_txn, Some(&table))?;
-
-        let keymap: HashMap<u128, &Vec<u8>> = data.iter().map(|(k, v)| (*k, v)).collect();
-        let mut sorted_keys: Vec<u128> = keymap.keys().cloned().collect();
-        sorted_keys.sort();
-
-        for key in sorted_keys {
-            if db.get(&rw_txn, &key)?.is_some() {
-                return Err(StorageError::KeyExists(key as u64));
-            }
-            db.put(&mut rw_txn, &key, keymap[&key])?;
-        }
-        rw_txn.commit()?;
-        Ok(())
-    }
-
-    pub fn batch_get(
+    fn batch_get(
         &self,
         table: String,
         keys: Vec<u128>,
@@ -226,25 +230,34 @@ impl LmdbBackend {
         Ok(values)
     }
 
-    pub fn flush(&self) -> Result<(), StorageError> {
+    fn keys(&self, table: String) -> Result<Vec<u128>, StorageError> {
+        let env = self.env.lock();
+        let ro_txn = env.read_txn()?;
+        let db: Database<U128<BigEndian>, DecodeIgnore> = env
+            .open_database(&ro_txn, Some(&table))?
+            .ok_or(StorageError::TableError("Table not found".to_string()))?;
+        let mut keys = Vec::new();
+        for entry in db.iter(&ro_txn)? {
+            let (key, ()) = entry?;
+            keys.push(key);
+        }
+        Ok(keys)
+    }
+
+    fn flush(&self) -> Result<(), StorageError> {
         let env = self.env.lock();
         env.clear_stale_readers()?;
         env.force_sync()?;
         Ok(())
     }
 
-    pub fn create_table(&self, table: String) -> Result<(), StorageError> {
+    fn create_table(&self, table: String) -> Result<(), StorageError> {
         let env = self.env.lock();
         let mut rw_txn = env.write_txn()?;
         env.create_database::<U128<BigEndian>, Bytes>(&mut rw_txn, Some(&table))?;
         rw_txn.commit()?;
         Ok(())
     }
-
-    pub fn close(&self) -> Result<(), StorageError> {
-        self.flush()?;
-        Ok(())
-    }
 }
 
 #[cfg(test)]
@@ -299,6 +312,23 @@ mod tests {
         remove_dir_all(path).unwrap();
     }
 
+    #[test]
+    fn test_keys() {
+        let path = tempdir().unwrap().keep();
+        {
+            let backend = LmdbBackend::initialize(Some(path.clone())).unwrap();
+            backend.create_table("test_table".to_string()).unwrap();
+            for key in [30u128, 10, 20] {
+                backend
+                    .insert("test_table".to_string(), key, vec![key as u8])
+                    .unwrap();
+            }
+            let keys = backend.keys("test_table".to_string()).unwrap();
+            assert_eq!(keys, vec![10, 20, 30]);
+        }
+        remove_dir_all(path).unwrap();
+    }
+
     #[test]
     fn test_concurrent_write() {
         let path = tempdir().unwrap().keep();
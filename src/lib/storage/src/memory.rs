@@ -0,0 +1,144 @@
+use crate::backend::StorageBackend;
+use crate::errors::StorageError;
+use parking_lot::RwLock;
+use std::collections::{BTreeMap, HashMap};
+
+type Table = BTreeMap<u128, Vec<u8>>;
+
+/// A backend that keeps everything in memory and loses it when dropped.
+///
+/// Meant for tests and tools that don't need their data to outlive them. It behaves like
+/// [`crate::lmdb::LmdbBackend`], including the errors for missing tables and keys.
+#[derive(Debug, Default)]
+pub struct MemoryBackend {
+    tables: RwLock<HashMap<String, Table>>,
+}
+
+fn table_not_found() -> StorageError {
+    StorageError::TableError("Table not found".to_string())
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl StorageBackend for MemoryBackend {
+    fn insert(&self, table: String, key: u128, value: Vec<u8>) -> Result<(), StorageError> {
+        let mut tables = self.tables.write();
+        let table = tables.entry(table).or_default();
+        if table.contains_key(&key) {
+            return Err(StorageError::KeyExists(key as u64));
+        }
+        table.insert(key, value);
+        Ok(())
+    }
+
+    fn get(&self, table: String, key: u128) -> Result<Option<Vec<u8>>, StorageError> {
+        let tables = self.tables.read();
+        let table = tables.get(&table).ok_or_else(table_not_found)?;
+        Ok(table.get(&key).cloned())
+    }
+
+    fn batch_get(
+        &self,
+        table: String,
+        keys: Vec<u128>,
+    ) -> Result<Vec<Option<Vec<u8>>>, StorageError> {
+        let tables = self.tables.read();
+        let table = tables.get(&table).ok_or_else(table_not_found)?;
+        Ok(keys.iter().map(|key| table.get(key).cloned()).collect())
+    }
+
+    fn upsert(&self, table: String, key: u128, value: Vec<u8>) -> Result<bool, StorageError> {
+        let mut tables = self.tables.write();
+        let table = tables.get_mut(&table).ok_or_else(table_not_found)?;
+        table.insert(key, value);
+        Ok(true)
+    }
+
+    fn batch_upsert(&self, table: String, data: Vec<(u128, Vec<u8>)>) -> Result<(), StorageError> {
+        let mut tables = self.tables.write();
+        tables.entry(table).or_default().extend(data);
+        Ok(())
+    }
+
+    fn exists(&self, table: String, key: u128) -> Result<bool, StorageError> {
+        let tables = self.tables.read();
+        let table = tables.get(&table).ok_or_else(table_not_found)?;
+        Ok(table.contains_key(&key))
+    }
+
+    fn delete(&self, table: String, key: u128) -> Result<(), StorageError> {
+        let mut tables = self.tables.write();
+        let table = tables.get_mut(&table).ok_or_else(table_not_found)?;
+        table
+            .remove(&key)
+            .map(|_| ())
+            .ok_or(StorageError::KeyNotFound(key as u64))
+    }
+
+    fn keys(&self, table: String) -> Result<Vec<u128>, StorageError> {
+        let tables = self.tables.read();
+        let table = tables.get(&table).ok_or_else(table_not_found)?;
+        Ok(table.keys().copied().collect())
+    }
+
+    fn table_exists(&self, table: String) -> Result<bool, StorageError> {
+        Ok(self.tables.read().contains_key(&table))
+    }
+
+    fn create_table(&self, table: String) -> Result<(), StorageError> {
+        self.tables.write().entry(table).or_default();
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    fn details(&self) -> String {
+        format!("In-memory ({} tables)", self.tables.read().len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_lmdb_semantics() {
+        let backend = MemoryBackend::new();
+        let table = || "test_table".to_string();
+
+        assert!(!backend.table_exists(table()).unwrap());
+        assert!(matches!(
+            backend.get(table(), 1),
+            Err(StorageError::TableError(_))
+        ));
+        assert!(backend.upsert(table(), 1, vec![1]).is_err());
+
+        backend.create_table(table()).unwrap();
+        backend.insert(table(), 30, vec![3]).unwrap();
+        assert!(matches!(
+            backend.insert(table(), 30, vec![4]),
+            Err(StorageError::KeyExists(30))
+        ));
+        backend
+            .batch_upsert(table(), vec![(10, vec![1]), (30, vec![5])])
+            .unwrap();
+        assert_eq!(
+            backend.batch_get(table(), vec![30, 20, 10]).unwrap(),
+            vec![Some(vec![5]), None, Some(vec![1])]
+        );
+        assert_eq!(backend.keys(table()).unwrap(), vec![10, 30]);
+
+        backend.delete(table(), 10).unwrap();
+        assert!(!backend.exists(table(), 10).unwrap());
+        assert!(matches!(
+            backend.delete(table(), 10),
+            Err(StorageError::KeyNotFound(10))
+        ));
+    }
+}
@@ -2,6 +2,7 @@ use std::hint::black_box;
 
 use criterion::Criterion;
 use ferrumc_world::{
+    dimension::Dimension,
     pos::{BlockPos, ChunkPos},
     World,
 };
@@ -13,11 +14,11 @@ pub(crate) fn bench_cache(c: &mut Criterion) {
     let mut group = c.benchmark_group("world_load");
     group.bench_function("Load chunk 1,1 uncached", |b| {
         b.iter_batched(
-            || World::new(&backend_path),
+            || World::new(&backend_path).unwrap(),
             |world| {
                 world.load_chunk(
                     ChunkPos::new(black_box(1), black_box(1)),
-                    black_box("overworld"),
+                    black_box(Dimension::Overworld),
                 )
             },
             criterion::BatchSize::PerIteration,
@@ -25,11 +26,11 @@ pub(crate) fn bench_cache(c: &mut Criterion) {
     });
     group.bench_function("Load chunk 1,1 uncached, owned", |b| {
         b.iter_batched(
-            || World::new(&backend_path),
+            || World::new(&backend_path).unwrap(),
             |world| {
                 world.load_chunk_owned(
                     ChunkPos::new(black_box(1), black_box(1)),
-                    black_box("overworld"),
+                    black_box(Dimension::Overworld),
                 )
             },
             criterion::BatchSize::PerIteration,
@@ -37,29 +38,31 @@ pub(crate) fn bench_cache(c: &mut Criterion) {
     });
     group.bench_function("Load block 1,1 uncached", |b| {
         b.iter_batched(
-            || World::new(&backend_path),
+            || World::new(&backend_path).unwrap(),
             |world| {
                 world.get_block_and_fetch(
                     BlockPos::of(black_box(1), black_box(1), black_box(1)),
-                    black_box("overworld"),
+                    black_box(Dimension::Overworld),
                 )
             },
             criterion::BatchSize::PerIteration,
         );
     });
-    let world = World::new(backend_path);
+    let world = World::new(backend_path).unwrap();
     let load_chunk = || -> std::sync::Arc<ferrumc_world::chunk_format::Chunk> {
-        world.load_chunk(ChunkPos::new(1, 1), "overworld").expect(
-            "Failed to load chunk. If it's a bitcode error, chances are the chunk format \
-             has changed since last generating a world so you'll need to regenerate",
-        )
+        world
+            .load_chunk(ChunkPos::new(1, 1), Dimension::Overworld)
+            .expect(
+                "Failed to load chunk. If it's a bitcode error, the world may hold chunks from \
+             an older format; run the `migrate` subcommand to upgrade them",
+            )
     };
     _ = load_chunk();
     group.bench_function("Load chunk 1,1 cached", |b| {
         b.iter(|| {
             world.load_chunk(
                 ChunkPos::new(black_box(1), black_box(1)),
-                black_box("overworld"),
+                black_box(Dimension::Overworld),
             )
         })
     });
@@ -67,7 +70,7 @@ pub(crate) fn bench_cache(c: &mut Criterion) {
         b.iter(|| {
             world.load_chunk_owned(
                 ChunkPos::new(black_box(1), black_box(1)),
-                black_box("overworld"),
+                black_box(Dimension::Overworld),
             )
         })
     });
@@ -75,7 +78,7 @@ pub(crate) fn bench_cache(c: &mut Criterion) {
         b.iter(|| {
             world.get_block_and_fetch(
                 BlockPos::of(black_box(1), black_box(1), black_box(1)),
-                black_box("overworld"),
+                black_box(Dimension::Overworld),
             )
         });
     });
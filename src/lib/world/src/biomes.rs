@@ -0,0 +1,408 @@
+//! Reading and writing biomes.
+//!
+//! Biomes are stored per section at a resolution of 4x4x4 blocks, giving 64 entries indexed
+//! `y << 4 | z << 2 | x` in biome coordinates. Like block states they use a palette of network ids
+//! with packed indexes into it, which grows a bit at a time as more biomes are added. A section
+//! with a single biome stores no data at all.
+
+use crate::chunk_format::{BiomeStates, Chunk};
+use crate::dimension::Dimension;
+use crate::errors::WorldError;
+use crate::pos::{BlockPos, ChunkBlockPos, ChunkPos};
+use crate::vanilla_chunk_format;
+use crate::World;
+use ferrumc_data::biomes::Biome;
+use ferrumc_general_purpose::data_packing::i32::{read_nbit_i32, write_nbit_i32};
+use ferrumc_net_codec::net_types::var_int::VarInt;
+use tracing::warn;
+
+/// The number of biome entries in a section.
+const BIOMES_PER_SECTION: usize = 64;
+
+/// Bits per entry the client expects when biomes are sent without a palette, enough for every
+/// biome id.
+pub const DIRECT_BITS_PER_BIOME: u8 = 7;
+
+/// Returns the index of the biome entry covering the given block within its section.
+fn biome_index(pos: ChunkBlockPos) -> usize {
+    let y = pos.y().rem_euclid(16) as usize >> 2;
+    (y << 4) | ((pos.z() as usize >> 2) << 2) | (pos.x() as usize >> 2)
+}
+
+fn biome_from_id(id: VarInt) -> Result<&'static Biome, WorldError> {
+    u16::try_from(id.0)
+        .ok()
+        .and_then(Biome::from_id)
+        .ok_or(WorldError::InvalidBiome(id.0))
+}
+
+impl BiomeStates {
+    /// A section filled with a single biome.
+    pub fn single(biome: &Biome) -> Self {
+        Self {
+            bits_per_biome: 0,
+            data: vec![],
+            palette: vec![VarInt::from(i32::from(biome.id))],
+        }
+    }
+
+    fn is_single(&self) -> bool {
+        self.palette.len() <= 1 || self.data.is_empty()
+    }
+
+    /// Returns the palette index stored for the given entry.
+    fn palette_index(&self, index: usize) -> Result<usize, WorldError> {
+        if self.is_single() {
+            return Ok(0);
+        }
+        let bits = self.bits_per_biome as usize;
+        let per_long = 64 / bits;
+        let long = self.data.get(index / per_long).ok_or_else(|| {
+            WorldError::InvalidBiomeData(format!("Missing packed data for biome index {index}"))
+        })?;
+        Ok(read_nbit_i32(long, bits, ((index % per_long) * bits) as u32)? as usize)
+    }
+
+    /// Returns the network id of the biome stored for the given entry.
+    pub fn get_id(&self, index: usize) -> Result<VarInt, WorldError> {
+        let palette_index = self.palette_index(index)?;
+        self.palette.get(palette_index).copied().ok_or_else(|| {
+            WorldError::InvalidBiomeData(format!("Palette index {palette_index} out of bounds"))
+        })
+    }
+
+    /// Returns the biome stored for the given entry.
+    pub fn get(&self, index: usize) -> Result<&'static Biome, WorldError> {
+        biome_from_id(self.get_id(index)?)
+    }
+
+    /// Sets the biome of a single entry, adding it to the palette and widening the entries if
+    /// needed. Returns true if the biome changed.
+    pub fn set(&mut self, index: usize, biome: &Biome) -> Result<bool, WorldError> {
+        let id = VarInt::from(i32::from(biome.id));
+        if self.get_id(index)? == id {
+            return Ok(false);
+        }
+        let palette_index = match self.palette.iter().position(|entry| *entry == id) {
+            Some(palette_index) => palette_index,
+            None => {
+                self.palette.push(id);
+                self.palette.len() - 1
+            }
+        };
+        let bits_needed = bits_for_palette(self.palette.len());
+        if self.data.is_empty() || bits_needed > self.bits_per_biome {
+            self.resize(bits_needed)?;
+        }
+
+        let bits = self.bits_per_biome;
+        let per_long = 64 / bits as usize;
+        write_nbit_i32(
+            &mut self.data[index / per_long],
+            ((index % per_long) * bits as usize) as u32,
+            palette_index as i32,
+            bits,
+        )?;
+        Ok(true)
+    }
+
+    /// Converts biomes read from a region file. Biomes we don't know about are replaced with
+    /// `fallback`.
+    pub(crate) fn from_vanilla(biomes: &vanilla_chunk_format::Biomes, fallback: &Biome) -> Self {
+        let palette: Vec<VarInt> = biomes
+            .palette
+            .iter()
+            .map(|name| {
+                let biome = Biome::from_name(name).unwrap_or_else(|| {
+                    warn!(
+                        "Unknown biome {name:?}, replacing it with {}",
+                        fallback.name
+                    );
+                    fallback
+                });
+                VarInt::from(i32::from(biome.id))
+            })
+            .collect();
+        match &biomes.data {
+            // Vanilla packs entries with as few bits as the palette allows, just like we do
+            Some(data) if palette.len() > 1 => Self {
+                bits_per_biome: bits_for_palette(palette.len()),
+                data: data.clone(),
+                palette,
+            },
+            _ => Self {
+                bits_per_biome: 0,
+                data: vec![],
+                palette: palette
+                    .first()
+                    .map(|id| vec![*id])
+                    .unwrap_or_else(|| vec![VarInt::from(i32::from(fallback.id))]),
+            },
+        }
+    }
+
+    /// Converts the biomes into the structure vanilla stores in region files.
+    pub(crate) fn to_vanilla(&self) -> Result<vanilla_chunk_format::Biomes, WorldError> {
+        let mut states = self.clone();
+        states.optimise()?;
+        let palette = states
+            .palette
+            .iter()
+            .map(|id| biome_from_id(*id).map(|biome| format!("minecraft:{}", biome.name)))
+            .collect::<Result<_, _>>()?;
+        Ok(vanilla_chunk_format::Biomes {
+            data: (!states.data.is_empty()).then_some(states.data),
+            palette,
+        })
+    }
+
+    /// Fills every entry with the given biome.
+    pub fn fill(&mut self, biome: &Biome) {
+        *self = Self::single(biome);
+    }
+
+    /// Repacks the entries with the given number of bits each.
+    fn resize(&mut self, new_bits: u8) -> Result<(), WorldError> {
+        let entries = (0..BIOMES_PER_SECTION)
+            .map(|index| self.palette_index(index))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.data = pack(&entries, new_bits)?;
+        self.bits_per_biome = new_bits;
+        Ok(())
+    }
+
+    /// Drops palette entries that are no longer used, shrinking the entries to match. Sections
+    /// left with a single biome stop storing data altogether.
+    pub fn optimise(&mut self) -> Result<(), WorldError> {
+        if self.is_single() {
+            self.palette.truncate(1);
+            self.data.clear();
+            self.bits_per_biome = 0;
+            return Ok(());
+        }
+        let mut palette = Vec::new();
+        let mut entries = Vec::with_capacity(BIOMES_PER_SECTION);
+        for index in 0..BIOMES_PER_SECTION {
+            let id = self.get_id(index)?;
+            let palette_index = match palette.iter().position(|entry| *entry == id) {
+                Some(palette_index) => palette_index,
+                None => {
+                    palette.push(id);
+                    palette.len() - 1
+                }
+            };
+            entries.push(palette_index);
+        }
+        if palette.len() == 1 {
+            *self = Self {
+                bits_per_biome: 0,
+                data: vec![],
+                palette,
+            };
+            return Ok(());
+        }
+        let bits = bits_for_palette(palette.len());
+        *self = Self {
+            bits_per_biome: bits,
+            data: pack(&entries, bits)?,
+            palette,
+        };
+        Ok(())
+    }
+}
+
+/// The number of bits needed to index a palette of the given length.
+fn bits_for_palette(len: usize) -> u8 {
+    (usize::BITS - (len.max(2) - 1).leading_zeros()) as u8
+}
+
+fn pack(entries: &[usize], bits: u8) -> Result<Vec<i64>, WorldError> {
+    let per_long = 64 / bits as usize;
+    let mut data = vec![0; entries.len().div_ceil(per_long)];
+    for (index, entry) in entries.iter().enumerate() {
+        write_nbit_i32(
+            &mut data[index / per_long],
+            ((index % per_long) * bits as usize) as u32,
+            *entry as i32,
+            bits,
+        )?;
+    }
+    Ok(data)
+}
+
+impl Chunk {
+    /// Returns the biome at the given position. Biomes cover 4x4x4 blocks, so every block in
+    /// the same cell has the same biome.
+    pub fn get_biome(&self, pos: ChunkBlockPos) -> Result<&'static Biome, WorldError> {
+        let section = self
+            .get_section(pos.section())
+            .ok_or(WorldError::SectionOutOfBounds(pos.section() as i32))?;
+        section.biome_states.get(biome_index(pos))
+    }
+
+    /// Sets the biome of the 4x4x4 cell containing the given position. Returns true if the biome
+    /// changed.
+    pub fn set_biome(&mut self, pos: ChunkBlockPos, biome: &Biome) -> Result<bool, WorldError> {
+        let section = self
+            .get_section_mut(pos.section())
+            .ok_or(WorldError::SectionOutOfBounds(pos.section() as i32))?;
+        section.biome_states.set(biome_index(pos), biome)
+    }
+
+    /// Sets the biome of the whole chunk.
+    pub fn fill_biome(&mut self, biome: &Biome) {
+        for section in &mut self.sections {
+            section.biome_states.fill(biome);
+        }
+    }
+
+    /// Sets the biome of every cell touching the box between the two corners, inclusive.
+    /// Parts of the box outside the chunk's height are ignored. Returns true if any biome
+    /// changed.
+    pub fn fill_biome_region(
+        &mut self,
+        from: ChunkBlockPos,
+        to: ChunkBlockPos,
+        biome: &Biome,
+    ) -> Result<bool, WorldError> {
+        let min_y = from.y().min(to.y()).max(self.min_y);
+        let max_y = from
+            .y()
+            .max(to.y())
+            .min(self.min_y + self.sections.len() as i16 * 16 - 1);
+        let mut changed = false;
+        for section_y in min_y.div_euclid(16)..=max_y.div_euclid(16) {
+            let section = self
+                .get_section_mut(section_y as i8)
+                .ok_or(WorldError::SectionOutOfBounds(section_y as i32))?;
+            let section_min = section_y * 16;
+            for y in (min_y.max(section_min) >> 2)..=(max_y.min(section_min + 15) >> 2) {
+                for z in (from.z().min(to.z()) >> 2)..=(from.z().max(to.z()) >> 2) {
+                    for x in (from.x().min(to.x()) >> 2)..=(from.x().max(to.x()) >> 2) {
+                        let pos = ChunkBlockPos::new(x << 2, y << 2, z << 2);
+                        changed |= section.biome_states.set(biome_index(pos), biome)?;
+                    }
+                }
+            }
+            section.biome_states.optimise()?;
+        }
+        Ok(changed)
+    }
+}
+
+impl World {
+    /// Sets the biome of every cell touching the box between the two corners, inclusive, across
+    /// as many chunks as it covers.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<ChunkPos>)` - The chunks whose biomes changed. Clients only learn about biomes
+    ///   with the rest of the chunk, so these need to be resent to players that have them loaded.
+    /// * `Err(WorldError)` - If a chunk couldn't be loaded or edited.
+    pub fn fill_biome(
+        &self,
+        from: BlockPos,
+        to: BlockPos,
+        dimension: Dimension,
+        biome: &Biome,
+    ) -> Result<Vec<ChunkPos>, WorldError> {
+        let min = from.pos.min(to.pos);
+        let max = from.pos.max(to.pos);
+        let mut changed = Vec::new();
+        for chunk_x in min.x.div_euclid(16)..=max.x.div_euclid(16) {
+            for chunk_z in min.z.div_euclid(16)..=max.z.div_euclid(16) {
+                let chunk_pos = ChunkPos::new(chunk_x, chunk_z);
+                let corner = |x: i32, z: i32, y: i32| {
+                    ChunkBlockPos::new(
+                        (x - chunk_x * 16).clamp(0, 15) as u8,
+                        y.clamp(i32::from(i16::MIN), i32::from(i16::MAX)) as i16,
+                        (z - chunk_z * 16).clamp(0, 15) as u8,
+                    )
+                };
+                let (chunk_changed, _) = self.edit_chunk(chunk_pos, dimension, |chunk| {
+                    chunk.fill_biome_region(
+                        corner(min.x, min.z, min.y),
+                        corner(max.x, max.z, max.y),
+                        biome,
+                    )
+                })?;
+                if chunk_changed {
+                    changed.push(chunk_pos);
+                }
+            }
+        }
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pos::ChunkHeight;
+
+    #[test]
+    fn test_set_and_get_biome() {
+        let mut chunk = Chunk::new(ChunkHeight::new(-64, 384));
+        let pos = ChunkBlockPos::new(5, 70, 9);
+        assert_eq!(chunk.get_biome(pos).unwrap(), &Biome::PLAINS);
+
+        assert!(chunk.set_biome(pos, &Biome::DESERT).unwrap());
+        assert!(!chunk.set_biome(pos, &Biome::DESERT).unwrap());
+        // The whole 4x4x4 cell changes, and nothing else
+        assert_eq!(
+            chunk.get_biome(ChunkBlockPos::new(4, 68, 8)).unwrap(),
+            &Biome::DESERT
+        );
+        assert_eq!(
+            chunk.get_biome(ChunkBlockPos::new(8, 70, 9)).unwrap(),
+            &Biome::PLAINS
+        );
+    }
+
+    #[test]
+    fn test_palette_grows_and_shrinks() {
+        let mut states = BiomeStates::single(&Biome::PLAINS);
+        for index in 0..BIOMES_PER_SECTION {
+            let biome = Biome::from_id(index as u16).unwrap();
+            states.set(index, biome).unwrap();
+        }
+        assert_eq!(states.bits_per_biome, 6);
+        for index in 0..BIOMES_PER_SECTION {
+            assert_eq!(states.get(index).unwrap().id, index as u16);
+        }
+
+        states.optimise().unwrap();
+        assert_eq!(states.palette.len(), BIOMES_PER_SECTION);
+        for index in 0..BIOMES_PER_SECTION {
+            states.set(index, &Biome::FOREST).unwrap();
+        }
+        states.optimise().unwrap();
+        assert_eq!(states, BiomeStates::single(&Biome::FOREST));
+    }
+
+    #[test]
+    fn test_fill_biome_region() {
+        let mut chunk = Chunk::new(ChunkHeight::new(-64, 384));
+        let changed = chunk
+            .fill_biome_region(
+                ChunkBlockPos::new(1, -100, 1),
+                ChunkBlockPos::new(6, -50, 2),
+                &Biome::SWAMP,
+            )
+            .unwrap();
+        assert!(changed);
+        assert_eq!(
+            chunk.get_biome(ChunkBlockPos::new(7, -49, 3)).unwrap(),
+            &Biome::SWAMP
+        );
+        assert_eq!(
+            chunk.get_biome(ChunkBlockPos::new(8, -49, 3)).unwrap(),
+            &Biome::PLAINS
+        );
+        assert_eq!(
+            chunk.get_biome(ChunkBlockPos::new(7, -45, 3)).unwrap(),
+            &Biome::PLAINS
+        );
+        assert_eq!(chunk.sections[0].biome_states.palette.len(), 2);
+    }
+}
@@ -0,0 +1,337 @@
+//! Block entities, the extra data some blocks carry on top of their block state, such as the
+//! items in a chest or the text on a sign.
+//!
+//! Only the tags that identify a block entity are modelled, everything else is kept as raw NBT so
+//! it survives being imported, stored and exported untouched.
+
+use crate::block_state_id::BlockStateId;
+use crate::chunk_format::Chunk;
+use crate::dimension::Dimension;
+use crate::errors::WorldError;
+use crate::pos::{BlockPos, ChunkBlockPos};
+use crate::World;
+use bitcode_derive::{Decode, Encode};
+use deepsize::DeepSizeOf;
+use ferrumc_nbt::RawCompound;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Encode, Decode, DeepSizeOf)]
+pub enum BlockEntityKind {
+    Furnace,
+    Chest,
+    TrappedChest,
+    Sign,
+    HangingSign,
+    Skull,
+    Banner,
+    /// Keeps a comparator's output signal strength, which its block state can't hold.
+    Comparator,
+}
+
+impl BlockEntityKind {
+    pub const ALL: [BlockEntityKind; 8] = [
+        BlockEntityKind::Furnace,
+        BlockEntityKind::Chest,
+        BlockEntityKind::TrappedChest,
+        BlockEntityKind::Sign,
+        BlockEntityKind::HangingSign,
+        BlockEntityKind::Skull,
+        BlockEntityKind::Banner,
+        BlockEntityKind::Comparator,
+    ];
+
+    /// The namespaced identifier vanilla saves block entities under, e.g. `minecraft:chest`.
+    pub const fn identifier(self) -> &'static str {
+        match self {
+            BlockEntityKind::Furnace => "minecraft:furnace",
+            BlockEntityKind::Chest => "minecraft:chest",
+            BlockEntityKind::TrappedChest => "minecraft:trapped_chest",
+            BlockEntityKind::Sign => "minecraft:sign",
+            BlockEntityKind::HangingSign => "minecraft:hanging_sign",
+            BlockEntityKind::Skull => "minecraft:skull",
+            BlockEntityKind::Banner => "minecraft:banner",
+            BlockEntityKind::Comparator => "minecraft:comparator",
+        }
+    }
+
+    /// Parses a block entity identifier. The `minecraft:` namespace is optional.
+    pub fn from_identifier(identifier: &str) -> Option<Self> {
+        let name = identifier.strip_prefix("minecraft:").unwrap_or(identifier);
+        BlockEntityKind::ALL
+            .into_iter()
+            .find(|kind| &kind.identifier()["minecraft:".len()..] == name)
+    }
+
+    /// The index of this type in the `minecraft:block_entity_type` registry.
+    pub const fn registry_id(self) -> i32 {
+        match self {
+            BlockEntityKind::Furnace => 0,
+            BlockEntityKind::Chest => 1,
+            BlockEntityKind::TrappedChest => 2,
+            BlockEntityKind::Sign => 7,
+            BlockEntityKind::HangingSign => 8,
+            BlockEntityKind::Skull => 16,
+            BlockEntityKind::Comparator => 19,
+            BlockEntityKind::Banner => 20,
+        }
+    }
+
+    /// The kind of block entity the block carries, if it's one we know about.
+    pub fn for_block(block: BlockStateId) -> Option<Self> {
+        let data = block.to_block_data()?;
+        let name = data.name.strip_prefix("minecraft:")?;
+        match name {
+            "furnace" => Some(BlockEntityKind::Furnace),
+            "chest" => Some(BlockEntityKind::Chest),
+            "trapped_chest" => Some(BlockEntityKind::TrappedChest),
+            "comparator" => Some(BlockEntityKind::Comparator),
+            "piston_head" => None,
+            _ if name.ends_with("_hanging_sign") => Some(BlockEntityKind::HangingSign),
+            _ if name.ends_with("_sign") => Some(BlockEntityKind::Sign),
+            _ if name.ends_with("_banner") => Some(BlockEntityKind::Banner),
+            _ if name.ends_with("_skull") || name.ends_with("_head") => {
+                Some(BlockEntityKind::Skull)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A block entity stored in a chunk.
+#[derive(Encode, Decode, Clone, DeepSizeOf, Eq, PartialEq, Debug)]
+pub struct BlockEntity {
+    pub kind: BlockEntityKind,
+    x: u8,
+    y: i16,
+    z: u8,
+    /// The rest of the block entity's tags as a network NBT compound. The id and position are
+    /// not included since they are stored above.
+    data: Vec<u8>,
+}
+
+impl BlockEntity {
+    /// A block entity without any data, like the one vanilla creates when the block is placed.
+    pub fn new(kind: BlockEntityKind, pos: ChunkBlockPos) -> Self {
+        Self::with_data(kind, pos, RawCompound::new())
+    }
+
+    pub fn with_data(kind: BlockEntityKind, pos: ChunkBlockPos, data: RawCompound) -> Self {
+        Self {
+            kind,
+            x: pos.x(),
+            y: pos.y(),
+            z: pos.z(),
+            data: data.to_network_bytes(),
+        }
+    }
+
+    pub fn pos(&self) -> ChunkBlockPos {
+        ChunkBlockPos::new(self.x, self.y, self.z)
+    }
+
+    pub fn data(&self) -> RawCompound {
+        RawCompound::from_network_bytes(&self.data)
+            .expect("Block entity data is always stored as a network compound")
+    }
+
+    pub fn set_data(&mut self, data: RawCompound) {
+        self.data = data.to_network_bytes();
+    }
+
+    /// The data as it's sent to clients, in the chunk packet or a block entity update.
+    pub fn network_data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl Chunk {
+    pub fn get_block_entity(&self, pos: ChunkBlockPos) -> Option<&BlockEntity> {
+        self.block_entities
+            .iter()
+            .find(|block_entity| block_entity.pos() == pos)
+    }
+
+    pub fn get_block_entity_mut(&mut self, pos: ChunkBlockPos) -> Option<&mut BlockEntity> {
+        self.block_entities
+            .iter_mut()
+            .find(|block_entity| block_entity.pos() == pos)
+    }
+
+    /// Adds a block entity, replacing the one already at its position.
+    ///
+    /// Fails if the block at that position doesn't carry this kind of block entity, so set the
+    /// block first.
+    pub fn set_block_entity(&mut self, block_entity: BlockEntity) -> Result<(), WorldError> {
+        let pos = block_entity.pos();
+        let block = self.get_block(pos)?;
+        if BlockEntityKind::for_block(block) != Some(block_entity.kind) {
+            return Err(WorldError::InvalidBlockEntity(format!(
+                "{} can't be placed in {block}",
+                block_entity.kind.identifier()
+            )));
+        }
+        self.remove_block_entity(pos);
+        self.block_entities.push(block_entity);
+        Ok(())
+    }
+
+    pub fn remove_block_entity(&mut self, pos: ChunkBlockPos) -> Option<BlockEntity> {
+        let index = self
+            .block_entities
+            .iter()
+            .position(|block_entity| block_entity.pos() == pos)?;
+        Some(self.block_entities.swap_remove(index))
+    }
+
+    /// Makes the block entity at the position match the block that was just placed there. A
+    /// block entity of the same kind is kept as is, otherwise it's replaced by an empty one, or
+    /// removed if the block doesn't carry one.
+    pub(crate) fn sync_block_entity(&mut self, pos: ChunkBlockPos, block: BlockStateId) {
+        let kind = BlockEntityKind::for_block(block);
+        if self
+            .get_block_entity(pos)
+            .map(|block_entity| block_entity.kind)
+            == kind
+        {
+            return;
+        }
+        self.remove_block_entity(pos);
+        if let Some(kind) = kind {
+            self.block_entities.push(BlockEntity::new(kind, pos));
+        }
+    }
+
+    /// Drops the block entities whose block has been replaced by one that doesn't carry them.
+    pub(crate) fn prune_block_entities(&mut self) {
+        let mut block_entities = std::mem::take(&mut self.block_entities);
+        block_entities.retain(|block_entity| {
+            self.get_block(block_entity.pos())
+                .is_ok_and(|block| BlockEntityKind::for_block(block) == Some(block_entity.kind))
+        });
+        self.block_entities = block_entities;
+    }
+}
+
+impl World {
+    pub fn get_block_entity(
+        &self,
+        pos: BlockPos,
+        dimension: Dimension,
+    ) -> Result<Option<BlockEntity>, WorldError> {
+        let chunk = self.load_chunk(pos.chunk(), dimension)?;
+        Ok(chunk.get_block_entity(pos.chunk_block_pos()).cloned())
+    }
+
+    /// Replaces the data of the block entity at the position and marks the chunk as dirty. The block there
+    /// has to carry a block entity already, see [`Chunk::set_block_entity`].
+    ///
+    /// Players that have the chunk loaded aren't told about the change, that's up to the caller.
+    pub fn set_block_entity_data(
+        &self,
+        pos: BlockPos,
+        dimension: Dimension,
+        data: RawCompound,
+    ) -> Result<(), WorldError> {
+        self.edit_chunk(pos.chunk(), dimension, |chunk| {
+            let block = chunk.get_block(pos.chunk_block_pos())?;
+            let kind = BlockEntityKind::for_block(block).ok_or_else(|| {
+                WorldError::InvalidBlockEntity(format!("{block} doesn't carry a block entity"))
+            })?;
+            chunk.set_block_entity(BlockEntity::with_data(kind, pos.chunk_block_pos(), data))
+        })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edit_batch::EditBatch;
+    use crate::pos::ChunkHeight;
+    use ferrumc_macros::block;
+
+    #[test]
+    fn test_kinds_match_blocks() {
+        assert_eq!(
+            BlockEntityKind::for_block(
+                block!("chest", {facing: "north", r#type: "single", waterlogged: false})
+            ),
+            Some(BlockEntityKind::Chest)
+        );
+        assert_eq!(
+            BlockEntityKind::for_block(block!("oak_sign", {rotation: 0, waterlogged: false})),
+            Some(BlockEntityKind::Sign)
+        );
+        assert_eq!(
+            BlockEntityKind::for_block(
+                block!("oak_wall_hanging_sign", {facing: "north", waterlogged: false})
+            ),
+            Some(BlockEntityKind::HangingSign)
+        );
+        assert_eq!(
+            BlockEntityKind::for_block(block!("white_wall_banner", {facing: "north"})),
+            Some(BlockEntityKind::Banner)
+        );
+        assert_eq!(
+            BlockEntityKind::for_block(block!("zombie_head", {powered: false, rotation: 0})),
+            Some(BlockEntityKind::Skull)
+        );
+        assert_eq!(
+            BlockEntityKind::for_block(block!("furnace", {facing: "north", lit: false})),
+            Some(BlockEntityKind::Furnace)
+        );
+        assert_eq!(BlockEntityKind::for_block(block!("stone")), None);
+        for kind in BlockEntityKind::ALL {
+            assert_eq!(
+                BlockEntityKind::from_identifier(kind.identifier()),
+                Some(kind)
+            );
+        }
+    }
+
+    #[test]
+    fn test_replaced_blocks_lose_their_block_entity() {
+        let mut chunk = Chunk::new(ChunkHeight::new(-64, 384));
+        let chest = block!("chest", {facing: "north", r#type: "single", waterlogged: false});
+        let first = ChunkBlockPos::new(1, 2, 3);
+        let second = ChunkBlockPos::new(4, 5, 6);
+
+        assert!(chunk
+            .set_block_entity(BlockEntity::new(BlockEntityKind::Chest, first))
+            .is_err());
+        chunk.set_block(first, chest).unwrap();
+        assert_eq!(
+            chunk.get_block_entity(first),
+            Some(&BlockEntity::new(BlockEntityKind::Chest, first))
+        );
+        assert!(chunk
+            .set_block_entity(BlockEntity::new(BlockEntityKind::Sign, first))
+            .is_err());
+
+        let mut batch = EditBatch::new(&mut chunk);
+        batch.set_block(second, chest);
+        batch.apply().unwrap();
+        assert!(chunk.get_block_entity(second).is_some());
+
+        // Swapping for the same kind of block keeps the contents
+        let contents =
+            RawCompound::from_network_bytes(&[10, 1, 0, 4, b'L', b'o', b'c', b'k', 1, 0]).unwrap();
+        chunk
+            .set_block_entity(BlockEntity::with_data(
+                BlockEntityKind::Chest,
+                first,
+                contents.clone(),
+            ))
+            .unwrap();
+        let facing_south = block!("chest", {facing: "south", r#type: "single", waterlogged: false});
+        chunk.set_block(first, facing_south).unwrap();
+        assert_eq!(chunk.get_block_entity(first).unwrap().data(), contents);
+
+        chunk.set_block(first, block!("stone")).unwrap();
+        assert!(chunk.get_block_entity(first).is_none());
+
+        let mut batch = EditBatch::new(&mut chunk);
+        batch.set_block(second, block!("air"));
+        batch.apply().unwrap();
+        assert!(chunk.block_entities.is_empty());
+    }
+}
@@ -2,9 +2,10 @@ use crate::vanilla_chunk_format::BlockData;
 use ahash::RandomState;
 use bitcode_derive::{Decode, Encode};
 use deepsize::DeepSizeOf;
+use ferrumc_data::blocks::BlockFamily;
 use ferrumc_net_codec::net_types::var_int::VarInt;
 use lazy_static::lazy_static;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Display;
 use std::process::exit;
 use tracing::error;
@@ -79,6 +80,46 @@ impl BlockStateId {
         VarInt(self.0 as i32)
     }
 
+    /// Finds the state for a block that may leave out some of its properties, which are then
+    /// taken from the block's default state. Returns `None` for unknown blocks or properties.
+    pub fn find(block_data: &BlockData) -> Option<Self> {
+        if let Some(id) = BLOCK2ID.get(block_data) {
+            return Some(BlockStateId(*id as u32));
+        }
+        let default =
+            BlockStateId(BlockFamily::by_name(&block_data.name)?.default_state).to_block_data()?;
+        let mut properties = default.properties.unwrap_or_default();
+        for (key, value) in block_data.properties.iter().flatten() {
+            *properties.get_mut(key)? = value.clone();
+        }
+        BLOCK2ID
+            .get(&BlockData {
+                name: block_data.name.clone(),
+                properties: Some(properties),
+            })
+            .map(|id| BlockStateId(*id as u32))
+    }
+
+    /// Every state of the block that has the given properties, so `oak_log` matches all three
+    /// axes and `oak_log[axis=y]` only one.
+    pub fn matching(block_data: &BlockData) -> Vec<Self> {
+        ID2BLOCK
+            .iter()
+            .enumerate()
+            .filter(|(_, candidate)| {
+                candidate.name == block_data.name
+                    && block_data.properties.iter().flatten().all(|(key, value)| {
+                        candidate
+                            .properties
+                            .as_ref()
+                            .and_then(|properties| properties.get(key))
+                            == Some(value)
+                    })
+            })
+            .map(|(id, _)| BlockStateId(id as u32))
+            .collect()
+    }
+
     /// Do Not use this by yourself. This is only useful for apis that use this as an index or key
     /// to get additionally information about this state.
     pub fn raw(&self) -> u32 {
@@ -97,6 +138,45 @@ impl Display for BlockStateId {
 }
 
 impl BlockData {
+    /// Parses a block state the way commands and Sponge schematics write it, e.g.
+    /// `minecraft:oak_log[axis=y]`. The namespace defaults to `minecraft` and the properties can
+    /// be left out, see [`BlockStateId::find`].
+    pub fn from_state_string(state: &str) -> BlockData {
+        let (name, properties) = match state.split_once('[') {
+            Some((name, properties)) => (name, properties.trim_end_matches(']')),
+            None => (state, ""),
+        };
+        let name = if name.contains(':') {
+            name.to_string()
+        } else {
+            format!("minecraft:{name}")
+        };
+        let properties = properties
+            .split(',')
+            .filter_map(|property| property.split_once('='))
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .collect::<BTreeMap<_, _>>();
+        BlockData {
+            name,
+            properties: (!properties.is_empty()).then_some(properties),
+        }
+    }
+
+    /// The inverse of [`BlockData::from_state_string`].
+    pub fn to_state_string(&self) -> String {
+        match &self.properties {
+            Some(properties) if !properties.is_empty() => {
+                let properties = properties
+                    .iter()
+                    .map(|(key, value)| format!("{key}={value}"))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                format!("{}[{properties}]", self.name)
+            }
+            _ => self.name.clone(),
+        }
+    }
+
     /// Converts a BlockData to a BlockStateId. Will panic if the ID is not found.
     pub fn to_block_state_id(&self) -> BlockStateId {
         BlockStateId::from_block_data(self)
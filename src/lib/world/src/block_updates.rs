@@ -0,0 +1,626 @@
+//! Block updates, how blocks react to the blocks next to them changing.
+//!
+//! Whenever a block changes, vanilla updates the six blocks around it. Blocks that can't survive
+//! without what they're attached to break: a torch whose wall was mined, the top half of a door
+//! without its bottom half, or wheat that's lost its farmland. Sand and gravel with nothing under
+//! them, and fluids that could now flow, schedule a tick to move a few game ticks later (see
+//! [`crate::scheduled_ticks`]). Everything a block update changes updates its own neighbours in
+//! turn.
+
+use crate::block_state_id::{BlockStateId, ID2BLOCK};
+use crate::dimension::Dimension;
+use crate::errors::WorldError;
+use crate::fluids::{FluidKind, FluidState, FluidUpdate};
+use crate::lighting::{property, MAX_LIGHT};
+use crate::pos::BlockPos;
+use crate::random_ticks::is_air;
+use crate::redstone::RedstoneUpdate;
+use crate::scheduled_ticks::{TickKind, TickRequest};
+use crate::vanilla_chunk_format::BlockData;
+use crate::World;
+use ahash::AHashSet;
+use bevy_math::IVec3;
+use ferrumc_macros::match_block;
+use lazy_static::lazy_static;
+
+const NEIGHBOURS: [IVec3; 6] = [
+    IVec3::Y,
+    IVec3::NEG_Y,
+    IVec3::X,
+    IVec3::NEG_X,
+    IVec3::Z,
+    IVec3::NEG_Z,
+];
+
+lazy_static! {
+    /// Every offset up to two blocks away, nearest first.
+    static ref REDSTONE_REACH: Vec<IVec3> = {
+        let mut offsets: Vec<IVec3> = (-2..=2)
+            .flat_map(|x| (-2..=2).flat_map(move |y| (-2..=2).map(move |z| IVec3::new(x, y, z))))
+            .filter(|offset| offset.abs().element_sum() <= 2)
+            .collect();
+        offsets.sort_by_key(|offset| offset.abs().element_sum());
+        offsets
+    };
+}
+
+/// Game ticks between a falling block losing what's under it and it falling.
+const FALL_DELAY: u32 = 2;
+
+/// What a block needs next to it to stay where it is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Support {
+    /// A full block on that side.
+    Sturdy(IVec3),
+    /// Any block below that isn't air.
+    NotAir,
+    Farmland,
+    /// Dirt, grass or farmland below.
+    Soil,
+    /// The other half of the same block on that side, with `key` set to `value`.
+    OtherHalf {
+        dir: IVec3,
+        key: &'static str,
+        value: &'static str,
+    },
+}
+
+/// Plants that need soil under them and have no other state to care about.
+const PLANTS: &[&str] = &[
+    "short_grass",
+    "fern",
+    "short_dry_grass",
+    "tall_dry_grass",
+    "bush",
+    "firefly_bush",
+    "dead_bush",
+    "dandelion",
+    "poppy",
+    "blue_orchid",
+    "allium",
+    "azure_bluet",
+    "red_tulip",
+    "orange_tulip",
+    "white_tulip",
+    "pink_tulip",
+    "oxeye_daisy",
+    "cornflower",
+    "lily_of_the_valley",
+    "torchflower",
+    "wither_rose",
+    "pink_petals",
+    "wildflowers",
+    "sweet_berry_bush",
+];
+
+const CROPS: &[&str] = &[
+    "wheat",
+    "carrots",
+    "potatoes",
+    "beetroots",
+    "melon_stem",
+    "pumpkin_stem",
+    "attached_melon_stem",
+    "attached_pumpkin_stem",
+    "torchflower_crop",
+    "pitcher_crop",
+];
+
+const SOIL: &[&str] = &[
+    "dirt",
+    "grass_block",
+    "coarse_dirt",
+    "podzol",
+    "rooted_dirt",
+    "mycelium",
+    "moss_block",
+    "mud",
+    "muddy_mangrove_roots",
+    "farmland",
+];
+
+/// Blocks that let light through but are still full blocks things can be attached to.
+const TRANSPARENT_FULL_BLOCKS: &[&str] =
+    &["glass", "ice", "frosted_ice", "slime_block", "honey_block"];
+
+lazy_static! {
+    static ref SUPPORTS: Vec<Vec<Support>> = ID2BLOCK.iter().map(supports).collect();
+    static ref FALLING_BLOCKS: Vec<bool> = ID2BLOCK.iter().map(falls).collect();
+}
+
+pub(crate) fn name(data: &BlockData) -> &str {
+    data.name.strip_prefix("minecraft:").unwrap_or(&data.name)
+}
+
+/// The direction a `facing` property points in.
+pub(crate) fn facing(data: &BlockData) -> Option<IVec3> {
+    match property(data, "facing")? {
+        "north" => Some(IVec3::NEG_Z),
+        "south" => Some(IVec3::Z),
+        "west" => Some(IVec3::NEG_X),
+        "east" => Some(IVec3::X),
+        "up" => Some(IVec3::Y),
+        "down" => Some(IVec3::NEG_Y),
+        _ => None,
+    }
+}
+
+fn supports(data: &BlockData) -> Vec<Support> {
+    let name = name(data);
+    if matches!(name, "torch" | "soul_torch" | "redstone_torch") {
+        return vec![Support::Sturdy(IVec3::NEG_Y)];
+    }
+    if name.ends_with("wall_torch") {
+        return facing(data)
+            .map(|dir| vec![Support::Sturdy(-dir)])
+            .unwrap_or_default();
+    }
+    // Levers and buttons can go on floors, walls and ceilings
+    if name == "lever" || name.ends_with("_button") {
+        return match (property(data, "face"), facing(data)) {
+            (Some("floor"), _) => vec![Support::Sturdy(IVec3::NEG_Y)],
+            (Some("ceiling"), _) => vec![Support::Sturdy(IVec3::Y)],
+            (Some("wall"), Some(dir)) => vec![Support::Sturdy(-dir)],
+            _ => vec![],
+        };
+    }
+    if name.ends_with("_bed") {
+        return match (property(data, "part"), facing(data)) {
+            (Some("foot"), Some(dir)) => vec![Support::OtherHalf {
+                dir,
+                key: "part",
+                value: "head",
+            }],
+            (Some("head"), Some(dir)) => vec![Support::OtherHalf {
+                dir: -dir,
+                key: "part",
+                value: "foot",
+            }],
+            _ => vec![],
+        };
+    }
+    let door = name.ends_with("_door");
+    match property(data, "half") {
+        Some("lower") => {
+            return vec![
+                Support::OtherHalf {
+                    dir: IVec3::Y,
+                    key: "half",
+                    value: "upper",
+                },
+                if door {
+                    Support::Sturdy(IVec3::NEG_Y)
+                } else {
+                    Support::Soil
+                },
+            ]
+        }
+        Some("upper") => {
+            return vec![Support::OtherHalf {
+                dir: IVec3::NEG_Y,
+                key: "half",
+                value: "lower",
+            }]
+        }
+        _ => {}
+    }
+    if CROPS.contains(&name) {
+        vec![Support::Farmland]
+    } else if PLANTS.contains(&name) || name.ends_with("_sapling") {
+        vec![Support::Soil]
+    } else if name.ends_with("_carpet") {
+        vec![Support::NotAir]
+    } else if name.ends_with("_pressure_plate")
+        || name.ends_with("rail")
+        || matches!(name, "redstone_wire" | "repeater" | "comparator")
+    {
+        vec![Support::Sturdy(IVec3::NEG_Y)]
+    } else {
+        vec![]
+    }
+}
+
+fn falls(data: &BlockData) -> bool {
+    let name = name(data);
+    matches!(
+        name,
+        "sand"
+            | "red_sand"
+            | "gravel"
+            | "suspicious_sand"
+            | "suspicious_gravel"
+            | "anvil"
+            | "chipped_anvil"
+            | "damaged_anvil"
+    ) || name.ends_with("_concrete_powder")
+}
+
+/// Whether things can be attached to the block. Without shapes to go on, that's any block that
+/// blocks light, plus a few see-through full blocks.
+pub(crate) fn is_sturdy(block: BlockStateId) -> bool {
+    if block.light_opacity() == MAX_LIGHT {
+        return true;
+    }
+    ID2BLOCK.get(block.raw() as usize).is_some_and(|data| {
+        let name = name(data);
+        TRANSPARENT_FULL_BLOCKS.contains(&name)
+            || name.ends_with("_stained_glass")
+            || name.ends_with("_leaves")
+    })
+}
+
+/// Whether a falling block falls into the block.
+pub(crate) fn is_free(block: BlockStateId) -> bool {
+    is_air(block)
+        || match_block!("water", block)
+        || match_block!("lava", block)
+        || match_block!("fire", block)
+        || match_block!("soul_fire", block)
+        || match_block!("short_grass", block)
+        || match_block!("fern", block)
+        || match_block!("dead_bush", block)
+}
+
+/// Whether the block breaks when what it's attached to goes.
+pub(crate) fn needs_support(block: BlockStateId) -> bool {
+    SUPPORTS
+        .get(block.raw() as usize)
+        .is_some_and(|supports| !supports.is_empty())
+}
+
+impl BlockStateId {
+    /// Whether the block falls when there's nothing under it, like sand and gravel.
+    pub fn is_falling_block(&self) -> bool {
+        FALLING_BLOCKS
+            .get(self.raw() as usize)
+            .copied()
+            .unwrap_or(false)
+    }
+}
+
+/// Reads and changes the blocks around a block update. Blocks in chunks that don't exist read as
+/// `None`, and nothing is broken for want of them.
+struct UpdateView<'a> {
+    world: &'a World,
+    dimension: Dimension,
+}
+
+impl UpdateView<'_> {
+    fn block(&self, pos: IVec3) -> Option<BlockStateId> {
+        self.world
+            .get_block_and_fetch(BlockPos { pos }, self.dimension)
+            .ok()
+    }
+
+    fn survives(&self, pos: IVec3, block: BlockStateId) -> bool {
+        SUPPORTS.get(block.raw() as usize).is_none_or(|supports| {
+            supports
+                .iter()
+                .all(|&support| self.holds(pos, block, support))
+        })
+    }
+
+    fn holds(&self, pos: IVec3, block: BlockStateId, support: Support) -> bool {
+        match support {
+            Support::Sturdy(dir) => self.block(pos + dir).is_none_or(is_sturdy),
+            Support::NotAir => self
+                .block(pos - IVec3::Y)
+                .is_none_or(|below| !is_air(below)),
+            Support::Farmland => self
+                .block(pos - IVec3::Y)
+                .is_none_or(|below| match_block!("farmland", below)),
+            Support::Soil => self.block(pos - IVec3::Y).is_none_or(|below| {
+                ID2BLOCK
+                    .get(below.raw() as usize)
+                    .is_some_and(|data| SOIL.contains(&name(data)))
+            }),
+            Support::OtherHalf { dir, key, value } => self.block(pos + dir).is_none_or(|other| {
+                block.is_same_block(other) && other.get_property(key) == Some(value)
+            }),
+        }
+    }
+
+    /// Whether the falling block at `pos` has somewhere to fall.
+    fn can_fall(&self, pos: IVec3) -> bool {
+        self.block(pos - IVec3::Y).is_some_and(is_free)
+    }
+}
+
+impl World {
+    /// Whether the block would stay put at `pos`, leaving out the other half of two block tall
+    /// blocks and beds, which won't have been placed yet.
+    pub(crate) fn is_supported(
+        &self,
+        pos: BlockPos,
+        dimension: Dimension,
+        block: BlockStateId,
+    ) -> bool {
+        let view = UpdateView {
+            world: self,
+            dimension,
+        };
+        SUPPORTS.get(block.raw() as usize).is_none_or(|supports| {
+            supports
+                .iter()
+                .filter(|support| !matches!(support, Support::OtherHalf { .. }))
+                .all(|&support| view.holds(pos.pos, block, support))
+        })
+    }
+
+    /// Updates the blocks next to every changed block, the way vanilla does after a block
+    /// changes. Blocks that have lost what they were attached to break straight away, and
+    /// falling blocks and fluids that should move have a tick scheduled. Redstone components up
+    /// to two blocks away work out their power again.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<BlockPos>)` - The blocks the update changed, which should be sent to any players
+    ///   that have them loaded. Their own neighbours have already been updated.
+    /// * `Err(WorldError)` - If a block couldn't be set or a tick couldn't be scheduled.
+    pub fn update_neighbours(
+        &self,
+        dimension: Dimension,
+        changed: &[BlockPos],
+    ) -> Result<Vec<BlockPos>, WorldError> {
+        let view = UpdateView {
+            world: self,
+            dimension,
+        };
+        let mut updated = Vec::new();
+        let mut ticks = Vec::new();
+        let mut scheduled = AHashSet::new();
+        let mut pending = changed.to_vec();
+        while let Some(changed) = pending.pop() {
+            let mut set = |pos, block| -> Result<(), WorldError> {
+                let pos = BlockPos { pos };
+                self.set_block_and_fetch(pos, dimension, block)?;
+                updated.push(pos);
+                pending.push(pos);
+                Ok(())
+            };
+            for offset in [IVec3::ZERO].into_iter().chain(NEIGHBOURS) {
+                let pos = changed.pos + offset;
+                let Some(block) = view.block(pos) else {
+                    continue;
+                };
+                // Blocks were placed where they are, so only a change next to them breaks them
+                if offset != IVec3::ZERO && !view.survives(pos, block) {
+                    let left = if block.get_property_as("waterlogged") == Some(true) {
+                        FluidState::source(FluidKind::Water).block()
+                    } else {
+                        BlockStateId::default()
+                    };
+                    set(pos, left)?;
+                    continue;
+                }
+                if block.is_falling_block()
+                    && view.can_fall(pos)
+                    && scheduled.insert((pos, TickKind::Block))
+                {
+                    ticks.push(TickRequest::new(
+                        BlockPos { pos },
+                        TickKind::Block,
+                        FALL_DELAY,
+                    ));
+                }
+                match self.fluid_update(BlockPos { pos }, dimension, block) {
+                    Some(FluidUpdate::Harden(block)) => set(pos, block)?,
+                    Some(FluidUpdate::Tick(delay)) if scheduled.insert((pos, TickKind::Fluid)) => {
+                        ticks.push(TickRequest::new(BlockPos { pos }, TickKind::Fluid, delay))
+                    }
+                    _ => {}
+                }
+            }
+
+            // Power reaches through a block into the ones around it, so redstone components
+            // two blocks away care about a change too
+            if !self.redstone_near(changed, dimension) {
+                continue;
+            }
+            for offset in REDSTONE_REACH.iter() {
+                let pos = changed.pos + *offset;
+                let Some(block) = view.block(pos) else {
+                    continue;
+                };
+                match self.redstone_update(BlockPos { pos }, dimension, block) {
+                    Some(RedstoneUpdate::Set(block)) => set(pos, block)?,
+                    Some(RedstoneUpdate::Tick(tick))
+                        if scheduled.insert((pos, TickKind::Block)) =>
+                    {
+                        ticks.push(tick)
+                    }
+                    _ => {}
+                }
+            }
+        }
+        self.schedule_ticks(dimension, ticks)?;
+        Ok(updated)
+    }
+
+    /// Runs the block tick at `pos`. Redstone components do what they were waiting to do (see
+    /// [`crate::redstone`]). A falling block with nothing under it drops straight down to where it
+    /// would land; there's no falling block entity, so it lands in the same tick.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<BlockPos>)` - Every block the tick changed.
+    /// * `Err(WorldError)` - If a block couldn't be set.
+    pub fn tick_block(
+        &self,
+        pos: BlockPos,
+        dimension: Dimension,
+    ) -> Result<Vec<BlockPos>, WorldError> {
+        let view = UpdateView {
+            world: self,
+            dimension,
+        };
+        let Some(block) = view.block(pos.pos) else {
+            return Ok(vec![]);
+        };
+        if let Some(changed) = self.tick_redstone(pos, dimension, block)? {
+            return Ok(changed);
+        }
+        if !block.is_falling_block() || !view.can_fall(pos.pos) {
+            return Ok(vec![]);
+        }
+        let mut landing = pos.pos - IVec3::Y;
+        while view.can_fall(landing) {
+            landing -= IVec3::Y;
+        }
+        let landing = BlockPos { pos: landing };
+        self.set_block_and_fetch(pos, dimension, BlockStateId::default())?;
+        self.set_block_and_fetch(landing, dimension, block)?;
+        Ok(vec![pos, landing])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_format::Chunk;
+    use crate::edit_batch::EditBatch;
+    use crate::pos::{ChunkBlockPos, ChunkPos};
+    use ferrumc_macros::block;
+    use ferrumc_storage::memory::MemoryBackend;
+    use std::sync::Arc;
+
+    /// A world of nine chunks with a stone floor at y = 63.
+    fn flat_world() -> World {
+        let world = World::with_backend(MemoryBackend::new()).unwrap();
+        for x in -1..=1 {
+            for z in -1..=1 {
+                let mut chunk = Chunk::new(Dimension::Overworld.height());
+                let mut batch = EditBatch::new(&mut chunk);
+                for bx in 0..16 {
+                    for bz in 0..16 {
+                        batch.set_block(ChunkBlockPos::new(bx, 63, bz), block!("stone"));
+                    }
+                }
+                batch.apply().unwrap();
+                world
+                    .save_chunk(ChunkPos::new(x, z), Dimension::Overworld, Arc::new(chunk))
+                    .unwrap();
+            }
+        }
+        world
+    }
+
+    fn set(world: &World, x: i32, y: i32, z: i32, block: BlockStateId) {
+        let pos = BlockPos::of(x, y, z);
+        world
+            .set_block_and_fetch(pos, Dimension::Overworld, block)
+            .unwrap();
+        world
+            .update_neighbours(Dimension::Overworld, &[pos])
+            .unwrap();
+    }
+
+    fn get(world: &World, x: i32, y: i32, z: i32) -> BlockStateId {
+        world
+            .get_block_and_fetch(BlockPos::of(x, y, z), Dimension::Overworld)
+            .unwrap()
+    }
+
+    fn settle(world: &World) {
+        for _ in 0..100 {
+            if world.scheduled_tick_count() == 0 {
+                return;
+            }
+            world.run_scheduled_ticks(usize::MAX);
+        }
+        panic!("blocks never settled");
+    }
+
+    #[test]
+    fn test_sand_falls_once_unsupported() {
+        let world = flat_world();
+        set(&world, 2, 64, 2, block!("stone"));
+        set(&world, 2, 65, 2, block!("sand"));
+        set(&world, 2, 66, 2, block!("gravel"));
+        assert_eq!(world.scheduled_tick_count(), 0);
+
+        set(&world, 2, 64, 2, BlockStateId::default());
+        assert_eq!(world.scheduled_tick_count(), 1);
+        // Nothing moves until the tick's delay has passed
+        assert!(world.run_scheduled_ticks(usize::MAX).is_empty());
+        settle(&world);
+        assert_eq!(get(&world, 2, 64, 2), block!("sand"));
+        assert_eq!(get(&world, 2, 65, 2), block!("gravel"));
+        assert_eq!(get(&world, 2, 66, 2), BlockStateId::default());
+    }
+
+    #[test]
+    fn test_attached_blocks_break_without_support() {
+        let world = flat_world();
+        set(&world, 0, 64, 0, block!("stone"));
+        set(&world, 0, 64, 1, block!("wall_torch", {facing: "south"}));
+        set(&world, 0, 65, 0, block!("torch"));
+        set(
+            &world,
+            5,
+            64,
+            5,
+            block!("oak_door", {facing: "north", half: "lower", hinge: "left", open: false, powered: false}),
+        );
+        set(
+            &world,
+            5,
+            65,
+            5,
+            block!("oak_door", {facing: "north", half: "upper", hinge: "left", open: false, powered: false}),
+        );
+
+        set(&world, 0, 64, 0, BlockStateId::default());
+        assert_eq!(get(&world, 0, 64, 1), BlockStateId::default());
+        assert_eq!(get(&world, 0, 65, 0), BlockStateId::default());
+
+        // Breaking the floor under a door breaks both halves
+        let changed = {
+            let pos = BlockPos::of(5, 63, 5);
+            world
+                .set_block_and_fetch(pos, Dimension::Overworld, BlockStateId::default())
+                .unwrap();
+            world
+                .update_neighbours(Dimension::Overworld, &[pos])
+                .unwrap()
+        };
+        assert!(changed.contains(&BlockPos::of(5, 64, 5)));
+        assert!(changed.contains(&BlockPos::of(5, 65, 5)));
+        assert_eq!(get(&world, 5, 65, 5), BlockStateId::default());
+    }
+
+    #[test]
+    fn test_waterlogged_blocks_leave_water() {
+        let world = flat_world();
+        set(&world, 3, 64, 3, block!("stone"));
+        set(
+            &world,
+            3,
+            65,
+            3,
+            block!("rail", {shape: "north_south", waterlogged: true}),
+        );
+        set(
+            &world,
+            3,
+            64,
+            4,
+            block!("stone_button", {face: "wall", facing: "south", powered: false}),
+        );
+
+        set(&world, 3, 64, 3, BlockStateId::default());
+        assert_eq!(get(&world, 3, 64, 4), BlockStateId::default());
+        assert_eq!(
+            get(&world, 3, 65, 3),
+            FluidState::source(FluidKind::Water).block()
+        );
+        // The water left behind flows on as usual
+        assert!(world
+            .has_scheduled_tick(
+                BlockPos::of(3, 65, 3),
+                Dimension::Overworld,
+                TickKind::Fluid
+            )
+            .unwrap());
+    }
+}
@@ -1,16 +1,21 @@
+use crate::block_entity::{BlockEntity, BlockEntityKind};
 use crate::block_state_id::{BlockStateId, BLOCK2ID};
-use crate::pos::ChunkHeight;
+use crate::dimension::Dimension;
+use crate::pos::{ChunkBlockPos, ChunkHeight, ChunkPos};
+use crate::scheduled_ticks::ScheduledTick;
 use crate::vanilla_chunk_format;
-use crate::vanilla_chunk_format::VanillaChunk;
+use crate::vanilla_chunk_format::{VanillaBlockEntity, VanillaChunk};
 use crate::{errors::WorldError, vanilla_chunk_format::VanillaHeightmaps};
 use bitcode_derive::{Decode, Encode};
 use deepsize::DeepSizeOf;
-use ferrumc_general_purpose::data_packing::i32::read_nbit_i32;
+use ferrumc_data::biomes::Biome;
+use ferrumc_general_purpose::data_packing::i32::{read_nbit_i32, write_nbit_i32};
 use ferrumc_macros::{block, NBTDeserialize, NBTSerialize};
+use ferrumc_nbt::RawCompound;
 use ferrumc_net_codec::net_types::var_int::VarInt;
 use std::cmp::max;
 use std::collections::HashMap;
-use tracing::error;
+use tracing::{error, warn};
 // #[cfg(test)]
 // const BLOCKSFILE: &[u8] = &[0];
 
@@ -21,12 +26,18 @@ use tracing::error;
 // in the binary.
 // #[cfg(not(test))]
 
+/// The data version written to exported chunks, matching Minecraft 1.21.8.
+pub(crate) const DATA_VERSION: i32 = 4440;
+
 #[derive(Encode, Decode, Clone, DeepSizeOf, Eq, PartialEq, Debug)]
 // This is a placeholder for the actual chunk format
 pub struct Chunk {
     pub min_y: i16,
     pub sections: Vec<Section>,
     pub heightmaps: Heightmaps,
+    pub block_entities: Vec<BlockEntity>,
+    /// Block and fluid ticks waiting to run, see [`World::schedule_ticks`](crate::World::schedule_ticks).
+    pub scheduled_ticks: Vec<ScheduledTick>,
 }
 
 #[derive(Encode, Decode, NBTDeserialize, NBTSerialize, Clone, DeepSizeOf, Debug)]
@@ -35,6 +46,10 @@ pub struct Chunk {
 pub struct Heightmaps {
     #[nbt(rename = "MOTION_BLOCKING")]
     pub motion_blocking: Vec<i64>,
+    #[nbt(rename = "MOTION_BLOCKING_NO_LEAVES")]
+    pub motion_blocking_no_leaves: Vec<i64>,
+    #[nbt(rename = "OCEAN_FLOOR")]
+    pub ocean_floor: Vec<i64>,
     #[nbt(rename = "WORLD_SURFACE")]
     pub world_surface: Vec<i64>,
 }
@@ -95,6 +110,8 @@ impl Heightmaps {
     pub fn new() -> Self {
         Heightmaps {
             motion_blocking: vec![],
+            motion_blocking_no_leaves: vec![],
+            ocean_floor: vec![],
             world_surface: vec![],
         }
     }
@@ -106,22 +123,12 @@ impl Default for Heightmaps {
     }
 }
 
-impl From<VanillaHeightmaps> for Heightmaps {
-    fn from(value: VanillaHeightmaps) -> Self {
-        Self {
-            motion_blocking: value.motion_blocking.unwrap_or_default(),
-            world_surface: value.world_surface.unwrap_or_default(),
-        }
-    }
-}
-
 impl VanillaChunk {
-    pub fn to_custom_format(&self) -> Result<Chunk, WorldError> {
-        let height = if self.dimension.as_ref().is_none_or(|s| s == "overworld") {
-            ChunkHeight::new(-64, 384)
-        } else {
-            ChunkHeight::new(0, 256)
-        };
+    /// Converts a chunk read from a region file of the given dimension. Sections outside the
+    /// dimension's height, such as the light-only sections vanilla stores above and below the
+    /// world, are dropped.
+    pub fn to_custom_format(&self, dimension: Dimension) -> Result<Chunk, WorldError> {
+        let height = dimension.height();
         let mut sections = vec![
             Section {
                 block_states: BlockStates {
@@ -129,18 +136,21 @@ impl VanillaChunk {
                     block_data: PaletteType::Single(VarInt::from(0)),
                     block_counts: HashMap::from([(BlockStateId::default(), 4096)]),
                 },
-                biome_states: BiomeStates {
-                    bits_per_biome: 0,
-                    data: vec![],
-                    palette: vec![VarInt::from(0)],
-                },
-                block_light: vec![255; 2048],
-                sky_light: vec![255; 2048],
+                biome_states: BiomeStates::single(dimension.default_biome()),
+                block_light: vec![0; 2048],
+                sky_light: vec![if dimension.has_sky_light() { 255 } else { 0 }; 2048],
             };
             height.height as usize >> 4
         ];
         for section in self.sections.as_ref().unwrap() {
             let y = section.y;
+            let Some(index) = (y as i16)
+                .checked_sub(height.min_y >> 4)
+                .and_then(|index| usize::try_from(index).ok())
+                .filter(|&index| index < sections.len())
+            else {
+                continue;
+            };
             let raw_block_data = section
                 .block_states
                 .as_ref()
@@ -151,7 +161,7 @@ impl VanillaChunk {
                 .as_ref()
                 .and_then(|bs| bs.palette.clone())
                 .unwrap_or_default();
-            let bits_per_block = max((palette.len() as f32).log2().ceil() as u8, 4);
+            let bits_per_block = vanilla_bits_per_block(palette.len());
             let mut block_counts = HashMap::new();
             for chunk in &raw_block_data {
                 let mut i = 0;
@@ -175,8 +185,13 @@ impl VanillaChunk {
                 }
             }
             let block_data = if raw_block_data.is_empty() {
-                block_counts.insert(BlockStateId::default(), 4096);
-                PaletteType::Single(VarInt::from(0))
+                // Vanilla leaves out the data when the palette only has one entry
+                let block = palette
+                    .first()
+                    .map(|block| block.to_block_state_id())
+                    .unwrap_or_default();
+                block_counts.insert(block, 4096);
+                PaletteType::Single(block.to_varint())
             } else {
                 PaletteType::Indirect {
                     bits_per_block,
@@ -208,29 +223,239 @@ impl VanillaChunk {
                 .iter()
                 .map(|&x| x as u8)
                 .collect();
-            let biome_states = BiomeStates {
-                // TODO: Implement biome states properly
-                bits_per_biome: 4,
-                data: vec![],
-                palette: vec![VarInt::from(0); 1],
-            };
+            let biome_states = section
+                .biomes
+                .as_ref()
+                .map(|biomes| BiomeStates::from_vanilla(biomes, dimension.default_biome()))
+                .unwrap_or_else(|| BiomeStates::single(dimension.default_biome()));
             let section = Section {
                 block_states,
                 biome_states,
                 block_light,
                 sky_light,
             };
-            sections[(y - (height.min_y >> 4) as i8) as usize] = section;
+            sections[index] = section;
         }
 
-        let heightmaps: Heightmaps = self.heightmaps.clone().map(Into::into).unwrap_or_default();
+        let block_entities = self
+            .block_entities
+            .iter()
+            .flatten()
+            .filter_map(|block_entity| self.to_custom_block_entity(block_entity, height))
+            .collect();
 
-        Ok(Chunk {
+        let mut chunk = Chunk {
             min_y: height.min_y,
             sections,
-            heightmaps,
+            heightmaps: Heightmaps::new(),
+            block_entities,
+            scheduled_ticks: Vec::new(),
+        };
+        // Vanilla leaves out heightmaps it doesn't need yet, so work them all out ourselves
+        chunk.recalculate_heightmaps();
+        Ok(chunk)
+    }
+
+    /// Block entities of types we don't know about, or that lie outside the chunk, are dropped.
+    fn to_custom_block_entity(
+        &self,
+        block_entity: &VanillaBlockEntity,
+        height: ChunkHeight,
+    ) -> Option<BlockEntity> {
+        let Some(kind) = BlockEntityKind::from_identifier(&block_entity.id) else {
+            warn!(
+                "Dropping block entity of unsupported type {:?} at {}, {}, {}",
+                block_entity.id, block_entity.x, block_entity.y, block_entity.z
+            );
+            return None;
+        };
+        let x = u8::try_from(block_entity.x - self.x_pos * 16).ok()?;
+        let z = u8::try_from(block_entity.z - self.z_pos * 16).ok()?;
+        let y = i16::try_from(block_entity.y).ok()?;
+        if x >= 16 || z >= 16 || y < height.min_y || y >= height.max_y() {
+            return None;
+        }
+        let data = RawCompound::from_network_bytes(&block_entity.data).ok()?;
+        Some(BlockEntity::with_data(
+            kind,
+            ChunkBlockPos::new(x, y, z),
+            data,
+        ))
+    }
+}
+
+impl VanillaChunk {
+    /// Converts a chunk into the NBT structure vanilla stores in region files. This is the
+    /// inverse of [`VanillaChunk::to_custom_format`].
+    pub fn from_custom_format(chunk: &Chunk, pos: ChunkPos) -> Result<Self, WorldError> {
+        let min_section = chunk.min_y >> 4;
+        let sections = chunk
+            .sections
+            .iter()
+            .enumerate()
+            .map(|(index, section)| {
+                Ok(vanilla_chunk_format::Section {
+                    block_states: Some(to_vanilla_block_states(&section.block_states)?),
+                    biomes: Some(section.biome_states.to_vanilla()?),
+                    y: (min_section + index as i16) as i8,
+                    block_light: to_vanilla_light(&section.block_light),
+                    sky_light: to_vanilla_light(&section.sky_light),
+                })
+            })
+            .collect::<Result<Vec<_>, WorldError>>()?;
+
+        let block_entities = chunk
+            .block_entities
+            .iter()
+            .map(|block_entity| {
+                let block_pos = block_entity.pos();
+                VanillaBlockEntity {
+                    id: block_entity.kind.identifier().to_string(),
+                    x: pos.x() * 16 + i32::from(block_pos.x()),
+                    y: i32::from(block_pos.y()),
+                    z: pos.z() * 16 + i32::from(block_pos.z()),
+                    data: block_entity.network_data().to_vec(),
+                }
+            })
+            .collect();
+
+        let non_empty = |heightmap: &Vec<i64>| (!heightmap.is_empty()).then(|| heightmap.clone());
+
+        Ok(VanillaChunk {
+            dimension: None,
+            status: "minecraft:full".to_string(),
+            data_version: DATA_VERSION,
+            heightmaps: Some(VanillaHeightmaps {
+                motion_blocking: non_empty(&chunk.heightmaps.motion_blocking),
+                motion_blocking_no_leaves: non_empty(&chunk.heightmaps.motion_blocking_no_leaves),
+                ocean_floor: non_empty(&chunk.heightmaps.ocean_floor),
+                world_surface: non_empty(&chunk.heightmaps.world_surface),
+            }),
+            is_light_on: Some(1),
+            inhabited_time: Some(0),
+            y_pos: i32::from(min_section),
+            x_pos: pos.x(),
+            z_pos: pos.z(),
+            structures: Some(vanilla_chunk_format::Structures {
+                starts: vanilla_chunk_format::Starts {},
+                references: vanilla_chunk_format::References {},
+            }),
+            last_update: Some(0),
+            sections: Some(sections),
+            block_entities: Some(block_entities),
+        })
+    }
+}
+
+/// Vanilla always stores block states with a palette, using at least 4 bits per entry.
+fn vanilla_bits_per_block(palette_len: usize) -> u8 {
+    max((palette_len as f32).log2().ceil() as u8, 4)
+}
+
+fn to_vanilla_block_states(
+    block_states: &BlockStates,
+) -> Result<vanilla_chunk_format::BlockStates, WorldError> {
+    let (palette, entries) = match &block_states.block_data {
+        PaletteType::Single(id) => (vec![*id], None),
+        PaletteType::Indirect {
+            bits_per_block,
+            data,
+            palette,
+        } => {
+            if palette.len() <= 1 || data.is_empty() {
+                (vec![palette.first().copied().unwrap_or_default()], None)
+            } else if *bits_per_block == vanilla_bits_per_block(palette.len()) {
+                // Already laid out the way vanilla expects it
+                return Ok(vanilla_chunk_format::BlockStates {
+                    data: Some(data.clone()),
+                    palette: Some(to_vanilla_palette(palette)?),
+                });
+            } else {
+                (
+                    palette.clone(),
+                    Some(unpack_entries(data, *bits_per_block)?),
+                )
+            }
+        }
+        PaletteType::Direct {
+            bits_per_block,
+            data,
+        } => {
+            let mut palette = Vec::new();
+            let mut palette_indexes = HashMap::new();
+            let entries = unpack_entries(data, *bits_per_block)?
+                .into_iter()
+                .map(|id| {
+                    *palette_indexes.entry(id).or_insert_with(|| {
+                        palette.push(VarInt::from(id as i32));
+                        palette.len() as u32 - 1
+                    })
+                })
+                .collect();
+            (palette, Some(entries))
+        }
+    };
+
+    let data = match entries {
+        Some(entries) if palette.len() > 1 => Some(pack_entries(
+            &entries,
+            vanilla_bits_per_block(palette.len()),
+        )?),
+        _ => None,
+    };
+    Ok(vanilla_chunk_format::BlockStates {
+        data,
+        palette: Some(to_vanilla_palette(&palette)?),
+    })
+}
+
+fn to_vanilla_palette(
+    palette: &[VarInt],
+) -> Result<Vec<vanilla_chunk_format::BlockData>, WorldError> {
+    palette
+        .iter()
+        .map(|id| {
+            let block = BlockStateId::from_varint(*id);
+            block
+                .to_block_data()
+                .ok_or(WorldError::MissingBlockMapping(block))
+        })
+        .collect()
+}
+
+/// Reads the 4096 entries of a section out of packed longs. Entries don't span longs.
+fn unpack_entries(data: &[i64], bits_per_entry: u8) -> Result<Vec<u32>, WorldError> {
+    let entries_per_long = 64 / bits_per_entry as usize;
+    (0..4096)
+        .map(|index| {
+            let long = data.get(index / entries_per_long).ok_or_else(|| {
+                WorldError::InvalidBlockStateData(format!(
+                    "Missing packed data for block index {index}"
+                ))
+            })?;
+            let offset = (index % entries_per_long) * bits_per_entry as usize;
+            Ok(read_nbit_i32(long, bits_per_entry as usize, offset as u32)? as u32)
         })
+        .collect()
+}
+
+fn pack_entries(entries: &[u32], bits_per_entry: u8) -> Result<Vec<i64>, WorldError> {
+    let entries_per_long = 64 / bits_per_entry as usize;
+    let mut data = vec![0i64; entries.len().div_ceil(entries_per_long)];
+    for (index, entry) in entries.iter().enumerate() {
+        let offset = (index % entries_per_long) * bits_per_entry as usize;
+        write_nbit_i32(
+            &mut data[index / entries_per_long],
+            offset as u32,
+            *entry as i32,
+            bits_per_entry,
+        )?;
     }
+    Ok(data)
+}
+
+fn to_vanilla_light(light: &[u8]) -> Option<Vec<i8>> {
+    (light.len() == 2048).then(|| light.iter().map(|&x| x as i8).collect())
 }
 
 impl Chunk {
@@ -243,23 +468,23 @@ impl Chunk {
                     block_data: PaletteType::Single(VarInt::from(0)),
                     block_counts: HashMap::from([(BlockStateId::default(), 4096)]),
                 },
-                biome_states: BiomeStates {
-                    bits_per_biome: 0,
-                    data: vec![],
-                    palette: vec![VarInt::from(0)],
-                },
-                block_light: vec![255; 2048],
+                biome_states: BiomeStates::single(&Biome::PLAINS),
+                block_light: vec![0; 2048],
                 sky_light: vec![255; 2048],
             })
             .collect();
         for section in &mut sections {
             section.optimise().expect("Failed to optimise section");
         }
-        Chunk {
+        let mut chunk = Chunk {
             min_y: height.min_y,
             sections,
             heightmaps: Heightmaps::new(),
-        }
+            block_entities: Vec::new(),
+            scheduled_ticks: Vec::new(),
+        };
+        chunk.recalculate_heightmaps();
+        chunk
     }
 
     pub fn get_section_mut(&mut self, section: i8) -> Option<&mut Section> {
@@ -0,0 +1,60 @@
+//! Locks that serialize writes to the same chunk, see [`World::edit_chunk`](crate::World::edit_chunk).
+
+use crate::dimension::Dimension;
+use crate::pos::ChunkPos;
+use parking_lot::{ReentrantMutex, ReentrantMutexGuard};
+
+/// How many locks chunks are spread over. Chunks that share a lock can't be edited at the same
+/// time, so this only needs to be large enough for that to be rare.
+const STRIPES: usize = 256;
+
+/// A fixed set of locks that chunks are assigned to by hash, so there's no per-chunk bookkeeping.
+///
+/// The locks are reentrant, so a thread that already holds a chunk's lock can take it again,
+/// e.g. when [`World::edit_chunk`](crate::World::edit_chunk) saves the chunk it's editing.
+pub(crate) struct ChunkLocks {
+    stripes: Box<[ReentrantMutex<()>]>,
+    hasher: ahash::RandomState,
+}
+
+pub(crate) type ChunkLockGuard<'a> = ReentrantMutexGuard<'a, ()>;
+
+impl ChunkLocks {
+    pub fn new() -> Self {
+        Self {
+            stripes: (0..STRIPES).map(|_| ReentrantMutex::new(())).collect(),
+            hasher: ahash::RandomState::new(),
+        }
+    }
+
+    fn stripe(&self, pos: ChunkPos, dimension: Dimension) -> usize {
+        self.hasher.hash_one((pos.pack(), dimension)) as usize % self.stripes.len()
+    }
+
+    pub fn lock(&self, pos: ChunkPos, dimension: Dimension) -> ChunkLockGuard<'_> {
+        self.stripes[self.stripe(pos, dimension)].lock()
+    }
+
+    /// Locks several chunks at once. The locks are always taken in the same order, so two threads
+    /// locking overlapping sets of chunks can't deadlock.
+    pub fn lock_all(
+        &self,
+        chunks: impl IntoIterator<Item = (ChunkPos, Dimension)>,
+    ) -> Vec<ChunkLockGuard<'_>> {
+        let mut stripes: Vec<_> = chunks
+            .into_iter()
+            .map(|(pos, dimension)| self.stripe(pos, dimension))
+            .collect();
+        stripes.sort_unstable();
+        stripes.dedup();
+        stripes
+            .into_iter()
+            .map(|stripe| self.stripes[stripe].lock())
+            .collect()
+    }
+
+    /// Locks a chunk and the eight around it, for edits whose light can spill into neighbours.
+    pub fn lock_around(&self, pos: ChunkPos, dimension: Dimension) -> Vec<ChunkLockGuard<'_>> {
+        self.lock_all((-1..=1).flat_map(|dz| (-1..=1).map(move |dx| (pos + (dx, dz), dimension))))
+    }
+}
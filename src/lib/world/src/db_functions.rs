@@ -1,48 +1,117 @@
 use crate::chunk_format::Chunk;
+use crate::dimension::Dimension;
 use crate::errors::WorldError;
 use crate::errors::WorldError::CorruptedChunkData;
+use crate::migrations::{decode_versioned, CHUNK_FORMAT_VERSION};
 use crate::pos::ChunkPos;
 // db_functions.rs
 use crate::warn;
 use crate::World;
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
 use ferrumc_config::server_config::get_global_config;
+use ferrumc_storage::backend::StorageBackend;
+use ferrumc_storage::compressors::{Compressor, CompressorType};
 use std::hash::Hasher;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use tracing::trace;
-use yazi::CompressionLevel;
 
 impl World {
     /// Save a chunk to the storage backend
     ///
     /// This function will save a chunk to the storage backend and update the cache with the new
     /// chunk data. If the chunk already exists in the cache, it will be updated with the new data.
+    ///
+    /// This writes straight away, use [`World::insert_chunk`] for chunks that change often.
     pub fn save_chunk(
         &self,
         pos: ChunkPos,
-        dimension: &str,
+        dimension: Dimension,
         chunk: Arc<Chunk>,
     ) -> Result<(), WorldError> {
+        let _lock = self.chunk_locks.lock(pos, dimension);
         let ret = save_chunk_internal(self, pos, dimension, &chunk);
-        self.cache.insert((pos, dimension.to_string()), chunk);
+        self.track_scheduled_ticks(pos, dimension, &chunk);
+        self.cache.insert((pos, dimension), chunk);
+        self.bump_revision(pos, dimension);
+        if ret.is_ok() {
+            self.dirty_chunks.remove(&(pos, dimension));
+        }
         ret
     }
 
+    /// Replace a chunk in the cache and mark it as dirty.
+    ///
+    /// Unlike [`World::save_chunk`] this doesn't touch the storage backend, the chunk is written
+    /// on the next [`World::sync`] or when it's evicted from the cache, whichever comes first. Use
+    /// this for edits so a chunk that changes many times between syncs is only written once.
+    ///
+    /// This replaces the whole chunk, so anything written since it was loaded is lost. Use
+    /// [`World::edit_chunk`] to change part of a chunk.
+    pub fn insert_chunk(&self, pos: ChunkPos, dimension: Dimension, chunk: Arc<Chunk>) {
+        let _lock = self.chunk_locks.lock(pos, dimension);
+        self.track_scheduled_ticks(pos, dimension, &chunk);
+        // Cache first, so the chunk is always in one of the two
+        self.cache.insert((pos, dimension), chunk.clone());
+        self.dirty_chunks.insert((pos, dimension), chunk);
+        self.bump_revision(pos, dimension);
+    }
+
+    /// A number that changes every time the chunk does, for caching things worked out from it.
+    ///
+    /// Revisions are only kept while the chunk is in memory. A chunk without one is handed a fresh
+    /// revision, so anything worked out from it before it was unloaded is never reused. Chunks
+    /// that aren't in memory get a fresh revision every time, otherwise every chunk that was ever
+    /// asked about would be tracked.
+    pub fn chunk_revision(&self, pos: ChunkPos, dimension: Dimension) -> u64 {
+        if let Some(revision) = self.chunk_revisions.get(&(pos, dimension)) {
+            return *revision;
+        }
+        if self.get_cached(pos, dimension).is_none() {
+            return self.next_revision.fetch_add(1, Ordering::Relaxed);
+        }
+        *self
+            .chunk_revisions
+            .entry((pos, dimension))
+            .or_insert_with(|| self.next_revision.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Only call this once the new chunk is in place, so anything that reads the new revision
+    /// also reads the new chunk.
+    fn bump_revision(&self, pos: ChunkPos, dimension: Dimension) {
+        let revision = self.next_revision.fetch_add(1, Ordering::Relaxed);
+        self.chunk_revisions.insert((pos, dimension), revision);
+    }
+
     /// Load a chunk from the storage backend. If the chunk is in the cache, it will be returned
     /// from the cache instead of the storage backend. If the chunk is not in the cache, it will be
     /// loaded from the storage backend and inserted into the cache.
-    pub fn load_chunk(&self, pos: ChunkPos, dimension: &str) -> Result<Arc<Chunk>, WorldError> {
-        if let Some(chunk) = self.cache.get(&(pos, dimension.to_string())) {
+    pub fn load_chunk(
+        &self,
+        pos: ChunkPos,
+        dimension: Dimension,
+    ) -> Result<Arc<Chunk>, WorldError> {
+        if let Some(chunk) = self.get_cached(pos, dimension) {
             return Ok(chunk);
         }
-        let chunk = load_chunk_internal(self, pos, dimension);
-        if let Ok(ref chunk) = chunk {
-            self.cache
-                .insert((pos, dimension.to_string()), Arc::from(chunk.clone()));
+        // Read it under the chunk's lock, otherwise a chunk that's changed while it's being read
+        // would be replaced in the cache by the older copy on disk
+        let _lock = self.chunk_locks.lock(pos, dimension);
+        if let Some(chunk) = self.get_cached(pos, dimension) {
+            return Ok(chunk);
         }
-        chunk.map(Arc::new)
+        let chunk = Arc::new(load_chunk_internal(self, pos, dimension)?);
+        self.track_scheduled_ticks(pos, dimension, &chunk);
+        self.cache.insert((pos, dimension), chunk.clone());
+        Ok(chunk)
     }
 
-    pub fn load_chunk_owned(&self, pos: ChunkPos, dimension: &str) -> Result<Chunk, WorldError> {
+    pub fn load_chunk_owned(
+        &self,
+        pos: ChunkPos,
+        dimension: Dimension,
+    ) -> Result<Chunk, WorldError> {
         self.load_chunk(pos, dimension).map(|c| c.as_ref().clone())
     }
 
@@ -51,8 +120,10 @@ impl World {
     /// It will first check if the chunk is in the cache and if it is, it will return true. If the
     /// chunk is not in the cache, it will check the storage backend for the chunk, returning true
     /// if it exists and false if it does not.
-    pub fn chunk_exists(&self, pos: ChunkPos, dimension: &str) -> Result<bool, WorldError> {
-        if self.cache.contains_key(&(pos, dimension.to_string())) {
+    pub fn chunk_exists(&self, pos: ChunkPos, dimension: Dimension) -> Result<bool, WorldError> {
+        if self.cache.contains_key(&(pos, dimension))
+            || self.dirty_chunks.contains_key(&(pos, dimension))
+        {
             return Ok(true);
         }
         chunk_exists_internal(self, pos, dimension)
@@ -61,24 +132,69 @@ impl World {
     /// Delete a chunk from the storage backend.
     ///
     /// This function will remove the chunk from the cache and delete it from the storage backend.
-    pub fn delete_chunk(&self, pos: ChunkPos, dimension: &str) -> Result<(), WorldError> {
-        self.cache.remove(&(pos, dimension.to_string()));
+    pub fn delete_chunk(&self, pos: ChunkPos, dimension: Dimension) -> Result<(), WorldError> {
+        let _lock = self.chunk_locks.lock(pos, dimension);
+        self.dirty_chunks.remove(&(pos, dimension));
+        self.cache.remove(&(pos, dimension));
+        self.ticking_chunks.remove(&(pos, dimension));
+        self.chunk_revisions.remove(&(pos, dimension));
         delete_chunk_internal(self, pos, dimension)
     }
 
+    /// List the positions of every chunk stored for a dimension.
+    ///
+    /// This reads the keys straight from the storage backend, so chunks that have been inserted
+    /// with [`World::insert_chunk`] but not written yet aren't included. Call [`World::sync`]
+    /// first if those matter.
+    pub fn stored_chunks(&self, dimension: Dimension) -> Result<Vec<ChunkPos>, WorldError> {
+        if !self.storage_backend.table_exists("chunks".to_string())? {
+            return Ok(vec![]);
+        }
+        let prefix = dimension_key_prefix(dimension);
+        Ok(self
+            .storage_backend
+            .keys("chunks".to_string())?
+            .into_iter()
+            .filter(|key| key & DIMENSION_KEY_MASK == prefix)
+            .map(|key| ChunkPos::unpack(key as u64))
+            .collect())
+    }
+
     /// Sync the storage backend.
     ///
-    /// This function will save all chunks in the cache to the storage backend and then sync the
-    /// storage backend. This should be run after inserting or updating a large number of chunks
-    /// to ensure that the data is properly saved to disk.
+    /// This function will save the chunks that have changed since they were last written to the
+    /// storage backend and then sync the storage backend. This should be run after inserting or
+    /// updating a large number of chunks to ensure that the data is properly saved to disk.
     pub fn sync(&self) -> Result<(), WorldError> {
-        for (k, v) in self.cache.iter() {
-            trace!("Syncing chunk: {:?}", k.0);
-            save_chunk_internal(self, k.0, &k.1, &v)?;
+        let dirty: Vec<_> = self.dirty_chunks.iter().map(|entry| *entry.key()).collect();
+        trace!("Syncing {} dirty chunks", dirty.len());
+        for (pos, dimension) in dirty {
+            write_back(
+                &*self.storage_backend,
+                &self.compressor,
+                &self.dirty_chunks,
+                pos,
+                dimension,
+            )?;
         }
+        self.save_game_time()?;
         sync_internal(self)
     }
 
+    /// How many chunks are waiting to be written by [`World::sync`].
+    pub fn dirty_chunk_count(&self) -> usize {
+        self.dirty_chunks.len()
+    }
+
+    /// A chunk that's in memory, either waiting to be written or in the cache. The unwritten copy
+    /// wins since it's always the newest.
+    pub(crate) fn get_cached(&self, pos: ChunkPos, dimension: Dimension) -> Option<Arc<Chunk>> {
+        self.dirty_chunks
+            .get(&(pos, dimension))
+            .map(|chunk| chunk.clone())
+            .or_else(|| self.cache.get(&(pos, dimension)))
+    }
+
     /// Load a batch of chunks from the storage backend.
     ///
     /// This function attempts to load as many chunks as it can find from the cache first, then fetches
@@ -86,22 +202,32 @@ impl World {
     /// returned as a vector.
     pub fn load_chunk_batch(
         &self,
-        coords: &[(ChunkPos, &str)],
+        coords: &[(ChunkPos, Dimension)],
     ) -> Result<Vec<Arc<Chunk>>, WorldError> {
         let mut found_chunks = Vec::new();
         let mut missing_chunks = Vec::new();
         for coord in coords {
-            if let Some(chunk) = self.cache.get(&(coord.0, coord.1.to_string())) {
+            if let Some(chunk) = self.get_cached(coord.0, coord.1) {
                 found_chunks.push(chunk);
             } else {
                 missing_chunks.push(*coord);
             }
         }
+        // Like [`World::load_chunk`], the missing chunks are read under their locks and checked
+        // again in case they were loaded or changed in the meantime
+        let _locks = self.chunk_locks.lock_all(missing_chunks.iter().copied());
+        missing_chunks.retain(|&(pos, dimension)| match self.get_cached(pos, dimension) {
+            Some(chunk) => {
+                found_chunks.push(chunk);
+                false
+            }
+            None => true,
+        });
         let fetched = load_chunk_batch_internal(self, &missing_chunks)?;
         for (chunk, (pos, dimension)) in fetched.into_iter().zip(missing_chunks) {
+            self.track_scheduled_ticks(pos, dimension, &chunk);
             let chunk = Arc::new(chunk);
-            self.cache
-                .insert((pos, dimension.to_string()), chunk.clone());
+            self.cache.insert((pos, dimension), chunk.clone());
             found_chunks.push(chunk);
         }
         Ok(found_chunks)
@@ -112,58 +238,137 @@ impl World {
     /// This function will load a chunk from the storage backend and insert it into the cache
     /// without returning the chunk. This is useful for preloading chunks into the cache before
     /// they are needed.
-    pub fn pre_cache(&self, pos: ChunkPos, dimension: &str) -> Result<(), WorldError> {
-        if self.cache.get(&(pos, dimension.to_string())).is_none() {
-            let chunk = load_chunk_internal(self, pos, dimension)?;
-            self.cache
-                .insert((pos, dimension.to_string()), Arc::new(chunk));
-        }
+    pub fn pre_cache(&self, pos: ChunkPos, dimension: Dimension) -> Result<(), WorldError> {
+        self.load_chunk(pos, dimension)?;
         Ok(())
     }
+
+    /// Re-compresses every stored chunk with the given compressor and returns how many were
+    /// converted.
+    ///
+    /// Cached chunks are saved first so nothing is lost. Chunks that already use the same
+    /// algorithm are converted too, since the level they were written with isn't recorded.
+    /// Chunks saved later still use the configured compressor.
+    pub fn recompress(&self, compressor: Compressor) -> Result<usize, WorldError> {
+        self.rewrite_records(|record| {
+            let (version, data) = decompress_record(record)?;
+            compress_record(version, &data, &compressor).map(Some)
+        })
+    }
+
+    /// Upgrades every stored chunk written with an older format version and returns how many
+    /// were upgraded.
+    ///
+    /// Old chunks are upgraded as they're loaded anyway, this just gets it over with in one go.
+    pub fn migrate(&self) -> Result<usize, WorldError> {
+        self.rewrite_records(|record| {
+            let (version, data) = decompress_record(record)?;
+            if version == CHUNK_FORMAT_VERSION {
+                return Ok(None);
+            }
+            let chunk = decode_versioned(version, &data)?;
+            encode_chunk(&chunk, &self.compressor).map(Some)
+        })
+    }
+
+    /// Passes every record in the `chunks` table through `rewrite`, saving the ones it returns a
+    /// new record for. Returns how many were rewritten.
+    fn rewrite_records(
+        &self,
+        rewrite: impl Fn(&[u8]) -> Result<Option<Vec<u8>>, WorldError>,
+    ) -> Result<usize, WorldError> {
+        self.sync()?;
+        if !self.storage_backend.table_exists("chunks".to_string())? {
+            return Ok(0);
+        }
+        let keys = self.storage_backend.keys("chunks".to_string())?;
+        let mut rewritten = 0;
+        for (index, keys) in keys.chunks(REWRITE_BATCH_SIZE).enumerate() {
+            let records = self
+                .storage_backend
+                .batch_get("chunks".to_string(), keys.to_vec())?;
+            let mut batch = Vec::new();
+            for (key, record) in keys.iter().zip(records) {
+                if let Some(record) = record.map(|record| rewrite(&record)).transpose()?.flatten() {
+                    batch.push((*key, record));
+                }
+            }
+            rewritten += batch.len();
+            self.storage_backend
+                .batch_upsert("chunks".to_string(), batch)?;
+            trace!(
+                "Rewrote {rewritten} chunks out of the first {}",
+                index * REWRITE_BATCH_SIZE + keys.len()
+            );
+        }
+        sync_internal(self)?;
+        Ok(rewritten)
+    }
 }
 
 pub(crate) fn save_chunk_internal(
     world: &World,
     pos: ChunkPos,
-    dimension: &str,
+    dimension: Dimension,
     chunk: &Chunk,
 ) -> Result<(), WorldError> {
-    if !world.storage_backend.table_exists("chunks".to_string())? {
-        world.storage_backend.create_table("chunks".to_string())?;
+    write_chunk(
+        &*world.storage_backend,
+        &world.compressor,
+        pos,
+        dimension,
+        chunk,
+    )
+}
+
+fn write_chunk(
+    storage_backend: &dyn StorageBackend,
+    compressor: &Compressor,
+    pos: ChunkPos,
+    dimension: Dimension,
+    chunk: &Chunk,
+) -> Result<(), WorldError> {
+    if !storage_backend.table_exists("chunks".to_string())? {
+        storage_backend.create_table("chunks".to_string())?;
     }
-    let as_bytes = yazi::compress(
-        &bitcode::encode(chunk),
-        yazi::Format::Zlib,
-        CompressionLevel::BestSpeed,
-    )?;
+    let as_bytes = encode_chunk(chunk, compressor)?;
     let digest = create_key(dimension, pos);
-    world
-        .storage_backend
-        .upsert("chunks".to_string(), digest, as_bytes)?;
+    storage_backend.upsert("chunks".to_string(), digest, as_bytes)?;
+    Ok(())
+}
+
+/// Writes a chunk if it's dirty and marks it as clean. Does nothing if it isn't dirty.
+///
+/// The chunk stays locked while it's written, so if it's changed again in the meantime the newer
+/// version waits and is marked dirty afterwards. This must not touch the cache since it's also
+/// called from the cache's eviction listener.
+pub(crate) fn write_back(
+    storage_backend: &dyn StorageBackend,
+    compressor: &Compressor,
+    dirty_chunks: &DashMap<(ChunkPos, Dimension), Arc<Chunk>>,
+    pos: ChunkPos,
+    dimension: Dimension,
+) -> Result<(), WorldError> {
+    if let Entry::Occupied(entry) = dirty_chunks.entry((pos, dimension)) {
+        write_chunk(storage_backend, compressor, pos, dimension, entry.get())?;
+        entry.remove();
+    }
     Ok(())
 }
 
 pub(crate) fn load_chunk_internal(
     world: &World,
     pos: ChunkPos,
-    dimension: &str,
+    dimension: Dimension,
 ) -> Result<Chunk, WorldError> {
     let digest = create_key(dimension, pos);
     match world.storage_backend.get("chunks".to_string(), digest)? {
-        Some(compressed) => {
-            let (data, checksum) = yazi::decompress(compressed.as_slice(), yazi::Format::Zlib)?;
-            if get_global_config().database.verify_chunk_data {
-                if let Some(expected_checksum) = checksum {
-                    let real_checksum = yazi::Adler32::from_buf(data.as_slice()).finish();
-                    if real_checksum != expected_checksum {
-                        return Err(CorruptedChunkData(real_checksum, expected_checksum));
-                    }
-                } else {
-                    warn!("Chunk data does not have a checksum, skipping verification.");
-                }
+        Some(record) => {
+            let (chunk, upgraded) = decode_chunk(&record)?;
+            if upgraded {
+                // Save the upgrade so it only happens once
+                save_chunk_internal(world, pos, dimension, &chunk)?;
             }
-            let chunk: Chunk = bitcode::decode(&data)
-                .map_err(|e| WorldError::BitcodeDecodeError(e.to_string()))?;
             Ok(chunk)
         }
         None => Err(WorldError::ChunkNotFound),
@@ -172,42 +377,40 @@ pub(crate) fn load_chunk_internal(
 
 pub(crate) fn load_chunk_batch_internal(
     world: &World,
-    coords: &[(ChunkPos, &str)],
+    coords: &[(ChunkPos, Dimension)],
 ) -> Result<Vec<Chunk>, WorldError> {
     let digests = coords
         .iter()
         .map(|&(pos, dim)| create_key(dim, pos))
         .collect();
-    world
+    let records = world
         .storage_backend
-        .batch_get("chunks".to_string(), digests)?
-        .iter()
-        .map(|chunk| match chunk {
-            Some(compressed) => {
-                let (data, checksum) = yazi::decompress(compressed, yazi::Format::Zlib)?;
-                if get_global_config().database.verify_chunk_data {
-                    if let Some(expected_checksum) = checksum {
-                        let real_checksum = yazi::Adler32::from_buf(data.as_slice()).finish();
-                        if real_checksum != expected_checksum {
-                            return Err(CorruptedChunkData(real_checksum, expected_checksum));
-                        }
-                    } else {
-                        warn!("Chunk data does not have a checksum, skipping verification.");
-                    }
-                }
-                let chunk: Chunk = bitcode::decode(&data)
-                    .map_err(|e| WorldError::BitcodeDecodeError(e.to_string()))?;
-                Ok(chunk)
-            }
-            None => Err(WorldError::ChunkNotFound),
-        })
-        .collect()
+        .batch_get("chunks".to_string(), digests)?;
+    let mut chunks = Vec::with_capacity(records.len());
+    let mut upgraded = Vec::new();
+    for (record, &(pos, dimension)) in records.iter().zip(coords) {
+        let (chunk, was_upgraded) =
+            decode_chunk(record.as_ref().ok_or(WorldError::ChunkNotFound)?)?;
+        if was_upgraded {
+            upgraded.push((
+                create_key(dimension, pos),
+                encode_chunk(&chunk, &world.compressor)?,
+            ));
+        }
+        chunks.push(chunk);
+    }
+    if !upgraded.is_empty() {
+        world
+            .storage_backend
+            .batch_upsert("chunks".to_string(), upgraded)?;
+    }
+    Ok(chunks)
 }
 
 pub(crate) fn chunk_exists_internal(
     world: &World,
     pos: ChunkPos,
-    dimension: &str,
+    dimension: Dimension,
 ) -> Result<bool, WorldError> {
     if !world.storage_backend.table_exists("chunks".to_string())? {
         return Ok(false);
@@ -219,7 +422,7 @@ pub(crate) fn chunk_exists_internal(
 pub(crate) fn delete_chunk_internal(
     world: &World,
     pos: ChunkPos,
-    dimension: &str,
+    dimension: Dimension,
 ) -> Result<(), WorldError> {
     let digest = create_key(dimension, pos);
     world.storage_backend.delete("chunks".to_string(), digest)?;
@@ -231,10 +434,366 @@ pub(crate) fn sync_internal(world: &World) -> Result<(), WorldError> {
     Ok(())
 }
 
-fn create_key(dimension: &str, pos: ChunkPos) -> u128 {
+/// How many chunks [`World::recompress`] and [`World::migrate`] hold in memory at once.
+const REWRITE_BATCH_SIZE: usize = 1024;
+
+/// Marks a record that starts with a header saying how it's compressed. Records without one were
+/// written before the compressor was configurable and are plain zlib, which can't start with this
+/// byte since the low bits of a zlib header's first byte are always 8.
+const RECORD_MAGIC: u8 = 0xFC;
+/// The magic byte, the compressor id, the chunk format version and an Adler-32 checksum of the
+/// uncompressed data.
+const RECORD_HEADER_LEN: usize = 8;
+
+/// Compresses a chunk into a record for the `chunks` table.
+pub(crate) fn encode_chunk(chunk: &Chunk, compressor: &Compressor) -> Result<Vec<u8>, WorldError> {
+    compress_record(CHUNK_FORMAT_VERSION, &bitcode::encode(chunk), compressor)
+}
+
+/// Decodes a record from the `chunks` table, upgrading it to the current format if needed. The
+/// flag says whether it was upgraded, in which case it should be saved again.
+pub(crate) fn decode_chunk(record: &[u8]) -> Result<(Chunk, bool), WorldError> {
+    let (version, data) = decompress_record(record)?;
+    Ok((
+        decode_versioned(version, &data)?,
+        version != CHUNK_FORMAT_VERSION,
+    ))
+}
+
+fn compress_record(
+    version: u16,
+    data: &[u8],
+    compressor: &Compressor,
+) -> Result<Vec<u8>, WorldError> {
+    let compressed = compressor.compress(data)?;
+    let mut record = Vec::with_capacity(RECORD_HEADER_LEN + compressed.len());
+    record.push(RECORD_MAGIC);
+    record.push(compressor.algorithm.id());
+    record.extend_from_slice(&version.to_be_bytes());
+    record.extend_from_slice(&yazi::Adler32::from_buf(data).finish().to_be_bytes());
+    record.extend_from_slice(&compressed);
+    Ok(record)
+}
+
+/// Returns the chunk format version of the record along with the uncompressed chunk. Records
+/// without a header predate versioning and are version 0.
+fn decompress_record(record: &[u8]) -> Result<(u16, Vec<u8>), WorldError> {
+    let verify = get_global_config().database.verify_chunk_data;
+    if record.first() != Some(&RECORD_MAGIC) {
+        let (data, checksum) = yazi::decompress(record, yazi::Format::Zlib)?;
+        if verify {
+            if let Some(expected_checksum) = checksum {
+                let real_checksum = yazi::Adler32::from_buf(data.as_slice()).finish();
+                if real_checksum != expected_checksum {
+                    return Err(CorruptedChunkData(real_checksum, expected_checksum));
+                }
+            } else {
+                warn!("Chunk data does not have a checksum, skipping verification.");
+            }
+        }
+        return Ok((0, data));
+    }
+    if record.len() < RECORD_HEADER_LEN {
+        return Err(WorldError::DecompressionError(
+            "Chunk record is shorter than its header".to_string(),
+        ));
+    }
+    let algorithm = CompressorType::from_id(record[1]).ok_or_else(|| {
+        WorldError::DecompressionError(format!("Unknown compressor id {}", record[1]))
+    })?;
+    let version = u16::from_be_bytes([record[2], record[3]]);
+    let expected_checksum = u32::from_be_bytes([record[4], record[5], record[6], record[7]]);
+    // The level only matters when compressing
+    let data = Compressor::create(algorithm, 0).decompress(&record[RECORD_HEADER_LEN..])?;
+    if verify {
+        let real_checksum = yazi::Adler32::from_buf(&data).finish();
+        if real_checksum != expected_checksum {
+            return Err(CorruptedChunkData(real_checksum, expected_checksum));
+        }
+    }
+    Ok((version, data))
+}
+
+/// The bits of a key that identify the dimension, the rest hold the packed chunk position.
+const DIMENSION_KEY_MASK: u128 = !((1 << 96) - 1);
+
+fn dimension_key_prefix(dimension: Dimension) -> u128 {
     let mut hasher = wyhash::WyHash::with_seed(0);
-    hasher.write(dimension.as_bytes());
+    hasher.write(dimension.name().as_bytes());
     hasher.write_u8(0xFF);
     let dim_hash = hasher.finish();
-    (dim_hash as u128) << 96 | pos.pack() as u128
+    (dim_hash as u128) << 96
+}
+
+fn create_key(dimension: Dimension, pos: ChunkPos) -> u128 {
+    dimension_key_prefix(dimension) | pos.pack() as u128
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_state_id::BlockStateId;
+    use crate::migrations::encode_v0;
+    use crate::pos::ChunkBlockPos;
+    use ferrumc_macros::block;
+    use ferrumc_storage::errors::StorageError;
+    use ferrumc_storage::memory::MemoryBackend;
+
+    fn record(world: &World, pos: ChunkPos, dimension: Dimension) -> Vec<u8> {
+        world
+            .storage_backend
+            .get("chunks".to_string(), create_key(dimension, pos))
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_recompressed_records() {
+        let world = World::with_backend(MemoryBackend::new()).unwrap();
+        let dimension = Dimension::Overworld;
+        let mut chunk = Chunk::new(dimension.height());
+        chunk
+            .set_block(ChunkBlockPos::new(3, 10, 4), block!("gold_block"))
+            .unwrap();
+        let positions = [ChunkPos::new(0, 0), ChunkPos::new(1, 0)];
+        for pos in positions {
+            world
+                .save_chunk(pos, dimension, Arc::new(chunk.clone()))
+                .unwrap();
+        }
+
+        let brotli = Compressor::create(CompressorType::Brotli, 5);
+        assert_eq!(world.recompress(brotli).unwrap(), 2);
+        world.cache.invalidate_all();
+        for pos in positions {
+            let record = record(&world, pos, dimension);
+            assert_eq!(record[..2], [RECORD_MAGIC, CompressorType::Brotli.id()]);
+            assert_eq!(*world.load_chunk(pos, dimension).unwrap(), chunk);
+        }
+
+        let mut corrupted = compress_record(CHUNK_FORMAT_VERSION, b"chunk", &brotli).unwrap();
+        corrupted[4] ^= 1;
+        assert!(matches!(
+            decompress_record(&corrupted),
+            Err(CorruptedChunkData(..))
+        ));
+    }
+
+    #[test]
+    fn test_legacy_records_are_migrated() {
+        let world = World::with_backend(MemoryBackend::new()).unwrap();
+        let dimension = Dimension::Overworld;
+        // A record from before chunks had a header, which is always format version 0
+        let mut expected = Chunk::new(dimension.height());
+        expected
+            .set_block(ChunkBlockPos::new(3, 10, 4), block!("gold_block"))
+            .unwrap();
+        let data = encode_v0(&expected);
+        let legacy =
+            yazi::compress(&data, yazi::Format::Zlib, yazi::CompressionLevel::BestSpeed).unwrap();
+        let positions = [
+            ChunkPos::new(0, 0),
+            ChunkPos::new(0, 1),
+            ChunkPos::new(0, 2),
+        ];
+        let records = positions
+            .iter()
+            .map(|pos| (create_key(dimension, *pos), legacy.clone()))
+            .collect();
+        world
+            .storage_backend
+            .batch_upsert("chunks".to_string(), records)
+            .unwrap();
+
+        // Loading upgrades the stored record
+        assert_eq!(
+            *world.load_chunk(positions[0], dimension).unwrap(),
+            expected
+        );
+        let upgraded = record(&world, positions[0], dimension);
+        assert_eq!(upgraded[0], RECORD_MAGIC);
+        assert_eq!(
+            decompress_record(&upgraded).unwrap().0,
+            CHUNK_FORMAT_VERSION
+        );
+
+        assert_eq!(world.migrate().unwrap(), 2);
+        assert_eq!(world.migrate().unwrap(), 0);
+        world.cache.invalidate_all();
+        for pos in positions {
+            assert_eq!(*world.load_chunk(pos, dimension).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_dirty_chunks_are_written_back() {
+        let world = World::with_backend(MemoryBackend::new()).unwrap();
+        let dimension = Dimension::End;
+        let mut chunk = Chunk::new(dimension.height());
+        chunk
+            .set_block(ChunkBlockPos::new(0, 60, 0), block!("end_stone"))
+            .unwrap();
+        let (first, second) = (ChunkPos::new(2, 2), ChunkPos::new(3, 2));
+
+        world.insert_chunk(first, dimension, Arc::new(chunk.clone()));
+        world.insert_chunk(second, dimension, Arc::new(chunk.clone()));
+        assert_eq!(world.dirty_chunk_count(), 2);
+        assert!(world.chunk_exists(first, dimension).unwrap());
+        assert!(world.stored_chunks(dimension).unwrap().is_empty());
+
+        // Evicting a dirty chunk writes it
+        world.cache.invalidate(&(first, dimension));
+        world.cache.run_pending_tasks();
+        assert_eq!(world.dirty_chunk_count(), 1);
+        assert_eq!(world.stored_chunks(dimension).unwrap(), vec![first]);
+
+        world.sync().unwrap();
+        assert_eq!(world.dirty_chunk_count(), 0);
+        let mut stored = world.stored_chunks(dimension).unwrap();
+        stored.sort_by_key(|pos| pos.x());
+        assert_eq!(stored, vec![first, second]);
+
+        // Reading a chunk doesn't make it dirty
+        world.cache.invalidate_all();
+        assert_eq!(*world.load_chunk(first, dimension).unwrap(), chunk);
+        assert_eq!(world.dirty_chunk_count(), 0);
+    }
+
+    #[test]
+    fn test_revisions_change_with_chunk() {
+        let world = World::with_backend(MemoryBackend::new()).unwrap();
+        let (pos, dimension) = (ChunkPos::new(-1, 5), Dimension::Overworld);
+        // Chunks that aren't in memory aren't tracked
+        let unsaved = world.chunk_revision(pos, dimension);
+        assert!(world.chunk_revision(pos, dimension) > unsaved);
+        assert!(world.chunk_revisions.is_empty());
+
+        let chunk = Arc::new(Chunk::new(dimension.height()));
+        world.save_chunk(pos, dimension, chunk.clone()).unwrap();
+        let saved = world.chunk_revision(pos, dimension);
+        assert_ne!(saved, unsaved);
+
+        // Unloading drops the revision, the chunk gets a fresh one when it's back
+        world.cache.invalidate_all();
+        world.cache.run_pending_tasks();
+        assert!(world.chunk_revisions.is_empty());
+        world.load_chunk(pos, dimension).unwrap();
+        let reloaded = world.chunk_revision(pos, dimension);
+        assert!(reloaded > saved);
+
+        world.insert_chunk(pos, dimension, chunk);
+        let inserted = world.chunk_revision(pos, dimension);
+        assert!(inserted > reloaded);
+        world.delete_chunk(pos, dimension).unwrap();
+        assert!(world.chunk_revisions.is_empty());
+        assert!(world.chunk_revision(pos, dimension) > inserted);
+        assert!(world.chunk_revisions.is_empty());
+    }
+
+    /// Holds the first chunk read back for long enough that the chunk can be edited meanwhile.
+    struct SlowFirstRead {
+        backend: MemoryBackend,
+        reading: std::sync::Mutex<Option<std::sync::mpsc::Sender<()>>>,
+    }
+
+    impl StorageBackend for SlowFirstRead {
+        fn insert(&self, table: String, key: u128, value: Vec<u8>) -> Result<(), StorageError> {
+            self.backend.insert(table, key, value)
+        }
+
+        fn get(&self, table: String, key: u128) -> Result<Option<Vec<u8>>, StorageError> {
+            let reading = (table == "chunks")
+                .then(|| self.reading.lock().unwrap().take())
+                .flatten();
+            if let Some(reading) = reading {
+                reading.send(()).unwrap();
+                std::thread::sleep(std::time::Duration::from_millis(200));
+            }
+            self.backend.get(table, key)
+        }
+
+        fn batch_get(
+            &self,
+            table: String,
+            keys: Vec<u128>,
+        ) -> Result<Vec<Option<Vec<u8>>>, StorageError> {
+            self.backend.batch_get(table, keys)
+        }
+
+        fn upsert(&self, table: String, key: u128, value: Vec<u8>) -> Result<bool, StorageError> {
+            self.backend.upsert(table, key, value)
+        }
+
+        fn batch_upsert(
+            &self,
+            table: String,
+            data: Vec<(u128, Vec<u8>)>,
+        ) -> Result<(), StorageError> {
+            self.backend.batch_upsert(table, data)
+        }
+
+        fn exists(&self, table: String, key: u128) -> Result<bool, StorageError> {
+            self.backend.exists(table, key)
+        }
+
+        fn delete(&self, table: String, key: u128) -> Result<(), StorageError> {
+            self.backend.delete(table, key)
+        }
+
+        fn keys(&self, table: String) -> Result<Vec<u128>, StorageError> {
+            self.backend.keys(table)
+        }
+
+        fn table_exists(&self, table: String) -> Result<bool, StorageError> {
+            self.backend.table_exists(table)
+        }
+
+        fn create_table(&self, table: String) -> Result<(), StorageError> {
+            self.backend.create_table(table)
+        }
+
+        fn flush(&self) -> Result<(), StorageError> {
+            self.backend.flush()
+        }
+
+        fn details(&self) -> String {
+            self.backend.details()
+        }
+    }
+
+    #[test]
+    fn test_loads_keep_concurrent_edits() {
+        let (reading, read_started) = std::sync::mpsc::channel();
+        let world = World::with_backend(SlowFirstRead {
+            backend: MemoryBackend::new(),
+            reading: std::sync::Mutex::new(Some(reading)),
+        })
+        .unwrap();
+        let (pos, dimension) = (ChunkPos::new(4, 4), Dimension::Overworld);
+        world
+            .save_chunk(pos, dimension, Arc::new(Chunk::new(dimension.height())))
+            .unwrap();
+        world.cache.invalidate_all();
+        world.cache.run_pending_tasks();
+
+        std::thread::scope(|scope| {
+            let loading = scope.spawn(|| world.load_chunk(pos, dimension).unwrap());
+            // Only the first read is slow, so without the chunk's lock the edit would finish
+            // first and the load would then cache the copy from before it
+            read_started.recv().unwrap();
+            world
+                .edit_chunk(pos, dimension, |chunk| {
+                    chunk.set_block(ChunkBlockPos::new(1, 70, 1), block!("stone"))
+                })
+                .unwrap();
+            loading.join().unwrap();
+        });
+
+        world.sync().unwrap();
+        let chunk = world.load_chunk(pos, dimension).unwrap();
+        assert_eq!(
+            chunk.get_block(ChunkBlockPos::new(1, 70, 1)).unwrap(),
+            block!("stone")
+        );
+    }
 }
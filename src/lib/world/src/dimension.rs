@@ -0,0 +1,138 @@
+//! Typed identifiers for the vanilla dimensions.
+//!
+//! A [`Dimension`] selects which set of chunks a position refers to. Its [`name`](Dimension::name)
+//! doubles as the storage key prefix, so the overworld keeps the `"overworld"` key chunks were
+//! saved under before dimensions were typed.
+
+use crate::pos::ChunkHeight;
+use bitcode_derive::{Decode, Encode};
+use deepsize::DeepSizeOf;
+use ferrumc_data::biomes::Biome;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Encode, Decode, DeepSizeOf)]
+pub enum Dimension {
+    #[default]
+    Overworld,
+    Nether,
+    End,
+}
+
+impl Dimension {
+    /// Every dimension the server knows about, in the order they are advertised to clients.
+    pub const ALL: [Dimension; 3] = [Dimension::Overworld, Dimension::Nether, Dimension::End];
+
+    /// The un-namespaced name of the dimension, e.g. `the_nether`.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Dimension::Overworld => "overworld",
+            Dimension::Nether => "the_nether",
+            Dimension::End => "the_end",
+        }
+    }
+
+    /// The namespaced identifier sent to clients, e.g. `minecraft:the_nether`.
+    pub const fn identifier(self) -> &'static str {
+        match self {
+            Dimension::Overworld => "minecraft:overworld",
+            Dimension::Nether => "minecraft:the_nether",
+            Dimension::End => "minecraft:the_end",
+        }
+    }
+
+    /// Parses a dimension identifier. The `minecraft:` namespace is optional.
+    pub fn from_identifier(identifier: &str) -> Option<Self> {
+        let name = identifier.strip_prefix("minecraft:").unwrap_or(identifier);
+        Dimension::ALL.into_iter().find(|dim| dim.name() == name)
+    }
+
+    /// The index of this dimension's type in the `minecraft:dimension_type` registry we send
+    /// during configuration.
+    pub const fn registry_id(self) -> i32 {
+        match self {
+            Dimension::Overworld => 0,
+            Dimension::End => 2,
+            Dimension::Nether => 3,
+        }
+    }
+
+    /// The vertical extent of chunks in this dimension.
+    pub const fn height(self) -> ChunkHeight {
+        match self {
+            Dimension::Overworld => ChunkHeight::new(-64, 384),
+            Dimension::Nether | Dimension::End => ChunkHeight::new(0, 256),
+        }
+    }
+
+    /// The biome chunks in this dimension have before anything else is set.
+    pub const fn default_biome(self) -> &'static Biome {
+        match self {
+            Dimension::Overworld => &Biome::PLAINS,
+            Dimension::Nether => &Biome::NETHER_WASTES,
+            Dimension::End => &Biome::THE_END,
+        }
+    }
+
+    pub const fn has_sky_light(self) -> bool {
+        matches!(self, Dimension::Overworld)
+    }
+
+    pub const fn sea_level(self) -> i32 {
+        match self {
+            Dimension::Overworld => 63,
+            Dimension::Nether => 32,
+            Dimension::End => 0,
+        }
+    }
+
+    /// The region folder for this dimension, relative to the root of a vanilla world save.
+    pub const fn region_dir(self) -> &'static str {
+        match self {
+            Dimension::Overworld => "region",
+            Dimension::Nether => "DIM-1/region",
+            Dimension::End => "DIM1/region",
+        }
+    }
+}
+
+impl Display for Dimension {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.identifier())
+    }
+}
+
+impl FromStr for Dimension {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Dimension::from_identifier(s).ok_or_else(|| format!("Unknown dimension: {s}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_with_and_without_namespace() {
+        assert_eq!("the_nether".parse(), Ok(Dimension::Nether));
+        assert_eq!("minecraft:the_end".parse(), Ok(Dimension::End));
+        assert_eq!(
+            Dimension::from_identifier("minecraft:overworld"),
+            Some(Dimension::Overworld)
+        );
+        assert!("nether".parse::<Dimension>().is_err());
+        for dim in Dimension::ALL {
+            assert_eq!(dim.to_string().parse(), Ok(dim));
+        }
+    }
+
+    #[test]
+    fn test_heights_match_vanilla() {
+        assert_eq!(Dimension::Overworld.height().min_y, -64);
+        assert_eq!(Dimension::Overworld.height().max_y(), 320);
+        assert_eq!(Dimension::Nether.height().max_y(), 256);
+        assert_eq!(Dimension::End.height().min_y, 0);
+    }
+}
@@ -1,5 +1,7 @@
+use crate::block_entity::BlockEntityKind;
 use crate::block_state_id::BlockStateId;
 use crate::chunk_format::{Chunk, PaletteType};
+use crate::lighting::LightEngine;
 use crate::pos::ChunkBlockPos;
 use crate::WorldError;
 use ahash::{AHashMap, AHashSet, AHasher};
@@ -39,6 +41,7 @@ pub struct EditBatch<'a> {
     pub(crate) edits: Vec<Edit>,
     chunk: &'a mut Chunk,
     tmp_palette_map: AHashMap<BlockStateId, usize>,
+    sky_light: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -47,6 +50,10 @@ pub(crate) struct Edit {
     pub(crate) block: BlockStateId,
 }
 
+/// Above this many edits, [`EditBatch::apply`] relights the whole chunk instead of updating light
+/// around each edited block.
+const FULL_RELIGHT_THRESHOLD: usize = 128;
+
 fn get_palette_hash(palette: &[VarInt]) -> i32 {
     let mut hasher = AHasher::default();
     palette.hash(&mut hasher);
@@ -65,9 +72,17 @@ impl<'a> EditBatch<'a> {
             edits: Vec::new(),
             chunk,
             tmp_palette_map: AHashMap::with_capacity(map_capacity),
+            sky_light: true,
         }
     }
 
+    /// Enables or disables sky light, see [`LightEngine::with_sky_light`]. Batches for chunks of
+    /// a dimension without a sky (such as the nether) should turn this off.
+    pub fn with_sky_light(mut self, enabled: bool) -> Self {
+        self.sky_light = enabled;
+        self
+    }
+
     /// Sets a block at the given chunk-relative coordinates.
     ///
     /// This won't have any effect until `apply()` is called.
@@ -77,7 +92,7 @@ impl<'a> EditBatch<'a> {
 
     /// Applies all edits in the batch to the chunk.
     ///
-    /// This will modify the chunk in place and clear the batch.
+    /// This will modify the chunk in place, update its light and heightmaps and clear the batch.
     /// Will return an error if there are no edits.
     pub fn apply(mut self) -> Result<(), WorldError> {
         if self.edits.is_empty() {
@@ -264,6 +279,35 @@ impl<'a> EditBatch<'a> {
             }
         }
 
+        // Most batches don't touch any block entities, so skip the lookups when we can
+        if !self.chunk.block_entities.is_empty()
+            || all_blocks
+                .iter()
+                .any(|block| BlockEntityKind::for_block(**block).is_some())
+        {
+            for edit in &self.edits {
+                let block = self.chunk.get_block(edit.pos)?;
+                self.chunk.sync_block_entity(edit.pos, block);
+            }
+        }
+
+        // Small batches are cheaper to light block by block, large ones (like terrain
+        // generation) are cheaper to relight in one go. The same goes for heightmaps.
+        if self.edits.len() > FULL_RELIGHT_THRESHOLD {
+            self.chunk.recalculate_heightmaps();
+            LightEngine::new(self.chunk)
+                .with_sky_light(self.sky_light)
+                .relight();
+        } else {
+            for edit in &self.edits {
+                self.chunk.update_heightmaps(edit.pos)?;
+            }
+            let mut engine = LightEngine::new(self.chunk).with_sky_light(self.sky_light);
+            for edit in &self.edits {
+                engine.update_block(edit.pos);
+            }
+        }
+
         // Clear edits after applying
         self.edits.clear();
 
@@ -1,7 +1,9 @@
 use crate::block_state_id::BlockStateId;
 use crate::chunk_format::{BlockStates, Chunk, PaletteType, Section};
+use crate::dimension::Dimension;
 use crate::errors::WorldError;
-use crate::pos::{BlockPos, ChunkBlockPos, SectionBlockPos};
+use crate::lighting::LightEngine;
+use crate::pos::{BlockPos, ChunkBlockPos, ChunkPos, SectionBlockPos};
 use crate::World;
 use ferrumc_general_purpose::data_packing::i32::read_nbit_i32;
 use ferrumc_macros::block;
@@ -35,46 +37,194 @@ impl World {
     pub fn get_block_and_fetch(
         &self,
         pos: BlockPos,
-        dimension: &str,
+        dimension: Dimension,
     ) -> Result<BlockStateId, WorldError> {
         let chunk = self.load_chunk(pos.chunk(), dimension)?;
         chunk.get_block(pos.chunk_block_pos())
     }
 
     /// Sets the block data at the specified coordinates in the given dimension.
-    /// Under the hood, this function just fetches the chunk containing the block and then calls
-    /// [`Chunk::set_block`] on it.
+    /// Under the hood, this function fetches the chunk containing the block, sets the block and
+    /// then updates the light around it. The neighbouring chunks are only fetched if the light
+    /// spills over into them.
     ///
     /// # Arguments
     ///
-    /// * `x` - The x-coordinate of the block.
-    /// * `y` - The y-coordinate of the block.
-    /// * `z` - The z-coordinate of the block.
+    /// * `pos` - The position of the block.
     /// * `dimension` - The dimension in which the block is located.
     /// * `block` - The block data to set.
     ///
     /// # Returns
     ///
-    /// * `Ok(())` - If the block data is successfully set.
+    /// * `Ok((BlockStateId, Vec<ChunkPos>))` - The block that was replaced, read under the same
+    ///   lock as the write, and the neighbouring chunks whose light changed. The chunk containing
+    ///   the block is not included. These are also queued to be resent to players, see
+    ///   [`World::take_relit_chunks`].
     /// * `Err(WorldError)` - If an error occurs while setting the block data.
     pub fn set_block_and_fetch(
         &self,
         pos: BlockPos,
-        dimension: &str,
+        dimension: Dimension,
         block: BlockStateId,
+    ) -> Result<(BlockStateId, Vec<ChunkPos>), WorldError> {
+        let chunk_pos = pos.chunk();
+        debug!("Chunk: {}", chunk_pos);
+
+        // Most changes don't light anything past the chunk, so it's lit on its own first
+        {
+            let _lock = self.chunk_locks.lock(chunk_pos, dimension);
+            let mut chunk = self.load_chunk_owned(chunk_pos, dimension)?;
+            let replaced = chunk.replace_block(pos.chunk_block_pos(), block)?;
+            let contained = {
+                let mut engine =
+                    LightEngine::new(&mut chunk).with_sky_light(dimension.has_sky_light());
+                engine.update_block(pos.chunk_block_pos());
+                !engine.reached_missing_neighbour()
+            };
+            if contained {
+                self.insert_chunk(chunk_pos, dimension, Arc::new(chunk));
+                return Ok((replaced, vec![]));
+            }
+        }
+
+        // The chunk's lock is let go of so all nine are taken in order, which means the chunk may
+        // have changed in between and the edit has to be made again
+        let _locks = self.chunk_locks.lock_around(chunk_pos, dimension);
+        let mut chunk = self.load_chunk_owned(chunk_pos, dimension)?;
+        let replaced = chunk.replace_block(pos.chunk_block_pos(), block)?;
+
+        let mut neighbours = self.load_light_neighbours(chunk_pos, dimension)?;
+        let changed = {
+            let mut engine = LightEngine::new(&mut chunk).with_sky_light(dimension.has_sky_light());
+            for ((dx, dz), neighbour) in &mut neighbours {
+                engine = engine.with_neighbour(*dx, *dz, neighbour);
+            }
+            engine.update_block(pos.chunk_block_pos());
+            engine.changed_neighbours()
+        };
+
+        self.insert_chunk(chunk_pos, dimension, Arc::new(chunk));
+        let relit = self.save_light_neighbours(chunk_pos, dimension, neighbours, &changed);
+        Ok((replaced, relit))
+    }
+
+    /// Sets several blocks that only make sense together, such as both halves of a door or bed.
+    /// They may span more than one chunk, so they're set one at a time with
+    /// [`World::set_block_and_fetch`]. If one of them fails, the ones already set are put back
+    /// so no part is left on its own.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If every block was set.
+    /// * `Err(WorldError)` - The error of the block that couldn't be set, after the others were
+    ///   put back.
+    pub fn set_blocks_and_fetch(
+        &self,
+        blocks: &[(BlockPos, BlockStateId)],
+        dimension: Dimension,
     ) -> Result<(), WorldError> {
-        let mut chunk = self.load_chunk_owned(pos.chunk(), dimension)?;
+        let mut replaced = Vec::with_capacity(blocks.len());
+        for &(pos, block) in blocks {
+            match self.set_block_and_fetch(pos, dimension, block) {
+                Ok((old, _)) => replaced.push((pos, old)),
+                Err(err) => {
+                    for (pos, old) in replaced.into_iter().rev() {
+                        if let Err(err) = self.set_block_and_fetch(pos, dimension, old) {
+                            error!("Failed to put back block at {pos}: {err}");
+                        }
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        Ok(())
+    }
 
-        debug!("Chunk: {}", pos.chunk());
+    /// Loads the chunk, passes it to `f` and stores the result, without any other edit to the
+    /// chunk being able to slip in between. Use this instead of pairing [`World::load_chunk_owned`]
+    /// with [`World::save_chunk`], which loses whatever was written to the chunk in the meantime.
+    ///
+    /// If `f` fails, the chunk is left untouched. Edits made by `f` should light the chunk for
+    /// `dimension`, e.g. through [`EditBatch::with_sky_light`](crate::edit_batch::EditBatch::with_sky_light).
+    /// If any blocks changed, the chunk is then relit along with its neighbours so light crosses
+    /// the borders between them, see [`World::relight_chunk`]. Use [`World::set_block_and_fetch`]
+    /// for single blocks, which only fetches the neighbours when the light reaches them.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok((R, Vec<BlockPos>))` - What `f` returned and every block whose state changed, which
+    ///   should be sent to any players that have the chunk loaded.
+    /// * `Err(WorldError)` - If the chunk couldn't be loaded or `f` failed.
+    pub fn edit_chunk<R>(
+        &self,
+        pos: ChunkPos,
+        dimension: Dimension,
+        f: impl FnOnce(&mut Chunk) -> Result<R, WorldError>,
+    ) -> Result<(R, Vec<BlockPos>), WorldError> {
+        let (ret, changed) = {
+            let _lock = self.chunk_locks.lock(pos, dimension);
+            let before = self.load_chunk(pos, dimension)?;
+            let mut chunk = (*before).clone();
+            let ret = f(&mut chunk)?;
+
+            if chunk == *before {
+                return Ok((ret, vec![]));
+            }
+            let changed: Vec<_> = before
+                .changed_blocks(&chunk)?
+                .into_iter()
+                .map(|block_pos| pos.chunk_block(block_pos))
+                .collect();
+            self.insert_chunk(pos, dimension, Arc::new(chunk));
+            (ret, changed)
+        };
+        if !changed.is_empty() {
+            self.relight_across_borders(pos, dimension);
+        }
+        Ok((ret, changed))
+    }
 
-        chunk.set_block(pos.chunk_block_pos(), block)?;
-        for section in &mut chunk.sections {
-            section.optimise()?;
+    /// Loads the chunk, or if it doesn't exist yet stores the one `generate` returns. The check
+    /// and the store happen under the chunk's lock, so a chunk generated or edited at the same
+    /// time is never replaced by a fresh one. A generated chunk is relit along with its stored
+    /// neighbours, since it was lit without them.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Arc<Chunk>)` - The chunk that is stored now.
+    /// * `Err(E)` - If the chunk couldn't be loaded or stored, or `generate` failed.
+    pub fn get_or_generate_chunk<E: From<WorldError>>(
+        &self,
+        pos: ChunkPos,
+        dimension: Dimension,
+        generate: impl FnOnce() -> Result<Chunk, E>,
+    ) -> Result<Arc<Chunk>, E> {
+        {
+            let _lock = self.chunk_locks.lock(pos, dimension);
+            if self.chunk_exists(pos, dimension)? {
+                return Ok(self.load_chunk(pos, dimension)?);
+            }
+            self.save_chunk(pos, dimension, Arc::new(generate()?))?;
         }
+        self.relight_across_borders(pos, dimension);
+        Ok(self.load_chunk(pos, dimension)?)
+    }
 
-        // Save chunk
-        self.save_chunk(pos.chunk(), dimension, Arc::new(chunk))?;
-        Ok(())
+    /// Relights a chunk that was just written along with its neighbours. The chunk's own lock has
+    /// to be let go of first, so the locks around it can be taken in order. The chunk is already
+    /// stored by then, so a failure is only logged.
+    fn relight_across_borders(&self, pos: ChunkPos, dimension: Dimension) {
+        // Without any neighbours the chunk's own light is already right
+        let has_neighbours = (-1..=1)
+            .flat_map(|dz| (-1..=1).map(move |dx| (dx, dz)))
+            .filter(|&offset| offset != (0, 0))
+            .any(|offset| self.chunk_exists(pos + offset, dimension).unwrap_or(true));
+        if !has_neighbours {
+            return;
+        }
+        if let Err(err) = self.relight_chunk(pos, dimension) {
+            error!("Failed to relight chunk {pos} in {dimension} along with its neighbours: {err}");
+        }
     }
 }
 
@@ -187,15 +337,38 @@ impl Chunk {
     /// If the block is the same as the old block, nothing happens.
     /// If the block is not in the palette, it is added.
     /// If the palette is in single block mode, it is converted to palette'd mode.
+    ///
+    /// Light and heightmaps inside this chunk are updated to match. Light crossing into neighbouring
+    /// chunks is not, use [`World::set_block_and_fetch`] for that. Sky light is updated as if the
+    /// chunk were under an open sky, so chunks of dimensions without one should be edited with an
+    /// [`EditBatch`](crate::edit_batch::EditBatch) that has sky light turned off.
     pub fn set_block(&mut self, pos: ChunkBlockPos, block: BlockStateId) -> Result<(), WorldError> {
+        if self.replace_block(pos, block)? != block {
+            LightEngine::new(self).update_block(pos);
+        }
+        Ok(())
+    }
+
+    /// Sets the block without touching light, returning the block that was there before.
+    pub(crate) fn replace_block(
+        &mut self,
+        pos: ChunkBlockPos,
+        block: BlockStateId,
+    ) -> Result<BlockStateId, WorldError> {
         let section = self
             .get_section_mut(pos.section())
             .ok_or(WorldError::SectionOutOfBounds(pos.section() as i32))?;
+        let old_block = section.get_block(pos.section_block_pos())?;
         section.set_block(pos.section_block_pos(), block)?;
 
         section.optimise()?;
 
-        Ok(())
+        if old_block != block {
+            self.sync_block_entity(pos, block);
+            self.update_heightmaps(pos)?;
+        }
+
+        Ok(old_block)
     }
 
     pub fn get_block(&self, pos: ChunkBlockPos) -> Result<BlockStateId, WorldError> {
@@ -205,9 +378,35 @@ impl Chunk {
         section.get_block(pos.section_block_pos())
     }
 
+    /// Lists the blocks that differ between this chunk and `other`, which must have the same
+    /// height. Only sections whose block states differ are scanned.
+    pub(crate) fn changed_blocks(&self, other: &Chunk) -> Result<Vec<ChunkBlockPos>, WorldError> {
+        let mut changed = Vec::new();
+        for (index, (old, new)) in self.sections.iter().zip(&other.sections).enumerate() {
+            if old.block_states == new.block_states {
+                continue;
+            }
+            let min_y = self.min_y + index as i16 * 16;
+            for y in min_y..min_y + 16 {
+                for z in 0..16 {
+                    for x in 0..16 {
+                        let pos = ChunkBlockPos::new(x, y, z);
+                        let section_pos = pos.section_block_pos();
+                        if old.get_block(section_pos)? != new.get_block(section_pos)? {
+                            changed.push(pos);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(changed)
+    }
+
     /// Sets the section at the specified index to the specified block data.
     /// If the section is out of bounds, an error is returned.
     ///
+    /// This doesn't update light, call [`Chunk::relight`] once done filling.
+    ///
     /// # Arguments
     ///
     /// * `section` - The index of the section to set.
@@ -219,7 +418,10 @@ impl Chunk {
     /// * `Err(WorldError)` - If an error occurs while setting the section.
     pub fn set_section(&mut self, section_y: i8, block: BlockStateId) -> Result<(), WorldError> {
         if let Some(section) = self.get_section_mut(section_y) {
-            section.fill(block)
+            section.fill(block)?;
+            self.prune_block_entities();
+            self.recalculate_heightmaps();
+            Ok(())
         } else {
             Err(WorldError::SectionOutOfBounds(section_y as i32))
         }
@@ -227,6 +429,8 @@ impl Chunk {
 
     /// Fills the chunk with the specified block.
     ///
+    /// This doesn't update light, call [`Chunk::relight`] once done filling.
+    ///
     /// # Arguments
     ///
     /// * `block` - The block data to fill the chunk with.
@@ -239,6 +443,8 @@ impl Chunk {
         for section in &mut self.sections {
             section.fill(block)?;
         }
+        self.prune_block_entities();
+        self.recalculate_heightmaps();
         Ok(())
     }
 }
@@ -465,11 +671,15 @@ impl Section {
                         }
                     }
                 }
+                self.block_states.block_counts.retain(|_, count| *count > 0);
+                // Highest first, so earlier removals don't shift the indexes still to be removed
+                remove_indexes.sort_unstable_by(|a, b| b.cmp(a));
                 for index in remove_indexes {
+                    palette.remove(index);
                     // Decrement any data entries that are higher than the removed index
                     for data_point in &mut *data {
                         let mut i = 0;
-                        while (i + *bits_per_block as usize) < 64 {
+                        while i + *bits_per_block as usize <= 64 {
                             let block_index =
                                 ferrumc_general_purpose::data_packing::u32::read_nbit_u32(
                                     data_point,
@@ -507,3 +717,283 @@ impl Section {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lighting::LightType;
+    use ferrumc_storage::memory::MemoryBackend;
+
+    #[test]
+    fn test_concurrent_edits_are_kept() {
+        let world = World::with_backend(MemoryBackend::new()).unwrap();
+        let pos = ChunkPos::new(3, -1);
+        world
+            .save_chunk(
+                pos,
+                Dimension::Overworld,
+                Arc::new(Chunk::new(Dimension::Overworld.height())),
+            )
+            .unwrap();
+
+        std::thread::scope(|scope| {
+            for x in 0..8u8 {
+                let world = &world;
+                scope.spawn(move || {
+                    for z in 0..16u8 {
+                        let ((), changed) = world
+                            .edit_chunk(pos, Dimension::Overworld, |chunk| {
+                                chunk.set_block(ChunkBlockPos::new(x, 70, z), block!("stone"))
+                            })
+                            .unwrap();
+                        let expected = pos.chunk_block(ChunkBlockPos::new(x, 70, z));
+                        assert_eq!(changed, vec![expected]);
+                    }
+                });
+            }
+        });
+
+        let chunk = world.load_chunk(pos, Dimension::Overworld).unwrap();
+        for x in 0..8 {
+            for z in 0..16 {
+                assert_eq!(
+                    chunk.get_block(ChunkBlockPos::new(x, 70, z)).unwrap(),
+                    block!("stone")
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_concurrent_sets_replace_once() {
+        let world = World::with_backend(MemoryBackend::new()).unwrap();
+        let pos = BlockPos::of(5, 64, 5);
+        world
+            .save_chunk(
+                pos.chunk(),
+                Dimension::Overworld,
+                Arc::new(Chunk::new(Dimension::Overworld.height())),
+            )
+            .unwrap();
+        world
+            .set_block_and_fetch(pos, Dimension::Overworld, block!("stone"))
+            .unwrap();
+
+        // Only one of the breaks gets the stone, the rest replace air
+        let replaced: Vec<BlockStateId> = std::thread::scope(|scope| {
+            let breaks: Vec<_> = (0..8)
+                .map(|_| {
+                    scope.spawn(|| {
+                        world
+                            .set_block_and_fetch(pos, Dimension::Overworld, BlockStateId::default())
+                            .unwrap()
+                            .0
+                    })
+                })
+                .collect();
+            breaks.into_iter().map(|b| b.join().unwrap()).collect()
+        });
+        assert_eq!(
+            replaced.iter().filter(|&&b| b == block!("stone")).count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_chunk_is_only_generated_once() {
+        let world = World::with_backend(MemoryBackend::new()).unwrap();
+        let pos = ChunkPos::new(-2, 5);
+        let generated = std::sync::atomic::AtomicUsize::new(0);
+
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    world
+                        .get_or_generate_chunk(pos, Dimension::Overworld, || {
+                            generated.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                            Ok::<_, WorldError>(Chunk::new(Dimension::Overworld.height()))
+                        })
+                        .unwrap();
+                    world
+                        .edit_chunk(pos, Dimension::Overworld, |chunk| {
+                            chunk.set_block(ChunkBlockPos::new(0, 70, 0), block!("stone"))
+                        })
+                        .unwrap();
+                });
+            }
+        });
+
+        assert_eq!(generated.into_inner(), 1);
+        let chunk = world.load_chunk(pos, Dimension::Overworld).unwrap();
+        assert_eq!(
+            chunk.get_block(ChunkBlockPos::new(0, 70, 0)).unwrap(),
+            block!("stone")
+        );
+    }
+
+    #[test]
+    fn test_failed_edit_is_discarded() {
+        let world = World::with_backend(MemoryBackend::new()).unwrap();
+        let pos = ChunkPos::new(0, 0);
+        world
+            .save_chunk(
+                pos,
+                Dimension::Overworld,
+                Arc::new(Chunk::new(Dimension::Overworld.height())),
+            )
+            .unwrap();
+
+        let result = world.edit_chunk(pos, Dimension::Overworld, |chunk| {
+            chunk.set_block(ChunkBlockPos::new(1, 64, 1), block!("stone"))?;
+            chunk.set_block(ChunkBlockPos::new(1, 4000, 1), block!("stone"))
+        });
+        assert!(matches!(result, Err(WorldError::SectionOutOfBounds(_))));
+        assert_eq!(world.dirty_chunk_count(), 0);
+        assert_eq!(
+            world
+                .get_block_and_fetch(BlockPos::of(1, 64, 1), Dimension::Overworld)
+                .unwrap(),
+            BlockStateId::default()
+        );
+
+        let ((), changed) = world
+            .edit_chunk(pos, Dimension::Overworld, |_| Ok(()))
+            .unwrap();
+        assert!(changed.is_empty());
+    }
+
+    #[test]
+    fn test_failed_multi_block_set_is_undone() {
+        let world = World::with_backend(MemoryBackend::new()).unwrap();
+        world
+            .save_chunk(
+                ChunkPos::new(0, 0),
+                Dimension::Overworld,
+                Arc::new(Chunk::new(Dimension::Overworld.height())),
+            )
+            .unwrap();
+
+        // The second block is in a chunk that was never stored
+        let placed = BlockPos::of(15, 64, 0);
+        let result = world.set_blocks_and_fetch(
+            &[
+                (placed, block!("stone")),
+                (BlockPos::of(16, 64, 0), block!("stone")),
+            ],
+            Dimension::Overworld,
+        );
+        assert!(matches!(result, Err(WorldError::ChunkNotFound)));
+        assert_eq!(
+            world
+                .get_block_and_fetch(placed, Dimension::Overworld)
+                .unwrap(),
+            BlockStateId::default()
+        );
+    }
+
+    #[test]
+    fn test_removing_a_palette_entry_keeps_other_blocks() {
+        let mut chunk = Chunk::new(Dimension::Overworld.height());
+        let blocks = [block!("stone"), block!("dirt"), block!("cobblestone")];
+        for x in 0..16u8 {
+            for z in 0..16u8 {
+                let block = blocks[(x as usize + z as usize) % blocks.len()];
+                chunk
+                    .set_block(ChunkBlockPos::new(x, 64, z), block)
+                    .unwrap();
+            }
+        }
+
+        // Dropping every dirt block removes it from the palette
+        for x in 0..16u8 {
+            for z in 0..16u8 {
+                if (x as usize + z as usize) % blocks.len() == 1 {
+                    chunk
+                        .set_block(ChunkBlockPos::new(x, 64, z), BlockStateId::default())
+                        .unwrap();
+                }
+            }
+        }
+
+        for x in 0..16u8 {
+            for z in 0..16u8 {
+                let expected = match (x as usize + z as usize) % blocks.len() {
+                    1 => BlockStateId::default(),
+                    i => blocks[i],
+                };
+                assert_eq!(
+                    chunk.get_block(ChunkBlockPos::new(x, 64, z)).unwrap(),
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_only_relit_neighbours_are_returned() {
+        let world = World::with_backend(MemoryBackend::new()).unwrap();
+        let dimension = Dimension::Overworld;
+        let (pos, east) = (ChunkPos::new(0, 0), ChunkPos::new(1, 0));
+        for chunk_pos in [pos, east] {
+            world
+                .save_chunk(
+                    chunk_pos,
+                    dimension,
+                    Arc::new(Chunk::new(dimension.height())),
+                )
+                .unwrap();
+        }
+
+        let (_, relit) = world
+            .set_block_and_fetch(BlockPos::of(8, 64, 8), dimension, block!("stone"))
+            .unwrap();
+        assert!(relit.is_empty());
+
+        let (_, relit) = world
+            .set_block_and_fetch(BlockPos::of(15, 64, 8), dimension, block!("glowstone"))
+            .unwrap();
+        assert_eq!(relit, vec![east]);
+        let east = world.load_chunk(east, dimension).unwrap();
+        assert_eq!(
+            east.get_light(LightType::Block, ChunkBlockPos::new(0, 64, 8))
+                .unwrap(),
+            14
+        );
+    }
+
+    #[test]
+    fn test_edits_and_generated_chunks_light_their_neighbours() {
+        let world = World::with_backend(MemoryBackend::new()).unwrap();
+        let dimension = Dimension::Overworld;
+        let east = ChunkPos::new(1, 0);
+        world
+            .save_chunk(east, dimension, Arc::new(Chunk::new(dimension.height())))
+            .unwrap();
+        let east_light = |world: &World| {
+            world
+                .load_chunk(east, dimension)
+                .unwrap()
+                .get_light(LightType::Block, ChunkBlockPos::new(0, 64, 8))
+                .unwrap()
+        };
+
+        world
+            .get_or_generate_chunk(ChunkPos::new(0, 0), dimension, || {
+                let mut chunk = Chunk::new(dimension.height());
+                chunk.set_block(ChunkBlockPos::new(15, 64, 8), block!("glowstone"))?;
+                Ok::<_, WorldError>(chunk)
+            })
+            .unwrap();
+        assert_eq!(east_light(&world), 14);
+        assert_eq!(world.take_relit_chunks(), vec![(east, dimension)]);
+        assert!(world.take_relit_chunks().is_empty());
+
+        world
+            .edit_chunk(ChunkPos::new(0, 0), dimension, |chunk| {
+                chunk.set_block(ChunkBlockPos::new(15, 64, 8), block!("stone"))
+            })
+            .unwrap();
+        assert_eq!(east_light(&world), 0);
+        assert_eq!(world.take_relit_chunks(), vec![(east, dimension)]);
+    }
+}
@@ -20,6 +20,10 @@ pub enum WorldError {
     InvalidCacheSize(String),
     #[error("Invalid Import Path: {0}")]
     InvalidImportPath(String),
+    #[error("Invalid Export Path: {0}")]
+    InvalidExportPath(String),
+    #[error("Export failed: {0}")]
+    ExportError(String),
     #[error("No region files")]
     NoRegionFiles,
     #[error("Unable to obtain permission to access file/folder: {0}")]
@@ -50,14 +54,28 @@ pub enum WorldError {
     InvalidBlock(BlockStateId),
     #[error("Invalid batching operation: {0}")]
     InvalidBatchingOperation(String),
+    #[error("Invalid block entity: {0}")]
+    InvalidBlockEntity(String),
     #[error("Invalid block state ID: {0}")]
     InvalidBlockStateId(BlockStateId),
+    #[error("Invalid biome ID: {0}")]
+    InvalidBiome(i32),
+    #[error("Invalid biome data: {0}")]
+    InvalidBiomeData(String),
+    #[error("Invalid schematic: {0}")]
+    InvalidSchematic(String),
     #[error("World generation error: {0}")]
     WorldGenerationError(String),
     #[error("Compression error: {0}")]
     CompressionError(String),
     #[error("Decompression error: {0}")]
     DecompressionError(String),
+    #[error("Chunk format version {0} is newer than this version of FerrumC supports")]
+    UnsupportedChunkVersion(u16),
+    #[error("Player data format version {0} is newer than this version of FerrumC supports")]
+    UnsupportedPlayerDataVersion(u16),
+    #[error("Player data for {0} couldn't be read, refusing to overwrite it")]
+    UnreadablePlayerData(String),
     #[error("Corrupted chunk data: got checksum {0}, expected checksum {1}")]
     CorruptedChunkData(u32, u32),
     #[error("NBT data error: {0}")]
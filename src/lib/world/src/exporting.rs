@@ -0,0 +1,462 @@
+use crate::chunk_format::DATA_VERSION;
+use crate::dimension::Dimension;
+use crate::errors::WorldError;
+use crate::pos::ChunkPos;
+use crate::vanilla_chunk_format::VanillaChunk;
+use crate::World;
+use ferrumc_anvil::writer::AnvilFileWriter;
+use ferrumc_macros::NBTSerialize;
+use ferrumc_nbt::{NBTSerializable, NBTSerializeOptions};
+use ferrumc_threadpool::ThreadPool;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{error, info};
+
+impl World {
+    /// Exports the world as a vanilla world save. Each dimension's chunks are written to the
+    /// region folder vanilla expects them in, so the overworld goes to `region`, the nether to
+    /// `DIM-1/region` and the end to `DIM1/region`. Existing region files are overwritten, and a
+    /// `level.dat` is written so vanilla can open the folder as a world.
+    pub fn export(&self, export_dir: PathBuf, threadpool: ThreadPool) -> Result<(), WorldError> {
+        check_export_path_validity(&export_dir)?;
+        // Only stored chunks are listed, so edits that haven't been written yet would be missed
+        self.sync()?;
+        self.write_level_dat(&export_dir)?;
+
+        let mut regions = Vec::new();
+        let mut total_chunks = 0;
+        for dimension in Dimension::ALL {
+            let chunks = self.stored_chunks(dimension)?;
+            total_chunks += chunks.len() as u64;
+            let mut by_region: BTreeMap<(i32, i32), Vec<ChunkPos>> = BTreeMap::new();
+            for pos in chunks {
+                by_region
+                    .entry((pos.x() >> 5, pos.z() >> 5))
+                    .or_default()
+                    .push(pos);
+            }
+            if !by_region.is_empty() {
+                std::fs::create_dir_all(export_dir.join(dimension.region_dir()))?;
+            }
+            regions.extend(
+                by_region
+                    .into_iter()
+                    .map(|(region, chunks)| (dimension, region, chunks)),
+            );
+        }
+
+        let progress_style = ProgressStyle::default_bar()
+            .template("[{elapsed_precise}/{eta_precise} eta] {bar:40.cyan/blue} {percent}%, {pos:>7}/{len:7}, {msg}")
+            .unwrap();
+
+        let progress = ProgressBar::new(total_chunks);
+        progress.set_style(progress_style);
+        progress.set_message("Exporting chunks...");
+
+        let start = std::time::Instant::now();
+
+        let mut batch = threadpool.batch();
+
+        for (dimension, (region_x, region_z), chunks) in regions {
+            let region_path = export_dir
+                .join(dimension.region_dir())
+                .join(format!("r.{region_x}.{region_z}.mca"));
+            batch.execute({
+                let self_clone = self.clone();
+                let progress = progress.clone();
+                move || {
+                    let res = self_clone.export_region(dimension, &chunks, region_path);
+                    progress.inc(chunks.len() as u64);
+                    res
+                }
+            });
+        }
+
+        let mut failed = false;
+        for result in batch.wait() {
+            if let Err(e) = result {
+                error!("Error exporting region: {}", e);
+                failed = true;
+            }
+        }
+
+        progress.finish_with_message("Export complete");
+
+        info!(
+            "Exported {} chunks in {:?}",
+            progress.position(),
+            start.elapsed()
+        );
+
+        if failed {
+            return Err(WorldError::ExportError(
+                "One or more regions could not be exported".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Writes the `level.dat` vanilla needs to open the world. Chunks that weren't exported are
+    /// generated by vanilla's own default generators.
+    fn write_level_dat(&self, export_dir: &Path) -> Result<(), WorldError> {
+        let last_played = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_millis() as i64);
+        let time = self.game_time() as i64;
+        let level = LevelDat {
+            data: LevelData {
+                data_version: DATA_VERSION,
+                version: LevelVersion {
+                    id: DATA_VERSION,
+                    name: GAME_VERSION.to_string(),
+                    series: "main".to_string(),
+                    snapshot: false,
+                },
+                storage_version: ANVIL_VERSION,
+                level_name: export_dir
+                    .file_name()
+                    .map_or_else(|| "world".to_string(), |name| name.to_string_lossy().into()),
+                game_type: 0,
+                difficulty: 2,
+                hardcore: false,
+                allow_commands: true,
+                initialized: true,
+                spawn_x: 0,
+                spawn_y: 100,
+                spawn_z: 0,
+                time,
+                day_time: time,
+                last_played,
+                world_gen_settings: WorldGenSettings {
+                    seed: 0,
+                    generate_features: true,
+                    bonus_chest: false,
+                    dimensions: Dimension::ALL
+                        .into_iter()
+                        .map(|dimension| {
+                            (
+                                dimension.identifier().to_string(),
+                                LevelStem::vanilla(dimension),
+                            )
+                        })
+                        .collect(),
+                },
+            },
+        };
+
+        let mut nbt = Vec::new();
+        level.serialize(&mut nbt, &NBTSerializeOptions::WithHeader(""));
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&nbt)?;
+        std::fs::write(export_dir.join("level.dat"), encoder.finish()?)?;
+        Ok(())
+    }
+
+    /// Writes the given chunks, which must all lie in the same region, to a region file.
+    fn export_region(
+        &self,
+        dimension: Dimension,
+        chunks: &[ChunkPos],
+        region_path: PathBuf,
+    ) -> Result<(), WorldError> {
+        let mut writer = AnvilFileWriter::new();
+        for &pos in chunks {
+            let chunk = self.load_chunk(pos, dimension)?;
+            let data = chunk_to_nbt(&VanillaChunk::from_custom_format(&chunk, pos)?);
+            writer.insert_chunk(pos.x() as u32, pos.z() as u32, &data)?;
+        }
+        writer.write(region_path)?;
+        Ok(())
+    }
+}
+
+/// The version the exported chunks are written for, which has to match [`DATA_VERSION`].
+const GAME_VERSION: &str = "1.21.8";
+
+/// Marks the world as stored in region files rather than the older formats.
+const ANVIL_VERSION: i32 = 19133;
+
+#[derive(NBTSerialize)]
+struct LevelDat {
+    #[nbt(rename = "Data")]
+    data: LevelData,
+}
+
+#[derive(NBTSerialize)]
+struct LevelData {
+    #[nbt(rename = "DataVersion")]
+    data_version: i32,
+    #[nbt(rename = "Version")]
+    version: LevelVersion,
+    #[nbt(rename = "version")]
+    storage_version: i32,
+    #[nbt(rename = "LevelName")]
+    level_name: String,
+    #[nbt(rename = "GameType")]
+    game_type: i32,
+    #[nbt(rename = "Difficulty")]
+    difficulty: i8,
+    hardcore: bool,
+    #[nbt(rename = "allowCommands")]
+    allow_commands: bool,
+    initialized: bool,
+    #[nbt(rename = "SpawnX")]
+    spawn_x: i32,
+    #[nbt(rename = "SpawnY")]
+    spawn_y: i32,
+    #[nbt(rename = "SpawnZ")]
+    spawn_z: i32,
+    #[nbt(rename = "Time")]
+    time: i64,
+    #[nbt(rename = "DayTime")]
+    day_time: i64,
+    #[nbt(rename = "LastPlayed")]
+    last_played: i64,
+    #[nbt(rename = "WorldGenSettings")]
+    world_gen_settings: WorldGenSettings,
+}
+
+#[derive(NBTSerialize)]
+struct LevelVersion {
+    #[nbt(rename = "Id")]
+    id: i32,
+    #[nbt(rename = "Name")]
+    name: String,
+    #[nbt(rename = "Series")]
+    series: String,
+    #[nbt(rename = "Snapshot")]
+    snapshot: bool,
+}
+
+#[derive(NBTSerialize)]
+struct WorldGenSettings {
+    seed: i64,
+    generate_features: bool,
+    bonus_chest: bool,
+    dimensions: BTreeMap<String, LevelStem>,
+}
+
+/// A dimension as listed in `level.dat`, along with the generator vanilla uses for it.
+#[derive(NBTSerialize)]
+struct LevelStem {
+    #[nbt(rename = "type")]
+    kind: String,
+    generator: ChunkGenerator,
+}
+
+#[derive(NBTSerialize)]
+struct ChunkGenerator {
+    #[nbt(rename = "type")]
+    kind: String,
+    settings: String,
+    biome_source: BiomeSource,
+}
+
+#[derive(NBTSerialize)]
+struct BiomeSource {
+    #[nbt(rename = "type")]
+    kind: String,
+    preset: Option<String>,
+}
+
+impl LevelStem {
+    fn vanilla(dimension: Dimension) -> LevelStem {
+        // The generator presets are named without the `the_`
+        let settings = match dimension {
+            Dimension::Overworld => "minecraft:overworld",
+            Dimension::Nether => "minecraft:nether",
+            Dimension::End => "minecraft:end",
+        };
+        let biome_source = match dimension {
+            Dimension::Overworld | Dimension::Nether => BiomeSource {
+                kind: "minecraft:multi_noise".to_string(),
+                preset: Some(settings.to_string()),
+            },
+            Dimension::End => BiomeSource {
+                kind: "minecraft:the_end".to_string(),
+                preset: None,
+            },
+        };
+        LevelStem {
+            kind: dimension.identifier().to_string(),
+            generator: ChunkGenerator {
+                kind: "minecraft:noise".to_string(),
+                settings: settings.to_string(),
+                biome_source,
+            },
+        }
+    }
+}
+
+/// Serializes a chunk the way vanilla does, as a compound with an empty root name.
+fn chunk_to_nbt(chunk: &VanillaChunk) -> Vec<u8> {
+    let mut buf = Vec::new();
+    chunk.serialize(&mut buf, &NBTSerializeOptions::WithHeader(""));
+    buf
+}
+
+fn check_export_path_validity(export_dir: &Path) -> Result<(), WorldError> {
+    if export_dir.is_file() {
+        return Err(WorldError::InvalidExportPath(
+            export_dir.display().to_string(),
+        ));
+    }
+    std::fs::create_dir_all(export_dir)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_entity::{BlockEntity, BlockEntityKind};
+    use crate::block_state_id::BlockStateId;
+    use crate::chunk_format::Chunk;
+    use crate::lighting::LightEngine;
+    use crate::pos::ChunkBlockPos;
+    use ferrumc_anvil::load_anvil_file;
+    use ferrumc_macros::block;
+    use ferrumc_nbt::{NbtTape, NbtTapeElement, RawCompound};
+    use ferrumc_storage::memory::MemoryBackend;
+    use std::io::Read;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    /// Writes the chunk to a region file and reads it back through the import path.
+    fn round_trip(chunk: &Chunk, pos: ChunkPos, dimension: Dimension, dir: &Path) -> Chunk {
+        let mut writer = AnvilFileWriter::new();
+        let data = chunk_to_nbt(&VanillaChunk::from_custom_format(chunk, pos).unwrap());
+        writer
+            .insert_chunk(pos.x() as u32, pos.z() as u32, &data)
+            .unwrap();
+        let region_path = dir.join(format!("r.{}.{}.mca", pos.x() >> 5, pos.z() >> 5));
+        writer.write(region_path.clone()).unwrap();
+
+        let region = load_anvil_file(region_path).unwrap();
+        let data = region
+            .get_chunk(pos.x() as u32, pos.z() as u32)
+            .unwrap()
+            .unwrap();
+        let vanilla = VanillaChunk::from_bytes(&data).unwrap();
+        assert_eq!((vanilla.x_pos, vanilla.z_pos), (pos.x(), pos.z()));
+        vanilla.to_custom_format(dimension).unwrap()
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let dir = tempdir().unwrap();
+        let dimension = Dimension::Overworld;
+        let pos = ChunkPos::new(-3, 40);
+
+        let mut chunk = Chunk::new(dimension.height());
+        chunk.set_section(-4, block!("stone")).unwrap();
+        chunk.set_section(-3, block!("stone")).unwrap();
+        let blocks = [
+            ((0, -49, 0), block!("dirt")),
+            ((15, -49, 15), block!("oak_log", {axis: "y"})),
+            ((7, 64, 3), block!("glowstone")),
+            ((1, 319, 14), block!("diamond_block")),
+        ];
+        for ((x, y, z), block) in blocks {
+            chunk.set_block(ChunkBlockPos::new(x, y, z), block).unwrap();
+        }
+        let chest_pos = ChunkBlockPos::new(9, -20, 12);
+        chunk
+            .set_block(
+                chest_pos,
+                block!("chest", {facing: "west", r#type: "single", waterlogged: false}),
+            )
+            .unwrap();
+        let mut contents = RawCompound::new();
+        contents.insert("CustomName", &"\"Loot\"");
+        chunk
+            .set_block_entity(BlockEntity::with_data(
+                BlockEntityKind::Chest,
+                chest_pos,
+                contents.clone(),
+            ))
+            .unwrap();
+        LightEngine::new(&mut chunk)
+            .with_sky_light(dimension.has_sky_light())
+            .relight();
+
+        // Import, export, then import again
+        let imported = round_trip(&chunk, pos, dimension, dir.path());
+        let reimported = round_trip(&imported, pos, dimension, dir.path());
+        assert_eq!(imported, reimported);
+
+        for ((x, y, z), block) in blocks {
+            let pos = ChunkBlockPos::new(x, y, z);
+            assert_eq!(imported.get_block(pos).unwrap(), block);
+        }
+        assert_eq!(
+            imported.get_block(ChunkBlockPos::new(4, -60, 4)).unwrap(),
+            block!("stone")
+        );
+        assert_eq!(
+            imported.get_block(ChunkBlockPos::new(4, 100, 4)).unwrap(),
+            BlockStateId::default()
+        );
+        assert_eq!(imported.block_entities, chunk.block_entities);
+        assert_eq!(
+            imported.get_block_entity(chest_pos).unwrap().data(),
+            contents
+        );
+        for (original, imported) in chunk.sections.iter().zip(&imported.sections) {
+            assert_eq!(original.block_light, imported.block_light);
+            assert_eq!(original.sky_light, imported.sky_light);
+        }
+    }
+
+    #[test]
+    fn test_export_includes_unsaved_chunks_and_level_dat() {
+        let dir = tempdir().unwrap();
+        let world = World::with_backend(MemoryBackend::new()).unwrap();
+        let pos = ChunkPos::new(2, -1);
+        // Only in memory, it hasn't been written to storage yet
+        let mut chunk = Chunk::new(Dimension::Nether.height());
+        chunk
+            .set_block(ChunkBlockPos::new(3, 40, 5), block!("netherrack"))
+            .unwrap();
+        world.insert_chunk(pos, Dimension::Nether, Arc::new(chunk));
+        assert_eq!(world.dirty_chunk_count(), 1);
+
+        world
+            .export(dir.path().to_path_buf(), ThreadPool::new())
+            .unwrap();
+
+        let region = load_anvil_file(dir.path().join("DIM-1/region/r.0.-1.mca")).unwrap();
+        let data = region
+            .get_chunk(pos.x() as u32, pos.z() as u32)
+            .unwrap()
+            .unwrap();
+        let exported = VanillaChunk::from_bytes(&data)
+            .unwrap()
+            .to_custom_format(Dimension::Nether)
+            .unwrap();
+        assert_eq!(
+            exported.get_block(ChunkBlockPos::new(3, 40, 5)).unwrap(),
+            block!("netherrack")
+        );
+
+        let mut level = Vec::new();
+        flate2::read::GzDecoder::new(&std::fs::read(dir.path().join("level.dat")).unwrap()[..])
+            .read_to_end(&mut level)
+            .unwrap();
+        let mut tape = NbtTape::new(&level);
+        tape.parse();
+        let data = tape.get("Data").unwrap();
+        assert!(matches!(
+            data.get("DataVersion"),
+            Some(NbtTapeElement::Int(DATA_VERSION))
+        ));
+        let dimensions = data
+            .get("WorldGenSettings")
+            .and_then(|settings| settings.get("dimensions"))
+            .and_then(|dimensions| dimensions.as_compound())
+            .unwrap();
+        assert_eq!(dimensions.len(), 3);
+    }
+}
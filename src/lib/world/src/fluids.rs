@@ -0,0 +1,773 @@
+//! Water and lava flow.
+//!
+//! Fluids only move when they are ticked. A change next to a fluid schedules a tick for it (see
+//! [`crate::scheduled_ticks`]), a few game ticks later depending on the fluid and dimension (see
+//! [`FluidRules`]), and that tick works out the fluid's new level from its neighbours and spreads
+//! it down or sideways towards the nearest drop, the same way vanilla does. Every block a tick
+//! changes schedules its neighbours in turn, until the fluid settles.
+
+use crate::block_state_id::{BlockStateId, BLOCK2ID, ID2BLOCK};
+use crate::dimension::Dimension;
+use crate::errors::WorldError;
+use crate::lighting::property;
+use crate::pos::BlockPos;
+use crate::vanilla_chunk_format::BlockData;
+use crate::World;
+use bevy_math::IVec3;
+use ferrumc_macros::block;
+use lazy_static::lazy_static;
+
+const HORIZONTAL: [IVec3; 4] = [IVec3::X, IVec3::NEG_X, IVec3::Z, IVec3::NEG_Z];
+
+/// The slope distance of a direction with no way down in reach. It's never a real distance (the
+/// search depth is set by [`FluidRules::slope_distance`]), so directions that tie on it still all
+/// get spread into.
+const NO_SLOPE: u8 = u8::MAX;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FluidKind {
+    Water,
+    Lava,
+}
+
+/// How a fluid behaves in a dimension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FluidRules {
+    /// Game ticks between a fluid being disturbed and it flowing on.
+    pub tick_delay: u32,
+    /// How much the level drops for every block the fluid flows sideways.
+    pub level_drop: u8,
+    /// How far sideways the fluid looks for a way down before spreading in every direction.
+    pub slope_distance: u8,
+    /// Whether two sources next to each other turn the flowing fluid between them into a source.
+    pub infinite_sources: bool,
+}
+
+impl FluidKind {
+    pub fn rules(self, dimension: Dimension) -> FluidRules {
+        match (self, dimension) {
+            (FluidKind::Water, _) => FluidRules {
+                tick_delay: 5,
+                level_drop: 1,
+                slope_distance: 4,
+                infinite_sources: true,
+            },
+            (FluidKind::Lava, Dimension::Nether) => FluidRules {
+                tick_delay: 10,
+                level_drop: 1,
+                slope_distance: 4,
+                infinite_sources: false,
+            },
+            (FluidKind::Lava, _) => FluidRules {
+                tick_delay: 30,
+                level_drop: 2,
+                slope_distance: 2,
+                infinite_sources: false,
+            },
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            FluidKind::Water => "minecraft:water",
+            FluidKind::Lava => "minecraft:lava",
+        }
+    }
+}
+
+/// A fluid block, with its `level` property as vanilla stores it: 0 for a source, 1 to 7 for
+/// fluid flowing further and further away from one and 8 for fluid falling down.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FluidState {
+    pub kind: FluidKind,
+    pub level: u8,
+}
+
+impl FluidState {
+    pub fn source(kind: FluidKind) -> Self {
+        Self { kind, level: 0 }
+    }
+
+    pub fn falling(kind: FluidKind) -> Self {
+        Self { kind, level: 8 }
+    }
+
+    /// Fluid holding `amount` of 8, for 1 to 7.
+    pub fn flowing(kind: FluidKind, amount: u8) -> Self {
+        Self {
+            kind,
+            level: 8 - amount.clamp(1, 7),
+        }
+    }
+
+    pub fn is_source(&self) -> bool {
+        self.level == 0
+    }
+
+    pub fn is_falling(&self) -> bool {
+        self.level >= 8
+    }
+
+    /// How full the block is, from 1 to 8. Sources and falling fluid are full.
+    pub fn amount(&self) -> u8 {
+        if self.is_source() || self.is_falling() {
+            8
+        } else {
+            8 - self.level
+        }
+    }
+
+    pub fn block(&self) -> BlockStateId {
+        FLUID_BLOCKS[self.kind as usize][self.level.min(15) as usize]
+    }
+}
+
+/// What a block state means to a flowing fluid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FluidBlock {
+    /// Air, or a block that fluids wash away.
+    Replaceable,
+    Fluid(FluidState),
+    /// A waterlogged block holds a water source, but can't be flowed into.
+    Waterlogged,
+    Solid,
+}
+
+/// Blocks without a waterlogged state that fluids flow through and destroy.
+const WASHED_AWAY_BLOCKS: &[&str] = &[
+    "air",
+    "cave_air",
+    "void_air",
+    "short_grass",
+    "tall_grass",
+    "short_dry_grass",
+    "tall_dry_grass",
+    "fern",
+    "large_fern",
+    "dead_bush",
+    "bush",
+    "firefly_bush",
+    "dandelion",
+    "poppy",
+    "blue_orchid",
+    "allium",
+    "azure_bluet",
+    "red_tulip",
+    "orange_tulip",
+    "white_tulip",
+    "pink_tulip",
+    "oxeye_daisy",
+    "cornflower",
+    "lily_of_the_valley",
+    "wither_rose",
+    "torchflower",
+    "sunflower",
+    "lilac",
+    "rose_bush",
+    "peony",
+    "pitcher_plant",
+    "pink_petals",
+    "wildflowers",
+    "leaf_litter",
+    "brown_mushroom",
+    "red_mushroom",
+    "wheat",
+    "carrots",
+    "potatoes",
+    "beetroots",
+    "melon_stem",
+    "pumpkin_stem",
+    "attached_melon_stem",
+    "attached_pumpkin_stem",
+    "sweet_berry_bush",
+    "nether_wart",
+    "sugar_cane",
+    "torch",
+    "wall_torch",
+    "soul_torch",
+    "soul_wall_torch",
+    "redstone_torch",
+    "redstone_wall_torch",
+    "redstone_wire",
+    "repeater",
+    "comparator",
+    "tripwire",
+    "snow",
+    "fire",
+    "soul_fire",
+    "cobweb",
+];
+
+lazy_static! {
+    static ref FLUID_BLOCK_KINDS: Vec<FluidBlock> = ID2BLOCK.iter().map(fluid_block).collect();
+    static ref FLUID_BLOCKS: [[BlockStateId; 16]; 2] = [FluidKind::Water, FluidKind::Lava]
+        .map(|kind| std::array::from_fn(|level| fluid_state_id(kind, level as u8)));
+}
+
+fn fluid_state_id(kind: FluidKind, level: u8) -> BlockStateId {
+    let data = BlockData {
+        name: kind.name().to_string(),
+        properties: Some([("level".to_string(), level.to_string())].into()),
+    };
+    BLOCK2ID
+        .get(&data)
+        .map(|id| BlockStateId::new(*id as u32))
+        .unwrap_or_default()
+}
+
+fn fluid_block(data: &BlockData) -> FluidBlock {
+    let name = data.name.strip_prefix("minecraft:").unwrap_or(&data.name);
+    let kind = match name {
+        "water" => Some(FluidKind::Water),
+        "lava" => Some(FluidKind::Lava),
+        _ => None,
+    };
+    if let Some(kind) = kind {
+        let level = property(data, "level")
+            .and_then(|level| level.parse().ok())
+            .unwrap_or(0);
+        return FluidBlock::Fluid(FluidState { kind, level });
+    }
+    match property(data, "waterlogged") {
+        Some("true") => FluidBlock::Waterlogged,
+        Some(_) => FluidBlock::Solid,
+        None if WASHED_AWAY_BLOCKS.contains(&name) || name.ends_with("_sapling") => {
+            FluidBlock::Replaceable
+        }
+        None => FluidBlock::Solid,
+    }
+}
+
+impl BlockStateId {
+    /// The fluid this block holds, counting waterlogged blocks as water sources.
+    pub fn fluid(&self) -> Option<FluidState> {
+        match FLUID_BLOCK_KINDS.get(self.raw() as usize)? {
+            FluidBlock::Fluid(state) => Some(*state),
+            FluidBlock::Waterlogged => Some(FluidState::source(FluidKind::Water)),
+            _ => None,
+        }
+    }
+}
+
+/// How a fluid reacts to a block update.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum FluidUpdate {
+    /// Lava touching water hardens straight away, into this block.
+    Harden(BlockStateId),
+    /// Anything else flows on when its tick runs, this many game ticks later.
+    Tick(u32),
+}
+
+/// Reads the blocks around a fluid. Blocks in chunks that don't exist or out of the world's height
+/// read as solid, so fluids never flow out of the loaded world.
+struct FluidView<'a> {
+    world: &'a World,
+    dimension: Dimension,
+}
+
+impl FluidView<'_> {
+    fn block(&self, pos: IVec3) -> Option<BlockStateId> {
+        self.world
+            .get_block_and_fetch(BlockPos { pos }, self.dimension)
+            .ok()
+    }
+
+    fn kind(&self, pos: IVec3) -> FluidBlock {
+        self.block(pos)
+            .and_then(|block| FLUID_BLOCK_KINDS.get(block.raw() as usize).copied())
+            .unwrap_or(FluidBlock::Solid)
+    }
+
+    fn fluid(&self, pos: IVec3, kind: FluidKind) -> Option<FluidState> {
+        self.block(pos)?.fluid().filter(|fluid| fluid.kind == kind)
+    }
+
+    fn set(
+        &self,
+        pos: IVec3,
+        block: BlockStateId,
+        changed: &mut Vec<BlockPos>,
+    ) -> Result<(), WorldError> {
+        let pos = BlockPos { pos };
+        self.world.set_block_and_fetch(pos, self.dimension, block)?;
+        changed.push(pos);
+        Ok(())
+    }
+
+    /// The block lava turns into when it touches water, or soul soil next to blue ice.
+    fn lava_interaction(&self, pos: IVec3, state: FluidState) -> Option<BlockStateId> {
+        let around = [IVec3::Y, IVec3::X, IVec3::NEG_X, IVec3::Z, IVec3::NEG_Z];
+        if around
+            .iter()
+            .any(|dir| self.fluid(pos + dir, FluidKind::Water).is_some())
+        {
+            return Some(if state.is_source() {
+                block!("obsidian")
+            } else {
+                block!("cobblestone")
+            });
+        }
+        let blue_ice = block!("blue_ice");
+        if self.block(pos - IVec3::Y) == Some(block!("soul_soil"))
+            && around
+                .iter()
+                .any(|dir| self.block(pos + dir) == Some(blue_ice))
+        {
+            return Some(block!("basalt", {axis: "y"}));
+        }
+        None
+    }
+
+    /// The fluid a block should hold given its neighbours, or `None` if it should dry up.
+    fn new_fluid(&self, pos: IVec3, kind: FluidKind) -> Option<FluidState> {
+        let rules = kind.rules(self.dimension);
+        let mut max_amount = 0;
+        let mut sources = 0;
+        for dir in HORIZONTAL {
+            if let Some(fluid) = self.fluid(pos + dir, kind) {
+                if fluid.is_source() {
+                    sources += 1;
+                }
+                max_amount = max_amount.max(fluid.amount());
+            }
+        }
+        if rules.infinite_sources && sources >= 2 {
+            let below = pos - IVec3::Y;
+            let on_solid = self.kind(below) == FluidBlock::Solid;
+            if on_solid
+                || self
+                    .fluid(below, kind)
+                    .is_some_and(|fluid| fluid.is_source())
+            {
+                return Some(FluidState::source(kind));
+            }
+        }
+        if self.fluid(pos + IVec3::Y, kind).is_some() {
+            return Some(FluidState::falling(kind));
+        }
+        match max_amount.saturating_sub(rules.level_drop) {
+            0 => None,
+            amount => Some(FluidState::flowing(kind, amount)),
+        }
+    }
+
+    /// Whether fluid can flow through the block while looking for a way down.
+    fn can_pass_through(&self, pos: IVec3, kind: FluidKind) -> bool {
+        match self.kind(pos) {
+            FluidBlock::Replaceable => true,
+            FluidBlock::Fluid(fluid) => fluid.kind == kind && !fluid.is_source(),
+            _ => false,
+        }
+    }
+
+    /// Whether fluid above the block would fall into it.
+    fn is_hole(&self, pos: IVec3, kind: FluidKind) -> bool {
+        self.kind(pos) == FluidBlock::Replaceable || self.fluid(pos, kind).is_some()
+    }
+
+    fn can_flow_into(&self, pos: IVec3, kind: FluidKind, dir: IVec3) -> bool {
+        match self.kind(pos) {
+            FluidBlock::Replaceable => true,
+            // Lava falling on water turns it into stone
+            FluidBlock::Fluid(fluid) => {
+                kind == FluidKind::Lava && fluid.kind == FluidKind::Water && dir == IVec3::NEG_Y
+            }
+            _ => false,
+        }
+    }
+
+    fn flow_into(
+        &self,
+        pos: IVec3,
+        fluid: FluidState,
+        changed: &mut Vec<BlockPos>,
+    ) -> Result<(), WorldError> {
+        let block = match self.kind(pos) {
+            FluidBlock::Fluid(target) if target.kind != fluid.kind => block!("stone"),
+            _ => fluid.block(),
+        };
+        self.set(pos, block, changed)
+    }
+
+    fn spread(
+        &self,
+        pos: IVec3,
+        state: FluidState,
+        changed: &mut Vec<BlockPos>,
+    ) -> Result<(), WorldError> {
+        let below = pos - IVec3::Y;
+        if self.can_flow_into(below, state.kind, IVec3::NEG_Y) {
+            self.flow_into(below, FluidState::falling(state.kind), changed)?;
+            let sources = HORIZONTAL
+                .iter()
+                .filter(|dir| {
+                    self.fluid(pos + **dir, state.kind)
+                        .is_some_and(|fluid| fluid.is_source())
+                })
+                .count();
+            if sources >= 3 {
+                self.spread_to_sides(pos, state, changed)?;
+            }
+        } else if state.is_source() || !self.is_hole(below, state.kind) {
+            self.spread_to_sides(pos, state, changed)?;
+        }
+        Ok(())
+    }
+
+    fn spread_to_sides(
+        &self,
+        pos: IVec3,
+        state: FluidState,
+        changed: &mut Vec<BlockPos>,
+    ) -> Result<(), WorldError> {
+        let rules = state.kind.rules(self.dimension);
+        let amount = if state.is_falling() {
+            7
+        } else {
+            state.amount().saturating_sub(rules.level_drop)
+        };
+        if amount == 0 {
+            return Ok(());
+        }
+        for (dir, fluid) in self.spread_targets(pos, state.kind) {
+            if self.can_flow_into(pos + dir, state.kind, dir) {
+                self.flow_into(pos + dir, fluid, changed)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The directions with the shortest way down, and the fluid that would flow that way.
+    fn spread_targets(&self, pos: IVec3, kind: FluidKind) -> Vec<(IVec3, FluidState)> {
+        let mut best = NO_SLOPE;
+        let mut targets = Vec::new();
+        for dir in HORIZONTAL {
+            let target = pos + dir;
+            if !self.can_pass_through(target, kind) {
+                continue;
+            }
+            let Some(fluid) = self.new_fluid(target, kind) else {
+                continue;
+            };
+            let distance = if self.is_hole(target - IVec3::Y, kind) {
+                0
+            } else {
+                self.slope_distance(target, 1, -dir, kind)
+            };
+            if distance < best {
+                targets.clear();
+                best = distance;
+            }
+            if distance == best {
+                targets.push((dir, fluid));
+            }
+        }
+        targets
+    }
+
+    fn slope_distance(&self, pos: IVec3, depth: u8, from: IVec3, kind: FluidKind) -> u8 {
+        let mut best = NO_SLOPE;
+        for dir in HORIZONTAL {
+            if dir == from {
+                continue;
+            }
+            let next = pos + dir;
+            if !self.can_pass_through(next, kind) {
+                continue;
+            }
+            if self.is_hole(next - IVec3::Y, kind) {
+                return depth;
+            }
+            if depth < kind.rules(self.dimension).slope_distance {
+                best = best.min(self.slope_distance(next, depth + 1, -dir, kind));
+            }
+        }
+        best
+    }
+}
+
+impl World {
+    /// Runs the tick of the fluid at `pos`, updating its level and letting it flow on.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<BlockPos>)` - Every block the fluid changed, whose neighbours should be updated
+    ///   with [`World::update_neighbours`] and which should be sent to any players that have them
+    ///   loaded.
+    /// * `Err(WorldError)` - If a block couldn't be set.
+    pub fn tick_fluid(
+        &self,
+        pos: BlockPos,
+        dimension: Dimension,
+    ) -> Result<Vec<BlockPos>, WorldError> {
+        let view = FluidView {
+            world: self,
+            dimension,
+        };
+        let mut changed = Vec::new();
+        let Some(mut state) = view.block(pos.pos).and_then(|block| block.fluid()) else {
+            return Ok(changed);
+        };
+        if state.kind == FluidKind::Lava {
+            if let Some(block) = view.lava_interaction(pos.pos, state) {
+                view.set(pos.pos, block, &mut changed)?;
+                return Ok(changed);
+            }
+        }
+        if !state.is_source() {
+            match view.new_fluid(pos.pos, state.kind) {
+                None => {
+                    view.set(pos.pos, BlockStateId::default(), &mut changed)?;
+                    return Ok(changed);
+                }
+                Some(new) if new != state => {
+                    view.set(pos.pos, new.block(), &mut changed)?;
+                    state = new;
+                }
+                Some(_) => {}
+            }
+        }
+        view.spread(pos.pos, state, &mut changed)?;
+        Ok(changed)
+    }
+
+    /// How the fluid in `block` at `pos` reacts to a block update, or `None` if it holds no fluid.
+    pub(crate) fn fluid_update(
+        &self,
+        pos: BlockPos,
+        dimension: Dimension,
+        block: BlockStateId,
+    ) -> Option<FluidUpdate> {
+        let state = block.fluid()?;
+        if state.kind == FluidKind::Lava {
+            let view = FluidView {
+                world: self,
+                dimension,
+            };
+            if let Some(block) = view.lava_interaction(pos.pos, state) {
+                return Some(FluidUpdate::Harden(block));
+            }
+        }
+        Some(FluidUpdate::Tick(state.kind.rules(dimension).tick_delay))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_format::Chunk;
+    use crate::edit_batch::EditBatch;
+    use crate::pos::{ChunkBlockPos, ChunkPos};
+    use ferrumc_storage::memory::MemoryBackend;
+    use std::sync::Arc;
+
+    /// A world of nine chunks with a stone floor at y = 63.
+    fn flat_world(dimension: Dimension) -> World {
+        let world = World::with_backend(MemoryBackend::new()).unwrap();
+        for x in -1..=1 {
+            for z in -1..=1 {
+                let mut chunk = Chunk::new(dimension.height());
+                let mut batch = EditBatch::new(&mut chunk);
+                for bx in 0..16 {
+                    for bz in 0..16 {
+                        batch.set_block(ChunkBlockPos::new(bx, 63, bz), block!("stone"));
+                    }
+                }
+                batch.apply().unwrap();
+                world
+                    .save_chunk(ChunkPos::new(x, z), dimension, Arc::new(chunk))
+                    .unwrap();
+            }
+        }
+        world
+    }
+
+    fn place(world: &World, dimension: Dimension, pos: BlockPos, block: BlockStateId) {
+        world.set_block_and_fetch(pos, dimension, block).unwrap();
+        world.update_neighbours(dimension, &[pos]).unwrap();
+    }
+
+    fn settle(world: &World) {
+        for _ in 0..2000 {
+            if world.scheduled_tick_count() == 0 {
+                return;
+            }
+            world.run_scheduled_ticks(usize::MAX);
+        }
+        panic!("fluid never settled");
+    }
+
+    fn fluid_at(world: &World, dimension: Dimension, x: i32, y: i32, z: i32) -> Option<FluidState> {
+        world
+            .get_block_and_fetch(BlockPos::of(x, y, z), dimension)
+            .unwrap()
+            .fluid()
+    }
+
+    #[test]
+    fn test_water_spreads_and_dries_up() {
+        let world = flat_world(Dimension::Overworld);
+        let source = BlockPos::of(0, 64, 0);
+        place(
+            &world,
+            Dimension::Overworld,
+            source,
+            FluidState::source(FluidKind::Water).block(),
+        );
+        settle(&world);
+
+        for distance in 1..=7 {
+            let fluid = fluid_at(&world, Dimension::Overworld, distance, 64, 0).unwrap();
+            assert_eq!(fluid.amount(), 8 - distance as u8);
+            assert_eq!(
+                fluid_at(&world, Dimension::Overworld, 0, 64, -distance),
+                Some(fluid)
+            );
+        }
+        assert_eq!(fluid_at(&world, Dimension::Overworld, 8, 64, 0), None);
+        assert_eq!(
+            fluid_at(&world, Dimension::Overworld, 3, 64, 4)
+                .unwrap()
+                .amount(),
+            1
+        );
+
+        place(
+            &world,
+            Dimension::Overworld,
+            source,
+            BlockStateId::default(),
+        );
+        settle(&world);
+        for distance in 0..=7 {
+            assert_eq!(
+                fluid_at(&world, Dimension::Overworld, distance, 64, 0),
+                None
+            );
+        }
+    }
+
+    #[test]
+    fn test_water_flows_towards_a_drop() {
+        let world = flat_world(Dimension::Overworld);
+        world
+            .set_block_and_fetch(
+                BlockPos::of(2, 63, 0),
+                Dimension::Overworld,
+                BlockStateId::default(),
+            )
+            .unwrap();
+        place(
+            &world,
+            Dimension::Overworld,
+            BlockPos::of(0, 64, 0),
+            FluidState::source(FluidKind::Water).block(),
+        );
+        for _ in 0..12 {
+            world.run_scheduled_ticks(usize::MAX);
+        }
+        // Only the side with the hole gets water at first
+        assert!(fluid_at(&world, Dimension::Overworld, 1, 64, 0).is_some());
+        assert!(fluid_at(&world, Dimension::Overworld, -1, 64, 0).is_none());
+        settle(&world);
+        assert!(fluid_at(&world, Dimension::Overworld, 2, 63, 0)
+            .unwrap()
+            .is_falling());
+    }
+
+    #[test]
+    fn test_two_sources_make_a_third() {
+        let world = flat_world(Dimension::Overworld);
+        let water = FluidState::source(FluidKind::Water).block();
+        place(&world, Dimension::Overworld, BlockPos::of(0, 64, 0), water);
+        place(&world, Dimension::Overworld, BlockPos::of(2, 64, 0), water);
+        settle(&world);
+        assert!(fluid_at(&world, Dimension::Overworld, 1, 64, 0)
+            .unwrap()
+            .is_source());
+
+        // Lava doesn't
+        let world = flat_world(Dimension::Overworld);
+        let lava = FluidState::source(FluidKind::Lava).block();
+        place(&world, Dimension::Overworld, BlockPos::of(0, 64, 0), lava);
+        place(&world, Dimension::Overworld, BlockPos::of(2, 64, 0), lava);
+        settle(&world);
+        assert!(!fluid_at(&world, Dimension::Overworld, 1, 64, 0)
+            .unwrap()
+            .is_source());
+    }
+
+    #[test]
+    fn test_lava_reach_depends_on_dimension() {
+        for (dimension, reach) in [(Dimension::Overworld, 3), (Dimension::Nether, 7)] {
+            let world = flat_world(dimension);
+            place(
+                &world,
+                dimension,
+                BlockPos::of(0, 64, 0),
+                FluidState::source(FluidKind::Lava).block(),
+            );
+            settle(&world);
+            assert!(
+                fluid_at(&world, dimension, reach, 64, 0).is_some(),
+                "{dimension}"
+            );
+            assert!(
+                fluid_at(&world, dimension, reach + 1, 64, 0).is_none(),
+                "{dimension}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_lava_and_water_interactions() {
+        let world = flat_world(Dimension::Overworld);
+        let lava = FluidState::source(FluidKind::Lava).block();
+        let water = FluidState::source(FluidKind::Water).block();
+
+        // Water flowing into a lava source makes obsidian, and into flowing lava cobblestone
+        place(&world, Dimension::Overworld, BlockPos::of(3, 64, 0), lava);
+        place(
+            &world,
+            Dimension::Overworld,
+            BlockPos::of(-3, 64, 0),
+            FluidState::flowing(FluidKind::Lava, 2).block(),
+        );
+        place(&world, Dimension::Overworld, BlockPos::of(0, 64, 0), water);
+        settle(&world);
+        let block_at = |x, y, z| {
+            world
+                .get_block_and_fetch(BlockPos::of(x, y, z), Dimension::Overworld)
+                .unwrap()
+        };
+        assert_eq!(block_at(3, 64, 0), block!("obsidian"));
+        assert_eq!(block_at(-3, 64, 0), block!("cobblestone"));
+
+        // Lava falling on water makes stone
+        place(&world, Dimension::Overworld, BlockPos::of(0, 66, 6), lava);
+        world
+            .set_block_and_fetch(BlockPos::of(0, 64, 6), Dimension::Overworld, water)
+            .unwrap();
+        settle(&world);
+        assert_eq!(block_at(0, 64, 6), block!("stone"));
+    }
+
+    #[test]
+    fn test_budget_and_delay() {
+        let world = flat_world(Dimension::Overworld);
+        let water = FluidState::source(FluidKind::Water).block();
+        place(&world, Dimension::Overworld, BlockPos::of(0, 64, 0), water);
+        place(&world, Dimension::Overworld, BlockPos::of(8, 64, 8), water);
+        assert_eq!(world.scheduled_tick_count(), 2);
+
+        // Nothing is due until the water's delay has passed
+        for _ in 0..4 {
+            assert!(world.run_scheduled_ticks(1).is_empty());
+        }
+        let changed = world.run_scheduled_ticks(1);
+        assert_eq!(changed[&Dimension::Overworld].len(), 4);
+        // The other source runs on the next tick, ahead of anything scheduled since
+        let changed = world.run_scheduled_ticks(1);
+        assert!(changed[&Dimension::Overworld].contains(&BlockPos::of(9, 64, 8)));
+    }
+}
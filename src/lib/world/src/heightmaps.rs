@@ -0,0 +1,507 @@
+//! Heightmap calculation and maintenance.
+//!
+//! A heightmap stores, for each of the 256 columns of a chunk, one more than the height of the
+//! highest block matching the heightmap's predicate, relative to the bottom of the world. Columns
+//! with no matching block store 0. Entries are packed into longs the way vanilla stores them,
+//! using just enough bits to hold the height of the world, without spanning two longs.
+//!
+//! Heightmaps are kept up to date by [`Chunk::set_block`], [`EditBatch::apply`] and the section
+//! fills, so they only need to be recalculated from scratch after editing sections directly.
+//!
+//! [`EditBatch::apply`]: crate::edit_batch::EditBatch::apply
+
+use crate::block_state_id::{BlockStateId, ID2BLOCK};
+use crate::chunk_format::{Chunk, Heightmaps, PaletteType};
+use crate::errors::WorldError;
+use crate::lighting::property;
+use crate::pos::ChunkBlockPos;
+use crate::vanilla_chunk_format::BlockData;
+use lazy_static::lazy_static;
+
+/// The heightmaps tracked for every chunk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HeightmapType {
+    /// The highest block that isn't air.
+    WorldSurface,
+    /// The highest block that blocks movement, ignoring fluids.
+    OceanFloor,
+    /// The highest block that blocks movement or contains a fluid.
+    MotionBlocking,
+    /// Like [`HeightmapType::MotionBlocking`], but leaves don't count.
+    MotionBlockingNoLeaves,
+}
+
+impl HeightmapType {
+    pub const ALL: [HeightmapType; 4] = [
+        HeightmapType::WorldSurface,
+        HeightmapType::OceanFloor,
+        HeightmapType::MotionBlocking,
+        HeightmapType::MotionBlockingNoLeaves,
+    ];
+
+    /// The heightmaps the client uses, and so the ones sent with chunk data.
+    pub const CLIENT: [HeightmapType; 3] = [
+        HeightmapType::WorldSurface,
+        HeightmapType::MotionBlocking,
+        HeightmapType::MotionBlockingNoLeaves,
+    ];
+
+    /// The id of this heightmap in the protocol.
+    pub fn protocol_id(self) -> i32 {
+        match self {
+            HeightmapType::WorldSurface => 1,
+            HeightmapType::OceanFloor => 3,
+            HeightmapType::MotionBlocking => 4,
+            HeightmapType::MotionBlockingNoLeaves => 5,
+        }
+    }
+
+    /// Returns true if the given block counts towards this heightmap.
+    pub fn matches(self, block: BlockStateId) -> bool {
+        let flags = HEIGHTMAP_FLAGS
+            .get(block.raw() as usize)
+            .copied()
+            .unwrap_or(u8::MAX);
+        flags & self.flag() != 0
+    }
+
+    fn flag(self) -> u8 {
+        match self {
+            HeightmapType::WorldSurface => 1,
+            HeightmapType::OceanFloor => 1 << 1,
+            HeightmapType::MotionBlocking => 1 << 2,
+            HeightmapType::MotionBlockingNoLeaves => 1 << 3,
+        }
+    }
+}
+
+lazy_static! {
+    /// For each block state, the bitset of heightmaps it counts towards.
+    static ref HEIGHTMAP_FLAGS: Vec<u8> = ID2BLOCK.iter().map(heightmap_flags).collect();
+}
+
+/// Blocks with no collision, or too small a collision box for vanilla to treat them as solid.
+const NON_SOLID_BLOCKS: &[&str] = &[
+    "water",
+    "lava",
+    "bubble_column",
+    "cobweb",
+    "bamboo_sapling",
+    "light",
+    "structure_void",
+    "fire",
+    "soul_fire",
+    "nether_portal",
+    "end_portal",
+    "end_gateway",
+    "moss_carpet",
+    "pale_moss_carpet",
+    "ladder",
+    "vine",
+    "glow_lichen",
+    "sculk_vein",
+    "resin_clump",
+    "lever",
+    "tripwire",
+    "tripwire_hook",
+    "redstone_wire",
+    "repeater",
+    "comparator",
+    "torch",
+    "wall_torch",
+    "soul_torch",
+    "soul_wall_torch",
+    "redstone_torch",
+    "redstone_wall_torch",
+    "lantern",
+    "soul_lantern",
+    "end_rod",
+    "lightning_rod",
+    "chain",
+    "flower_pot",
+    "conduit",
+    "sea_pickle",
+    "turtle_egg",
+    "frogspawn",
+    "cocoa",
+    "lily_pad",
+    "kelp",
+    "kelp_plant",
+    "seagrass",
+    "tall_seagrass",
+    "sugar_cane",
+    "short_grass",
+    "tall_grass",
+    "short_dry_grass",
+    "tall_dry_grass",
+    "fern",
+    "large_fern",
+    "dead_bush",
+    "bush",
+    "firefly_bush",
+    "sweet_berry_bush",
+    "rose_bush",
+    "lilac",
+    "peony",
+    "sunflower",
+    "pitcher_plant",
+    "pitcher_crop",
+    "torchflower",
+    "torchflower_crop",
+    "dandelion",
+    "poppy",
+    "blue_orchid",
+    "allium",
+    "azure_bluet",
+    "oxeye_daisy",
+    "cornflower",
+    "lily_of_the_valley",
+    "wither_rose",
+    "open_eyeblossom",
+    "closed_eyeblossom",
+    "pink_petals",
+    "wildflowers",
+    "leaf_litter",
+    "cave_vines",
+    "cave_vines_plant",
+    "twisting_vines",
+    "twisting_vines_plant",
+    "weeping_vines",
+    "weeping_vines_plant",
+    "hanging_roots",
+    "pale_hanging_moss",
+    "spore_blossom",
+    "crimson_roots",
+    "warped_roots",
+    "nether_sprouts",
+    "crimson_fungus",
+    "warped_fungus",
+    "nether_wart",
+    "brown_mushroom",
+    "red_mushroom",
+    "wheat",
+    "carrots",
+    "potatoes",
+    "beetroots",
+    "melon_stem",
+    "pumpkin_stem",
+    "attached_melon_stem",
+    "attached_pumpkin_stem",
+    "big_dripleaf_stem",
+    "small_dripleaf",
+    "mangrove_propagule",
+    "candle",
+];
+
+/// Name suffixes shared by whole families of blocks that aren't solid.
+const NON_SOLID_SUFFIXES: &[&str] = &[
+    "_sapling",
+    "_tulip",
+    "_carpet",
+    "_rail",
+    "_sign",
+    "_banner",
+    "_button",
+    "_pressure_plate",
+    "_head",
+    "_skull",
+    "_candle",
+    "_coral",
+    "_coral_fan",
+    "_amethyst_bud",
+    "amethyst_cluster",
+];
+
+/// Blocks that always contain a fluid, whether or not they have a `waterlogged` property.
+const FLUID_BLOCKS: &[&str] = &[
+    "water",
+    "lava",
+    "bubble_column",
+    "kelp",
+    "kelp_plant",
+    "seagrass",
+    "tall_seagrass",
+];
+
+fn heightmap_flags(data: &BlockData) -> u8 {
+    let name = data.name.strip_prefix("minecraft:").unwrap_or(&data.name);
+    if matches!(name, "air" | "cave_air" | "void_air") {
+        return 0;
+    }
+
+    let blocks_motion = match name {
+        // Snow only gets a collision box once it's a few layers deep
+        "snow" => property(data, "layers")
+            .and_then(|layers| layers.parse::<u8>().ok())
+            .is_some_and(|layers| layers >= 3),
+        _ if name.starts_with("potted_") => false,
+        _ => {
+            !NON_SOLID_BLOCKS.contains(&name)
+                && !NON_SOLID_SUFFIXES
+                    .iter()
+                    .any(|suffix| name.ends_with(suffix))
+        }
+    };
+    let has_fluid = FLUID_BLOCKS.contains(&name) || property(data, "waterlogged") == Some("true");
+    let is_leaves = name.ends_with("_leaves");
+
+    let mut flags = HeightmapType::WorldSurface.flag();
+    if blocks_motion {
+        flags |= HeightmapType::OceanFloor.flag();
+    }
+    if blocks_motion || has_fluid {
+        flags |= HeightmapType::MotionBlocking.flag();
+        if !is_leaves {
+            flags |= HeightmapType::MotionBlockingNoLeaves.flag();
+        }
+    }
+    flags
+}
+
+impl Heightmaps {
+    pub fn get(&self, kind: HeightmapType) -> &Vec<i64> {
+        match kind {
+            HeightmapType::WorldSurface => &self.world_surface,
+            HeightmapType::OceanFloor => &self.ocean_floor,
+            HeightmapType::MotionBlocking => &self.motion_blocking,
+            HeightmapType::MotionBlockingNoLeaves => &self.motion_blocking_no_leaves,
+        }
+    }
+
+    fn get_mut(&mut self, kind: HeightmapType) -> &mut Vec<i64> {
+        match kind {
+            HeightmapType::WorldSurface => &mut self.world_surface,
+            HeightmapType::OceanFloor => &mut self.ocean_floor,
+            HeightmapType::MotionBlocking => &mut self.motion_blocking,
+            HeightmapType::MotionBlockingNoLeaves => &mut self.motion_blocking_no_leaves,
+        }
+    }
+}
+
+/// How entries are packed into a heightmap for a world of the given height.
+#[derive(Clone, Copy)]
+struct Packing {
+    bits: usize,
+    entries_per_long: usize,
+}
+
+impl Packing {
+    fn new(world_height: usize) -> Self {
+        // Enough bits to store every value from 0 to the height of the world
+        let bits = (usize::BITS - world_height.leading_zeros()).max(1) as usize;
+        Self {
+            bits,
+            entries_per_long: 64 / bits,
+        }
+    }
+
+    fn longs(self) -> usize {
+        256usize.div_ceil(self.entries_per_long)
+    }
+
+    fn get(self, data: &[i64], column: usize) -> usize {
+        let shift = (column % self.entries_per_long) * self.bits;
+        let long = data[column / self.entries_per_long] as u64;
+        ((long >> shift) & ((1 << self.bits) - 1)) as usize
+    }
+
+    fn set(self, data: &mut [i64], column: usize, value: usize) {
+        let shift = (column % self.entries_per_long) * self.bits;
+        let mask = ((1u64 << self.bits) - 1) << shift;
+        let long = &mut data[column / self.entries_per_long];
+        *long = ((*long as u64 & !mask) | ((value as u64) << shift & mask)) as i64;
+    }
+}
+
+fn column_index(x: u8, z: u8) -> usize {
+    (z as usize) << 4 | x as usize
+}
+
+impl Chunk {
+    fn heightmap_packing(&self) -> Packing {
+        Packing::new(self.sections.len() * 16)
+    }
+
+    /// Returns the y coordinate just above the highest block in the column that counts towards
+    /// the given heightmap, or the bottom of the world if there is none.
+    ///
+    /// Heightmaps are calculated if they're missing, which only happens if a chunk's sections
+    /// were replaced without calling [`Chunk::recalculate_heightmaps`].
+    pub fn height(&self, kind: HeightmapType, x: u8, z: u8) -> i16 {
+        let packing = self.heightmap_packing();
+        let heightmap = self.heightmaps.get(kind);
+        let offset = if heightmap.len() == packing.longs() {
+            packing.get(heightmap, column_index(x, z))
+        } else {
+            self.scan_height(kind, x, z, self.sections.len() * 16)
+        };
+        self.min_y + offset as i16
+    }
+
+    /// Recalculates every heightmap of the chunk from its blocks.
+    pub fn recalculate_heightmaps(&mut self) {
+        let packing = self.heightmap_packing();
+        let mut heightmaps = Heightmaps::new();
+        for kind in HeightmapType::ALL {
+            let heightmap = heightmaps.get_mut(kind);
+            *heightmap = vec![0; packing.longs()];
+            for z in 0..16 {
+                for x in 0..16 {
+                    let offset = self.scan_height(kind, x, z, self.sections.len() * 16);
+                    packing.set(heightmap, column_index(x, z), offset);
+                }
+            }
+        }
+        self.heightmaps = heightmaps;
+    }
+
+    /// Updates the heightmaps after the block at `pos` changed.
+    pub(crate) fn update_heightmaps(&mut self, pos: ChunkBlockPos) -> Result<(), WorldError> {
+        let packing = self.heightmap_packing();
+        if HeightmapType::ALL
+            .iter()
+            .any(|kind| self.heightmaps.get(*kind).len() != packing.longs())
+        {
+            self.recalculate_heightmaps();
+            return Ok(());
+        }
+
+        let block = self.get_block(pos)?;
+        let column = column_index(pos.x(), pos.z());
+        // One more than the block's offset from the bottom, as stored in the heightmap
+        let offset = (pos.y() - self.min_y) as usize + 1;
+        for kind in HeightmapType::ALL {
+            let current = packing.get(self.heightmaps.get(kind), column);
+            let new = if kind.matches(block) {
+                current.max(offset)
+            } else if current == offset {
+                // The top block was removed, so look for the next one down
+                self.scan_height(kind, pos.x(), pos.z(), offset - 1)
+            } else {
+                continue;
+            };
+            if new != current {
+                packing.set(self.heightmaps.get_mut(kind), column, new);
+            }
+        }
+        Ok(())
+    }
+
+    /// Finds the heightmap value of a column by scanning down from just below `below`, skipping
+    /// sections that are filled with a single block that doesn't match.
+    fn scan_height(&self, kind: HeightmapType, x: u8, z: u8, below: usize) -> usize {
+        let mut offset = below;
+        while offset > 0 {
+            let section = &self.sections[(offset - 1) / 16];
+            if let PaletteType::Single(block) = &section.block_states.block_data {
+                if kind.matches(BlockStateId::from_varint(*block)) {
+                    return offset;
+                }
+                offset -= (offset - 1) % 16 + 1;
+                continue;
+            }
+            let pos = ChunkBlockPos::new(x, self.min_y + offset as i16 - 1, z);
+            if section
+                .get_block(pos.section_block_pos())
+                .is_ok_and(|block| kind.matches(block))
+            {
+                return offset;
+            }
+            offset -= 1;
+        }
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edit_batch::EditBatch;
+    use crate::pos::ChunkHeight;
+    use ferrumc_macros::block;
+
+    #[test]
+    fn test_predicates() {
+        use HeightmapType::*;
+        let stone = block!("stone");
+        assert!(HeightmapType::ALL.iter().all(|kind| kind.matches(stone)));
+        assert!(HeightmapType::ALL
+            .iter()
+            .all(|kind| !kind.matches(BlockStateId::default())));
+
+        let water = block!("water", {level: 0});
+        assert!(WorldSurface.matches(water));
+        assert!(!OceanFloor.matches(water));
+        assert!(MotionBlocking.matches(water));
+        assert!(MotionBlockingNoLeaves.matches(water));
+
+        let leaves = block!("oak_leaves", {distance: 7, persistent: false, waterlogged: false});
+        assert!(OceanFloor.matches(leaves));
+        assert!(MotionBlocking.matches(leaves));
+        assert!(!MotionBlockingNoLeaves.matches(leaves));
+
+        let grass = block!("short_grass");
+        assert!(WorldSurface.matches(grass));
+        assert!(!MotionBlocking.matches(grass));
+    }
+
+    #[test]
+    fn test_packing_matches_vanilla() {
+        let chunk = Chunk::new(ChunkHeight::new(-64, 384));
+        // 9 bits per entry, 7 entries per long
+        assert_eq!(chunk.heightmaps.motion_blocking.len(), 37);
+        assert_eq!(chunk.height(HeightmapType::WorldSurface, 3, 4), -64);
+    }
+
+    #[test]
+    fn test_set_block_updates_heightmaps() {
+        let mut chunk = Chunk::new(ChunkHeight::new(-64, 384));
+        chunk.set_section(-4, block!("stone")).unwrap();
+        assert_eq!(chunk.height(HeightmapType::OceanFloor, 0, 0), -48);
+
+        chunk
+            .set_block(ChunkBlockPos::new(0, 10, 0), block!("water", {level: 0}))
+            .unwrap();
+        chunk
+            .set_block(ChunkBlockPos::new(0, 20, 0), block!("short_grass"))
+            .unwrap();
+        assert_eq!(chunk.height(HeightmapType::WorldSurface, 0, 0), 21);
+        assert_eq!(chunk.height(HeightmapType::MotionBlocking, 0, 0), 11);
+        assert_eq!(chunk.height(HeightmapType::OceanFloor, 0, 0), -48);
+        assert_eq!(chunk.height(HeightmapType::WorldSurface, 1, 0), -48);
+
+        chunk
+            .set_block(ChunkBlockPos::new(0, 20, 0), BlockStateId::default())
+            .unwrap();
+        assert_eq!(chunk.height(HeightmapType::WorldSurface, 0, 0), 11);
+        chunk
+            .set_block(ChunkBlockPos::new(0, 10, 0), BlockStateId::default())
+            .unwrap();
+        assert_eq!(chunk.height(HeightmapType::MotionBlocking, 0, 0), -48);
+    }
+
+    #[test]
+    fn test_incremental_updates_match_recalculation() {
+        let mut chunk = Chunk::new(ChunkHeight::new(-64, 384));
+        chunk.set_section(0, block!("stone")).unwrap();
+        let mut batch = EditBatch::new(&mut chunk);
+        for x in 0..16u8 {
+            for z in 0..16u8 {
+                let y = i16::from(x + z) * 3;
+                batch.set_block(ChunkBlockPos::new(x, y, z), BlockStateId::default());
+                batch.set_block(
+                    ChunkBlockPos::new(x, y + 40, z),
+                    block!("oak_leaves", {distance: 7, persistent: false, waterlogged: false}),
+                );
+            }
+        }
+        batch.apply().unwrap();
+        chunk
+            .set_block(ChunkBlockPos::new(15, 15, 15), BlockStateId::default())
+            .unwrap();
+
+        let incremental = chunk.heightmaps.clone();
+        chunk.recalculate_heightmaps();
+        assert_eq!(incremental, chunk.heightmaps);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::dimension::Dimension;
 use crate::errors::WorldError;
 use crate::pos::ChunkPos;
 use crate::vanilla_chunk_format::VanillaChunk;
@@ -14,27 +15,30 @@ use tracing::{error, info};
 impl World {
     fn get_chunk_count(&self, import_dir: &Path) -> Result<u64, WorldError> {
         info!("Counting chunks in import directory...");
-        let regions_dir = import_dir.join("region").read_dir()?;
         let chunk_count = AtomicU64::new(0);
 
-        regions_dir
-            .par_bridge()
-            .try_for_each(|region_file| -> Result<(), WorldError> {
-                let entry = region_file?;
-                if entry.path().is_dir() {
-                    return Ok(());
-                }
+        for (_, region_dir) in region_dirs(import_dir) {
+            region_dir.read_dir()?.par_bridge().try_for_each(
+                |region_file| -> Result<(), WorldError> {
+                    let entry = region_file?;
+                    if entry.path().is_dir() {
+                        return Ok(());
+                    }
 
-                if let Ok(anvil_file) = load_anvil_file(entry.path()) {
-                    chunk_count
-                        .fetch_add(anvil_file.get_locations().len() as u64, Ordering::Relaxed);
-                }
-                Ok(())
-            })?;
+                    if let Ok(anvil_file) = load_anvil_file(entry.path()) {
+                        chunk_count
+                            .fetch_add(anvil_file.get_locations().len() as u64, Ordering::Relaxed);
+                    }
+                    Ok(())
+                },
+            )?;
+        }
 
         Ok(chunk_count.load(Ordering::Relaxed))
     }
 
+    /// Imports a vanilla world save. Chunks are read from the overworld's `region` folder as well
+    /// as the nether's `DIM-1/region` and the end's `DIM1/region` folders, if they exist.
     pub fn import(
         &mut self,
         import_dir: PathBuf,
@@ -56,54 +60,54 @@ impl World {
 
         let start = std::time::Instant::now();
 
-        let regions_dir = import_dir.join("region").read_dir()?;
-
         let mut batch = threadpool.batch();
 
         let arc_self = Arc::new(self.clone());
 
         progress.set_message("Importing chunks...");
 
-        for region_result in regions_dir {
-            let region_entry = region_result?;
-            if region_entry.path().is_dir() {
-                continue;
-            }
-
-            let anvil_file = match load_anvil_file(region_entry.path()) {
-                Ok(file) => file,
-                Err(e) => {
-                    error!(
-                        "Failed to load region file {}: {}",
-                        region_entry.path().display(),
-                        e
-                    );
+        for (dimension, region_dir) in region_dirs(&import_dir) {
+            for region_result in region_dir.read_dir()? {
+                let region_entry = region_result?;
+                if region_entry.path().is_dir() {
                     continue;
                 }
-            };
-
-            let locations = anvil_file.get_locations();
-            let location_count = locations.len();
-
-            for (index, location) in locations.iter().enumerate() {
-                if let Ok(Some(chunk_data)) = anvil_file.get_chunk_from_location(*location) {
-                    if let Ok(vanilla_chunk) = VanillaChunk::from_bytes(&chunk_data) {
-                        batch.execute({
-                            let self_clone = arc_self.clone();
-                            let progress = progress.clone();
-                            move || {
-                                let res = self_clone.save_chunk(
-                                    ChunkPos::new(vanilla_chunk.x_pos, vanilla_chunk.z_pos),
-                                    vanilla_chunk.dimension.as_deref().unwrap_or("overworld"),
-                                    vanilla_chunk.to_custom_format()?.into(),
-                                );
-                                progress.inc(1);
-                                if index == location_count - 1 {
-                                    self_clone.storage_backend.flush()?;
+
+                let anvil_file = match load_anvil_file(region_entry.path()) {
+                    Ok(file) => file,
+                    Err(e) => {
+                        error!(
+                            "Failed to load region file {}: {}",
+                            region_entry.path().display(),
+                            e
+                        );
+                        continue;
+                    }
+                };
+
+                let locations = anvil_file.get_locations();
+                let location_count = locations.len();
+
+                for (index, location) in locations.iter().enumerate() {
+                    if let Ok(Some(chunk_data)) = anvil_file.get_chunk_from_location(*location) {
+                        if let Ok(vanilla_chunk) = VanillaChunk::from_bytes(&chunk_data) {
+                            batch.execute({
+                                let self_clone = arc_self.clone();
+                                let progress = progress.clone();
+                                move || {
+                                    let res = self_clone.save_chunk(
+                                        ChunkPos::new(vanilla_chunk.x_pos, vanilla_chunk.z_pos),
+                                        dimension,
+                                        vanilla_chunk.to_custom_format(dimension)?.into(),
+                                    );
+                                    progress.inc(1);
+                                    if index == location_count - 1 {
+                                        self_clone.storage_backend.flush()?;
+                                    }
+                                    res
                                 }
-                                res
-                            }
-                        })
+                            })
+                        }
                     }
                 }
             }
@@ -120,18 +124,29 @@ impl World {
 
         progress.finish_with_message("Import complete");
 
-        arc_self.storage_backend.flush()?;
-
         info!(
             "Imported {} chunks in {:?}",
             progress.position(),
             start.elapsed()
         );
 
+        arc_self.import_player_data(&import_dir)?;
+
+        arc_self.storage_backend.flush()?;
+
         Ok(())
     }
 }
 
+/// The region folders of every dimension present in a vanilla world save.
+fn region_dirs(import_dir: &Path) -> Vec<(Dimension, PathBuf)> {
+    Dimension::ALL
+        .into_iter()
+        .map(|dimension| (dimension, import_dir.join(dimension.region_dir())))
+        .filter(|(_, dir)| dir.is_dir())
+        .collect()
+}
+
 fn check_paths_validity(import_dir: &Path) -> Result<(), WorldError> {
     if !import_dir.exists() {
         return Err(WorldError::InvalidImportPath(
@@ -1,32 +1,84 @@
+pub mod biomes;
+pub mod block_entity;
+pub mod block_properties;
 pub mod block_state_id;
+pub mod block_updates;
 pub mod chunk_format;
+mod chunk_locks;
 mod db_functions;
+pub mod dimension;
 pub mod edit_batch;
 pub mod edits;
 pub mod errors;
+mod exporting;
+pub mod fluids;
+pub mod heightmaps;
 mod importing;
+pub mod lighting;
+pub mod loot;
+pub mod migrations;
+pub mod placement;
+pub mod player_data;
 pub mod pos;
+pub mod random_ticks;
+pub mod redstone;
+pub mod region_edit;
+pub mod scheduled_ticks;
+pub mod schematic;
 pub mod vanilla_chunk_format;
 
 use crate::chunk_format::Chunk;
+use crate::chunk_locks::ChunkLocks;
+use crate::dimension::Dimension;
 use crate::errors::WorldError;
-use crate::pos::ChunkPos;
+use crate::pos::{BlockPos, ChunkPos};
+use crate::scheduled_ticks::load_game_time;
+use dashmap::{DashMap, DashSet};
+use db_functions::write_back;
 use deepsize::DeepSizeOf;
 use ferrumc_config::server_config::get_global_config;
 use ferrumc_general_purpose::paths::get_root_path;
+use ferrumc_storage::backend::StorageBackend;
+use ferrumc_storage::compressors::{Compressor, CompressorType};
 use ferrumc_storage::lmdb::LmdbBackend;
+use moka::notification::RemovalCause;
 use moka::sync::Cache;
 use std::fs::create_dir_all;
 use std::path::{Path, PathBuf};
-use std::process::exit;
+use std::sync::atomic::AtomicU64;
 use std::sync::Arc;
 use std::time::Duration;
 use tracing::{error, trace, warn};
 
 #[derive(Clone)]
 pub struct World {
-    storage_backend: LmdbBackend,
-    cache: Cache<(ChunkPos, String), Arc<Chunk>>,
+    storage_backend: Arc<dyn StorageBackend>,
+    /// How chunks are compressed when they're saved. Chunks are tagged with their compressor, so
+    /// changing this doesn't affect loading the ones that are already stored.
+    compressor: Compressor,
+    cache: Cache<(ChunkPos, Dimension), Arc<Chunk>>,
+    /// Chunks that have changed since they were last written to the storage backend, see
+    /// [`World::insert_chunk`]. They're kept here as well as in the cache so they can't be lost
+    /// to an eviction before they're written.
+    dirty_chunks: Arc<DashMap<(ChunkPos, Dimension), Arc<Chunk>>>,
+    /// Held while a chunk is being written to, see [`World::edit_chunk`].
+    chunk_locks: Arc<ChunkLocks>,
+    /// The revision of every cached chunk that has been given one, see [`World::chunk_revision`].
+    /// Revisions are handed out from `next_revision`, so they're never reused even for a chunk
+    /// that's unloaded or deleted and comes back later.
+    chunk_revisions: Arc<DashMap<(ChunkPos, Dimension), u64>>,
+    next_revision: Arc<AtomicU64>,
+    /// Game ticks since the world was created, see [`World::game_time`].
+    game_time: Arc<AtomicU64>,
+    /// When the next scheduled tick is due in every chunk in memory that has any, see
+    /// [`World::run_scheduled_ticks`].
+    ticking_chunks: Arc<DashMap<(ChunkPos, Dimension), u64>>,
+    /// The game time every pressure plate that's down was last pressed at, see
+    /// [`World::press_pressure_plates`].
+    pressed_plates: Arc<DashMap<(BlockPos, Dimension), u64>>,
+    /// Chunks whose light was changed by something next to them, waiting to be resent to the
+    /// players that have them loaded, see [`World::take_relit_chunks`].
+    relit_chunks: Arc<DashSet<(ChunkPos, Dimension)>>,
 }
 
 fn check_config_validity() -> Result<(), WorldError> {
@@ -75,52 +127,148 @@ fn check_config_validity() -> Result<(), WorldError> {
     Ok(())
 }
 
+/// The chunk compressor set in the config.
+fn config_compressor() -> Result<Compressor, WorldError> {
+    let config = &get_global_config().database;
+    let algorithm = config
+        .compression
+        .parse::<CompressorType>()
+        .map_err(|e| WorldError::InvalidCompressor(e.to_string()))?;
+    Compressor::checked(algorithm, config.compression_level)
+        .map_err(|e| WorldError::InvalidCompressor(e.to_string()))
+}
+
 impl World {
-    /// Creates a new world instance.
+    /// Creates a new world instance backed by the LMDB database at the given path.
     ///
     /// You'd probably want to call this at the start of your program. And then use the returned
     /// in a state struct or something.
-    pub fn new(backend_path: impl Into<PathBuf>) -> Self {
-        if let Err(e) = check_config_validity() {
-            error!("Fatal error in database config: {}", e);
-            exit(1);
-        }
+    pub fn new(backend_path: impl Into<PathBuf>) -> Result<Self, WorldError> {
+        check_config_validity()?;
         let mut backend_path = backend_path.into();
         // Clones are kinda ok here since this is only run once at startup.
         if backend_path.is_relative() {
             backend_path = get_root_path().join(backend_path);
         }
-        let storage_backend =
-            LmdbBackend::initialize(Some(backend_path)).expect("Failed to initialize database");
+        let storage_backend = LmdbBackend::initialize(Some(backend_path))?;
+        Self::with_backend(storage_backend)
+    }
 
-        if get_global_config().database.cache_ttl != 0
-            && get_global_config().database.cache_capacity == 0
-        {
+    /// Creates a world that stores its chunks in the given backend.
+    ///
+    /// Use a [`MemoryBackend`](ferrumc_storage::memory::MemoryBackend) for a world that never
+    /// touches the disk, e.g. in tests.
+    pub fn with_backend(
+        storage_backend: impl StorageBackend + 'static,
+    ) -> Result<Self, WorldError> {
+        let config = &get_global_config().database;
+        if config.cache_ttl != 0 && config.cache_capacity == 0 {
             error!("Cache TTL and capacity must both be set to 0 or both be set to a value greater than 0.");
-            exit(1);
+            return Err(WorldError::InvalidCacheSize(format!(
+                "TTL is {}s but capacity is 0",
+                config.cache_ttl
+            )));
         }
 
-        let eviction_listener = move |key, _, cause| {
-            trace!("Evicting key: {:?}, cause: {:?}", key, cause);
+        let compressor = config_compressor()?;
+
+        let storage_backend: Arc<dyn StorageBackend> = Arc::new(storage_backend);
+        let dirty_chunks: Arc<DashMap<_, Arc<Chunk>>> = Arc::new(DashMap::new());
+        let ticking_chunks: Arc<DashMap<_, u64>> = Arc::new(DashMap::new());
+        let chunk_revisions: Arc<DashMap<_, u64>> = Arc::new(DashMap::new());
+        let game_time = load_game_time(&*storage_backend)?;
+
+        let eviction_listener = {
+            let storage_backend = storage_backend.clone();
+            let dirty_chunks = dirty_chunks.clone();
+            let ticking_chunks = ticking_chunks.clone();
+            let chunk_revisions = chunk_revisions.clone();
+            move |key: Arc<(ChunkPos, Dimension)>, _, cause| {
+                trace!("Evicting key: {:?}, cause: {:?}", key, cause);
+                // A replaced chunk is still cached, just with newer contents
+                if cause == RemovalCause::Replaced {
+                    return;
+                }
+                let (pos, dimension) = *key;
+                // Unloaded chunks don't tick, their ticks carry on once they're loaded again
+                ticking_chunks.remove(&(pos, dimension));
+                // It gets a fresh revision if it's loaded again
+                chunk_revisions.remove(&(pos, dimension));
+                if let Err(e) = write_back(
+                    &*storage_backend,
+                    &compressor,
+                    &dirty_chunks,
+                    pos,
+                    dimension,
+                ) {
+                    error!(
+                        "Failed to write back evicted chunk {pos} in {dimension}, it'll be retried \
+                        on the next sync: {e}"
+                    );
+                }
+            }
         };
 
         let cache = Cache::builder()
             .eviction_listener(eviction_listener)
             .weigher(|_k, v: &Arc<Chunk>| v.deep_size_of() as u32)
-            .time_to_live(Duration::from_secs(get_global_config().database.cache_ttl))
-            .max_capacity(get_global_config().database.cache_capacity * 1024)
+            .time_to_live(Duration::from_secs(config.cache_ttl))
+            .max_capacity(config.cache_capacity * 1024)
             .build();
 
-        World {
+        Ok(World {
             storage_backend,
+            compressor,
             cache,
-        }
+            dirty_chunks,
+            chunk_locks: Arc::new(ChunkLocks::new()),
+            chunk_revisions,
+            next_revision: Arc::new(AtomicU64::new(1)),
+            game_time: Arc::new(AtomicU64::new(game_time)),
+            ticking_chunks,
+            pressed_plates: Arc::new(DashMap::new()),
+            relit_chunks: Arc::new(DashSet::new()),
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::block_state_id::BlockStateId;
+    use crate::pos::ChunkBlockPos;
+    use ferrumc_macros::block;
+    use ferrumc_storage::memory::MemoryBackend;
+
+    #[test]
+    fn test_in_memory_world() {
+        let world = World::with_backend(MemoryBackend::new()).unwrap();
+        let pos = ChunkPos::new(4, -2);
+        assert!(!world.chunk_exists(pos, Dimension::Nether).unwrap());
+
+        let mut chunk = Chunk::new(Dimension::Nether.height());
+        chunk
+            .set_block(ChunkBlockPos::new(1, 40, 2), block!("netherrack"))
+            .unwrap();
+        world
+            .save_chunk(pos, Dimension::Nether, Arc::new(chunk))
+            .unwrap();
+        world.cache.invalidate_all();
+
+        let loaded = world.load_chunk(pos, Dimension::Nether).unwrap();
+        assert_eq!(
+            loaded.get_block(ChunkBlockPos::new(1, 40, 2)).unwrap(),
+            block!("netherrack")
+        );
+        assert_eq!(world.stored_chunks(Dimension::Nether).unwrap(), vec![pos]);
+        assert!(world
+            .stored_chunks(Dimension::Overworld)
+            .unwrap()
+            .is_empty());
+
+        world.delete_chunk(pos, Dimension::Nether).unwrap();
+        assert!(!world.chunk_exists(pos, Dimension::Nether).unwrap());
+    }
 
     #[test]
     #[ignore]
@@ -129,11 +277,14 @@ mod tests {
             std::env::current_dir()
                 .unwrap()
                 .join("../../../target/debug/world"),
-        );
-        let chunk = world.load_chunk(ChunkPos::new(1, 1), "overworld").expect(
-            "Failed to load chunk. If it's a bitcode error, chances are the chunk format \
-             has changed since last generating a world so you'll need to regenerate",
-        );
+        )
+        .unwrap();
+        let chunk = world
+            .load_chunk(ChunkPos::new(1, 1), Dimension::Overworld)
+            .expect(
+                "Failed to load chunk. If it's a bitcode error, the chunk format has probably \
+             changed without a migration, see the migrations module",
+            );
         let encoded = bitcode::encode(&chunk);
         std::fs::write("../../../.etc/raw_chunk.dat", encoded).unwrap();
     }
@@ -0,0 +1,1086 @@
+//! Sky and block light propagation.
+//!
+//! Light is stored per section as two nibble arrays (see [`Section::block_light`] and
+//! [`Section::sky_light`]), indexed the same way as the protocol expects (`y << 8 | z << 4 | x`).
+//! The [`LightEngine`] works on a chunk plus up to eight of its neighbours so light can flow over
+//! chunk borders, and uses the usual breadth-first "increase" and "decrease" passes for incremental
+//! updates.
+
+use crate::block_state_id::{BlockStateId, ID2BLOCK};
+use crate::chunk_format::{Chunk, PaletteType, Section};
+use crate::dimension::Dimension;
+use crate::errors::WorldError;
+use crate::pos::{ChunkBlockPos, ChunkPos};
+use crate::vanilla_chunk_format::BlockData;
+use crate::World;
+use lazy_static::lazy_static;
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// The brightest a light level can be.
+pub const MAX_LIGHT: u8 = 15;
+
+const CENTER: usize = 4;
+
+const DIRECTIONS: [(i32, i32, i32); 6] = [
+    (1, 0, 0),
+    (-1, 0, 0),
+    (0, 1, 0),
+    (0, -1, 0),
+    (0, 0, 1),
+    (0, 0, -1),
+];
+
+/// (x, y, z, level), with x and z relative to the origin of the centre chunk.
+type LightNode = (i32, i32, i32, u8);
+
+/// Neighbouring chunks keyed by their (dx, dz) offset from the chunk being lit.
+pub(crate) type LightNeighbours = Vec<((i32, i32), Chunk)>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LightType {
+    Sky,
+    Block,
+}
+
+/// How a block state interacts with light.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LightProperties {
+    /// The light level this block emits on its own.
+    pub emission: u8,
+    /// How much light is lost when passing through this block. Even fully transparent blocks
+    /// still reduce light by 1 per block travelled, except for sky light travelling straight down.
+    pub opacity: u8,
+}
+
+lazy_static! {
+    static ref LIGHT_PROPERTIES: Vec<LightProperties> =
+        ID2BLOCK.iter().map(light_properties).collect();
+}
+
+impl BlockStateId {
+    /// Returns the emission and opacity of this block state.
+    pub fn light_properties(&self) -> LightProperties {
+        LIGHT_PROPERTIES
+            .get(self.raw() as usize)
+            .copied()
+            .unwrap_or(LightProperties {
+                emission: 0,
+                opacity: MAX_LIGHT,
+            })
+    }
+
+    pub fn light_emission(&self) -> u8 {
+        self.light_properties().emission
+    }
+
+    pub fn light_opacity(&self) -> u8 {
+        self.light_properties().opacity
+    }
+}
+
+pub(crate) fn property<'a>(data: &'a BlockData, key: &str) -> Option<&'a str> {
+    data.properties
+        .as_ref()
+        .and_then(|props| props.get(key))
+        .map(String::as_str)
+}
+
+fn property_num(data: &BlockData, key: &str) -> u8 {
+    property(data, key)
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0)
+}
+
+fn is_lit(data: &BlockData) -> bool {
+    property(data, "lit") == Some("true")
+}
+
+fn light_properties(data: &BlockData) -> LightProperties {
+    let name = data.name.strip_prefix("minecraft:").unwrap_or(&data.name);
+    LightProperties {
+        emission: emission(name, data),
+        opacity: opacity(name, data),
+    }
+}
+
+fn emission(name: &str, data: &BlockData) -> u8 {
+    let lit = |level: u8| if is_lit(data) { level } else { 0 };
+    match name {
+        "glowstone"
+        | "sea_lantern"
+        | "jack_o_lantern"
+        | "lantern"
+        | "lava"
+        | "lava_cauldron"
+        | "fire"
+        | "beacon"
+        | "conduit"
+        | "shroomlight"
+        | "end_gateway"
+        | "end_portal"
+        | "ochre_froglight"
+        | "verdant_froglight"
+        | "pearlescent_froglight" => 15,
+        "campfire" | "redstone_lamp" => lit(15),
+        "torch" | "wall_torch" | "end_rod" => 14,
+        "cave_vines" | "cave_vines_plant" => {
+            if property(data, "berries") == Some("true") {
+                14
+            } else {
+                0
+            }
+        }
+        "furnace" | "blast_furnace" | "smoker" => lit(13),
+        "nether_portal" => 11,
+        "soul_torch" | "soul_wall_torch" | "soul_lantern" | "soul_fire" | "crying_obsidian" => 10,
+        "soul_campfire" => lit(10),
+        "redstone_ore" | "deepslate_redstone_ore" => lit(9),
+        "enchanting_table" | "ender_chest" | "glow_lichen" => 7,
+        "redstone_torch" | "redstone_wall_torch" => lit(7),
+        "sculk_catalyst" => 6,
+        "amethyst_cluster" => 5,
+        "large_amethyst_bud" => 4,
+        "magma_block" => 3,
+        "medium_amethyst_bud" | "firefly_bush" => 2,
+        "small_amethyst_bud"
+        | "brewing_stand"
+        | "brown_mushroom"
+        | "dragon_egg"
+        | "end_portal_frame"
+        | "sculk_sensor"
+        | "calibrated_sculk_sensor" => 1,
+        "light" => property_num(data, "level").min(MAX_LIGHT),
+        "respawn_anchor" => match property_num(data, "charges") {
+            0 => 0,
+            charges => (charges * 4 - 1).min(MAX_LIGHT),
+        },
+        "sea_pickle" => {
+            if property(data, "waterlogged") == Some("true") {
+                (3 + 3 * property_num(data, "pickles")).min(MAX_LIGHT)
+            } else {
+                0
+            }
+        }
+        _ if name.ends_with("candle_cake") => lit(3),
+        _ if name.ends_with("candle") => lit(3 * property_num(data, "candles")),
+        _ if name.ends_with("copper_bulb") => match name.strip_prefix("waxed_").unwrap_or(name) {
+            "copper_bulb" => lit(15),
+            "exposed_copper_bulb" => lit(12),
+            "weathered_copper_bulb" => lit(8),
+            "oxidized_copper_bulb" => lit(4),
+            _ => 0,
+        },
+        _ => 0,
+    }
+}
+
+/// Blocks that don't fill their whole space and so let light through.
+const TRANSPARENT_BLOCKS: &[&str] = &[
+    "glass",
+    "glass_pane",
+    "iron_bars",
+    "chain",
+    "ladder",
+    "lever",
+    "scaffolding",
+    "snow",
+    "cake",
+    "bell",
+    "lectern",
+    "hopper",
+    "cauldron",
+    "water_cauldron",
+    "lava_cauldron",
+    "powder_snow_cauldron",
+    "brewing_stand",
+    "enchanting_table",
+    "stonecutter",
+    "grindstone",
+    "anvil",
+    "chipped_anvil",
+    "damaged_anvil",
+    "end_portal_frame",
+    "dragon_egg",
+    "conduit",
+    "beacon",
+    "spawner",
+    "trial_spawner",
+    "vault",
+    "barrier",
+    "light",
+    "structure_void",
+    "moving_piston",
+    "piston_head",
+    "end_rod",
+    "lightning_rod",
+    "flower_pot",
+    "decorated_pot",
+    "campfire",
+    "soul_campfire",
+    "fire",
+    "soul_fire",
+    "nether_portal",
+    "end_portal",
+    "end_gateway",
+    "lantern",
+    "soul_lantern",
+    "torch",
+    "wall_torch",
+    "soul_torch",
+    "soul_wall_torch",
+    "redstone_torch",
+    "redstone_wall_torch",
+    "redstone_wire",
+    "repeater",
+    "comparator",
+    "daylight_detector",
+    "tripwire",
+    "tripwire_hook",
+    "rail",
+    "chest",
+    "trapped_chest",
+    "ender_chest",
+    "bamboo",
+    "bamboo_sapling",
+    "sugar_cane",
+    "cactus",
+    "kelp",
+    "kelp_plant",
+    "seagrass",
+    "tall_seagrass",
+    "short_grass",
+    "tall_grass",
+    "short_dry_grass",
+    "tall_dry_grass",
+    "fern",
+    "large_fern",
+    "dead_bush",
+    "bush",
+    "firefly_bush",
+    "sweet_berry_bush",
+    "rose_bush",
+    "lilac",
+    "peony",
+    "sunflower",
+    "pitcher_plant",
+    "pitcher_crop",
+    "torchflower",
+    "torchflower_crop",
+    "dandelion",
+    "poppy",
+    "blue_orchid",
+    "allium",
+    "azure_bluet",
+    "oxeye_daisy",
+    "cornflower",
+    "lily_of_the_valley",
+    "wither_rose",
+    "open_eyeblossom",
+    "closed_eyeblossom",
+    "pink_petals",
+    "wildflowers",
+    "leaf_litter",
+    "lily_pad",
+    "vine",
+    "glow_lichen",
+    "sculk_vein",
+    "cave_vines",
+    "cave_vines_plant",
+    "twisting_vines",
+    "twisting_vines_plant",
+    "weeping_vines",
+    "weeping_vines_plant",
+    "hanging_roots",
+    "pale_hanging_moss",
+    "crimson_roots",
+    "warped_roots",
+    "nether_sprouts",
+    "crimson_fungus",
+    "warped_fungus",
+    "nether_wart",
+    "brown_mushroom",
+    "red_mushroom",
+    "wheat",
+    "carrots",
+    "potatoes",
+    "beetroots",
+    "melon_stem",
+    "pumpkin_stem",
+    "attached_melon_stem",
+    "attached_pumpkin_stem",
+    "cocoa",
+    "sea_pickle",
+    "turtle_egg",
+    "sniffer_egg",
+    "frogspawn",
+    "spore_blossom",
+    "big_dripleaf",
+    "big_dripleaf_stem",
+    "small_dripleaf",
+    "pointed_dripstone",
+    "sculk_sensor",
+    "calibrated_sculk_sensor",
+    "sculk_shrieker",
+    "azalea",
+    "flowering_azalea",
+    "chorus_plant",
+    "chorus_flower",
+    "mangrove_propagule",
+    "heavy_core",
+    "farmland",
+    "dirt_path",
+    "candle",
+    "candle_cake",
+    "resin_clump",
+];
+
+/// Name suffixes shared by whole families of non-full blocks.
+const TRANSPARENT_SUFFIXES: &[&str] = &[
+    "_stained_glass",
+    "_stained_glass_pane",
+    "_slab",
+    "_stairs",
+    "_fence",
+    "_fence_gate",
+    "_wall",
+    "_door",
+    "_trapdoor",
+    "_sign",
+    "_banner",
+    "_button",
+    "_pressure_plate",
+    "_rail",
+    "_carpet",
+    "_sapling",
+    "_bed",
+    "_candle",
+    "_candle_cake",
+    "_head",
+    "_skull",
+    "_tulip",
+    "_coral",
+    "_fan",
+    "_amethyst_bud",
+    "amethyst_cluster",
+];
+
+fn opacity(name: &str, data: &BlockData) -> u8 {
+    match name {
+        "air" | "cave_air" | "void_air" => return 0,
+        "water" | "bubble_column" | "ice" | "frosted_ice" | "cobweb" | "slime_block"
+        | "honey_block" | "powder_snow" => return 1,
+        _ if name.ends_with("_leaves") => return 1,
+        _ => {}
+    }
+
+    let transparent = if name.ends_with("_slab") {
+        property(data, "type") != Some("double")
+    } else {
+        TRANSPARENT_BLOCKS.contains(&name)
+            || name.starts_with("potted_")
+            || TRANSPARENT_SUFFIXES
+                .iter()
+                .any(|suffix| name.ends_with(suffix))
+    };
+
+    if !transparent {
+        MAX_LIGHT
+    } else if property(data, "waterlogged") == Some("true") {
+        1
+    } else {
+        0
+    }
+}
+
+impl Section {
+    fn light_array(&self, kind: LightType) -> &Vec<u8> {
+        match kind {
+            LightType::Sky => &self.sky_light,
+            LightType::Block => &self.block_light,
+        }
+    }
+
+    fn light_array_mut(&mut self, kind: LightType) -> &mut Vec<u8> {
+        match kind {
+            LightType::Sky => &mut self.sky_light,
+            LightType::Block => &mut self.block_light,
+        }
+    }
+
+    /// Reads a light level by its packed index. Missing light data reads as darkness.
+    fn light(&self, kind: LightType, index: usize) -> u8 {
+        match self.light_array(kind).get(index >> 1) {
+            Some(byte) if index & 1 == 0 => byte & 0x0F,
+            Some(byte) => byte >> 4,
+            None => 0,
+        }
+    }
+
+    fn set_light(&mut self, kind: LightType, index: usize, level: u8) {
+        let array = self.light_array_mut(kind);
+        if array.len() != 2048 {
+            *array = vec![0; 2048];
+        }
+        let byte = &mut array[index >> 1];
+        if index & 1 == 0 {
+            *byte = (*byte & 0xF0) | (level & 0x0F);
+        } else {
+            *byte = (*byte & 0x0F) | (level << 4);
+        }
+    }
+
+    /// Whether any block in this section's palette emits light.
+    fn has_emitters(&self) -> bool {
+        match &self.block_states.block_data {
+            PaletteType::Single(val) => BlockStateId::from_varint(*val).light_emission() > 0,
+            PaletteType::Indirect { palette, .. } => palette
+                .iter()
+                .any(|id| BlockStateId::from_varint(*id).light_emission() > 0),
+            PaletteType::Direct { .. } => true,
+        }
+    }
+}
+
+impl Chunk {
+    /// Returns the light level of the given type at the given position.
+    pub fn get_light(&self, kind: LightType, pos: ChunkBlockPos) -> Result<u8, WorldError> {
+        let section = self
+            .get_section(pos.section())
+            .ok_or(WorldError::SectionOutOfBounds(pos.section() as i32))?;
+        Ok(section.light(kind, pos.section_block_pos().pack() as usize))
+    }
+
+    /// Recomputes all light in this chunk, ignoring any neighbouring chunks.
+    ///
+    /// Use [`World::relight_chunk`] if light should also flow into and out of the surrounding chunks.
+    pub fn relight(&mut self) {
+        LightEngine::new(self).relight();
+    }
+}
+
+/// Propagates light through a chunk and its loaded neighbours.
+///
+/// Coordinates are relative to the origin of the centre chunk, so neighbours cover -16..32 on
+/// the x and z axes. Neighbours that were not provided are treated as unavailable: light never
+/// enters or leaves through them.
+///
+/// # Example
+/// ```
+/// # use ferrumc_macros::block;
+/// # use ferrumc_world::block_state_id::BlockStateId;
+/// # use ferrumc_world::chunk_format::Chunk;
+/// # use ferrumc_world::lighting::LightEngine;
+/// # use ferrumc_world::pos::ChunkHeight;
+/// let mut chunk = Chunk::new(ChunkHeight::new(-64, 384));
+/// let mut east = Chunk::new(ChunkHeight::new(-64, 384));
+/// let mut engine = LightEngine::new(&mut chunk).with_neighbour(1, 0, &mut east);
+/// engine.relight();
+/// let changed = engine.changed_neighbours();
+/// ```
+pub struct LightEngine<'a> {
+    chunks: [Option<&'a mut Chunk>; 9],
+    changed: [bool; 9],
+    /// Set when light had to stop at a neighbour that wasn't provided.
+    missing_neighbour: Cell<bool>,
+    min_y: i32,
+    max_y: i32,
+    sky_light: bool,
+}
+
+impl<'a> LightEngine<'a> {
+    pub fn new(chunk: &'a mut Chunk) -> Self {
+        let min_y = chunk.min_y as i32;
+        let max_y = min_y + chunk.sections.len() as i32 * 16;
+        let mut chunks = [const { None }; 9];
+        chunks[CENTER] = Some(chunk);
+        Self {
+            chunks,
+            changed: [false; 9],
+            missing_neighbour: Cell::new(false),
+            min_y,
+            max_y,
+            sky_light: true,
+        }
+    }
+
+    /// Adds the neighbouring chunk at the given offset (each of `dx` and `dz` in `-1..=1`).
+    /// Neighbours are expected to share the centre chunk's height.
+    pub fn with_neighbour(mut self, dx: i32, dz: i32, chunk: &'a mut Chunk) -> Self {
+        debug_assert!((-1..=1).contains(&dx) && (-1..=1).contains(&dz) && (dx, dz) != (0, 0));
+        self.chunks[((dz + 1) * 3 + dx + 1) as usize] = Some(chunk);
+        self
+    }
+
+    /// Enables or disables sky light. Dimensions without a sky (such as the nether) should turn
+    /// this off, in which case [`relight`](Self::relight) clears the centre's sky light and
+    /// block updates leave it untouched.
+    pub fn with_sky_light(mut self, enabled: bool) -> Self {
+        self.sky_light = enabled;
+        self
+    }
+
+    /// The offsets of the neighbouring chunks whose light was changed by this engine.
+    pub fn changed_neighbours(&self) -> Vec<(i32, i32)> {
+        (0..9)
+            .filter(|&slot| slot != CENTER && self.changed[slot])
+            .map(|slot| (slot as i32 % 3 - 1, slot as i32 / 3 - 1))
+            .collect()
+    }
+
+    /// Whether light had to stop at the border of a neighbouring chunk that wasn't provided, in
+    /// which case the light along that border may be wrong until it's worked out again with the
+    /// neighbour.
+    pub fn reached_missing_neighbour(&self) -> bool {
+        self.missing_neighbour.get()
+    }
+
+    /// Recomputes all light in the centre chunk from scratch, removing any light it used to
+    /// spread into its neighbours and pulling light back in from them.
+    pub fn relight(&mut self) {
+        if !self.sky_light {
+            if let Some(chunk) = self.chunks[CENTER].as_deref_mut() {
+                for section in &mut chunk.sections {
+                    section.sky_light = vec![0; 2048];
+                }
+            }
+        }
+        for kind in self.light_types() {
+            let mut removal = VecDeque::new();
+            let mut increase = VecDeque::new();
+
+            // Light that flowed out of the old contents of this chunk has to be withdrawn first.
+            // The chunk may have been lit without its neighbours since, so its own light can't
+            // tell what that was. Everything next to its borders is withdrawn and refilled from
+            // whatever is left instead.
+            if self.has_neighbours() {
+                self.for_each_border(|_, x, y, z| removal.push_back((x, y, z, MAX_LIGHT)));
+            }
+
+            if let Some(chunk) = self.chunks[CENTER].as_deref_mut() {
+                for section in &mut chunk.sections {
+                    *section.light_array_mut(kind) = vec![0; 2048];
+                }
+            }
+            self.changed[CENTER] = true;
+
+            self.propagate_decrease(kind, &mut removal, &mut increase);
+
+            match kind {
+                LightType::Sky => self.seed_sky(&mut increase),
+                LightType::Block => self.seed_emitters(&mut increase),
+            }
+
+            // Pull in light from the neighbours' borders.
+            if self.has_neighbours() {
+                self.for_each_border(|engine, x, y, z| {
+                    for (dx, _, dz) in DIRECTIONS {
+                        let (nx, nz) = (x + dx, z + dz);
+                        if (0..16).contains(&nx) && (0..16).contains(&nz) {
+                            continue;
+                        }
+                        let level = engine.light(kind, nx, y, nz);
+                        if level > 0 && engine.block(nx, y, nz).is_some() {
+                            increase.push_back((nx, y, nz, level));
+                        }
+                    }
+                });
+            }
+
+            self.propagate_increase(kind, &mut increase);
+        }
+    }
+
+    /// Updates light around a single block in the centre chunk after it was changed.
+    ///
+    /// The block must already be set in the chunk. This can be called for several positions in a
+    /// row after a batch of edits.
+    pub fn update_block(&mut self, pos: ChunkBlockPos) {
+        let (x, y, z) = (pos.x() as i32, pos.y() as i32, pos.z() as i32);
+        if y < self.min_y || y >= self.max_y {
+            return;
+        }
+        for kind in self.light_types() {
+            let mut removal = VecDeque::new();
+            let mut increase = VecDeque::new();
+
+            let old = self.light(kind, x, y, z);
+            self.set_light(kind, x, y, z, 0);
+            removal.push_back((x, y, z, old));
+
+            let source = self.source_level(kind, x, y, z);
+            if source > 0 {
+                self.set_light(kind, x, y, z, source);
+                increase.push_back((x, y, z, source));
+            }
+
+            self.propagate_decrease(kind, &mut removal, &mut increase);
+
+            // Let the surrounding light flow back into the changed block.
+            for (dx, dy, dz) in DIRECTIONS {
+                let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+                if ny < self.min_y || ny >= self.max_y || self.block(nx, ny, nz).is_none() {
+                    continue;
+                }
+                let level = self.light(kind, nx, ny, nz);
+                if level > 0 {
+                    increase.push_back((nx, ny, nz, level));
+                }
+            }
+
+            self.propagate_increase(kind, &mut increase);
+        }
+    }
+
+    fn light_types(&self) -> Vec<LightType> {
+        if self.sky_light {
+            vec![LightType::Block, LightType::Sky]
+        } else {
+            vec![LightType::Block]
+        }
+    }
+
+    fn has_neighbours(&self) -> bool {
+        self.chunks
+            .iter()
+            .enumerate()
+            .any(|(slot, chunk)| slot != CENTER && chunk.is_some())
+    }
+
+    fn for_each_border(&mut self, mut f: impl FnMut(&mut Self, i32, i32, i32)) {
+        for y in self.min_y..self.max_y {
+            for i in 0..16 {
+                f(self, 0, y, i);
+                f(self, 15, y, i);
+                if i != 0 && i != 15 {
+                    f(self, i, y, 0);
+                    f(self, i, y, 15);
+                }
+            }
+        }
+    }
+
+    fn slot(x: i32, z: i32) -> Option<usize> {
+        if !(-16..32).contains(&x) || !(-16..32).contains(&z) {
+            return None;
+        }
+        Some((((z >> 4) + 1) * 3 + (x >> 4) + 1) as usize)
+    }
+
+    fn section_index(&self, y: i32) -> usize {
+        ((y - self.min_y) >> 4) as usize
+    }
+
+    fn block(&self, x: i32, y: i32, z: i32) -> Option<BlockStateId> {
+        let slot = Self::slot(x, z)?;
+        let Some(chunk) = self.chunks[slot].as_deref() else {
+            self.missing_neighbour.set(true);
+            return None;
+        };
+        let section = chunk.sections.get(self.section_index(y))?;
+        let pos = ChunkBlockPos::new((x & 15) as u8, y as i16, (z & 15) as u8);
+        section.get_block(pos.section_block_pos()).ok()
+    }
+
+    fn light(&self, kind: LightType, x: i32, y: i32, z: i32) -> u8 {
+        if y >= self.max_y {
+            return if kind == LightType::Sky && self.sky_light {
+                MAX_LIGHT
+            } else {
+                0
+            };
+        }
+        if y < self.min_y {
+            return 0;
+        }
+        let Some(chunk) = Self::slot(x, z).and_then(|slot| self.chunks[slot].as_deref()) else {
+            return 0;
+        };
+        let pos = ChunkBlockPos::new((x & 15) as u8, y as i16, (z & 15) as u8);
+        chunk
+            .sections
+            .get(self.section_index(y))
+            .map(|section| section.light(kind, pos.section_block_pos().pack() as usize))
+            .unwrap_or(0)
+    }
+
+    fn set_light(&mut self, kind: LightType, x: i32, y: i32, z: i32, level: u8) {
+        let Some(slot) = Self::slot(x, z) else {
+            return;
+        };
+        let section_index = self.section_index(y);
+        let Some(section) = self.chunks[slot]
+            .as_deref_mut()
+            .and_then(|chunk| chunk.sections.get_mut(section_index))
+        else {
+            return;
+        };
+        let pos = ChunkBlockPos::new((x & 15) as u8, y as i16, (z & 15) as u8);
+        let index = pos.section_block_pos().pack() as usize;
+        if section.light(kind, index) != level || section.light_array(kind).len() != 2048 {
+            section.set_light(kind, index, level);
+            self.changed[slot] = true;
+        }
+    }
+
+    /// The light a block produces by itself: its emission for block light, or direct sky access
+    /// for sky light.
+    fn source_level(&self, kind: LightType, x: i32, y: i32, z: i32) -> u8 {
+        let Some(block) = self.block(x, y, z) else {
+            return 0;
+        };
+        match kind {
+            LightType::Block => block.light_emission(),
+            LightType::Sky if y == self.max_y - 1 => {
+                MAX_LIGHT.saturating_sub(block.light_opacity())
+            }
+            LightType::Sky => 0,
+        }
+    }
+
+    /// The level light arriving at a block ends up at, given the level it came from.
+    fn attenuate(kind: LightType, level: u8, dy: i32, opacity: u8) -> u8 {
+        if kind == LightType::Sky && dy == -1 && level == MAX_LIGHT && opacity == 0 {
+            MAX_LIGHT
+        } else {
+            level.saturating_sub(opacity.max(1))
+        }
+    }
+
+    fn seed_sky(&mut self, increase: &mut VecDeque<LightNode>) {
+        for x in 0..16 {
+            for z in 0..16 {
+                let mut level = MAX_LIGHT;
+                for y in (self.min_y..self.max_y).rev() {
+                    let opacity = self.block(x, y, z).map_or(MAX_LIGHT, |b| b.light_opacity());
+                    level = Self::attenuate(LightType::Sky, level, -1, opacity);
+                    if level == 0 {
+                        break;
+                    }
+                    self.set_light(LightType::Sky, x, y, z, level);
+                }
+            }
+        }
+
+        // Only blocks next to something darker can spread any further, which keeps the queue
+        // small for open sky.
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in (self.min_y..self.max_y).rev() {
+                    let level = self.light(LightType::Sky, x, y, z);
+                    if level == 0 {
+                        break;
+                    }
+                    let spreads = level < MAX_LIGHT
+                        || DIRECTIONS.iter().any(|(dx, dy, dz)| {
+                            *dy == 0
+                                && self.block(x + dx, y, z + dz).is_some()
+                                && self.light(LightType::Sky, x + dx, y, z + dz) < MAX_LIGHT - 1
+                        });
+                    if spreads {
+                        increase.push_back((x, y, z, level));
+                    }
+                }
+            }
+        }
+    }
+
+    fn seed_emitters(&mut self, increase: &mut VecDeque<LightNode>) {
+        let mut emitters = Vec::new();
+        if let Some(chunk) = self.chunks[CENTER].as_deref() {
+            for (i, section) in chunk.sections.iter().enumerate() {
+                if !section.has_emitters() {
+                    continue;
+                }
+                let base_y = self.min_y + i as i32 * 16;
+                for index in 0..4096 {
+                    let (x, y, z) = (index & 15, base_y + (index >> 8), (index >> 4) & 15);
+                    if let Some(block) = self.block(x, y, z) {
+                        let emission = block.light_emission();
+                        if emission > 0 {
+                            emitters.push((x, y, z, emission));
+                        }
+                    }
+                }
+            }
+        }
+        for (x, y, z, emission) in emitters {
+            self.set_light(LightType::Block, x, y, z, emission);
+            increase.push_back((x, y, z, emission));
+        }
+    }
+
+    fn propagate_increase(&mut self, kind: LightType, queue: &mut VecDeque<LightNode>) {
+        while let Some((x, y, z, level)) = queue.pop_front() {
+            // Skip entries that were overwritten after being queued.
+            if self.light(kind, x, y, z) != level {
+                continue;
+            }
+            for (dx, dy, dz) in DIRECTIONS {
+                let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+                if ny < self.min_y || ny >= self.max_y {
+                    continue;
+                }
+                let Some(block) = self.block(nx, ny, nz) else {
+                    continue;
+                };
+                let new_level = Self::attenuate(kind, level, dy, block.light_opacity());
+                if new_level > self.light(kind, nx, ny, nz) {
+                    self.set_light(kind, nx, ny, nz, new_level);
+                    queue.push_back((nx, ny, nz, new_level));
+                }
+            }
+        }
+    }
+
+    fn propagate_decrease(
+        &mut self,
+        kind: LightType,
+        removal: &mut VecDeque<LightNode>,
+        increase: &mut VecDeque<LightNode>,
+    ) {
+        while let Some((x, y, z, level)) = removal.pop_front() {
+            for (dx, dy, dz) in DIRECTIONS {
+                let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+                if ny < self.min_y || ny >= self.max_y || self.block(nx, ny, nz).is_none() {
+                    continue;
+                }
+                let neighbour = self.light(kind, nx, ny, nz);
+                if neighbour == 0 {
+                    continue;
+                }
+                let lit_by_removed = neighbour < level
+                    || (kind == LightType::Sky
+                        && dy == -1
+                        && level == MAX_LIGHT
+                        && neighbour == MAX_LIGHT);
+                if lit_by_removed {
+                    self.set_light(kind, nx, ny, nz, 0);
+                    removal.push_back((nx, ny, nz, neighbour));
+                    let source = self.source_level(kind, nx, ny, nz);
+                    if source > 0 {
+                        self.set_light(kind, nx, ny, nz, source);
+                        increase.push_back((nx, ny, nz, source));
+                    }
+                } else {
+                    // Lit from somewhere else, so it can refill the removed area.
+                    increase.push_back((nx, ny, nz, neighbour));
+                }
+            }
+        }
+    }
+}
+
+impl World {
+    /// Recomputes the light of a stored chunk, including light crossing into and out of its
+    /// stored neighbours.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<ChunkPos>)` - The neighbouring chunks whose light changed.
+    /// * `Err(WorldError)` - If the chunk or one of its neighbours couldn't be loaded.
+    pub fn relight_chunk(
+        &self,
+        pos: ChunkPos,
+        dimension: Dimension,
+    ) -> Result<Vec<ChunkPos>, WorldError> {
+        let _locks = self.chunk_locks.lock_around(pos, dimension);
+        let mut chunk = self.load_chunk_owned(pos, dimension)?;
+        let mut neighbours = self.load_light_neighbours(pos, dimension)?;
+        let changed = {
+            let mut engine = LightEngine::new(&mut chunk).with_sky_light(dimension.has_sky_light());
+            for ((dx, dz), neighbour) in &mut neighbours {
+                engine = engine.with_neighbour(*dx, *dz, neighbour);
+            }
+            engine.relight();
+            engine.changed_neighbours()
+        };
+        self.insert_chunk(pos, dimension, Arc::new(chunk));
+        Ok(self.save_light_neighbours(pos, dimension, neighbours, &changed))
+    }
+
+    /// Loads every stored chunk around `pos`, keyed by its offset from `pos`.
+    pub(crate) fn load_light_neighbours(
+        &self,
+        pos: ChunkPos,
+        dimension: Dimension,
+    ) -> Result<LightNeighbours, WorldError> {
+        let mut neighbours = Vec::with_capacity(8);
+        for dz in -1..=1 {
+            for dx in -1..=1 {
+                if (dx, dz) == (0, 0) {
+                    continue;
+                }
+                let neighbour_pos = pos + (dx, dz);
+                if self.chunk_exists(neighbour_pos, dimension)? {
+                    neighbours.push(((dx, dz), self.load_chunk_owned(neighbour_pos, dimension)?));
+                }
+            }
+        }
+        Ok(neighbours)
+    }
+
+    /// Stores the neighbours whose offsets are in `changed`, queues them to be resent and returns
+    /// their positions.
+    ///
+    /// Relighting a chunk takes its light out of the neighbours before putting it back, so a
+    /// neighbour can be marked as changed and still end up the same. Those are left alone.
+    pub(crate) fn save_light_neighbours(
+        &self,
+        pos: ChunkPos,
+        dimension: Dimension,
+        neighbours: LightNeighbours,
+        changed: &[(i32, i32)],
+    ) -> Vec<ChunkPos> {
+        let mut saved = Vec::with_capacity(changed.len());
+        for (offset, neighbour) in neighbours {
+            let neighbour_pos = pos + offset;
+            if !changed.contains(&offset)
+                || self
+                    .get_cached(neighbour_pos, dimension)
+                    .is_some_and(|stored| *stored == neighbour)
+            {
+                continue;
+            }
+            self.insert_chunk(neighbour_pos, dimension, Arc::new(neighbour));
+            self.relit_chunks.insert((neighbour_pos, dimension));
+            saved.push(neighbour_pos);
+        }
+        saved
+    }
+
+    /// Takes the chunks whose light changed because of something next to them since this was
+    /// last called. Players that have them loaded should be sent them again, otherwise they keep
+    /// the old light.
+    pub fn take_relit_chunks(&self) -> Vec<(ChunkPos, Dimension)> {
+        let relit: Vec<_> = self.relit_chunks.iter().map(|entry| *entry).collect();
+        for key in &relit {
+            self.relit_chunks.remove(key);
+        }
+        relit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pos::ChunkHeight;
+    use ferrumc_macros::block;
+
+    fn sky(chunk: &Chunk, x: u8, y: i16, z: u8) -> u8 {
+        chunk.get_light(LightType::Sky, (x, y, z).into()).unwrap()
+    }
+
+    fn block_light(chunk: &Chunk, x: u8, y: i16, z: u8) -> u8 {
+        chunk.get_light(LightType::Block, (x, y, z).into()).unwrap()
+    }
+
+    #[test]
+    fn test_light_properties() {
+        assert_eq!(block!("air").light_opacity(), 0);
+        assert_eq!(block!("stone").light_opacity(), 15);
+        assert_eq!(block!("bedrock").light_opacity(), 15);
+        assert_eq!(block!("glass").light_opacity(), 0);
+        assert_eq!(block!("water", {level: 0}).light_opacity(), 1);
+        assert_eq!(
+            block!("oak_leaves", {distance: 7, persistent: false, waterlogged: false})
+                .light_opacity(),
+            1
+        );
+        assert_eq!(block!("fire_coral_block").light_opacity(), 15);
+        assert_eq!(
+            block!("jack_o_lantern", {facing: "north"}).light_emission(),
+            15
+        );
+        assert_eq!(block!("torch").light_emission(), 14);
+        assert_eq!(block!("stone").light_emission(), 0);
+    }
+
+    #[test]
+    fn test_open_sky_is_fully_lit() {
+        let mut chunk = Chunk::new(ChunkHeight::new(-64, 384));
+        chunk.relight();
+        assert_eq!(sky(&chunk, 0, -64, 0), 15);
+        assert_eq!(sky(&chunk, 8, 100, 8), 15);
+        assert_eq!(block_light(&chunk, 8, 100, 8), 0);
+    }
+
+    #[test]
+    fn test_roof_casts_shadow() {
+        let mut chunk = Chunk::new(ChunkHeight::new(-64, 384));
+        chunk.relight();
+        for x in 0..16 {
+            for z in 0..16 {
+                chunk
+                    .set_block((x, 100, z).into(), block!("stone"))
+                    .unwrap();
+            }
+        }
+        assert_eq!(sky(&chunk, 8, 101, 8), 15);
+        assert_eq!(sky(&chunk, 8, 100, 8), 0);
+        assert_eq!(sky(&chunk, 8, 99, 8), 0);
+
+        // Opening a hole lets light back in, fading sideways.
+        chunk.set_block((8, 100, 8).into(), block!("air")).unwrap();
+        assert_eq!(sky(&chunk, 8, 50, 8), 15);
+        assert_eq!(sky(&chunk, 9, 50, 8), 14);
+        assert_eq!(sky(&chunk, 11, 50, 8), 12);
+    }
+
+    #[test]
+    fn test_block_light_spreads_and_is_removed() {
+        let mut chunk = Chunk::new(ChunkHeight::new(-64, 384));
+        chunk
+            .set_block((8, 64, 8).into(), block!("glowstone"))
+            .unwrap();
+        assert_eq!(block_light(&chunk, 8, 64, 8), 15);
+        assert_eq!(block_light(&chunk, 9, 64, 8), 14);
+        assert_eq!(block_light(&chunk, 8, 70, 8), 9);
+
+        chunk.set_block((8, 64, 8).into(), block!("air")).unwrap();
+        assert_eq!(block_light(&chunk, 8, 64, 8), 0);
+        assert_eq!(block_light(&chunk, 8, 70, 8), 0);
+    }
+
+    #[test]
+    fn test_light_crosses_chunk_borders() {
+        let mut chunk = Chunk::new(ChunkHeight::new(-64, 384));
+        let mut east = Chunk::new(ChunkHeight::new(-64, 384));
+        chunk
+            .set_block((15, 64, 8).into(), block!("glowstone"))
+            .unwrap();
+
+        let mut engine = LightEngine::new(&mut chunk).with_neighbour(1, 0, &mut east);
+        engine.relight();
+        assert_eq!(engine.changed_neighbours(), vec![(1, 0)]);
+        assert_eq!(block_light(&east, 0, 64, 8), 14);
+        assert_eq!(block_light(&east, 3, 64, 8), 11);
+
+        chunk
+            .replace_block((15, 64, 8).into(), block!("stone"))
+            .unwrap();
+        let mut engine = LightEngine::new(&mut chunk).with_neighbour(1, 0, &mut east);
+        engine.update_block((15, 64, 8).into());
+        assert_eq!(block_light(&east, 0, 64, 8), 0);
+    }
+
+    #[test]
+    fn test_missing_neighbours_are_reported() {
+        let mut chunk = Chunk::new(ChunkHeight::new(-64, 384));
+        chunk
+            .replace_block((8, 64, 8).into(), block!("stone"))
+            .unwrap();
+        let mut engine = LightEngine::new(&mut chunk);
+        engine.update_block((8, 64, 8).into());
+        // The shadow only reaches straight down
+        assert!(!engine.reached_missing_neighbour());
+
+        chunk
+            .replace_block((8, 64, 8).into(), block!("glowstone"))
+            .unwrap();
+        let mut engine = LightEngine::new(&mut chunk);
+        engine.update_block((8, 64, 8).into());
+        assert!(engine.reached_missing_neighbour());
+    }
+}
@@ -0,0 +1,529 @@
+//! What blocks drop when they're broken.
+//!
+//! Every block that drops anything has a loot table in the generated block data, in the shape
+//! vanilla's loot table files have: pools that each pick from their entries a number of times,
+//! with conditions deciding which entries can be picked and functions changing how many items
+//! come out. [`BlockStateId::drops`] rolls a block's table for whatever broke it. Only the parts
+//! of loot tables that make sense for blocks are supported; conditions on anything else never
+//! pass and functions that don't change the count are ignored.
+
+use crate::block_state_id::BlockStateId;
+use ferrumc_data::blocks::{
+    Block, BonusFormula, LootCondition, LootEntry, LootEntryKind, LootFunction, LootFunctionKind,
+    LootPool, LootTable, NumberProvider,
+};
+use ferrumc_data::generated::tags::TagData;
+use rand::Rng;
+
+/// What broke a block, which decides what it drops.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LootContext<'a> {
+    /// The item the block was broken with, e.g. `minecraft:iron_pickaxe`, or `None` for a bare
+    /// hand.
+    pub tool: Option<&'a str>,
+    /// The tool's enchantments and their levels, e.g. `("minecraft:fortune", 3)`.
+    pub enchantments: &'a [(&'a str, u32)],
+    /// How big the explosion was if the block was blown up rather than mined.
+    pub explosion_radius: Option<f32>,
+}
+
+impl LootContext<'_> {
+    fn level(&self, enchantment: &str) -> u32 {
+        self.enchantments
+            .iter()
+            .find(|(name, _)| same_id(name, enchantment))
+            .map_or(0, |&(_, level)| level)
+    }
+}
+
+/// A stack of items a block dropped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ItemDrop {
+    pub item: &'static str,
+    pub count: u32,
+}
+
+/// Whether two IDs are the same, with or without the `minecraft:` namespace.
+fn same_id(a: &str, b: &str) -> bool {
+    a.trim_start_matches("minecraft:") == b.trim_start_matches("minecraft:")
+}
+
+fn item_tag(name: &str) -> &'static [&'static str] {
+    TagData::get_item_tag(name).map_or(&[], |tag| tag.values)
+}
+
+impl BlockStateId {
+    /// The loot table the block drops from, or `None` for blocks that never drop anything.
+    pub fn loot_table(&self) -> Option<&'static LootTable> {
+        let family = self.family()?;
+        Block::by_name(family.name.trim_start_matches("minecraft:"))?.loot_table
+    }
+
+    /// Rolls what the block drops when broken the way `context` describes.
+    pub fn drops(&self, context: &LootContext, rng: &mut impl Rng) -> Vec<ItemDrop> {
+        match self.loot_table() {
+            Some(table) => roll(table, *self, context, rng),
+            None => vec![],
+        }
+    }
+}
+
+/// Rolls a loot table for `block` broken the way `context` describes.
+pub fn roll(
+    table: &'static LootTable,
+    block: BlockStateId,
+    context: &LootContext,
+    rng: &mut impl Rng,
+) -> Vec<ItemDrop> {
+    let mut roller = Roller {
+        block,
+        context,
+        rng,
+        drops: vec![],
+    };
+    for pool in table.pools {
+        roller.pool(pool, table.functions);
+    }
+    roller.drops
+}
+
+struct Roller<'a, R> {
+    block: BlockStateId,
+    context: &'a LootContext<'a>,
+    rng: &'a mut R,
+    drops: Vec<ItemDrop>,
+}
+
+impl<R: Rng> Roller<'_, R> {
+    fn pool(&mut self, pool: &'static LootPool, table_functions: &'static [LootFunction]) {
+        if !self.all(pool.conditions) {
+            return;
+        }
+        // Bonus rolls only come from luck, which breaking blocks never has
+        let rolls = self.int(pool.rolls);
+        for _ in 0..rolls {
+            let mut candidates = vec![];
+            for entry in pool.entries {
+                self.expand(entry, &mut candidates);
+            }
+            let Some((entry, item)) = self.pick(&candidates) else {
+                continue;
+            };
+            let items = match (item, &entry.kind) {
+                (Some(item), _) => std::slice::from_ref(item),
+                (None, LootEntryKind::Tag { name, .. }) => item_tag(name),
+                _ => &[],
+            };
+            for &item in items {
+                let count = [entry.functions, pool.functions, table_functions]
+                    .into_iter()
+                    .flatten()
+                    .fold(1, |count, function| self.function(function, count));
+                if count > 0 {
+                    self.drops.push(ItemDrop {
+                        item,
+                        count: count as u32,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Adds what the entry can drop to `candidates` and returns whether its conditions passed.
+    /// Each candidate is an entry along with the one item it drops, or `None` for entries that
+    /// drop a whole tag or nothing.
+    fn expand(
+        &mut self,
+        entry: &'static LootEntry,
+        candidates: &mut Vec<(&'static LootEntry, Option<&'static &'static str>)>,
+    ) -> bool {
+        if !self.all(entry.conditions) {
+            return false;
+        }
+        match &entry.kind {
+            LootEntryKind::Item(item) => candidates.push((entry, Some(item))),
+            LootEntryKind::Tag { name, expand: true } => {
+                candidates.extend(item_tag(name).iter().map(|item| (entry, Some(item))))
+            }
+            LootEntryKind::Tag { .. } | LootEntryKind::Empty => candidates.push((entry, None)),
+            LootEntryKind::Alternatives(children) => {
+                return children.iter().any(|child| self.expand(child, candidates));
+            }
+            LootEntryKind::Group(children) => {
+                for child in *children {
+                    self.expand(child, candidates);
+                }
+            }
+            LootEntryKind::Sequence(children) => {
+                return children.iter().all(|child| self.expand(child, candidates));
+            }
+        }
+        true
+    }
+
+    fn pick<T: Copy>(
+        &mut self,
+        candidates: &[(&'static LootEntry, T)],
+    ) -> Option<(&'static LootEntry, T)> {
+        if candidates.len() <= 1 {
+            return candidates.first().copied();
+        }
+        let total: u32 = candidates.iter().map(|(entry, _)| entry.weight).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut pick = self.rng.random_range(0..total);
+        for &(entry, item) in candidates {
+            if pick < entry.weight {
+                return Some((entry, item));
+            }
+            pick -= entry.weight;
+        }
+        None
+    }
+
+    fn int(&mut self, number: NumberProvider) -> i32 {
+        match number {
+            NumberProvider::Constant(value) => value.floor() as i32,
+            NumberProvider::Uniform { min, max } => {
+                let (min, max) = (min.floor() as i32, max.floor() as i32);
+                if min >= max {
+                    min
+                } else {
+                    self.rng.random_range(min..=max)
+                }
+            }
+            NumberProvider::Binomial { n, p } => {
+                (0..n.floor() as i32).filter(|_| self.chance(p)).count() as i32
+            }
+        }
+    }
+
+    fn chance(&mut self, chance: f32) -> bool {
+        self.rng.random::<f32>() < chance
+    }
+
+    fn all(&mut self, conditions: &'static [LootCondition]) -> bool {
+        conditions.iter().all(|condition| self.condition(condition))
+    }
+
+    fn condition(&mut self, condition: &'static LootCondition) -> bool {
+        match condition {
+            LootCondition::SurvivesExplosion => match self.context.explosion_radius {
+                Some(radius) => self.rng.random::<f32>() <= 1.0 / radius,
+                None => true,
+            },
+            LootCondition::MatchTool {
+                items,
+                enchantments,
+            } => {
+                let item_matches = items.is_empty()
+                    || self.context.tool.is_some_and(|tool| {
+                        items.iter().any(|item| match item.strip_prefix('#') {
+                            Some(tag) => item_tag(tag).iter().any(|item| same_id(item, tool)),
+                            None => same_id(item, tool),
+                        })
+                    });
+                item_matches
+                    && enchantments
+                        .iter()
+                        .all(|&(enchantment, level)| self.context.level(enchantment) >= level)
+            }
+            LootCondition::TableBonus {
+                enchantment,
+                chances,
+            } => {
+                let level = self.context.level(enchantment) as usize;
+                match chances.get(level).or(chances.last()) {
+                    Some(&chance) => self.chance(chance),
+                    None => false,
+                }
+            }
+            LootCondition::RandomChance(chance) => self.chance(*chance),
+            LootCondition::BlockStateProperty(properties) => properties
+                .iter()
+                .all(|&(name, value)| self.block.get_property(name) == Some(value)),
+            LootCondition::Inverted(term) => !self.condition(term),
+            LootCondition::AnyOf(terms) => terms.iter().any(|term| self.condition(term)),
+            LootCondition::AllOf(terms) => self.all(terms),
+            LootCondition::Unsupported => false,
+        }
+    }
+
+    fn function(&mut self, function: &'static LootFunction, count: i32) -> i32 {
+        if !self.all(function.conditions) {
+            return count;
+        }
+        match function.kind {
+            LootFunctionKind::SetCount { count: number, add } => {
+                let number = self.int(number);
+                if add {
+                    count + number
+                } else {
+                    number
+                }
+            }
+            LootFunctionKind::ApplyBonus {
+                enchantment,
+                formula,
+            } => {
+                let level = self.context.level(enchantment) as i32;
+                match formula {
+                    BonusFormula::OreDrops if level > 0 => {
+                        let bonus = (self.rng.random_range(0..level + 2) - 1).max(0);
+                        count * (bonus + 1)
+                    }
+                    BonusFormula::OreDrops => count,
+                    BonusFormula::UniformBonusCount { bonus_multiplier } => {
+                        count + self.rng.random_range(0..=bonus_multiplier * level)
+                    }
+                    BonusFormula::BinomialWithBonusCount { extra, probability } => {
+                        count
+                            + (0..level + extra)
+                                .filter(|_| self.chance(probability))
+                                .count() as i32
+                    }
+                }
+            }
+            LootFunctionKind::LimitCount { min, max } => {
+                let count = min.map_or(count, |min| count.max(min));
+                max.map_or(count, |max| count.min(max))
+            }
+            LootFunctionKind::ExplosionDecay => match self.context.explosion_radius {
+                Some(radius) => (0..count).filter(|_| self.chance(1.0 / radius)).count() as i32,
+                None => count,
+            },
+            LootFunctionKind::Other => count,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ferrumc_macros::block;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    const SILK_TOUCH: LootCondition = LootCondition::MatchTool {
+        items: &[],
+        enchantments: &[("minecraft:silk_touch", 1)],
+    };
+
+    const fn item(name: &'static str) -> LootEntryKind {
+        LootEntryKind::Item(name)
+    }
+
+    const fn entry(
+        kind: LootEntryKind,
+        conditions: &'static [LootCondition],
+        functions: &'static [LootFunction],
+    ) -> LootEntry {
+        LootEntry {
+            kind,
+            weight: 1,
+            quality: 0,
+            conditions,
+            functions,
+        }
+    }
+
+    /// A pool that picks one of the entries once.
+    const fn pool(entries: &'static [LootEntry]) -> LootPool {
+        LootPool {
+            rolls: NumberProvider::Constant(1.0),
+            bonus_rolls: 0.0,
+            entries,
+            conditions: &[],
+            functions: &[],
+        }
+    }
+
+    /// Stone, which drops cobblestone unless mined with Silk Touch.
+    static STONE: LootTable = LootTable {
+        pools: &[pool(&[entry(
+            LootEntryKind::Alternatives(&[
+                entry(item("minecraft:stone"), &[SILK_TOUCH], &[]),
+                entry(
+                    item("minecraft:cobblestone"),
+                    &[LootCondition::SurvivesExplosion],
+                    &[],
+                ),
+            ]),
+            &[],
+            &[],
+        )])],
+        functions: &[],
+    };
+
+    /// An ore that drops 2 to 5 items, more with Fortune.
+    static ORE: LootTable = LootTable {
+        pools: &[pool(&[entry(
+            item("minecraft:lapis_lazuli"),
+            &[],
+            &[
+                LootFunction {
+                    kind: LootFunctionKind::SetCount {
+                        count: NumberProvider::Uniform { min: 2.0, max: 5.0 },
+                        add: false,
+                    },
+                    conditions: &[],
+                },
+                LootFunction {
+                    kind: LootFunctionKind::ApplyBonus {
+                        enchantment: "minecraft:fortune",
+                        formula: BonusFormula::OreDrops,
+                    },
+                    conditions: &[],
+                },
+                LootFunction {
+                    kind: LootFunctionKind::ExplosionDecay,
+                    conditions: &[],
+                },
+            ],
+        )])],
+        functions: &[],
+    };
+
+    /// Wheat, which only drops wheat once fully grown, and only with shears drops grass.
+    static CROP: LootTable = LootTable {
+        pools: &[pool(&[entry(
+            LootEntryKind::Alternatives(&[
+                entry(
+                    item("minecraft:wheat"),
+                    &[LootCondition::BlockStateProperty(&[("age", "7")])],
+                    &[],
+                ),
+                entry(
+                    item("minecraft:short_grass"),
+                    &[LootCondition::MatchTool {
+                        items: &["minecraft:shears"],
+                        enchantments: &[],
+                    }],
+                    &[],
+                ),
+                entry(LootEntryKind::Empty, &[], &[]),
+            ]),
+            &[],
+            &[],
+        )])],
+        functions: &[],
+    };
+
+    fn rng() -> StdRng {
+        StdRng::seed_from_u64(3)
+    }
+
+    #[test]
+    fn test_tool_conditions() {
+        let stone = block!("stone");
+        let hand = LootContext::default();
+        assert_eq!(
+            roll(&STONE, stone, &hand, &mut rng()),
+            vec![ItemDrop {
+                item: "minecraft:cobblestone",
+                count: 1
+            }]
+        );
+        let silk_touch = LootContext {
+            tool: Some("minecraft:diamond_pickaxe"),
+            enchantments: &[("minecraft:silk_touch", 1)],
+            ..Default::default()
+        };
+        assert_eq!(
+            roll(&STONE, stone, &silk_touch, &mut rng()),
+            vec![ItemDrop {
+                item: "minecraft:stone",
+                count: 1
+            }]
+        );
+
+        let young = block!("wheat", {age: 3});
+        let grown = block!("wheat", {age: 7});
+        assert_eq!(roll(&CROP, young, &hand, &mut rng()), vec![]);
+        assert_eq!(
+            roll(&CROP, grown, &hand, &mut rng())[0].item,
+            "minecraft:wheat"
+        );
+        let shears = LootContext {
+            tool: Some("shears"),
+            ..Default::default()
+        };
+        assert_eq!(
+            roll(&CROP, young, &shears, &mut rng())[0].item,
+            "minecraft:short_grass"
+        );
+    }
+
+    #[test]
+    fn test_counts_and_fortune() {
+        let ore = block!("lapis_ore");
+        let mut rng = rng();
+        let counts = |context: &LootContext, rng: &mut StdRng| {
+            (0..500)
+                .map(|_| roll(&ORE, ore, context, rng)[0].count)
+                .collect::<Vec<_>>()
+        };
+
+        let plain = counts(&LootContext::default(), &mut rng);
+        assert_eq!(plain.iter().min(), Some(&2));
+        assert_eq!(plain.iter().max(), Some(&5));
+
+        let fortune = counts(
+            &LootContext {
+                enchantments: &[("fortune", 3)],
+                ..Default::default()
+            },
+            &mut rng,
+        );
+        assert_eq!(fortune.iter().max(), Some(&20));
+        assert!(fortune.iter().sum::<u32>() > plain.iter().sum::<u32>());
+
+        // Only some of the blocks blown up by a big explosion drop anything
+        let exploded = LootContext {
+            explosion_radius: Some(8.0),
+            ..Default::default()
+        };
+        let dropped = (0..500)
+            .filter(|_| !roll(&STONE, block!("stone"), &exploded, &mut rng).is_empty())
+            .count();
+        assert!(dropped > 20 && dropped < 120, "{dropped}");
+    }
+
+    #[test]
+    fn test_generated_tables() {
+        let hand = LootContext::default();
+        let drops = |block: BlockStateId, context: &LootContext| {
+            block
+                .drops(context, &mut rng())
+                .iter()
+                .map(|drop| (drop.item, drop.count))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            drops(block!("stone"), &hand),
+            [("minecraft:cobblestone", 1)]
+        );
+        assert_eq!(drops(block!("glass"), &hand), []);
+        assert_eq!(drops(block!("air"), &hand), []);
+        assert_eq!(
+            drops(
+                block!("oak_slab", {r#type: "double", waterlogged: false}),
+                &hand
+            ),
+            [("minecraft:oak_slab", 2)]
+        );
+        let shears = LootContext {
+            tool: Some("minecraft:shears"),
+            ..Default::default()
+        };
+        assert_eq!(
+            drops(
+                block!("oak_leaves", {distance: 7, persistent: false, waterlogged: false}),
+                &shears
+            ),
+            [("minecraft:oak_leaves", 1)]
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! Versioning for the chunk format stored in the database.
+//!
+//! Chunks are stored as bitcode, which isn't self-describing, so any change to the layout of
+//! [`Chunk`] or the types inside it would make the chunks already stored unreadable. Instead every
+//! record is stamped with the [`CHUNK_FORMAT_VERSION`] it was written with, and older ones are
+//! upgraded one version at a time when they're loaded.
+//!
+//! To change the layout:
+//! 1. Copy the current definitions of the types that are about to change into a new module for
+//!    the current version, and point the decoders of older versions at the copies so they keep
+//!    reading the layout they were written for.
+//! 2. Bump [`CHUNK_FORMAT_VERSION`] and add a migration from the copies to the new layout.
+
+use crate::chunk_format::{Chunk, Heightmaps, Section};
+use crate::errors::WorldError;
+use ferrumc_data::biomes::Biome;
+use std::borrow::Cow;
+
+/// The version of the layout chunks are currently written with.
+pub const CHUNK_FORMAT_VERSION: u16 = 4;
+
+/// Upgrades an encoded chunk to the next version.
+type Migration = fn(&[u8]) -> Result<Vec<u8>, WorldError>;
+
+/// `MIGRATIONS[n]` upgrades a chunk from version `n` to `n + 1`.
+const MIGRATIONS: [Migration; CHUNK_FORMAT_VERSION as usize] =
+    [v0_to_v1, v1_to_v2, v2_to_v3, v3_to_v4];
+
+/// Decodes a chunk written with the given format version, upgrading it first if it's older than
+/// the current one.
+pub fn decode_versioned(version: u16, data: &[u8]) -> Result<Chunk, WorldError> {
+    if version > CHUNK_FORMAT_VERSION {
+        return Err(WorldError::UnsupportedChunkVersion(version));
+    }
+    let mut data = Cow::Borrowed(data);
+    for migration in &MIGRATIONS[version as usize..] {
+        data = Cow::Owned(migration(&data)?);
+    }
+    decode(&data)
+}
+
+fn decode<'a, T: bitcode::Decode<'a>>(data: &'a [u8]) -> Result<T, WorldError> {
+    bitcode::decode(data).map_err(|e| WorldError::BitcodeDecodeError(e.to_string()))
+}
+
+/// Chunks from before block entities were stored with them.
+mod v0 {
+    use super::v1::Heightmaps;
+    use super::*;
+    use bitcode_derive::{Decode, Encode};
+
+    #[derive(Encode, Decode)]
+    pub(super) struct Chunk {
+        pub min_y: i16,
+        pub sections: Vec<Section>,
+        pub heightmaps: Heightmaps,
+    }
+}
+
+fn v0_to_v1(data: &[u8]) -> Result<Vec<u8>, WorldError> {
+    let chunk: v0::Chunk = decode(data)?;
+    let chunk = v1::Chunk {
+        min_y: chunk.min_y,
+        sections: chunk.sections,
+        heightmaps: chunk.heightmaps,
+        block_entities: vec![],
+    };
+    Ok(bitcode::encode(&chunk))
+}
+
+/// Chunks from before the ocean floor and no-leaves heightmaps were tracked.
+mod v1 {
+    use super::*;
+    use crate::block_entity::BlockEntity;
+    use bitcode_derive::{Decode, Encode};
+
+    #[derive(Encode, Decode)]
+    pub(super) struct Chunk {
+        pub min_y: i16,
+        pub sections: Vec<Section>,
+        pub heightmaps: Heightmaps,
+        pub block_entities: Vec<BlockEntity>,
+    }
+
+    #[derive(Encode, Decode, Clone)]
+    pub(super) struct Heightmaps {
+        pub motion_blocking: Vec<i64>,
+        pub world_surface: Vec<i64>,
+    }
+}
+
+/// The old heightmaps were rarely filled in, so they're recalculated rather than carried over.
+fn v1_to_v2(data: &[u8]) -> Result<Vec<u8>, WorldError> {
+    let chunk: v1::Chunk = decode(data)?;
+    let mut chunk = Chunk {
+        min_y: chunk.min_y,
+        sections: chunk.sections,
+        heightmaps: Heightmaps::new(),
+        block_entities: chunk.block_entities,
+        scheduled_ticks: vec![],
+    };
+    chunk.recalculate_heightmaps();
+    Ok(bitcode::encode(&v3::Chunk::from(chunk)))
+}
+
+/// Biomes used to hold a placeholder that happened to be the id of the badlands, and were never
+/// sent to clients. The layout hasn't changed, but the dimension isn't known here, so they're all
+/// reset to plains.
+fn v2_to_v3(data: &[u8]) -> Result<Vec<u8>, WorldError> {
+    let mut chunk = Chunk::from(decode::<v3::Chunk>(data)?);
+    chunk.fill_biome(&Biome::PLAINS);
+    Ok(bitcode::encode(&v3::Chunk::from(chunk)))
+}
+
+/// Chunks from before block and fluid ticks were scheduled.
+mod v3 {
+    use super::*;
+    use crate::block_entity::BlockEntity;
+    use bitcode_derive::{Decode, Encode};
+
+    #[derive(Encode, Decode)]
+    pub(super) struct Chunk {
+        pub min_y: i16,
+        pub sections: Vec<Section>,
+        pub heightmaps: Heightmaps,
+        pub block_entities: Vec<BlockEntity>,
+    }
+
+    impl From<Chunk> for super::Chunk {
+        fn from(chunk: Chunk) -> Self {
+            Self {
+                min_y: chunk.min_y,
+                sections: chunk.sections,
+                heightmaps: chunk.heightmaps,
+                block_entities: chunk.block_entities,
+                scheduled_ticks: vec![],
+            }
+        }
+    }
+
+    impl From<super::Chunk> for Chunk {
+        fn from(chunk: super::Chunk) -> Self {
+            Self {
+                min_y: chunk.min_y,
+                sections: chunk.sections,
+                heightmaps: chunk.heightmaps,
+                block_entities: chunk.block_entities,
+            }
+        }
+    }
+}
+
+fn v3_to_v4(data: &[u8]) -> Result<Vec<u8>, WorldError> {
+    let chunk = Chunk::from(decode::<v3::Chunk>(data)?);
+    Ok(bitcode::encode(&chunk))
+}
+
+/// Encodes a chunk the way it was stored before versioning, for testing upgrades.
+#[cfg(test)]
+pub(crate) fn encode_v0(chunk: &Chunk) -> Vec<u8> {
+    bitcode::encode(&v0::Chunk {
+        min_y: chunk.min_y,
+        sections: chunk.sections.clone(),
+        heightmaps: v1::Heightmaps {
+            motion_blocking: chunk.heightmaps.motion_blocking.clone(),
+            world_surface: chunk.heightmaps.world_surface.clone(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_state_id::BlockStateId;
+    use crate::pos::{ChunkBlockPos, ChunkHeight};
+    use ferrumc_macros::block;
+
+    #[test]
+    fn test_upgrade_v0_chunk() {
+        let mut chunk = Chunk::new(ChunkHeight::new(-64, 384));
+        chunk
+            .set_block(ChunkBlockPos::new(5, 70, 9), block!("diamond_ore"))
+            .unwrap();
+        let data = encode_v0(&chunk);
+        assert!(bitcode::decode::<Chunk>(&data).is_err());
+
+        assert_eq!(decode_versioned(0, &data).unwrap(), chunk);
+
+        let current = bitcode::encode(&chunk);
+        assert_eq!(
+            decode_versioned(CHUNK_FORMAT_VERSION, &current).unwrap(),
+            chunk
+        );
+        assert!(matches!(
+            decode_versioned(CHUNK_FORMAT_VERSION + 1, &current),
+            Err(WorldError::UnsupportedChunkVersion(_))
+        ));
+    }
+
+    #[test]
+    fn test_upgrade_v1_chunk_fills_heightmaps() {
+        let mut chunk = Chunk::new(ChunkHeight::new(-64, 384));
+        chunk
+            .set_block(ChunkBlockPos::new(5, 70, 9), block!("stone"))
+            .unwrap();
+        let data = bitcode::encode(&v1::Chunk {
+            min_y: chunk.min_y,
+            sections: chunk.sections.clone(),
+            heightmaps: v1::Heightmaps {
+                motion_blocking: vec![],
+                world_surface: vec![],
+            },
+            block_entities: vec![],
+        });
+
+        let upgraded = decode_versioned(1, &data).unwrap();
+        assert_eq!(upgraded, chunk);
+        assert_eq!(
+            upgraded.height(crate::heightmaps::HeightmapType::OceanFloor, 5, 9),
+            71
+        );
+    }
+}
@@ -0,0 +1,816 @@
+//! Working out the state a block is placed in.
+//!
+//! An item only maps to its block's default state, so without this every stair would face north
+//! and every log would stand upright. Vanilla picks the state from the side of the block that was
+//! clicked, where on that side the player clicked, which way they're looking and what's already
+//! there:
+//!
+//! * Logs and other pillars line up with the clicked side (`axis`).
+//! * Stairs, slabs and trapdoors go in the top or bottom half depending on where the click landed
+//!   (`half` and `type`), and a slab placed onto a matching slab merges into a double slab.
+//! * Furnaces, chests, pistons and the like face the player, while stairs, doors and beds face
+//!   away from them (`facing`). Signs, banners and heads turn to any of 16 angles (`rotation`).
+//! * Torches, signs, banners, heads and coral fans placed against a wall become their wall
+//!   variant, and levers and buttons go on the floor, ceiling or a wall (`face`).
+//! * Doors, tall flowers and beds place both halves at once, and doors pick the hinge side that
+//!   pairs them up with a door next to them (`hinge`).
+//! * Blocks that can hold water are waterlogged when placed into a water source.
+//!
+//! Blocks that need something to stand on aren't placed without it. Stairs don't curve round
+//! corners, and fences, walls and panes don't connect to their neighbours.
+
+use crate::block_state_id::{BlockStateId, ID2BLOCK};
+use crate::block_updates::{facing, is_sturdy, name};
+use crate::dimension::Dimension;
+use crate::errors::WorldError;
+use crate::lighting::property;
+use crate::pos::BlockPos;
+use crate::random_ticks::is_air;
+use crate::vanilla_chunk_format::BlockData;
+use crate::World;
+use bevy_math::{IVec3, Vec3};
+use ferrumc_macros::match_block;
+use std::collections::BTreeMap;
+
+/// Blocks that are replaced by a block placed into them instead of being placed against.
+const REPLACEABLE: &[&str] = &[
+    "water",
+    "lava",
+    "short_grass",
+    "fern",
+    "tall_grass",
+    "large_fern",
+    "short_dry_grass",
+    "tall_dry_grass",
+    "bush",
+    "dead_bush",
+    "seagrass",
+    "tall_seagrass",
+    "vine",
+    "glow_lichen",
+    "fire",
+    "soul_fire",
+    "crimson_roots",
+    "warped_roots",
+    "nether_sprouts",
+    "hanging_roots",
+    "structure_void",
+    "light",
+];
+
+/// Blocks that face the side they were placed against.
+const FACES_CLICKED_SIDE: &[&str] = &["end_rod", "lightning_rod", "ladder", "tripwire_hook"];
+
+/// Blocks that face the way the player is looking, rather than back at them.
+const FACES_AWAY: &[&str] = &["campfire", "soul_campfire", "observer"];
+
+/// Where a player clicked to place a block.
+#[derive(Clone, Copy, Debug)]
+pub struct PlacementContext {
+    /// The block that was clicked.
+    pub clicked: BlockPos,
+    /// The side of the clicked block, pointing out of it. See [`PlacementContext::face`].
+    pub face: IVec3,
+    /// Where the click landed, relative to the clicked block's lowest corner, so each coordinate
+    /// is from 0 to 1.
+    pub cursor: Vec3,
+    /// The player's yaw in degrees, 0 when looking south.
+    pub yaw: f32,
+    /// The player's pitch in degrees, 90 when looking straight down.
+    pub pitch: f32,
+}
+
+impl PlacementContext {
+    /// The direction of a block face sent by the client, from 0 to 5 for down, up, north, south,
+    /// west and east.
+    pub fn face(id: i32) -> Option<IVec3> {
+        match id {
+            0 => Some(IVec3::NEG_Y),
+            1 => Some(IVec3::Y),
+            2 => Some(IVec3::NEG_Z),
+            3 => Some(IVec3::Z),
+            4 => Some(IVec3::NEG_X),
+            5 => Some(IVec3::X),
+            _ => None,
+        }
+    }
+
+    /// The horizontal direction the player is looking in.
+    fn horizontal(&self) -> IVec3 {
+        match ((self.yaw / 90.0 + 0.5).floor() as i32).rem_euclid(4) {
+            0 => IVec3::Z,
+            1 => IVec3::NEG_X,
+            2 => IVec3::NEG_Z,
+            _ => IVec3::X,
+        }
+    }
+
+    /// The direction the player is looking in, up and down included.
+    fn looking(&self) -> IVec3 {
+        let (yaw, pitch) = (self.yaw.to_radians(), self.pitch.to_radians());
+        let look = Vec3::new(
+            -yaw.sin() * pitch.cos(),
+            -pitch.sin(),
+            yaw.cos() * pitch.cos(),
+        );
+        let abs = look.abs();
+        if abs.y >= abs.x && abs.y >= abs.z {
+            IVec3::new(0, look.y.signum() as i32, 0)
+        } else if abs.x >= abs.z {
+            IVec3::new(look.x.signum() as i32, 0, 0)
+        } else {
+            IVec3::new(0, 0, look.z.signum() as i32)
+        }
+    }
+
+    /// One of 16 angles, turned `offset` degrees from the player's yaw.
+    fn rotation(&self, offset: f32) -> i32 {
+        (((self.yaw + offset) / 22.5 + 0.5).floor() as i32).rem_euclid(16)
+    }
+
+    /// Where the click landed relative to the lowest corner of `target`, which isn't the clicked
+    /// block when the new block goes next to it.
+    fn hit(&self, target: BlockPos) -> Vec3 {
+        (self.clicked.pos - target.pos).as_vec3() + self.cursor
+    }
+
+    /// Whether the block goes in the top half of the space, like a slab placed on the underside
+    /// of a block or high up on its side.
+    fn upper_half(&self, target: BlockPos) -> bool {
+        self.face == IVec3::NEG_Y || (self.face.y == 0 && self.hit(target).y > 0.5)
+    }
+}
+
+fn direction_name(dir: IVec3) -> &'static str {
+    match (dir.x, dir.y, dir.z) {
+        (0, 1, 0) => "up",
+        (0, -1, 0) => "down",
+        (0, 0, -1) => "north",
+        (0, 0, 1) => "south",
+        (-1, 0, 0) => "west",
+        _ => "east",
+    }
+}
+
+fn axis_name(dir: IVec3) -> &'static str {
+    if dir.x != 0 {
+        "x"
+    } else if dir.y != 0 {
+        "y"
+    } else {
+        "z"
+    }
+}
+
+/// The direction 90 degrees clockwise, looking down.
+fn clockwise(dir: IVec3) -> IVec3 {
+    IVec3::new(-dir.z, 0, dir.x)
+}
+
+fn is_replaceable(block: BlockStateId) -> bool {
+    if is_air(block) {
+        return true;
+    }
+    if match_block!("snow", block) {
+        return block.get_property("layers") == Some("1");
+    }
+    ID2BLOCK
+        .get(block.raw() as usize)
+        .is_some_and(|data| REPLACEABLE.contains(&name(data)))
+}
+
+fn is_water_source(block: BlockStateId) -> bool {
+    match_block!("water", block) && block.get_property("level") == Some("0")
+}
+
+/// The block waterlogged if it's going where a water source is, and dry otherwise.
+fn waterlog(block: BlockStateId, replaced: BlockStateId) -> BlockStateId {
+    let waterlogged = if is_water_source(replaced) {
+        "true"
+    } else {
+        "false"
+    };
+    set(block, "waterlogged", waterlogged)
+}
+
+fn is_slab(block: BlockStateId) -> bool {
+    ID2BLOCK
+        .get(block.raw() as usize)
+        .is_some_and(|data| name(data).ends_with("_slab"))
+}
+
+/// The block a standing block turns into against a wall, like `wall_torch` for `torch`.
+fn wall_variant(name: &str) -> Option<String> {
+    let wall = if name.ends_with("torch") {
+        format!("{}wall_torch", name.strip_suffix("torch")?)
+    } else if let Some(wood) = name.strip_suffix("_hanging_sign") {
+        format!("{wood}_wall_hanging_sign")
+    } else if let Some(wood) = name.strip_suffix("_sign") {
+        format!("{wood}_wall_sign")
+    } else if let Some(colour) = name.strip_suffix("_banner") {
+        format!("{colour}_wall_banner")
+    } else if let Some(mob) = name.strip_suffix("_head") {
+        format!("{mob}_wall_head")
+    } else if let Some(mob) = name.strip_suffix("_skull") {
+        format!("{mob}_wall_skull")
+    } else {
+        format!("{}_coral_wall_fan", name.strip_suffix("_coral_fan")?)
+    };
+    (!wall.contains("_wall_wall")).then_some(wall)
+}
+
+/// The state with `key` set to `value`, or unchanged if the block doesn't have that property or
+/// value.
+fn set(block: BlockStateId, key: &str, value: &str) -> BlockStateId {
+    block.with_property(key, value).unwrap_or(block)
+}
+
+/// Picks the state for a block placed at `target`, or `None` if it can't go there.
+fn orient(
+    block: BlockStateId,
+    context: &PlacementContext,
+    target: BlockPos,
+) -> Option<BlockStateId> {
+    let data = ID2BLOCK.get(block.raw() as usize)?;
+    let name = name(data);
+    let face = context.face;
+    let horizontal = context.horizontal();
+    let has = |key: &str| property(data, key).is_some();
+
+    if face.y == 0 {
+        if let Some(wall) = wall_variant(name) {
+            let mut properties = BTreeMap::new();
+            properties.insert("facing".to_string(), direction_name(face).to_string());
+            let wall = BlockStateId::find(&BlockData {
+                name: format!("minecraft:{wall}"),
+                properties: Some(properties),
+            })?;
+            // The wall variant keeps whatever state the two have in common, like a redstone
+            // torch being lit
+            return Some(
+                data.properties
+                    .iter()
+                    .flatten()
+                    .filter(|(key, _)| !matches!(key.as_str(), "rotation" | "facing"))
+                    .fold(wall, |wall, (key, value)| set(wall, key, value)),
+            );
+        }
+    }
+    if name.ends_with("_hanging_sign") && face == IVec3::Y {
+        return None;
+    }
+
+    let mut state = block;
+    if has("axis") {
+        state = set(state, "axis", axis_name(face));
+    }
+    if has("rotation") {
+        let offset = if name.ends_with("_head") || name.ends_with("_skull") {
+            0.0
+        } else {
+            180.0
+        };
+        state = set(state, "rotation", &context.rotation(offset).to_string());
+    }
+    if has("face") && has("facing") {
+        // Levers, buttons and grindstones
+        let (attached, facing) = match face.y {
+            1 => ("floor", horizontal),
+            -1 => ("ceiling", horizontal),
+            _ => ("wall", face),
+        };
+        state = set(state, "face", attached);
+        state = set(state, "facing", direction_name(facing));
+    } else if name.ends_with("_trapdoor") {
+        let (facing, top) = if face.y == 0 {
+            (face, context.upper_half(target))
+        } else {
+            (-horizontal, face == IVec3::NEG_Y)
+        };
+        state = set(state, "facing", direction_name(facing));
+        state = set(state, "half", if top { "top" } else { "bottom" });
+    } else if has("facing") {
+        let vertical = state.with_property("facing", "up").is_some();
+        let facing = if FACES_CLICKED_SIDE.contains(&name)
+            || name.ends_with("shulker_box")
+            || name.ends_with("amethyst_bud")
+            || name == "amethyst_cluster"
+        {
+            face
+        } else if name == "hopper" {
+            if face.y == 0 {
+                -face
+            } else {
+                IVec3::NEG_Y
+            }
+        } else if name.ends_with("anvil") {
+            clockwise(horizontal)
+        } else if FACES_AWAY.contains(&name)
+            || name.ends_with("_stairs")
+            || name.ends_with("_door")
+            || name.ends_with("_fence_gate")
+            || name.ends_with("_bed")
+        {
+            if vertical {
+                context.looking()
+            } else {
+                horizontal
+            }
+        } else if vertical {
+            -context.looking()
+        } else {
+            -horizontal
+        };
+        // A ladder has to go on the side of a block
+        state = state.with_property("facing", direction_name(facing))?;
+    }
+    if name.ends_with("_stairs") {
+        let top = context.upper_half(target);
+        state = set(state, "half", if top { "top" } else { "bottom" });
+    }
+    if is_slab(state) {
+        let top = context.upper_half(target);
+        state = set(state, "type", if top { "top" } else { "bottom" });
+    }
+    if state.get_property("half") == Some("upper") {
+        state = set(state, "half", "lower");
+    }
+    Some(state)
+}
+
+impl World {
+    /// Works out which blocks to set when a player places `block` by clicking on a block, using
+    /// the clicked side, where on it they clicked and which way they're looking. See the
+    /// [module docs](self) for what's taken into account.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(Vec<(BlockPos, BlockStateId)>))` - The blocks to set, which is two for doors,
+    ///   beds and tall plants.
+    /// * `Ok(None)` - If there's no room for the block, or it has nothing to stand on.
+    /// * `Err(WorldError)` - If the clicked block couldn't be read.
+    pub fn resolve_placement(
+        &self,
+        block: BlockStateId,
+        context: &PlacementContext,
+        dimension: Dimension,
+    ) -> Result<Option<Vec<(BlockPos, BlockStateId)>>, WorldError> {
+        let get = |pos: BlockPos| self.get_block_and_fetch(pos, dimension).ok();
+        let clicked = self.get_block_and_fetch(context.clicked, dimension)?;
+
+        // A slab placed onto the open side of a matching slab fills the rest of the block
+        let merge = |existing: BlockStateId| {
+            (is_slab(block) && block.is_same_block(existing))
+                .then(|| existing.get_property("type"))
+                .flatten()
+                .filter(|half| *half != "double")
+                .map(|_| set(set(existing, "type", "double"), "waterlogged", "false"))
+        };
+        let open_side = match clicked.get_property("type") {
+            Some("bottom") => IVec3::Y,
+            _ => IVec3::NEG_Y,
+        };
+        if context.face == open_side {
+            if let Some(double) = merge(clicked) {
+                return Ok(Some(vec![(context.clicked, double)]));
+            }
+        }
+
+        let target = if is_replaceable(clicked) && !block.is_same_block(clicked) {
+            context.clicked
+        } else {
+            BlockPos {
+                pos: context.clicked.pos + context.face,
+            }
+        };
+        let Some(existing) = get(target) else {
+            return Ok(None);
+        };
+        if let Some(double) = merge(existing) {
+            return Ok(Some(vec![(target, double)]));
+        }
+        if !is_replaceable(existing) {
+            return Ok(None);
+        }
+
+        let Some(mut state) = orient(block, context, target) else {
+            return Ok(None);
+        };
+        state = waterlog(state, existing);
+        if !self.is_supported(target, dimension, state) {
+            return Ok(None);
+        }
+
+        let data = ID2BLOCK.get(state.raw() as usize);
+        let other = if state.get_property("half") == Some("lower") {
+            BlockPos {
+                pos: target.pos + IVec3::Y,
+            }
+        } else if state.get_property("part") == Some("foot") {
+            let Some(facing) = data.and_then(facing) else {
+                return Ok(None);
+            };
+            BlockPos {
+                pos: target.pos + facing,
+            }
+        } else {
+            return Ok(Some(vec![(target, state)]));
+        };
+        let Some(other_existing) = get(other).filter(|block| is_replaceable(*block)) else {
+            return Ok(None);
+        };
+        if state.get_property("hinge").is_some() {
+            state = set(
+                state,
+                "hinge",
+                self.door_hinge(state, context, target, dimension),
+            );
+        }
+        let mut other_state = match state.get_property("half") {
+            Some(_) => set(state, "half", "upper"),
+            None => set(state, "part", "head"),
+        };
+        other_state = waterlog(other_state, other_existing);
+        Ok(Some(vec![(target, state), (other, other_state)]))
+    }
+
+    /// The side a door's hinge goes on. Doors next to each other open away from each other, and
+    /// otherwise the hinge goes on the side with more blocks next to it, or else the side nearer
+    /// the click.
+    fn door_hinge(
+        &self,
+        door: BlockStateId,
+        context: &PlacementContext,
+        target: BlockPos,
+        dimension: Dimension,
+    ) -> &'static str {
+        let get = |offset: IVec3| {
+            self.get_block_and_fetch(
+                BlockPos {
+                    pos: target.pos + offset,
+                },
+                dimension,
+            )
+            .ok()
+        };
+        let facing = context.horizontal();
+        let right = clockwise(facing);
+        let left = -right;
+        let is_door = |offset| {
+            get(offset).is_some_and(|other| {
+                door.is_same_block(other) && other.get_property("half") == Some("lower")
+            })
+        };
+        let sturdy = |offset| get(offset).is_some_and(is_sturdy) as i32;
+        let solid =
+            sturdy(right) + sturdy(right + IVec3::Y) - sturdy(left) - sturdy(left + IVec3::Y);
+        let (door_left, door_right) = (is_door(left), is_door(right));
+
+        if (door_left && !door_right) || solid > 0 {
+            return "right";
+        }
+        if (door_right && !door_left) || solid < 0 {
+            return "left";
+        }
+        let hit = context.hit(target);
+        let on_right = match (facing.x, facing.z) {
+            (0, -1) => hit.x > 0.5,
+            (0, 1) => hit.x < 0.5,
+            (-1, 0) => hit.z < 0.5,
+            _ => hit.z > 0.5,
+        };
+        if on_right {
+            "right"
+        } else {
+            "left"
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_format::Chunk;
+    use crate::edit_batch::EditBatch;
+    use crate::pos::{ChunkBlockPos, ChunkPos};
+    use ferrumc_macros::block;
+    use ferrumc_storage::memory::MemoryBackend;
+    use std::sync::Arc;
+
+    /// A single chunk with a stone floor at y = 63.
+    fn flat_world() -> World {
+        let world = World::with_backend(MemoryBackend::new()).unwrap();
+        let mut chunk = Chunk::new(Dimension::Overworld.height());
+        let mut batch = EditBatch::new(&mut chunk);
+        for x in 0..16 {
+            for z in 0..16 {
+                batch.set_block(ChunkBlockPos::new(x, 63, z), block!("stone"));
+            }
+        }
+        batch.apply().unwrap();
+        world
+            .save_chunk(ChunkPos::new(0, 0), Dimension::Overworld, Arc::new(chunk))
+            .unwrap();
+        world
+    }
+
+    /// Clicks the top of the floor at (x, z), looking `yaw` degrees round and slightly down.
+    fn on_floor(x: i32, z: i32, yaw: f32) -> PlacementContext {
+        PlacementContext {
+            clicked: BlockPos::of(x, 63, z),
+            face: IVec3::Y,
+            cursor: Vec3::new(0.5, 1.0, 0.5),
+            yaw,
+            pitch: 30.0,
+        }
+    }
+
+    fn place(
+        world: &World,
+        block: BlockStateId,
+        context: PlacementContext,
+    ) -> Option<Vec<(BlockPos, BlockStateId)>> {
+        let placed = world
+            .resolve_placement(block, &context, Dimension::Overworld)
+            .unwrap()?;
+        for (pos, block) in &placed {
+            world
+                .set_block_and_fetch(*pos, Dimension::Overworld, *block)
+                .unwrap();
+        }
+        Some(placed)
+    }
+
+    #[test]
+    fn test_facing_axis_and_half() {
+        let world = flat_world();
+        // Looking north, stairs face north and furnaces face back at the player
+        let placed = place(&world, block!("oak_stairs", {facing: "north", half: "bottom", shape: "straight", waterlogged: false}), on_floor(1, 1, 180.0)).unwrap();
+        assert_eq!(
+            placed,
+            vec![(
+                BlockPos::of(1, 64, 1),
+                block!("oak_stairs", {facing: "north", half: "bottom", shape: "straight", waterlogged: false})
+            )]
+        );
+        let placed = place(
+            &world,
+            block!("furnace", {facing: "north", lit: false}),
+            on_floor(2, 1, 180.0),
+        )
+        .unwrap();
+        assert_eq!(
+            placed[0].1,
+            block!("furnace", {facing: "south", lit: false})
+        );
+
+        // Clicking high up on the side of a block puts stairs upside down
+        let context = PlacementContext {
+            clicked: BlockPos::of(2, 64, 1),
+            face: IVec3::X,
+            cursor: Vec3::new(1.0, 0.8, 0.5),
+            yaw: 90.0,
+            pitch: 0.0,
+        };
+        let placed = place(&world, block!("oak_stairs", {facing: "north", half: "bottom", shape: "straight", waterlogged: false}), context).unwrap();
+        assert_eq!(
+            placed,
+            vec![(
+                BlockPos::of(3, 64, 1),
+                block!("oak_stairs", {facing: "west", half: "top", shape: "straight", waterlogged: false})
+            )]
+        );
+
+        // Logs line up with the clicked side
+        let context = PlacementContext {
+            clicked: BlockPos::of(1, 64, 1),
+            face: IVec3::NEG_Z,
+            cursor: Vec3::new(0.5, 0.5, 0.0),
+            yaw: 0.0,
+            pitch: 0.0,
+        };
+        let placed = place(&world, block!("oak_log", {axis: "y"}), context).unwrap();
+        assert_eq!(
+            placed,
+            vec![(BlockPos::of(1, 64, 0), block!("oak_log", {axis: "z"}))]
+        );
+
+        // Looking straight down, a piston faces up
+        let context = PlacementContext {
+            pitch: 90.0,
+            ..on_floor(5, 5, 0.0)
+        };
+        let placed = place(
+            &world,
+            block!("piston", {extended: false, facing: "north"}),
+            context,
+        )
+        .unwrap();
+        assert_eq!(
+            placed[0].1,
+            block!("piston", {extended: false, facing: "up"})
+        );
+    }
+
+    #[test]
+    fn test_wall_variants_and_support() {
+        let world = flat_world();
+        let against_wall = PlacementContext {
+            clicked: BlockPos::of(1, 64, 1),
+            face: IVec3::NEG_X,
+            cursor: Vec3::new(0.0, 0.5, 0.5),
+            yaw: -90.0,
+            pitch: 0.0,
+        };
+        place(&world, block!("stone"), on_floor(1, 1, 0.0)).unwrap();
+
+        let placed = place(&world, block!("torch"), against_wall).unwrap();
+        assert_eq!(
+            placed,
+            vec![(
+                BlockPos::of(0, 64, 1),
+                block!("wall_torch", {facing: "west"})
+            )]
+        );
+        let placed = place(&world, block!("torch"), on_floor(3, 3, 0.0)).unwrap();
+        assert_eq!(placed[0].1, block!("torch"));
+
+        // Looking south, a sign faces back north at the player
+        let placed = place(
+            &world,
+            block!("oak_sign", {rotation: 0, waterlogged: false}),
+            on_floor(4, 4, 0.0),
+        )
+        .unwrap();
+        assert_eq!(
+            placed[0].1,
+            block!("oak_sign", {rotation: 8, waterlogged: false})
+        );
+
+        // There's nothing to hang a torch from under the floor
+        let context = PlacementContext {
+            clicked: BlockPos::of(6, 63, 6),
+            face: IVec3::NEG_Y,
+            cursor: Vec3::new(0.5, 0.0, 0.5),
+            yaw: 0.0,
+            pitch: -60.0,
+        };
+        assert_eq!(place(&world, block!("torch"), context), None);
+
+        // Levers go on the wall they were placed against
+        let placed = place(
+            &world,
+            block!("lever", {face: "wall", facing: "north", powered: false}),
+            PlacementContext {
+                clicked: BlockPos::of(1, 64, 1),
+                face: IVec3::Z,
+                cursor: Vec3::new(0.5, 0.5, 1.0),
+                yaw: 180.0,
+                pitch: 0.0,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            placed,
+            vec![(
+                BlockPos::of(1, 64, 2),
+                block!("lever", {face: "wall", facing: "south", powered: false})
+            )]
+        );
+    }
+
+    #[test]
+    fn test_slabs_merge_and_waterlog() {
+        let world = flat_world();
+        let slab = block!("oak_slab", {r#type: "bottom", waterlogged: false});
+        place(&world, slab, on_floor(1, 1, 0.0)).unwrap();
+        let placed = place(
+            &world,
+            slab,
+            PlacementContext {
+                clicked: BlockPos::of(1, 64, 1),
+                cursor: Vec3::new(0.5, 0.5, 0.5),
+                ..on_floor(1, 1, 0.0)
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            placed,
+            vec![(
+                BlockPos::of(1, 64, 1),
+                block!("oak_slab", {r#type: "double", waterlogged: false})
+            )]
+        );
+
+        // Placed into water high up on the side of a block, a slab is a waterlogged top slab
+        world
+            .set_block_and_fetch(
+                BlockPos::of(3, 64, 3),
+                Dimension::Overworld,
+                block!("water", {level: 0}),
+            )
+            .unwrap();
+        place(&world, block!("stone"), on_floor(3, 4, 0.0)).unwrap();
+        let placed = place(
+            &world,
+            slab,
+            PlacementContext {
+                clicked: BlockPos::of(3, 64, 4),
+                face: IVec3::NEG_Z,
+                cursor: Vec3::new(0.5, 0.7, 0.0),
+                yaw: 0.0,
+                pitch: 0.0,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            placed,
+            vec![(
+                BlockPos::of(3, 64, 3),
+                block!("oak_slab", {r#type: "top", waterlogged: true})
+            )]
+        );
+    }
+
+    #[test]
+    fn test_two_block_placements() {
+        let world = flat_world();
+        let door = block!("oak_door", {facing: "north", half: "lower", hinge: "left", open: false, powered: false});
+        let placed = place(&world, door, on_floor(4, 4, 180.0)).unwrap();
+        assert_eq!(
+            placed,
+            vec![
+                (
+                    BlockPos::of(4, 64, 4),
+                    block!("oak_door", {facing: "north", half: "lower", hinge: "left", open: false, powered: false})
+                ),
+                (
+                    BlockPos::of(4, 65, 4),
+                    block!("oak_door", {facing: "north", half: "upper", hinge: "left", open: false, powered: false})
+                ),
+            ]
+        );
+        // A door placed to the right of it hinges on the other side so the pair opens outwards
+        let placed = place(&world, door, on_floor(5, 4, 180.0)).unwrap();
+        assert_eq!(
+            placed[0].1,
+            block!("oak_door", {facing: "north", half: "lower", hinge: "right", open: false, powered: false})
+        );
+
+        let placed = place(
+            &world,
+            block!("red_bed", {facing: "north", occupied: false, part: "foot"}),
+            on_floor(8, 8, -90.0),
+        )
+        .unwrap();
+        assert_eq!(
+            placed,
+            vec![
+                (
+                    BlockPos::of(8, 64, 8),
+                    block!("red_bed", {facing: "east", occupied: false, part: "foot"})
+                ),
+                (
+                    BlockPos::of(9, 64, 8),
+                    block!("red_bed", {facing: "east", occupied: false, part: "head"})
+                ),
+            ]
+        );
+
+        // Tall plants need grass, and room above them
+        world
+            .set_block_and_fetch(
+                BlockPos::of(10, 63, 10),
+                Dimension::Overworld,
+                block!("grass_block", {snowy: false}),
+            )
+            .unwrap();
+        let sunflower = block!("sunflower", {half: "lower"});
+        assert_eq!(place(&world, sunflower, on_floor(11, 10, 0.0)), None);
+        world
+            .set_block_and_fetch(
+                BlockPos::of(10, 65, 10),
+                Dimension::Overworld,
+                block!("stone"),
+            )
+            .unwrap();
+        assert_eq!(place(&world, sunflower, on_floor(10, 10, 0.0)), None);
+        world
+            .set_block_and_fetch(
+                BlockPos::of(10, 65, 10),
+                Dimension::Overworld,
+                BlockStateId::default(),
+            )
+            .unwrap();
+        assert_eq!(
+            place(&world, sunflower, on_floor(10, 10, 0.0)).unwrap(),
+            vec![
+                (BlockPos::of(10, 64, 10), sunflower),
+                (
+                    BlockPos::of(10, 65, 10),
+                    block!("sunflower", {half: "upper"})
+                ),
+            ]
+        );
+    }
+}
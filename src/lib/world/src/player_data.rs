@@ -0,0 +1,670 @@
+//! Storage for the data of players that aren't online, such as their position and inventory.
+//!
+//! The world crate doesn't know about the ECS components players are made of, so the data is kept
+//! in plain types here and converted to and from components by the server state.
+//!
+//! Like chunks, the records are bitcode, which isn't self-describing, so each one is stamped with
+//! the [`PLAYER_DATA_FORMAT_VERSION`] it was written with and older ones are upgraded when
+//! they're loaded. To change the layout, copy the types that are about to change into a module
+//! for the current version, bump the version and add a migration from the copies.
+
+use crate::dimension::Dimension;
+use crate::errors::WorldError;
+use crate::World;
+use bitcode_derive::{Decode, Encode};
+use ferrumc_macros::NBTDeserialize;
+use std::borrow::Cow;
+use std::io::Read;
+use std::path::Path;
+use tracing::{error, info, warn};
+
+const PLAYER_DATA_TABLE: &str = "playerdata";
+
+/// The version of the layout player data is currently written with.
+pub const PLAYER_DATA_FORMAT_VERSION: u16 = 2;
+
+/// Marks a record that starts with the format version it was written with. Records without one
+/// predate versioning, and bitcode doesn't start those with this byte for any valid game mode.
+const RECORD_MAGIC: u8 = 0xFC;
+/// The magic byte and the format version.
+const RECORD_HEADER_LEN: usize = 3;
+
+/// Upgrades encoded player data to the next version.
+type Migration = fn(&[u8]) -> Result<Vec<u8>, WorldError>;
+
+/// `MIGRATIONS[n]` upgrades player data from version `n` to `n + 1`.
+const MIGRATIONS: [Migration; PLAYER_DATA_FORMAT_VERSION as usize] = [v0_to_v1, v1_to_v2];
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq)]
+pub struct StoredPlayerData {
+    pub game_mode: u8,
+    pub dimension: Dimension,
+    pub position: (f64, f64, f64),
+    /// Yaw and pitch
+    pub rotation: (f32, f32),
+    pub abilities: StoredAbilities,
+    pub health: f32,
+    pub max_health: f32,
+    pub food_level: u8,
+    pub saturation: f32,
+    pub exhaustion: f32,
+    pub xp_progress: f32,
+    pub xp_level: u32,
+    pub xp_total: u32,
+    /// Indexed the way the player inventory window is, so the hotbar starts at 36
+    pub inventory: Vec<StoredItem>,
+    pub ender_chest: Vec<StoredItem>,
+    pub effects: Vec<StoredEffect>,
+}
+
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq)]
+pub struct StoredAbilities {
+    pub invulnerable: bool,
+    pub flying: bool,
+    pub may_fly: bool,
+    pub creative_mode: bool,
+    pub may_build: bool,
+    pub flying_speed: f32,
+    pub walking_speed: f32,
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq)]
+pub struct StoredItem {
+    pub slot: u16,
+    /// The item's protocol id
+    pub item: i32,
+    pub count: i32,
+    pub components_to_add: Vec<i32>,
+    pub components_to_remove: Vec<i32>,
+    /// The registry id and level of each of the item's enchantments
+    pub enchantments: Vec<(i32, i32)>,
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq)]
+pub struct StoredEffect {
+    /// The effect's identifier, e.g. `minecraft:speed`
+    pub id: String,
+    pub amplifier: u8,
+    pub duration_ticks: u32,
+}
+
+impl World {
+    pub fn save_player_data(&self, uuid: u128, data: &StoredPlayerData) -> Result<(), WorldError> {
+        if !self
+            .storage_backend
+            .table_exists(PLAYER_DATA_TABLE.to_string())?
+        {
+            self.storage_backend
+                .create_table(PLAYER_DATA_TABLE.to_string())?;
+        }
+        self.storage_backend.upsert(
+            PLAYER_DATA_TABLE.to_string(),
+            uuid,
+            encode_player_data(data),
+        )?;
+        Ok(())
+    }
+
+    /// Loads the data saved for a player, or `None` if they've never played here.
+    pub fn load_player_data(&self, uuid: u128) -> Result<Option<StoredPlayerData>, WorldError> {
+        if !self
+            .storage_backend
+            .table_exists(PLAYER_DATA_TABLE.to_string())?
+        {
+            return Ok(None);
+        }
+        self.storage_backend
+            .get(PLAYER_DATA_TABLE.to_string(), uuid)?
+            .map(|record| decode_player_data(&record))
+            .transpose()
+    }
+
+    /// Imports the `playerdata/<uuid>.dat` files of a vanilla world save, returning how many
+    /// players were imported. Files that can't be read are skipped.
+    pub(crate) fn import_player_data(&self, import_dir: &Path) -> Result<usize, WorldError> {
+        let player_dir = import_dir.join("playerdata");
+        if !player_dir.is_dir() {
+            return Ok(0);
+        }
+        let mut imported = 0;
+        for entry in player_dir.read_dir()? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|extension| extension != "dat") {
+                continue;
+            }
+            let Some(uuid) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(parse_uuid)
+            else {
+                warn!("Skipping player data file {}", path.display());
+                continue;
+            };
+            match read_vanilla_player_data(&path) {
+                Ok(data) => {
+                    self.save_player_data(uuid, &data)?;
+                    imported += 1;
+                }
+                Err(e) => error!("Failed to import {}: {}", path.display(), e),
+            }
+        }
+        info!("Imported data of {} players", imported);
+        Ok(imported)
+    }
+}
+
+fn encode_player_data(data: &StoredPlayerData) -> Vec<u8> {
+    let data = bitcode::encode(data);
+    let mut record = Vec::with_capacity(RECORD_HEADER_LEN + data.len());
+    record.push(RECORD_MAGIC);
+    record.extend_from_slice(&PLAYER_DATA_FORMAT_VERSION.to_be_bytes());
+    record.extend_from_slice(&data);
+    record
+}
+
+/// Decodes a record from the player data table, upgrading it first if it's older than the
+/// current format.
+fn decode_player_data(record: &[u8]) -> Result<StoredPlayerData, WorldError> {
+    let (version, data) = match record {
+        [RECORD_MAGIC, high, low, data @ ..] => (u16::from_be_bytes([*high, *low]), data),
+        [RECORD_MAGIC, ..] => {
+            return Err(WorldError::BitcodeDecodeError(
+                "Player data record is shorter than its header".to_string(),
+            ))
+        }
+        _ => (0, record),
+    };
+    if version > PLAYER_DATA_FORMAT_VERSION {
+        return Err(WorldError::UnsupportedPlayerDataVersion(version));
+    }
+    let mut data = Cow::Borrowed(data);
+    for migration in &MIGRATIONS[version as usize..] {
+        data = Cow::Owned(migration(&data)?);
+    }
+    decode(&data)
+}
+
+fn decode<'a, T: bitcode::Decode<'a>>(data: &'a [u8]) -> Result<T, WorldError> {
+    bitcode::decode(data).map_err(|e| WorldError::BitcodeDecodeError(e.to_string()))
+}
+
+/// Records from before versioning, the layout hasn't changed since.
+fn v0_to_v1(data: &[u8]) -> Result<Vec<u8>, WorldError> {
+    Ok(data.to_vec())
+}
+
+/// Player data from before item enchantments were stored.
+mod v1 {
+    use super::*;
+
+    #[derive(Encode, Decode)]
+    pub(super) struct StoredPlayerData {
+        pub game_mode: u8,
+        pub dimension: Dimension,
+        pub position: (f64, f64, f64),
+        pub rotation: (f32, f32),
+        pub abilities: StoredAbilities,
+        pub health: f32,
+        pub max_health: f32,
+        pub food_level: u8,
+        pub saturation: f32,
+        pub exhaustion: f32,
+        pub xp_progress: f32,
+        pub xp_level: u32,
+        pub xp_total: u32,
+        pub inventory: Vec<StoredItem>,
+        pub ender_chest: Vec<StoredItem>,
+        pub effects: Vec<StoredEffect>,
+    }
+
+    #[derive(Encode, Decode)]
+    pub(super) struct StoredItem {
+        pub slot: u16,
+        pub item: i32,
+        pub count: i32,
+        pub components_to_add: Vec<i32>,
+        pub components_to_remove: Vec<i32>,
+    }
+
+    impl From<StoredItem> for super::StoredItem {
+        fn from(item: StoredItem) -> Self {
+            Self {
+                slot: item.slot,
+                item: item.item,
+                count: item.count,
+                components_to_add: item.components_to_add,
+                components_to_remove: item.components_to_remove,
+                enchantments: Vec::new(),
+            }
+        }
+    }
+}
+
+fn v1_to_v2(data: &[u8]) -> Result<Vec<u8>, WorldError> {
+    let data: v1::StoredPlayerData = decode(data)?;
+    let items = |items: Vec<v1::StoredItem>| items.into_iter().map(Into::into).collect();
+    Ok(bitcode::encode(&StoredPlayerData {
+        game_mode: data.game_mode,
+        dimension: data.dimension,
+        position: data.position,
+        rotation: data.rotation,
+        abilities: data.abilities,
+        health: data.health,
+        max_health: data.max_health,
+        food_level: data.food_level,
+        saturation: data.saturation,
+        exhaustion: data.exhaustion,
+        xp_progress: data.xp_progress,
+        xp_level: data.xp_level,
+        xp_total: data.xp_total,
+        inventory: items(data.inventory),
+        ender_chest: items(data.ender_chest),
+        effects: data.effects,
+    }))
+}
+
+fn parse_uuid(uuid: &str) -> Option<u128> {
+    let hex = uuid.replace('-', "");
+    if hex.len() != 32 {
+        return None;
+    }
+    u128::from_str_radix(&hex, 16).ok()
+}
+
+fn read_vanilla_player_data(path: &Path) -> Result<StoredPlayerData, WorldError> {
+    let compressed = std::fs::read(path)?;
+    let mut data = Vec::new();
+    flate2::read::GzDecoder::new(compressed.as_slice()).read_to_end(&mut data)?;
+    Ok(VanillaPlayerData::from_bytes(&data)?.into())
+}
+
+#[derive(NBTDeserialize, Debug)]
+struct VanillaPlayerData {
+    #[nbt(rename = "Pos")]
+    pos: Option<Vec<f64>>,
+    #[nbt(rename = "Rotation")]
+    rotation: Option<Vec<f32>>,
+    #[nbt(rename = "Dimension")]
+    dimension: Option<String>,
+    #[nbt(rename = "playerGameType")]
+    game_type: Option<i32>,
+    #[nbt(rename = "Health")]
+    health: Option<f32>,
+    #[nbt(rename = "foodLevel")]
+    food_level: Option<i32>,
+    #[nbt(rename = "foodSaturationLevel")]
+    food_saturation: Option<f32>,
+    #[nbt(rename = "foodExhaustionLevel")]
+    food_exhaustion: Option<f32>,
+    #[nbt(rename = "XpP")]
+    xp_progress: Option<f32>,
+    #[nbt(rename = "XpLevel")]
+    xp_level: Option<i32>,
+    #[nbt(rename = "XpTotal")]
+    xp_total: Option<i32>,
+    #[nbt(rename = "Inventory")]
+    inventory: Option<Vec<VanillaItem>>,
+    #[nbt(rename = "EnderItems")]
+    ender_items: Option<Vec<VanillaItem>>,
+    equipment: Option<VanillaEquipment>,
+    abilities: Option<VanillaAbilities>,
+    active_effects: Option<Vec<VanillaEffect>>,
+}
+
+/// Every field is optional since a list element that fails to parse takes the whole file down.
+#[derive(NBTDeserialize, Debug)]
+struct VanillaItem {
+    #[nbt(rename = "Slot")]
+    slot: Option<i8>,
+    id: Option<String>,
+    count: Option<i32>,
+}
+
+/// Armour and the offhand item, which vanilla has kept out of `Inventory` since 1.21.5
+#[derive(NBTDeserialize, Debug)]
+struct VanillaEquipment {
+    head: Option<VanillaItem>,
+    chest: Option<VanillaItem>,
+    legs: Option<VanillaItem>,
+    feet: Option<VanillaItem>,
+    offhand: Option<VanillaItem>,
+}
+
+#[derive(NBTDeserialize, Debug)]
+struct VanillaAbilities {
+    invulnerable: Option<i8>,
+    flying: Option<i8>,
+    #[nbt(rename = "mayfly")]
+    may_fly: Option<i8>,
+    #[nbt(rename = "instabuild")]
+    instant_build: Option<i8>,
+    #[nbt(rename = "mayBuild")]
+    may_build: Option<i8>,
+    #[nbt(rename = "flySpeed")]
+    fly_speed: Option<f32>,
+    #[nbt(rename = "walkSpeed")]
+    walk_speed: Option<f32>,
+}
+
+#[derive(NBTDeserialize, Debug)]
+struct VanillaEffect {
+    id: Option<String>,
+    amplifier: Option<i8>,
+    duration: Option<i32>,
+}
+
+/// Maps a vanilla inventory slot to the player inventory window slot.
+fn inventory_window_slot(slot: i8) -> Option<u16> {
+    match slot {
+        0..=8 => Some(36 + slot as u16),
+        9..=35 => Some(slot as u16),
+        100..=103 => Some(108 - slot as u16),
+        -106 => Some(45),
+        _ => None,
+    }
+}
+
+impl VanillaItem {
+    fn to_stored(&self, slot: Option<u16>) -> Option<StoredItem> {
+        let slot = slot?;
+        let item = ferrumc_registry::lookup_item_protocol_id(self.id.as_deref()?)?;
+        Some(StoredItem {
+            slot,
+            item,
+            count: self.count.unwrap_or(1),
+            components_to_add: Vec::new(),
+            components_to_remove: Vec::new(),
+            enchantments: Vec::new(),
+        })
+    }
+}
+
+impl From<VanillaPlayerData> for StoredPlayerData {
+    fn from(value: VanillaPlayerData) -> Self {
+        let position = match value.pos.as_deref() {
+            Some(&[x, y, z]) => (x, y, z),
+            _ => (0.0, 0.0, 0.0),
+        };
+        let rotation = match value.rotation.as_deref() {
+            Some(&[yaw, pitch]) => (yaw, pitch),
+            _ => (0.0, 0.0),
+        };
+        let dimension = value
+            .dimension
+            .as_deref()
+            .and_then(Dimension::from_identifier)
+            .unwrap_or_default();
+
+        // Item components aren't supported yet, so items are imported without them
+        let mut inventory: Vec<StoredItem> = value
+            .inventory
+            .iter()
+            .flatten()
+            .filter_map(|item| item.to_stored(item.slot.and_then(inventory_window_slot)))
+            .collect();
+        if let Some(equipment) = &value.equipment {
+            let slots = [
+                (&equipment.head, 5),
+                (&equipment.chest, 6),
+                (&equipment.legs, 7),
+                (&equipment.feet, 8),
+                (&equipment.offhand, 45),
+            ];
+            inventory.extend(
+                slots
+                    .into_iter()
+                    .filter_map(|(item, slot)| item.as_ref()?.to_stored(Some(slot))),
+            );
+        }
+        let ender_chest = value
+            .ender_items
+            .iter()
+            .flatten()
+            .filter_map(|item| {
+                let slot = item.slot.and_then(|slot| u16::try_from(slot).ok());
+                item.to_stored(slot.filter(|&slot| slot < 27))
+            })
+            .collect();
+        let effects = value
+            .active_effects
+            .iter()
+            .flatten()
+            .filter_map(|effect| {
+                Some(StoredEffect {
+                    id: effect.id.clone()?,
+                    amplifier: effect.amplifier.unwrap_or_default() as u8,
+                    duration_ticks: effect.duration.unwrap_or_default().max(0) as u32,
+                })
+            })
+            .collect();
+
+        let game_mode = value
+            .game_type
+            .and_then(|game_type| u8::try_from(game_type).ok())
+            .filter(|&game_type| game_type <= 3)
+            .unwrap_or_default();
+        let abilities = value.abilities.as_ref();
+        let flag = |get: fn(&VanillaAbilities) -> Option<i8>, default: bool| {
+            abilities.and_then(get).map_or(default, |value| value != 0)
+        };
+
+        StoredPlayerData {
+            game_mode,
+            dimension,
+            position,
+            rotation,
+            abilities: StoredAbilities {
+                invulnerable: flag(|a| a.invulnerable, false),
+                flying: flag(|a| a.flying, false),
+                may_fly: flag(|a| a.may_fly, false),
+                creative_mode: flag(|a| a.instant_build, false),
+                may_build: flag(|a| a.may_build, true),
+                flying_speed: abilities.and_then(|a| a.fly_speed).unwrap_or(0.05),
+                walking_speed: abilities.and_then(|a| a.walk_speed).unwrap_or(0.1),
+            },
+            health: value.health.unwrap_or(20.0),
+            max_health: 20.0,
+            food_level: value.food_level.unwrap_or(20).clamp(0, 20) as u8,
+            saturation: value.food_saturation.unwrap_or(5.0),
+            exhaustion: value.food_exhaustion.unwrap_or_default(),
+            xp_progress: value.xp_progress.unwrap_or_default(),
+            xp_level: value.xp_level.unwrap_or_default().max(0) as u32,
+            xp_total: value.xp_total.unwrap_or_default().max(0) as u32,
+            inventory,
+            ender_chest,
+            effects,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ferrumc_nbt::{NBTSerializable, NBTSerializeOptions, RawCompound};
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    fn item(slot: Option<i8>, id: &str, count: i32) -> RawCompound {
+        let mut item = RawCompound::new();
+        if let Some(slot) = slot {
+            item.insert("Slot", &slot);
+        }
+        item.insert("id", &id);
+        item.insert("count", &count);
+        item
+    }
+
+    #[test]
+    fn test_import_vanilla_player_data() {
+        let mut player = RawCompound::new();
+        player.insert("Pos", &vec![12.5f64, 70.0, -3.25]);
+        player.insert("Rotation", &vec![90.0f32, -10.0]);
+        player.insert("Dimension", &"minecraft:the_nether");
+        player.insert("playerGameType", &1i32);
+        player.insert("Health", &13.0f32);
+        player.insert("foodLevel", &17i32);
+        player.insert("XpLevel", &5i32);
+        player.insert(
+            "Inventory",
+            &vec![
+                item(Some(0), "minecraft:stone", 64),
+                item(Some(20), "minecraft:dirt", 3),
+                item(Some(1), "minecraft:not_an_item", 1),
+            ],
+        );
+        let mut equipment = RawCompound::new();
+        equipment.insert("head", &item(None, "minecraft:diamond_helmet", 1));
+        player.insert("equipment", &equipment);
+        player.insert("EnderItems", &vec![item(Some(26), "minecraft:stone", 2)]);
+        let mut abilities = RawCompound::new();
+        abilities.insert("mayfly", &1i8);
+        abilities.insert("flying", &1i8);
+        abilities.insert("instabuild", &1i8);
+        player.insert("abilities", &abilities);
+
+        let dir = tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("playerdata")).unwrap();
+        let mut nbt = Vec::new();
+        player.serialize(&mut nbt, &NBTSerializeOptions::WithHeader(""));
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&nbt).unwrap();
+        let uuid = "4d2b3c8e-1f0a-4b6c-9d8e-7f6a5b4c3d2e";
+        std::fs::write(
+            dir.path().join("playerdata").join(format!("{uuid}.dat")),
+            encoder.finish().unwrap(),
+        )
+        .unwrap();
+
+        let data =
+            read_vanilla_player_data(&dir.path().join("playerdata").join(format!("{uuid}.dat")))
+                .unwrap();
+        assert_eq!(parse_uuid(uuid), Some(0x4d2b3c8e1f0a4b6c9d8e7f6a5b4c3d2e));
+        assert_eq!(data.position, (12.5, 70.0, -3.25));
+        assert_eq!(data.rotation, (90.0, -10.0));
+        assert_eq!(data.dimension, Dimension::Nether);
+        assert_eq!(data.game_mode, 1);
+        assert_eq!(data.health, 13.0);
+        assert_eq!(data.food_level, 17);
+        assert_eq!(data.xp_level, 5);
+        assert!(data.abilities.may_fly && data.abilities.flying && data.abilities.creative_mode);
+
+        let slots: Vec<_> = data
+            .inventory
+            .iter()
+            .map(|item| (item.slot, item.count))
+            .collect();
+        assert_eq!(slots, [(36, 64), (20, 3), (5, 1)]);
+        assert_eq!(
+            data.inventory[0].item,
+            ferrumc_registry::lookup_item_protocol_id("minecraft:stone").unwrap()
+        );
+        assert_eq!(data.ender_chest.len(), 1);
+        assert_eq!(data.ender_chest[0].slot, 26);
+    }
+
+    fn stored_player(game_mode: u8) -> StoredPlayerData {
+        StoredPlayerData {
+            game_mode,
+            dimension: Dimension::Nether,
+            position: (1.5, 64.0, -8.25),
+            rotation: (45.0, 10.0),
+            abilities: StoredAbilities {
+                invulnerable: false,
+                flying: false,
+                may_fly: false,
+                creative_mode: false,
+                may_build: true,
+                flying_speed: 0.05,
+                walking_speed: 0.1,
+            },
+            health: 17.0,
+            max_health: 20.0,
+            food_level: 12,
+            saturation: 1.5,
+            exhaustion: 0.25,
+            xp_progress: 0.5,
+            xp_level: 30,
+            xp_total: 1395,
+            inventory: vec![StoredItem {
+                slot: 36,
+                item: 1,
+                count: 64,
+                components_to_add: vec![10],
+                components_to_remove: Vec::new(),
+                enchantments: vec![(33, 1)],
+            }],
+            ender_chest: Vec::new(),
+            effects: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_player_data_roundtrip() {
+        let world = World::with_backend(ferrumc_storage::memory::MemoryBackend::new()).unwrap();
+        let data = stored_player(1);
+        world.save_player_data(7, &data).unwrap();
+        assert_eq!(world.load_player_data(7).unwrap(), Some(data));
+        assert_eq!(world.load_player_data(8).unwrap(), None);
+    }
+
+    /// Encodes player data the way it was stored before enchantments were, which is how it was
+    /// stored before versioning too.
+    fn encode_v1(data: &StoredPlayerData) -> Vec<u8> {
+        let items = |items: &[StoredItem]| {
+            items
+                .iter()
+                .map(|item| v1::StoredItem {
+                    slot: item.slot,
+                    item: item.item,
+                    count: item.count,
+                    components_to_add: item.components_to_add.clone(),
+                    components_to_remove: item.components_to_remove.clone(),
+                })
+                .collect()
+        };
+        bitcode::encode(&v1::StoredPlayerData {
+            game_mode: data.game_mode,
+            dimension: data.dimension,
+            position: data.position,
+            rotation: data.rotation,
+            abilities: data.abilities,
+            health: data.health,
+            max_health: data.max_health,
+            food_level: data.food_level,
+            saturation: data.saturation,
+            exhaustion: data.exhaustion,
+            xp_progress: data.xp_progress,
+            xp_level: data.xp_level,
+            xp_total: data.xp_total,
+            inventory: items(&data.inventory),
+            ender_chest: items(&data.ender_chest),
+            effects: data.effects.clone(),
+        })
+    }
+
+    #[test]
+    fn test_old_player_data_is_upgraded() {
+        for game_mode in 0..=3 {
+            let data = stored_player(game_mode);
+            let mut upgraded = data.clone();
+            upgraded.inventory[0].enchantments.clear();
+
+            let legacy = encode_v1(&data);
+            assert_ne!(legacy.first(), Some(&RECORD_MAGIC));
+            assert_eq!(decode_player_data(&legacy).unwrap(), upgraded);
+
+            let mut record = vec![RECORD_MAGIC];
+            record.extend_from_slice(&1u16.to_be_bytes());
+            record.extend_from_slice(&legacy);
+            assert_eq!(decode_player_data(&record).unwrap(), upgraded);
+        }
+
+        let mut record = encode_player_data(&stored_player(0));
+        record[1..3].copy_from_slice(&(PLAYER_DATA_FORMAT_VERSION + 1).to_be_bytes());
+        assert!(matches!(
+            decode_player_data(&record),
+            Err(WorldError::UnsupportedPlayerDataVersion(_))
+        ));
+    }
+}
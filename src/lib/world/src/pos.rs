@@ -13,7 +13,7 @@ use bevy_math::Vec3Swizzles;
 use bevy_math::{DVec3, I16Vec3};
 use ferrumc_net_codec::net_types::network_position::NetworkPosition;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct BlockPos {
     /// (i26, i12, i26)
     pub pos: IVec3,
@@ -44,7 +44,7 @@ impl BlockPos {
 
     pub fn section(&self) -> SectionPos {
         SectionPos {
-            pos: self.pos.div_euclid((16, 16, 16).into()) * 16,
+            pos: self.pos.div_euclid((16, 16, 16).into()),
         }
     }
 
@@ -168,6 +168,14 @@ impl ChunkPos {
     pub fn pack(&self) -> u64 {
         (((self.z() as u64) & ((1 << 22) - 1)) << 22) | ((self.x() as u64) & ((1 << 22) - 1))
     }
+
+    /// The inverse of [`ChunkPos::pack`]. Bits above the packed coordinates are ignored.
+    pub fn unpack(packed: u64) -> Self {
+        // Shift each 22 bit coordinate to the top of an i32 and back down to sign extend it
+        let x = ((packed & ((1 << 22) - 1)) as i32) << 10 >> 10;
+        let z = (((packed >> 22) & ((1 << 22) - 1)) as i32) << 10 >> 10;
+        Self::new(x, z)
+    }
 }
 
 impl Display for ChunkPos {
@@ -262,6 +270,18 @@ impl ChunkBlockPos {
     pub fn section(&self) -> i8 {
         self.pos.y.div_euclid(16) as i8
     }
+
+    pub fn x(&self) -> u8 {
+        self.pos.x as u8
+    }
+
+    pub fn y(&self) -> i16 {
+        self.pos.y
+    }
+
+    pub fn z(&self) -> u8 {
+        self.pos.z as u8
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -299,6 +319,8 @@ impl From<IVec2> for ColumnPos {
     }
 }
 
+/// The position of a 16x16x16 section, in sections.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct SectionPos {
     pos: IVec3,
 }
@@ -307,6 +329,14 @@ impl SectionPos {
     pub fn chunk(&self) -> ChunkPos {
         ChunkPos { pos: self.pos.xz() }
     }
+
+    /// Packed representation as used by the protocol: x (i22), z (i22), y (i20) from most to
+    /// least significant bits.
+    pub fn pack(&self) -> i64 {
+        ((self.pos.x as i64 & 0x3FFFFF) << 42)
+            | ((self.pos.z as i64 & 0x3FFFFF) << 20)
+            | (self.pos.y as i64 & 0xFFFFF)
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -0,0 +1,552 @@
+//! Random block ticks.
+//!
+//! Every game tick vanilla picks a few random blocks in each loaded section (the `randomTickSpeed`
+//! gamerule, 3 by default) and lets them do something slow: crops grow, grass spreads onto dirt
+//! and dies when covered, leaves cut off from their logs decay and ice and snow melt near light.
+//! [`World::random_tick_chunk`] does the same for one chunk. Only a few blocks do anything when
+//! ticked, so sections without any of them are skipped without picking blocks at all.
+
+use crate::block_state_id::{BlockStateId, ID2BLOCK};
+use crate::chunk_format::{PaletteType, Section};
+use crate::dimension::Dimension;
+use crate::errors::WorldError;
+use crate::lighting::{LightType, MAX_LIGHT};
+use crate::pos::{BlockPos, ChunkBlockPos, ChunkPos};
+use crate::World;
+use ahash::AHashMap;
+use bevy_math::IVec3;
+use ferrumc_macros::{block, match_block};
+use ferrumc_net_codec::net_types::var_int::VarInt;
+use ferrumc_threadpool::ThreadPool;
+use lazy_static::lazy_static;
+use rand::Rng;
+use tracing::error;
+
+/// Crops only grow with at least this much light above them, and grass only spreads from it.
+const MIN_GROWTH_LIGHT: u8 = 9;
+
+/// Ice and snow melt once block light around them goes over this.
+const MELT_LIGHT: u8 = 11;
+
+/// What a block does when it is randomly ticked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RandomTick {
+    /// Grows one `age` at a time up to `max_age`.
+    Crop {
+        max_age: u8,
+    },
+    /// Dies back to dirt when covered, and otherwise spreads onto dirt nearby.
+    Grass,
+    /// Leaves too far from a log.
+    DecayingLeaves,
+    Ice,
+    Snow,
+}
+
+lazy_static! {
+    static ref RANDOM_TICKS: Vec<Option<RandomTick>> = (0..ID2BLOCK.len())
+        .map(|id| random_tick(BlockStateId::new(id as u32)))
+        .collect();
+}
+
+fn random_tick(block: BlockStateId) -> Option<RandomTick> {
+    if match_block!("wheat", block)
+        || match_block!("carrots", block)
+        || match_block!("potatoes", block)
+    {
+        Some(RandomTick::Crop { max_age: 7 })
+    } else if match_block!("beetroots", block) {
+        Some(RandomTick::Crop { max_age: 3 })
+    } else if match_block!("grass_block", block) || match_block!("mycelium", block) {
+        Some(RandomTick::Grass)
+    } else if is_leaves(block) {
+        // Leaves keep track of how far the nearest log is, only the furthest ones ever decay
+        (block.get_property_as("persistent") == Some(false)
+            && block.get_property_as("distance") == Some(7u8))
+        .then_some(RandomTick::DecayingLeaves)
+    } else if match_block!("ice", block) {
+        Some(RandomTick::Ice)
+    } else if match_block!("snow", block) {
+        Some(RandomTick::Snow)
+    } else {
+        None
+    }
+}
+
+fn is_leaves(block: BlockStateId) -> bool {
+    match_block!("oak_leaves", block)
+        || match_block!("spruce_leaves", block)
+        || match_block!("birch_leaves", block)
+        || match_block!("jungle_leaves", block)
+        || match_block!("acacia_leaves", block)
+        || match_block!("cherry_leaves", block)
+        || match_block!("dark_oak_leaves", block)
+        || match_block!("pale_oak_leaves", block)
+        || match_block!("mangrove_leaves", block)
+        || match_block!("azalea_leaves", block)
+        || match_block!("flowering_azalea_leaves", block)
+}
+
+impl Section {
+    /// Whether any block in this section's palette does something when randomly ticked.
+    fn ticks_randomly(&self) -> bool {
+        let ticks = |id: &VarInt| {
+            RANDOM_TICKS
+                .get(id.0 as usize)
+                .is_some_and(|tick| tick.is_some())
+        };
+        match &self.block_states.block_data {
+            PaletteType::Single(val) => ticks(val),
+            PaletteType::Indirect { palette, .. } => palette.iter().any(ticks),
+            PaletteType::Direct { .. } => true,
+        }
+    }
+}
+
+/// Reads and changes blocks around a random tick, which can reach into neighbouring chunks.
+struct TickView<'a> {
+    world: &'a World,
+    dimension: Dimension,
+}
+
+impl TickView<'_> {
+    fn block(&self, pos: IVec3) -> Option<BlockStateId> {
+        self.world
+            .get_block_and_fetch(BlockPos { pos }, self.dimension)
+            .ok()
+    }
+
+    fn light(&self, pos: IVec3, kind: LightType) -> u8 {
+        let pos = BlockPos { pos };
+        self.world
+            .load_chunk(pos.chunk(), self.dimension)
+            .ok()
+            .and_then(|chunk| chunk.get_light(kind, pos.chunk_block_pos()).ok())
+            .unwrap_or(0)
+    }
+
+    /// The brighter of the sky and block light at a position. There's no day and night cycle
+    /// yet, so sky light counts in full.
+    fn brightness(&self, pos: IVec3) -> u8 {
+        self.light(pos, LightType::Sky)
+            .max(self.light(pos, LightType::Block))
+    }
+
+    fn set(
+        &self,
+        pos: IVec3,
+        block: BlockStateId,
+        changed: &mut Vec<BlockPos>,
+    ) -> Result<(), WorldError> {
+        let pos = BlockPos { pos };
+        self.world.set_block_and_fetch(pos, self.dimension, block)?;
+        changed.push(pos);
+        Ok(())
+    }
+
+    fn tick(
+        &self,
+        pos: IVec3,
+        block: BlockStateId,
+        tick: RandomTick,
+        rng: &mut impl Rng,
+        changed: &mut Vec<BlockPos>,
+    ) -> Result<(), WorldError> {
+        match tick {
+            RandomTick::Crop { max_age } => {
+                // Beetroots grow a third slower than other crops
+                if match_block!("beetroots", block) && rng.random_range(0..3) == 0 {
+                    return Ok(());
+                }
+                let age: u8 = block.get_property_as("age").unwrap_or(max_age);
+                if age >= max_age || self.brightness(pos) < MIN_GROWTH_LIGHT {
+                    return Ok(());
+                }
+                let chance = (25.0 / self.growth_speed(pos, block)) as u32 + 1;
+                if rng.random_range(0..chance) == 0 {
+                    if let Some(grown) = block.with_property("age", age + 1) {
+                        self.set(pos, grown, changed)?;
+                    }
+                }
+            }
+            RandomTick::Grass => {
+                if !self.can_be_grass(pos) {
+                    return self.set(pos, block!("dirt"), changed);
+                }
+                if self.brightness(pos + IVec3::Y) < MIN_GROWTH_LIGHT {
+                    return Ok(());
+                }
+                let spread = if match_block!("mycelium", block) {
+                    block!("mycelium", {snowy: false})
+                } else {
+                    block!("grass_block", {snowy: false})
+                };
+                for _ in 0..4 {
+                    let target = pos
+                        + IVec3::new(
+                            rng.random_range(-1..=1),
+                            rng.random_range(-3..=1),
+                            rng.random_range(-1..=1),
+                        );
+                    if self.block(target) != Some(block!("dirt")) || !self.can_spread_to(target) {
+                        continue;
+                    }
+                    let snowy = self.block(target + IVec3::Y).is_some_and(is_snow);
+                    let grown = if snowy {
+                        spread.with_property("snowy", true).unwrap_or(spread)
+                    } else {
+                        spread
+                    };
+                    self.set(target, grown, changed)?;
+                }
+            }
+            RandomTick::DecayingLeaves => {
+                let left = if block.get_property_as("waterlogged") == Some(true) {
+                    block!("water", {level: 0})
+                } else {
+                    BlockStateId::default()
+                };
+                self.set(pos, left, changed)?;
+            }
+            RandomTick::Ice => {
+                let light = self.light(pos, LightType::Block);
+                if light <= MELT_LIGHT.saturating_sub(block.light_opacity()) {
+                    return Ok(());
+                }
+                // Nothing holds the water in place over a drop, and the nether boils it away
+                let below = self.block(pos - IVec3::Y).unwrap_or_default();
+                let melted = if self.dimension == Dimension::Nether || is_air(below) {
+                    BlockStateId::default()
+                } else {
+                    block!("water", {level: 0})
+                };
+                self.set(pos, melted, changed)?;
+            }
+            RandomTick::Snow => {
+                if self.light(pos, LightType::Block) > MELT_LIGHT {
+                    self.set(pos, BlockStateId::default(), changed)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// How fast a crop grows, from the farmland under and around it. Crops planted in solid
+    /// blocks or in full rows of the same crop grow at half speed, like vanilla.
+    fn growth_speed(&self, pos: IVec3, crop: BlockStateId) -> f32 {
+        let mut speed = 1.0;
+        for dx in -1..=1 {
+            for dz in -1..=1 {
+                let Some(soil) = self.block(pos + IVec3::new(dx, -1, dz)) else {
+                    continue;
+                };
+                if !match_block!("farmland", soil) {
+                    continue;
+                }
+                let mut gain = if soil
+                    .get_property_as::<u8>("moisture")
+                    .is_some_and(|m| m > 0)
+                {
+                    3.0
+                } else {
+                    1.0
+                };
+                if dx != 0 || dz != 0 {
+                    gain /= 4.0;
+                }
+                speed += gain;
+            }
+        }
+
+        let is_crop = |offset: IVec3| {
+            self.block(pos + offset)
+                .is_some_and(|block| block.is_same_block(crop))
+        };
+        let along_x = is_crop(IVec3::X) || is_crop(IVec3::NEG_X);
+        let along_z = is_crop(IVec3::Z) || is_crop(IVec3::NEG_Z);
+        let diagonal = [(1, 1), (1, -1), (-1, 1), (-1, -1)]
+            .into_iter()
+            .any(|(dx, dz)| is_crop(IVec3::new(dx, 0, dz)));
+        if diagonal || (along_x && along_z) {
+            speed /= 2.0;
+        }
+        speed
+    }
+
+    /// Grass can stay where light still gets through the block above it.
+    fn can_be_grass(&self, pos: IVec3) -> bool {
+        let Some(above) = self.block(pos + IVec3::Y) else {
+            return false;
+        };
+        if match_block!("snow", above) && above.get_property("layers") == Some("1") {
+            return true;
+        }
+        if above.fluid().is_some_and(|fluid| fluid.amount() == 8) {
+            return false;
+        }
+        above.light_opacity() < MAX_LIGHT
+    }
+
+    fn can_spread_to(&self, pos: IVec3) -> bool {
+        self.can_be_grass(pos)
+            && !self
+                .block(pos + IVec3::Y)
+                .and_then(|above| above.fluid())
+                .is_some()
+    }
+}
+
+fn is_snow(block: BlockStateId) -> bool {
+    match_block!("snow", block)
+        || match_block!("snow_block", block)
+        || match_block!("powder_snow", block)
+}
+
+pub(crate) fn is_air(block: BlockStateId) -> bool {
+    match_block!("air", block) || match_block!("cave_air", block) || match_block!("void_air", block)
+}
+
+impl World {
+    /// Randomly ticks `speed` blocks in every section of a chunk, the way vanilla's
+    /// `randomTickSpeed` does.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<BlockPos>)` - Every block that changed, which may include blocks in neighbouring
+    ///   chunks when grass spreads over a chunk border.
+    /// * `Err(WorldError)` - If the chunk couldn't be loaded or a changed block couldn't be set.
+    pub fn random_tick_chunk(
+        &self,
+        pos: ChunkPos,
+        dimension: Dimension,
+        speed: u32,
+        rng: &mut impl Rng,
+    ) -> Result<Vec<BlockPos>, WorldError> {
+        // Pick the blocks up front so the chunk isn't held while ticks edit it
+        let mut picked = Vec::new();
+        {
+            let chunk = self.load_chunk(pos, dimension)?;
+            for (index, section) in chunk.sections.iter().enumerate() {
+                if !section.ticks_randomly() {
+                    continue;
+                }
+                let min_y = chunk.min_y + index as i16 * 16;
+                for _ in 0..speed {
+                    let block_pos = ChunkBlockPos::new(
+                        rng.random_range(0..16),
+                        min_y + rng.random_range(0..16),
+                        rng.random_range(0..16),
+                    );
+                    let block = section.get_block(block_pos.section_block_pos())?;
+                    if let Some(tick) = RANDOM_TICKS.get(block.raw() as usize).copied().flatten() {
+                        picked.push((pos.chunk_block(block_pos), block, tick));
+                    }
+                }
+            }
+        }
+
+        let view = TickView {
+            world: self,
+            dimension,
+        };
+        let mut changed = Vec::new();
+        for (block_pos, block, tick) in picked {
+            view.tick(block_pos.pos, block, tick, rng, &mut changed)?;
+        }
+        Ok(changed)
+    }
+
+    /// Randomly ticks every given chunk on the thread pool. Chunks that fail to tick are logged
+    /// and skipped.
+    ///
+    /// Returns every block that changed, by dimension.
+    pub fn random_tick_chunks(
+        &self,
+        pool: &ThreadPool,
+        chunks: impl IntoIterator<Item = (Dimension, ChunkPos)>,
+        speed: u32,
+    ) -> AHashMap<Dimension, Vec<BlockPos>> {
+        let mut batch = pool.batch();
+        for (dimension, pos) in chunks {
+            let world = self.clone();
+            batch.execute(move || {
+                let result = world.random_tick_chunk(pos, dimension, speed, &mut rand::rng());
+                (dimension, pos, result)
+            });
+        }
+
+        let mut changed: AHashMap<Dimension, Vec<BlockPos>> = AHashMap::new();
+        for (dimension, pos, result) in batch.wait() {
+            match result {
+                Ok(blocks) if blocks.is_empty() => {}
+                Ok(blocks) => changed.entry(dimension).or_default().extend(blocks),
+                Err(err) => error!("Failed to random tick chunk {pos} in {dimension}: {err}"),
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_format::Chunk;
+    use crate::edit_batch::EditBatch;
+    use ferrumc_storage::memory::MemoryBackend;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::sync::Arc;
+
+    /// A single chunk with a dirt floor at y = 63.
+    fn dirt_world(dimension: Dimension) -> World {
+        let world = World::with_backend(MemoryBackend::new()).unwrap();
+        let mut chunk = Chunk::new(dimension.height());
+        let mut batch = EditBatch::new(&mut chunk);
+        for x in 0..16 {
+            for z in 0..16 {
+                batch.set_block(ChunkBlockPos::new(x, 63, z), block!("dirt"));
+            }
+        }
+        batch.apply().unwrap();
+        chunk.relight();
+        world
+            .save_chunk(ChunkPos::new(0, 0), dimension, Arc::new(chunk))
+            .unwrap();
+        world
+    }
+
+    fn set(world: &World, dimension: Dimension, x: i32, y: i32, z: i32, block: BlockStateId) {
+        world
+            .set_block_and_fetch(BlockPos::of(x, y, z), dimension, block)
+            .unwrap();
+    }
+
+    fn get(world: &World, dimension: Dimension, x: i32, y: i32, z: i32) -> BlockStateId {
+        world
+            .get_block_and_fetch(BlockPos::of(x, y, z), dimension)
+            .unwrap()
+    }
+
+    /// Random ticks the chunk at full speed until `done` or a few thousand ticks have passed.
+    fn tick_until(world: &World, dimension: Dimension, mut done: impl FnMut(&World) -> bool) {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..4000 {
+            if done(world) {
+                return;
+            }
+            world
+                .random_tick_chunk(ChunkPos::new(0, 0), dimension, 64, &mut rng)
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_only_ticking_blocks_are_picked() {
+        assert_eq!(random_tick(block!("stone")), None);
+        assert_eq!(
+            random_tick(block!("wheat", {age: 3})),
+            Some(RandomTick::Crop { max_age: 7 })
+        );
+        assert_eq!(
+            random_tick(block!("oak_leaves", {distance: 7, persistent: false, waterlogged: false})),
+            Some(RandomTick::DecayingLeaves)
+        );
+        assert_eq!(
+            random_tick(block!("oak_leaves", {distance: 7, persistent: true, waterlogged: false})),
+            None
+        );
+        assert_eq!(
+            random_tick(block!("oak_leaves", {distance: 2, persistent: false, waterlogged: false})),
+            None
+        );
+
+        // Nothing in a plain dirt chunk ticks
+        let world = dirt_world(Dimension::Overworld);
+        let mut rng = StdRng::seed_from_u64(1);
+        let changed = world
+            .random_tick_chunk(ChunkPos::new(0, 0), Dimension::Overworld, 4096, &mut rng)
+            .unwrap();
+        assert!(changed.is_empty());
+    }
+
+    #[test]
+    fn test_crops_grow_in_light() {
+        let world = dirt_world(Dimension::Overworld);
+        let dim = Dimension::Overworld;
+        set(&world, dim, 4, 63, 4, block!("farmland", {moisture: 7}));
+        set(&world, dim, 4, 64, 4, block!("wheat", {age: 0}));
+        // Covered up, a crop gets no light and never grows
+        set(&world, dim, 10, 63, 10, block!("farmland", {moisture: 7}));
+        set(&world, dim, 10, 64, 10, block!("carrots", {age: 0}));
+        set(&world, dim, 10, 65, 10, block!("stone"));
+        for x in 9..=11 {
+            for z in 9..=11 {
+                if (x, z) != (10, 10) {
+                    for y in 64..=65 {
+                        set(&world, dim, x, y, z, block!("stone"));
+                    }
+                }
+            }
+        }
+
+        tick_until(&world, dim, |world| {
+            get(world, dim, 4, 64, 4) == block!("wheat", {age: 7})
+        });
+        assert_eq!(get(&world, dim, 4, 64, 4), block!("wheat", {age: 7}));
+        assert_eq!(get(&world, dim, 10, 64, 10), block!("carrots", {age: 0}));
+    }
+
+    #[test]
+    fn test_grass_spreads_and_dies_when_covered() {
+        let world = dirt_world(Dimension::Overworld);
+        let dim = Dimension::Overworld;
+        set(&world, dim, 8, 63, 8, block!("grass_block", {snowy: false}));
+        set(&world, dim, 0, 63, 0, block!("grass_block", {snowy: false}));
+        set(&world, dim, 0, 64, 0, block!("stone"));
+
+        tick_until(&world, dim, |world| {
+            get(world, dim, 9, 63, 8) == block!("grass_block", {snowy: false})
+        });
+        assert_eq!(
+            get(&world, dim, 9, 63, 8),
+            block!("grass_block", {snowy: false})
+        );
+        assert_eq!(get(&world, dim, 0, 63, 0), block!("dirt"));
+    }
+
+    #[test]
+    fn test_leaves_decay_and_ice_melts() {
+        let world = dirt_world(Dimension::Overworld);
+        let dim = Dimension::Overworld;
+        let decaying = block!("oak_leaves", {distance: 7, persistent: false, waterlogged: false});
+        let kept = block!("oak_leaves", {distance: 7, persistent: true, waterlogged: false});
+        set(&world, dim, 2, 64, 2, decaying);
+        set(&world, dim, 5, 64, 5, kept);
+        set(&world, dim, 8, 64, 8, block!("ice"));
+        set(&world, dim, 9, 64, 8, block!("glowstone"));
+        set(&world, dim, 12, 64, 12, block!("ice"));
+
+        tick_until(&world, dim, |world| {
+            get(world, dim, 2, 64, 2) == BlockStateId::default()
+                && get(world, dim, 8, 64, 8) != block!("ice")
+        });
+        assert_eq!(get(&world, dim, 2, 64, 2), BlockStateId::default());
+        assert_eq!(get(&world, dim, 5, 64, 5), kept);
+        assert_eq!(get(&world, dim, 8, 64, 8), block!("water", {level: 0}));
+        // Away from the glowstone it's too dark for the ice to melt
+        assert_eq!(get(&world, dim, 12, 64, 12), block!("ice"));
+    }
+
+    #[test]
+    fn test_ice_evaporates_in_the_nether() {
+        let world = dirt_world(Dimension::Nether);
+        let dim = Dimension::Nether;
+        set(&world, dim, 8, 64, 8, block!("ice"));
+        set(&world, dim, 9, 64, 8, block!("glowstone"));
+
+        tick_until(&world, dim, |world| {
+            get(world, dim, 8, 64, 8) != block!("ice")
+        });
+        assert_eq!(get(&world, dim, 8, 64, 8), BlockStateId::default());
+    }
+}
@@ -0,0 +1,1338 @@
+//! Redstone.
+//!
+//! Power comes from levers, buttons, pressure plates, redstone torches and redstone blocks, and
+//! reaches the blocks next to them either straight away or through a solid block they power. Wire
+//! carries it on, losing a level for every block, repeaters and comparators pass it along after a
+//! delay, and lamps, doors, trapdoors and pistons react to it.
+//!
+//! It all runs off block updates and scheduled ticks, the same way vanilla does: wire, doors and
+//! lamps turning on change as soon as their power does, while torches, repeaters, comparators,
+//! pistons and lamps turning off wait for their tick. Ticks run in a fixed order (see
+//! [`World::run_scheduled_ticks`]), so a circuit always does the same thing.
+//!
+//! Some of vanilla is left out. Torches don't burn out, comparators don't read containers, pistons
+//! move blocks in one go rather than over two ticks and don't drag slime or honey along, and
+//! nothing is powered by quasi-connectivity.
+
+use crate::block_entity::BlockEntityKind;
+use crate::block_properties::PropertyValue;
+use crate::block_state_id::{BlockStateId, ID2BLOCK};
+use crate::block_updates::{facing, is_free, name, needs_support};
+use crate::chunk_format::{PaletteType, Section};
+use crate::dimension::Dimension;
+use crate::errors::WorldError;
+use crate::lighting::{property, MAX_LIGHT};
+use crate::pos::BlockPos;
+use crate::scheduled_ticks::{TickKind, TickRequest};
+use crate::vanilla_chunk_format::BlockData;
+use crate::World;
+use bevy_math::IVec3;
+use ferrumc_macros::block;
+use ferrumc_nbt::RawCompound;
+use ferrumc_net_codec::net_types::var_int::VarInt;
+use lazy_static::lazy_static;
+
+const DIRECTIONS: [IVec3; 6] = [
+    IVec3::NEG_Y,
+    IVec3::Y,
+    IVec3::NEG_Z,
+    IVec3::Z,
+    IVec3::NEG_X,
+    IVec3::X,
+];
+
+/// The sides of a block wire can connect to, with the property that holds the connection.
+const WIRE_SIDES: [(&str, IVec3); 4] = [
+    ("north", IVec3::NEG_Z),
+    ("south", IVec3::Z),
+    ("west", IVec3::NEG_X),
+    ("east", IVec3::X),
+];
+
+pub const MAX_POWER: u8 = 15;
+
+/// Game ticks between the block a torch is on changing and the torch changing.
+const TORCH_DELAY: u32 = 2;
+const COMPARATOR_DELAY: u32 = 2;
+/// Lamps turn on straight away, but only turn off a few game ticks after losing power.
+const LAMP_OFF_DELAY: u32 = 4;
+/// Pistons move as soon as the scheduled ticks next run.
+const PISTON_DELAY: u32 = 0;
+/// The most blocks a piston can push.
+const PUSH_LIMIT: usize = 12;
+
+/// Blocks pistons can't move.
+const IMMOVABLE_BLOCKS: &[&str] = &[
+    "obsidian",
+    "crying_obsidian",
+    "respawn_anchor",
+    "bedrock",
+    "barrier",
+    "end_portal_frame",
+    "end_portal",
+    "end_gateway",
+    "reinforced_deepslate",
+    "enchanting_table",
+    "ender_chest",
+    "beacon",
+    "jukebox",
+    "spawner",
+    "hopper",
+    "dispenser",
+    "dropper",
+    "brewing_stand",
+    "lectern",
+    "barrel",
+    "command_block",
+    "chain_command_block",
+    "repeating_command_block",
+    "structure_block",
+    "jigsaw",
+    "piston_head",
+    "moving_piston",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Plate {
+    /// Only pressed by players and mobs.
+    Stone,
+    Wooden,
+    /// Gives off one level of power for every thing on it.
+    LightWeighted,
+    /// Gives off one level of power for every ten things on it.
+    HeavyWeighted,
+}
+
+impl Plate {
+    fn power(self, pressed_by: usize) -> u8 {
+        let power = match self {
+            Plate::Stone | Plate::Wooden => MAX_POWER as usize,
+            Plate::LightWeighted => pressed_by,
+            Plate::HeavyWeighted => pressed_by.div_ceil(10),
+        };
+        power.min(MAX_POWER as usize) as u8
+    }
+
+    /// Game ticks between checks for whether the plate is still being pressed.
+    fn delay(self) -> u32 {
+        match self {
+            Plate::Stone | Plate::Wooden => 20,
+            Plate::LightWeighted | Plate::HeavyWeighted => 10,
+        }
+    }
+}
+
+/// A block that gives off or reacts to power.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Component {
+    Wire,
+    RedstoneBlock,
+    /// `attached` points at the block the torch is on.
+    Torch {
+        attached: IVec3,
+    },
+    Lever {
+        attached: IVec3,
+    },
+    /// `ticks` is how long the button stays pressed.
+    Button {
+        attached: IVec3,
+        ticks: u32,
+    },
+    PressurePlate(Plate),
+    /// Repeaters and comparators take power in from the block `facing` points at, and give it out
+    /// on the opposite side.
+    Repeater {
+        facing: IVec3,
+    },
+    Comparator {
+        facing: IVec3,
+    },
+    Lamp,
+    /// Only wooden and copper doors and trapdoors can be opened by hand.
+    Door {
+        by_hand: bool,
+    },
+    Trapdoor {
+        by_hand: bool,
+    },
+    Piston {
+        facing: IVec3,
+        sticky: bool,
+    },
+}
+
+lazy_static! {
+    static ref COMPONENTS: Vec<Option<Component>> = ID2BLOCK.iter().map(component).collect();
+}
+
+/// Which block a lever or button is on, from its `face` and `facing`.
+fn attached_to(data: &BlockData) -> Option<IVec3> {
+    match property(data, "face")? {
+        "floor" => Some(IVec3::NEG_Y),
+        "ceiling" => Some(IVec3::Y),
+        _ => facing(data).map(|dir| -dir),
+    }
+}
+
+fn component(data: &BlockData) -> Option<Component> {
+    let name = name(data);
+    Some(match name {
+        "redstone_wire" => Component::Wire,
+        "redstone_block" => Component::RedstoneBlock,
+        "redstone_torch" => Component::Torch {
+            attached: IVec3::NEG_Y,
+        },
+        "redstone_wall_torch" => Component::Torch {
+            attached: -facing(data)?,
+        },
+        "lever" => Component::Lever {
+            attached: attached_to(data)?,
+        },
+        _ if name.ends_with("_button") => Component::Button {
+            attached: attached_to(data)?,
+            ticks: if matches!(name, "stone_button" | "polished_blackstone_button") {
+                20
+            } else {
+                30
+            },
+        },
+        "stone_pressure_plate" | "polished_blackstone_pressure_plate" => {
+            Component::PressurePlate(Plate::Stone)
+        }
+        "light_weighted_pressure_plate" => Component::PressurePlate(Plate::LightWeighted),
+        "heavy_weighted_pressure_plate" => Component::PressurePlate(Plate::HeavyWeighted),
+        _ if name.ends_with("_pressure_plate") => Component::PressurePlate(Plate::Wooden),
+        "repeater" => Component::Repeater {
+            facing: facing(data)?,
+        },
+        "comparator" => Component::Comparator {
+            facing: facing(data)?,
+        },
+        "redstone_lamp" => Component::Lamp,
+        "iron_door" => Component::Door { by_hand: false },
+        _ if name.ends_with("_door") => Component::Door { by_hand: true },
+        "iron_trapdoor" => Component::Trapdoor { by_hand: false },
+        _ if name.ends_with("_trapdoor") => Component::Trapdoor { by_hand: true },
+        "piston" | "sticky_piston" => Component::Piston {
+            facing: facing(data)?,
+            sticky: name == "sticky_piston",
+        },
+        _ => return None,
+    })
+}
+
+fn component_of(block: BlockStateId) -> Option<Component> {
+    COMPONENTS.get(block.raw() as usize).copied().flatten()
+}
+
+/// Whether power passes through the block: solid blocks that aren't redstone components
+/// themselves, plus lamps.
+fn is_conductor(block: BlockStateId) -> bool {
+    match component_of(block) {
+        None | Some(Component::Lamp) => block.light_opacity() == MAX_LIGHT,
+        Some(_) => false,
+    }
+}
+
+fn is_on(block: BlockStateId, key: &str) -> bool {
+    block.get_property_as(key) == Some(true)
+}
+
+fn level(block: BlockStateId, key: &str) -> u8 {
+    block.get_property_as(key).unwrap_or(0)
+}
+
+/// The same block with a property changed, or the block as it is if it doesn't have it.
+fn with(block: BlockStateId, key: &str, value: impl PropertyValue) -> BlockStateId {
+    block.with_property(key, value).unwrap_or(block)
+}
+
+fn facing_name(dir: IVec3) -> &'static str {
+    match (dir.x, dir.y, dir.z) {
+        (0, 0, -1) => "north",
+        (0, 0, 1) => "south",
+        (-1, 0, 0) => "west",
+        (1, 0, 0) => "east",
+        (0, 1, 0) => "up",
+        _ => "down",
+    }
+}
+
+fn plate_power(block: BlockStateId, plate: Plate) -> u8 {
+    match plate {
+        Plate::LightWeighted | Plate::HeavyWeighted => level(block, "power"),
+        Plate::Stone | Plate::Wooden if is_on(block, "powered") => MAX_POWER,
+        Plate::Stone | Plate::Wooden => 0,
+    }
+}
+
+fn with_plate_power(block: BlockStateId, plate: Plate, power: u8) -> BlockStateId {
+    match plate {
+        Plate::LightWeighted | Plate::HeavyWeighted => with(block, "power", power),
+        Plate::Stone | Plate::Wooden => with(block, "powered", power > 0),
+    }
+}
+
+/// The two sides of a repeater or comparator that aren't its input or output.
+fn sides(facing: IVec3) -> [IVec3; 2] {
+    [
+        IVec3::new(-facing.z, 0, facing.x),
+        IVec3::new(facing.z, 0, -facing.x),
+    ]
+}
+
+fn is_diode(component: Option<Component>) -> Option<IVec3> {
+    match component? {
+        Component::Repeater { facing } | Component::Comparator { facing } => Some(facing),
+        _ => None,
+    }
+}
+
+impl Section {
+    /// Whether any block in this section's palette is a redstone component.
+    fn has_redstone(&self) -> bool {
+        let is_component = |id: &VarInt| {
+            COMPONENTS
+                .get(id.0 as usize)
+                .is_some_and(|component| component.is_some())
+        };
+        match &self.block_states.block_data {
+            PaletteType::Single(val) => is_component(val),
+            PaletteType::Indirect { palette, .. } => palette.iter().any(is_component),
+            PaletteType::Direct { .. } => true,
+        }
+    }
+}
+
+/// What a redstone component does about a block update.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RedstoneUpdate {
+    /// Changes straight away, into this block.
+    Set(BlockStateId),
+    /// Changes when this tick runs.
+    Tick(TickRequest),
+}
+
+/// Reads and changes the blocks in a circuit. Blocks in chunks that don't exist read as `None`,
+/// which gives off no power.
+struct RedstoneView<'a> {
+    world: &'a World,
+    dimension: Dimension,
+}
+
+impl RedstoneView<'_> {
+    fn block(&self, pos: IVec3) -> Option<BlockStateId> {
+        self.world
+            .get_block_and_fetch(BlockPos { pos }, self.dimension)
+            .ok()
+    }
+
+    fn component(&self, pos: IVec3) -> Option<(BlockStateId, Component)> {
+        let block = self.block(pos)?;
+        Some((block, component_of(block)?))
+    }
+
+    fn set(
+        &self,
+        pos: IVec3,
+        block: BlockStateId,
+        changed: &mut Vec<BlockPos>,
+    ) -> Result<(), WorldError> {
+        let pos = BlockPos { pos };
+        self.world.set_block_and_fetch(pos, self.dimension, block)?;
+        changed.push(pos);
+        Ok(())
+    }
+
+    fn stored_output(&self, pos: IVec3) -> u8 {
+        self.world
+            .get_block_entity(BlockPos { pos }, self.dimension)
+            .ok()
+            .flatten()
+            .and_then(|block_entity| block_entity.data().get::<i32>("OutputSignal"))
+            .map_or(0, |output| output.clamp(0, MAX_POWER as i32) as u8)
+    }
+
+    fn store_output(&self, pos: IVec3, output: u8) -> Result<(), WorldError> {
+        let mut data = RawCompound::new();
+        data.insert("OutputSignal", &i32::from(output));
+        self.world
+            .set_block_entity_data(BlockPos { pos }, self.dimension, data)
+    }
+
+    /// The power the component at `pos` gives the block next to it in direction `towards`: what
+    /// that block reads straight from it, and what it puts into that block if it's a conductor.
+    fn emitted(
+        &self,
+        pos: IVec3,
+        block: BlockStateId,
+        component: Component,
+        towards: IVec3,
+    ) -> (u8, u8) {
+        match component {
+            Component::RedstoneBlock => (MAX_POWER, 0),
+            Component::Lever { attached } | Component::Button { attached, .. }
+                if is_on(block, "powered") =>
+            {
+                (MAX_POWER, if towards == attached { MAX_POWER } else { 0 })
+            }
+            // A torch doesn't power the block it's on, but does power the block above it through
+            Component::Torch { attached } if is_on(block, "lit") => (
+                if towards == attached { 0 } else { MAX_POWER },
+                if towards == IVec3::Y { MAX_POWER } else { 0 },
+            ),
+            Component::PressurePlate(plate) => {
+                let power = plate_power(block, plate);
+                (power, if towards == IVec3::NEG_Y { power } else { 0 })
+            }
+            Component::Repeater { facing } if towards == -facing && is_on(block, "powered") => {
+                (MAX_POWER, MAX_POWER)
+            }
+            Component::Comparator { facing } if towards == -facing => {
+                let output = self.stored_output(pos);
+                (output, output)
+            }
+            Component::Wire => {
+                let points = towards == IVec3::NEG_Y
+                    || WIRE_SIDES.iter().any(|(key, dir)| {
+                        *dir == towards && block.get_property(key).is_some_and(|v| v != "none")
+                    });
+                let power = if points { level(block, "power") } else { 0 };
+                (power, power)
+            }
+            _ => (0, 0),
+        }
+    }
+
+    /// The power reaching `target` from the block next to it in direction `dir`. Wire doesn't
+    /// count when working out the power of other wire, which has its own rules.
+    fn signal_into(&self, target: IVec3, dir: IVec3, for_wire: bool) -> u8 {
+        let from = target + dir;
+        let Some(block) = self.block(from) else {
+            return 0;
+        };
+        if is_conductor(block) {
+            // A conductor passes on what's put into it from every other side
+            return DIRECTIONS
+                .iter()
+                .filter(|side| **side != -dir)
+                .filter_map(|side| {
+                    let (block, component) = self.component(from + *side)?;
+                    if for_wire && component == Component::Wire {
+                        return None;
+                    }
+                    Some(self.emitted(from + *side, block, component, -*side).1)
+                })
+                .max()
+                .unwrap_or(0);
+        }
+        match component_of(block) {
+            Some(Component::Wire) if for_wire => 0,
+            Some(component) => self.emitted(from, block, component, -dir).0,
+            None => 0,
+        }
+    }
+
+    fn power(&self, pos: IVec3, for_wire: bool) -> u8 {
+        DIRECTIONS
+            .iter()
+            .map(|dir| self.signal_into(pos, *dir, for_wire))
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn is_wire(&self, pos: IVec3) -> bool {
+        self.component(pos)
+            .is_some_and(|(_, component)| component == Component::Wire)
+    }
+
+    fn wire_level(&self, pos: IVec3) -> u8 {
+        match self.component(pos) {
+            Some((block, Component::Wire)) => level(block, "power"),
+            _ => 0,
+        }
+    }
+
+    fn is_conductor_at(&self, pos: IVec3) -> bool {
+        self.block(pos).is_some_and(is_conductor)
+    }
+
+    /// The power wire at `pos` should have: whatever reaches it from anything other than wire,
+    /// or one less than the wire next to it, whichever is more. Wire steps up and down blocks,
+    /// unless a conductor is in the way.
+    fn wire_power(&self, pos: IVec3) -> u8 {
+        let mut power = self.power(pos, true);
+        let above_conductor = self.is_conductor_at(pos + IVec3::Y);
+        for (_, dir) in WIRE_SIDES {
+            if power == MAX_POWER {
+                break;
+            }
+            let side = pos + dir;
+            let mut neighbour = self.wire_level(side);
+            if self.is_conductor_at(side) {
+                if !above_conductor {
+                    neighbour = neighbour.max(self.wire_level(side + IVec3::Y));
+                }
+            } else {
+                neighbour = neighbour.max(self.wire_level(side - IVec3::Y));
+            }
+            power = power.max(neighbour.saturating_sub(1));
+        }
+        power
+    }
+
+    /// Whether wire connects to the block next to it in direction `dir`.
+    fn connects(&self, pos: IVec3, dir: IVec3) -> bool {
+        match self.component(pos + dir) {
+            Some((_, Component::Repeater { facing })) => facing == dir || facing == -dir,
+            Some((
+                _,
+                Component::Wire
+                | Component::RedstoneBlock
+                | Component::Torch { .. }
+                | Component::Lever { .. }
+                | Component::Button { .. }
+                | Component::PressurePlate(_)
+                | Component::Comparator { .. },
+            )) => true,
+            _ => false,
+        }
+    }
+
+    /// The wire at `pos` with its power and the sides it connects to worked out, or `None` if
+    /// it's already right.
+    fn wire_state(&self, pos: IVec3, block: BlockStateId) -> Option<BlockStateId> {
+        let power = self.wire_power(pos);
+        let above_conductor = self.is_conductor_at(pos + IVec3::Y);
+        let mut sides = WIRE_SIDES.map(|(_, dir)| {
+            let side = pos + dir;
+            let side_conductor = self.is_conductor_at(side);
+            if !above_conductor && side_conductor && self.is_wire(side + IVec3::Y) {
+                "up"
+            } else if self.connects(pos, dir) || (!side_conductor && self.is_wire(side - IVec3::Y))
+            {
+                "side"
+            } else {
+                "none"
+            }
+        });
+        // Lone wire is a cross, and wire that only connects on one side runs straight through
+        match sides.iter().filter(|side| **side != "none").count() {
+            0 => sides = ["side"; 4],
+            1 => {
+                let connected = sides.iter().position(|side| *side != "none").unwrap_or(0);
+                sides[connected ^ 1] = "side";
+            }
+            _ => {}
+        }
+
+        let current = level(block, "power") == power
+            && WIRE_SIDES
+                .iter()
+                .zip(sides)
+                .all(|((key, _), side)| block.get_property(key) == Some(side));
+        if current {
+            return None;
+        }
+        let mut wire = with(block, "power", power);
+        for ((key, _), side) in WIRE_SIDES.iter().zip(sides) {
+            wire = with(wire, key, side);
+        }
+        Some(wire)
+    }
+
+    /// The power going into the back of a repeater or comparator.
+    fn diode_input(&self, pos: IVec3, facing: IVec3) -> u8 {
+        self.signal_into(pos, facing, false)
+            .max(self.wire_level(pos + facing))
+    }
+
+    /// The power going into the sides of a comparator, or whether a repeater is locked. Only
+    /// wire, redstone blocks and other repeaters and comparators count.
+    fn side_input(&self, pos: IVec3, facing: IVec3, diodes_only: bool) -> u8 {
+        sides(facing)
+            .into_iter()
+            .map(|side| match self.component(pos + side) {
+                Some((_, Component::RedstoneBlock)) if !diodes_only => MAX_POWER,
+                Some((block, Component::Wire)) if !diodes_only => level(block, "power"),
+                Some((block, component)) if is_diode(Some(component)).is_some() => {
+                    self.emitted(pos + side, block, component, -side).0
+                }
+                _ => 0,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn comparator_output(&self, pos: IVec3, block: BlockStateId, facing: IVec3) -> u8 {
+        let back = self.diode_input(pos, facing);
+        let side = self.side_input(pos, facing, false);
+        if block.get_property("mode") == Some("subtract") {
+            back.saturating_sub(side)
+        } else if back >= side {
+            back
+        } else {
+            0
+        }
+    }
+
+    /// Repeaters and comparators feeding another one that isn't facing back into them go first,
+    /// so chains of them keep their timing, then ones turning off.
+    fn diode_priority(&self, pos: IVec3, facing: IVec3, turning_off: bool) -> i8 {
+        let front = is_diode(self.component(pos - facing).map(|(_, component)| component));
+        if front.is_some_and(|front| front != -facing) {
+            -3
+        } else if turning_off {
+            -2
+        } else {
+            -1
+        }
+    }
+
+    /// Whether a piston is powered, from any side but its front.
+    fn piston_powered(&self, pos: IVec3, facing: IVec3) -> bool {
+        DIRECTIONS
+            .iter()
+            .filter(|dir| **dir != facing)
+            .any(|dir| self.signal_into(pos, *dir, false) > 0)
+    }
+
+    fn is_door_powered(&self, pos: IVec3, block: BlockStateId) -> bool {
+        let other = if block.get_property("half") == Some("upper") {
+            pos - IVec3::Y
+        } else {
+            pos + IVec3::Y
+        };
+        self.power(pos, false) > 0
+            || self
+                .block(other)
+                .is_some_and(|other_block| block.is_same_block(other_block))
+                && self.power(other, false) > 0
+    }
+
+    fn update(&self, pos: IVec3, block: BlockStateId) -> Option<RedstoneUpdate> {
+        let tick = |delay, priority| {
+            Some(RedstoneUpdate::Tick(
+                TickRequest::new(BlockPos { pos }, TickKind::Block, delay).with_priority(priority),
+            ))
+        };
+        match component_of(block)? {
+            Component::Wire => self.wire_state(pos, block).map(RedstoneUpdate::Set),
+            Component::Torch { attached } => {
+                let lit = self.signal_into(pos, attached, false) == 0;
+                (lit != is_on(block, "lit")).then(|| tick(TORCH_DELAY, 0))?
+            }
+            Component::Repeater { facing } => {
+                let locked = self.side_input(pos, facing, true) > 0;
+                if locked != is_on(block, "locked") {
+                    return Some(RedstoneUpdate::Set(with(block, "locked", locked)));
+                }
+                let powered = is_on(block, "powered");
+                if locked || (self.diode_input(pos, facing) > 0) == powered {
+                    return None;
+                }
+                let delay = u32::from(level(block, "delay").max(1)) * 2;
+                tick(delay, self.diode_priority(pos, facing, powered))
+            }
+            Component::Comparator { facing } => {
+                let output = self.comparator_output(pos, block, facing);
+                let powered = is_on(block, "powered");
+                if output == self.stored_output(pos) && (output > 0) == powered {
+                    return None;
+                }
+                tick(
+                    COMPARATOR_DELAY,
+                    self.diode_priority(pos, facing, output == 0),
+                )
+            }
+            Component::Lamp => {
+                let powered = self.power(pos, false) > 0;
+                match (powered, is_on(block, "lit")) {
+                    (true, false) => Some(RedstoneUpdate::Set(with(block, "lit", "true"))),
+                    (false, true) => tick(LAMP_OFF_DELAY, 0),
+                    _ => None,
+                }
+            }
+            // Power only opens and closes doors when it changes, so one opened by hand stays open
+            Component::Door { .. } => {
+                let powered = self.is_door_powered(pos, block);
+                (powered != is_on(block, "powered")).then(|| {
+                    RedstoneUpdate::Set(with(with(block, "powered", powered), "open", powered))
+                })
+            }
+            Component::Trapdoor { .. } => {
+                let powered = self.power(pos, false) > 0;
+                (powered != is_on(block, "powered")).then(|| {
+                    RedstoneUpdate::Set(with(with(block, "powered", powered), "open", powered))
+                })
+            }
+            Component::Piston { facing, .. } => {
+                let powered = self.piston_powered(pos, facing);
+                (powered != is_on(block, "extended")).then(|| tick(PISTON_DELAY, 0))?
+            }
+            Component::RedstoneBlock
+            | Component::Lever { .. }
+            | Component::Button { .. }
+            | Component::PressurePlate(_) => None,
+        }
+    }
+
+    fn tick(
+        &self,
+        pos: IVec3,
+        block: BlockStateId,
+        component: Component,
+    ) -> Result<Vec<BlockPos>, WorldError> {
+        let mut changed = Vec::new();
+        match component {
+            Component::Torch { attached } => {
+                let lit = self.signal_into(pos, attached, false) == 0;
+                if lit != is_on(block, "lit") {
+                    self.set(pos, with(block, "lit", lit), &mut changed)?;
+                }
+            }
+            Component::Repeater { facing } => {
+                if is_on(block, "locked") {
+                    return Ok(changed);
+                }
+                let input = self.diode_input(pos, facing) > 0;
+                let powered = is_on(block, "powered");
+                // A repeater that's turned on stays on for at least its delay, however short the
+                // pulse was, and turns off again on its next tick
+                if powered && !input {
+                    self.set(pos, with(block, "powered", "false"), &mut changed)?;
+                } else if !powered {
+                    self.set(pos, with(block, "powered", "true"), &mut changed)?;
+                }
+            }
+            Component::Comparator { facing } => {
+                let output = self.comparator_output(pos, block, facing);
+                if output != self.stored_output(pos) {
+                    self.store_output(pos, output)?;
+                    changed.push(BlockPos { pos });
+                }
+                let powered = output > 0;
+                if powered != is_on(block, "powered") {
+                    self.set(pos, with(block, "powered", powered), &mut changed)?;
+                }
+            }
+            Component::Lamp => {
+                if is_on(block, "lit") && self.power(pos, false) == 0 {
+                    self.set(pos, with(block, "lit", "false"), &mut changed)?;
+                }
+            }
+            Component::Button { .. } => {
+                if is_on(block, "powered") {
+                    self.set(pos, with(block, "powered", "false"), &mut changed)?;
+                }
+            }
+            Component::PressurePlate(plate) => {
+                let key = (BlockPos { pos }, self.dimension);
+                let pressed_at = self.world.pressed_plates.get(&key).map(|at| *at);
+                if pressed_at.is_some_and(|at| at + 1 >= self.world.game_time()) {
+                    // Still being stood on, check again later
+                    self.world.schedule_ticks(
+                        self.dimension,
+                        [TickRequest::new(key.0, TickKind::Block, plate.delay())],
+                    )?;
+                } else {
+                    self.world.pressed_plates.remove(&key);
+                    if plate_power(block, plate) > 0 {
+                        self.set(pos, with_plate_power(block, plate, 0), &mut changed)?;
+                    }
+                }
+            }
+            Component::Piston { facing, sticky } => {
+                let powered = self.piston_powered(pos, facing);
+                match (powered, is_on(block, "extended")) {
+                    (true, false) => self.extend(pos, block, facing, sticky, &mut changed)?,
+                    (false, true) => self.retract(pos, block, facing, sticky, &mut changed)?,
+                    _ => {}
+                }
+            }
+            Component::Wire
+            | Component::RedstoneBlock
+            | Component::Lever { .. }
+            | Component::Door { .. }
+            | Component::Trapdoor { .. } => {}
+        }
+        Ok(changed)
+    }
+
+    fn is_movable(block: BlockStateId) -> bool {
+        let Some(data) = ID2BLOCK.get(block.raw() as usize) else {
+            return false;
+        };
+        let extended_piston = matches!(name(data), "piston" | "sticky_piston")
+            && property(data, "extended") == Some("true");
+        !extended_piston
+            && !IMMOVABLE_BLOCKS.contains(&name(data))
+            && BlockEntityKind::for_block(block).is_none()
+    }
+
+    /// Whether a piston breaks the block rather than moving it.
+    fn is_crushed(block: BlockStateId) -> bool {
+        is_free(block) || needs_support(block)
+    }
+
+    fn extend(
+        &self,
+        pos: IVec3,
+        block: BlockStateId,
+        facing: IVec3,
+        sticky: bool,
+        changed: &mut Vec<BlockPos>,
+    ) -> Result<(), WorldError> {
+        let mut pushed = Vec::new();
+        let mut at = pos + facing;
+        loop {
+            let Some(block) = self.block(at) else {
+                return Ok(());
+            };
+            if Self::is_crushed(block) {
+                break;
+            }
+            if !Self::is_movable(block) || pushed.len() == PUSH_LIMIT {
+                return Ok(());
+            }
+            pushed.push((at, block));
+            at += facing;
+        }
+        for (at, block) in pushed.into_iter().rev() {
+            self.set(at + facing, block, changed)?;
+        }
+        let head = block!("piston_head", {facing: "north", short: false, r#type: "normal"});
+        let head = with(
+            with(head, "facing", facing_name(facing)),
+            "type",
+            if sticky { "sticky" } else { "normal" },
+        );
+        self.set(pos + facing, head, changed)?;
+        self.set(pos, with(block, "extended", "true"), changed)
+    }
+
+    fn retract(
+        &self,
+        pos: IVec3,
+        block: BlockStateId,
+        facing: IVec3,
+        sticky: bool,
+        changed: &mut Vec<BlockPos>,
+    ) -> Result<(), WorldError> {
+        self.set(pos, with(block, "extended", "false"), changed)?;
+        let head = pos + facing;
+        if !self
+            .block(head)
+            .and_then(|head| ID2BLOCK.get(head.raw() as usize))
+            .is_some_and(|data| name(data) == "piston_head")
+        {
+            return Ok(());
+        }
+        let pulled = head + facing;
+        match self.block(pulled) {
+            Some(block) if sticky && Self::is_movable(block) && !Self::is_crushed(block) => {
+                self.set(head, block, changed)?;
+                self.set(pulled, BlockStateId::default(), changed)
+            }
+            _ => self.set(head, BlockStateId::default(), changed),
+        }
+    }
+}
+
+impl World {
+    /// Whether there are any redstone components close enough to `pos` to care about it
+    /// changing. Only whole sections are checked, so this can say yes when the answer is no.
+    pub(crate) fn redstone_near(&self, pos: BlockPos, dimension: Dimension) -> bool {
+        let mut sections = Vec::with_capacity(8);
+        for dx in [-2, 2] {
+            for dy in [-2, 2] {
+                for dz in [-2, 2] {
+                    let corner = BlockPos {
+                        pos: pos.pos + IVec3::new(dx, dy, dz),
+                    };
+                    let section = (corner.chunk(), corner.chunk_block_pos().section());
+                    if !sections.contains(&section) {
+                        sections.push(section);
+                    }
+                }
+            }
+        }
+        sections.into_iter().any(|(chunk, section)| {
+            self.load_chunk(chunk, dimension).is_ok_and(|chunk| {
+                chunk
+                    .get_section(section)
+                    .is_some_and(|section| section.has_redstone())
+            })
+        })
+    }
+
+    /// What the redstone component in `block` at `pos` does about a block update near it, or
+    /// `None` if it isn't a component or is already right.
+    pub(crate) fn redstone_update(
+        &self,
+        pos: BlockPos,
+        dimension: Dimension,
+        block: BlockStateId,
+    ) -> Option<RedstoneUpdate> {
+        RedstoneView {
+            world: self,
+            dimension,
+        }
+        .update(pos.pos, block)
+    }
+
+    /// Runs the block tick of the redstone component in `block` at `pos`.
+    ///
+    /// Returns `None` if the block isn't a component, otherwise the blocks that changed.
+    pub(crate) fn tick_redstone(
+        &self,
+        pos: BlockPos,
+        dimension: Dimension,
+        block: BlockStateId,
+    ) -> Result<Option<Vec<BlockPos>>, WorldError> {
+        let Some(component) = component_of(block) else {
+            return Ok(None);
+        };
+        RedstoneView {
+            world: self,
+            dimension,
+        }
+        .tick(pos.pos, block, component)
+        .map(Some)
+    }
+
+    /// The redstone power reaching the block at `pos`, from 0 to 15.
+    pub fn redstone_power(&self, pos: BlockPos, dimension: Dimension) -> u8 {
+        RedstoneView {
+            world: self,
+            dimension,
+        }
+        .power(pos.pos, false)
+    }
+
+    /// Uses the block at `pos` the way a player right clicking it does: flips levers, presses
+    /// buttons, changes the delay of repeaters and the mode of comparators, and opens and closes
+    /// doors and trapdoors that can be opened by hand.
+    ///
+    /// The blocks around the ones that changed still need updating, see
+    /// [`World::update_neighbours`].
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(Vec<BlockPos>))` - The blocks that changed.
+    /// * `Ok(None)` - If the block can't be used, so whatever the player is holding should be.
+    /// * `Err(WorldError)` - If a block couldn't be read or set.
+    pub fn use_block(
+        &self,
+        pos: BlockPos,
+        dimension: Dimension,
+    ) -> Result<Option<Vec<BlockPos>>, WorldError> {
+        let view = RedstoneView {
+            world: self,
+            dimension,
+        };
+        let block = self.get_block_and_fetch(pos, dimension)?;
+        let mut changed = Vec::new();
+        let toggled = |key| with(block, key, !is_on(block, key));
+        match component_of(block) {
+            Some(Component::Lever { .. }) => view.set(pos.pos, toggled("powered"), &mut changed)?,
+            Some(Component::Button { ticks, .. }) => {
+                if !is_on(block, "powered") {
+                    view.set(pos.pos, with(block, "powered", "true"), &mut changed)?;
+                    self.schedule_ticks(
+                        dimension,
+                        [TickRequest::new(pos, TickKind::Block, ticks)],
+                    )?;
+                }
+            }
+            Some(Component::Repeater { .. }) => {
+                let delay = level(block, "delay") % 4 + 1;
+                view.set(pos.pos, with(block, "delay", delay), &mut changed)?;
+            }
+            Some(Component::Comparator { .. }) => {
+                let mode = if block.get_property("mode") == Some("subtract") {
+                    "compare"
+                } else {
+                    "subtract"
+                };
+                view.set(pos.pos, with(block, "mode", mode), &mut changed)?;
+            }
+            Some(Component::Door { by_hand: true }) => {
+                let open = !is_on(block, "open");
+                let other = if block.get_property("half") == Some("upper") {
+                    pos.pos - IVec3::Y
+                } else {
+                    pos.pos + IVec3::Y
+                };
+                view.set(pos.pos, with(block, "open", open), &mut changed)?;
+                if let Some(other_block) = view.block(other).filter(|b| block.is_same_block(*b)) {
+                    view.set(other, with(other_block, "open", open), &mut changed)?;
+                }
+            }
+            Some(Component::Trapdoor { by_hand: true }) => {
+                view.set(pos.pos, toggled("open"), &mut changed)?
+            }
+            _ => return Ok(None),
+        }
+        Ok(Some(changed))
+    }
+
+    /// Whether the block at `pos` is a pressure plate, so anything standing in it presses it.
+    pub fn is_pressure_plate(&self, pos: BlockPos, dimension: Dimension) -> bool {
+        RedstoneView {
+            world: self,
+            dimension,
+        }
+        .component(pos.pos)
+        .is_some_and(|(_, component)| matches!(component, Component::PressurePlate(_)))
+    }
+
+    /// Presses pressure plates, each with how many players and mobs are on it. Positions that
+    /// aren't pressure plates are skipped.
+    ///
+    /// A plate stays down for as long as it keeps being pressed every game tick, and comes back up
+    /// on its next check after that, a second later or half a second for weighted plates.
+    ///
+    /// Returns the plates that changed.
+    pub fn press_pressure_plates(
+        &self,
+        dimension: Dimension,
+        plates: impl IntoIterator<Item = (BlockPos, usize)>,
+    ) -> Result<Vec<BlockPos>, WorldError> {
+        let view = RedstoneView {
+            world: self,
+            dimension,
+        };
+        let now = self.game_time();
+        let mut changed = Vec::new();
+        let mut ticks = Vec::new();
+        for (pos, pressed_by) in plates {
+            let Some((block, Component::PressurePlate(plate))) = view.component(pos.pos) else {
+                continue;
+            };
+            let power = plate.power(pressed_by);
+            if power == 0 {
+                continue;
+            }
+            self.pressed_plates.insert((pos, dimension), now);
+            if power != plate_power(block, plate) {
+                view.set(pos.pos, with_plate_power(block, plate, power), &mut changed)?;
+            }
+            ticks.push(TickRequest::new(pos, TickKind::Block, plate.delay()));
+        }
+        self.schedule_ticks(dimension, ticks)?;
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_format::Chunk;
+    use crate::edit_batch::EditBatch;
+    use crate::pos::{ChunkBlockPos, ChunkPos};
+    use ferrumc_storage::memory::MemoryBackend;
+    use std::sync::Arc;
+
+    /// A world of nine chunks with a stone floor at y = 63.
+    fn flat_world() -> World {
+        let world = World::with_backend(MemoryBackend::new()).unwrap();
+        for x in -1..=1 {
+            for z in -1..=1 {
+                let mut chunk = Chunk::new(Dimension::Overworld.height());
+                let mut batch = EditBatch::new(&mut chunk);
+                for bx in 0..16 {
+                    for bz in 0..16 {
+                        batch.set_block(ChunkBlockPos::new(bx, 63, bz), block!("stone"));
+                    }
+                }
+                batch.apply().unwrap();
+                world
+                    .save_chunk(ChunkPos::new(x, z), Dimension::Overworld, Arc::new(chunk))
+                    .unwrap();
+            }
+        }
+        world
+    }
+
+    fn place(world: &World, x: i32, y: i32, z: i32, block: BlockStateId) {
+        let pos = BlockPos::of(x, y, z);
+        world
+            .set_block_and_fetch(pos, Dimension::Overworld, block)
+            .unwrap();
+        world
+            .update_neighbours(Dimension::Overworld, &[pos])
+            .unwrap();
+    }
+
+    fn use_block(world: &World, x: i32, y: i32, z: i32) {
+        let changed = world
+            .use_block(BlockPos::of(x, y, z), Dimension::Overworld)
+            .unwrap()
+            .expect("block can be used");
+        world
+            .update_neighbours(Dimension::Overworld, &changed)
+            .unwrap();
+    }
+
+    fn get(world: &World, x: i32, y: i32, z: i32) -> BlockStateId {
+        world
+            .get_block_and_fetch(BlockPos::of(x, y, z), Dimension::Overworld)
+            .unwrap()
+    }
+
+    fn settle(world: &World) {
+        for _ in 0..200 {
+            if world.scheduled_tick_count() == 0 {
+                return;
+            }
+            world.run_scheduled_ticks(usize::MAX);
+        }
+        panic!("circuit never settled");
+    }
+
+    fn wire() -> BlockStateId {
+        block!("redstone_wire", {power: 0, north: "none", south: "none", east: "none", west: "none"})
+    }
+
+    fn lever() -> BlockStateId {
+        block!("lever", {face: "floor", facing: "north", powered: false})
+    }
+
+    #[test]
+    fn test_wire_carries_power_from_a_lever_to_a_lamp() {
+        let world = flat_world();
+        place(&world, 0, 64, 0, lever());
+        for x in 1..=4 {
+            place(&world, x, 64, 0, wire());
+        }
+        place(&world, 5, 64, 0, block!("redstone_lamp", {lit: false}));
+
+        use_block(&world, 0, 64, 0);
+        for x in 1..=4 {
+            assert_eq!(level(get(&world, x, 64, 0), "power"), 16 - x as u8);
+        }
+        assert_eq!(get(&world, 2, 64, 0).get_property("west"), Some("side"));
+        assert!(is_on(get(&world, 5, 64, 0), "lit"));
+
+        use_block(&world, 0, 64, 0);
+        assert_eq!(level(get(&world, 1, 64, 0), "power"), 0);
+        // Lamps take a moment to turn off
+        assert!(is_on(get(&world, 5, 64, 0), "lit"));
+        settle(&world);
+        assert!(!is_on(get(&world, 5, 64, 0), "lit"));
+    }
+
+    #[test]
+    fn test_torch_turns_off_when_its_block_is_powered() {
+        let world = flat_world();
+        place(&world, 0, 64, 0, block!("stone"));
+        place(
+            &world,
+            0,
+            64,
+            1,
+            block!("lever", {face: "wall", facing: "south", powered: false}),
+        );
+        place(
+            &world,
+            0,
+            64,
+            -1,
+            block!("redstone_wall_torch", {facing: "north", lit: true}),
+        );
+        assert_eq!(world.scheduled_tick_count(), 0);
+
+        use_block(&world, 0, 64, 1);
+        assert_eq!(
+            world.redstone_power(BlockPos::of(0, 64, 0), Dimension::Overworld),
+            15
+        );
+        world.run_scheduled_ticks(usize::MAX);
+        assert!(is_on(get(&world, 0, 64, -1), "lit"));
+        world.run_scheduled_ticks(usize::MAX);
+        assert!(!is_on(get(&world, 0, 64, -1), "lit"));
+
+        use_block(&world, 0, 64, 1);
+        settle(&world);
+        assert!(is_on(get(&world, 0, 64, -1), "lit"));
+    }
+
+    #[test]
+    fn test_repeater_delays_and_locks() {
+        let world = flat_world();
+        place(&world, 0, 64, 0, lever());
+        place(
+            &world,
+            1,
+            64,
+            0,
+            block!("repeater", {delay: 2, facing: "west", locked: false, powered: false}),
+        );
+        place(&world, 2, 64, 0, block!("redstone_lamp", {lit: false}));
+
+        use_block(&world, 0, 64, 0);
+        for _ in 0..3 {
+            world.run_scheduled_ticks(usize::MAX);
+            assert!(!is_on(get(&world, 2, 64, 0), "lit"));
+        }
+        world.run_scheduled_ticks(usize::MAX);
+        assert!(is_on(get(&world, 2, 64, 0), "lit"));
+
+        // A powered repeater pointing into its side locks it where it is
+        place(&world, 1, 64, 2, block!("redstone_block"));
+        place(
+            &world,
+            1,
+            64,
+            1,
+            block!("repeater", {delay: 1, facing: "south", locked: false, powered: false}),
+        );
+        settle(&world);
+        assert!(is_on(get(&world, 1, 64, 0), "locked"));
+        use_block(&world, 0, 64, 0);
+        settle(&world);
+        assert!(is_on(get(&world, 1, 64, 0), "powered"));
+        assert!(is_on(get(&world, 2, 64, 0), "lit"));
+    }
+
+    #[test]
+    fn test_comparator_compares_and_subtracts() {
+        let world = flat_world();
+        place(&world, -1, 64, 0, block!("redstone_block"));
+        place(
+            &world,
+            0,
+            64,
+            0,
+            block!("comparator", {facing: "west", mode: "subtract", powered: false}),
+        );
+        place(&world, 1, 64, 0, wire());
+        // A side input of 12
+        place(&world, 0, 64, 5, lever());
+        for z in 1..=4 {
+            place(&world, 0, 64, z, wire());
+        }
+        use_block(&world, 0, 64, 5);
+        settle(&world);
+        assert_eq!(level(get(&world, 0, 64, 1), "power"), 12);
+        assert_eq!(level(get(&world, 1, 64, 0), "power"), 3);
+        assert!(is_on(get(&world, 0, 64, 0), "powered"));
+
+        use_block(&world, 0, 64, 0);
+        settle(&world);
+        assert_eq!(level(get(&world, 1, 64, 0), "power"), 15);
+
+        // Survives the chunk being saved and loaded again
+        world.sync().unwrap();
+        world.cache.invalidate_all();
+        assert_eq!(level(get(&world, 1, 64, 0), "power"), 15);
+        assert_eq!(
+            RedstoneView {
+                world: &world,
+                dimension: Dimension::Overworld
+            }
+            .stored_output(IVec3::new(0, 64, 0)),
+            15
+        );
+    }
+
+    #[test]
+    fn test_sticky_piston_pushes_and_pulls() {
+        let world = flat_world();
+        place(
+            &world,
+            0,
+            64,
+            0,
+            block!("sticky_piston", {extended: false, facing: "east"}),
+        );
+        place(&world, 1, 64, 0, block!("stone"));
+        place(&world, 2, 64, 0, block!("dirt"));
+        place(&world, 3, 64, 0, block!("short_grass"));
+        place(&world, 0, 64, -1, lever());
+
+        use_block(&world, 0, 64, -1);
+        settle(&world);
+        assert!(is_on(get(&world, 0, 64, 0), "extended"));
+        assert_eq!(
+            get(&world, 1, 64, 0),
+            block!("piston_head", {facing: "east", short: false, r#type: "sticky"})
+        );
+        assert_eq!(get(&world, 2, 64, 0), block!("stone"));
+        assert_eq!(get(&world, 3, 64, 0), block!("dirt"));
+
+        use_block(&world, 0, 64, -1);
+        settle(&world);
+        assert!(!is_on(get(&world, 0, 64, 0), "extended"));
+        assert_eq!(get(&world, 1, 64, 0), block!("stone"));
+        assert_eq!(get(&world, 2, 64, 0), BlockStateId::default());
+        assert_eq!(get(&world, 3, 64, 0), block!("dirt"));
+
+        // Nothing moves past an immovable block
+        place(&world, 2, 64, 0, block!("obsidian"));
+        use_block(&world, 0, 64, -1);
+        settle(&world);
+        assert!(!is_on(get(&world, 0, 64, 0), "extended"));
+    }
+
+    #[test]
+    fn test_buttons_and_plates_power_doors_for_a_while() {
+        let world = flat_world();
+        let door = |half| {
+            let door = block!("iron_door", {facing: "north", half: "lower", hinge: "left", open: false, powered: false});
+            with(door, "half", half)
+        };
+        place(&world, 0, 64, 0, door("lower"));
+        place(&world, 0, 65, 0, door("upper"));
+        place(
+            &world,
+            1,
+            64,
+            0,
+            block!("stone_button", {face: "floor", facing: "north", powered: false}),
+        );
+
+        // Doors made of iron only open with power
+        assert_eq!(
+            world
+                .use_block(BlockPos::of(0, 64, 0), Dimension::Overworld)
+                .unwrap(),
+            None
+        );
+        use_block(&world, 1, 64, 0);
+        assert!(is_on(get(&world, 0, 64, 0), "open"));
+        assert!(is_on(get(&world, 0, 65, 0), "open"));
+        for _ in 0..19 {
+            world.run_scheduled_ticks(usize::MAX);
+        }
+        assert!(is_on(get(&world, 0, 65, 0), "open"));
+        world.run_scheduled_ticks(usize::MAX);
+        assert!(!is_on(get(&world, 0, 65, 0), "open"));
+
+        let plate = BlockPos::of(0, 64, 1);
+        place(
+            &world,
+            0,
+            64,
+            1,
+            block!("stone_pressure_plate", {powered: false}),
+        );
+        assert!(world.is_pressure_plate(BlockPos::of(0, 64, 1), Dimension::Overworld));
+        assert!(!world.is_pressure_plate(BlockPos::of(0, 63, 1), Dimension::Overworld));
+        for _ in 0..30 {
+            let changed = world
+                .press_pressure_plates(Dimension::Overworld, [(plate, 1)])
+                .unwrap();
+            world
+                .update_neighbours(Dimension::Overworld, &changed)
+                .unwrap();
+            world.run_scheduled_ticks(usize::MAX);
+            assert!(is_on(get(&world, 0, 64, 0), "open"));
+        }
+        settle(&world);
+        assert!(!is_on(get(&world, 0, 64, 1), "powered"));
+        assert!(!is_on(get(&world, 0, 64, 0), "open"));
+    }
+}
@@ -0,0 +1,782 @@
+//! Edits that span many chunks, such as filling a box, replacing one block with another or moving
+//! a build, for world editing tools.
+//!
+//! Every chunk is edited on its own task on the thread pool, through [`World::edit_chunk`]. The
+//! blocks and block entities each edit replaced are recorded in a [`RegionEdit`], so it can be
+//! undone and redone, see [`EditHistory`].
+
+use crate::block_entity::BlockEntity;
+use crate::block_state_id::BlockStateId;
+use crate::chunk_format::Chunk;
+use crate::dimension::Dimension;
+use crate::edit_batch::EditBatch;
+use crate::errors::WorldError;
+use crate::pos::{BlockPos, ChunkBlockPos, ChunkPos};
+use crate::schematic::{Mirror, Rotation, Schematic};
+use crate::World;
+use ahash::{AHashMap, AHashSet};
+use bevy_math::IVec3;
+use ferrumc_macros::block;
+use ferrumc_threadpool::ThreadPool;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tracing::error;
+
+/// How many changed blocks [`EditHistory`] keeps, over all its edits, before it forgets the
+/// oldest edits.
+const MAX_HISTORY_CHANGES: usize = 4_000_000;
+
+/// A box of blocks between two corners, inclusive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region {
+    min: IVec3,
+    max: IVec3,
+}
+
+impl Region {
+    pub fn new(corner: BlockPos, other_corner: BlockPos) -> Self {
+        Self {
+            min: corner.pos.min(other_corner.pos),
+            max: corner.pos.max(other_corner.pos),
+        }
+    }
+
+    pub fn min(&self) -> BlockPos {
+        BlockPos { pos: self.min }
+    }
+
+    pub fn max(&self) -> BlockPos {
+        BlockPos { pos: self.max }
+    }
+
+    pub fn size(&self) -> IVec3 {
+        self.max - self.min + IVec3::ONE
+    }
+
+    pub fn volume(&self) -> u64 {
+        self.size().as_u64vec3().element_product()
+    }
+
+    pub fn contains(&self, pos: BlockPos) -> bool {
+        pos.pos.cmpge(self.min).all() && pos.pos.cmple(self.max).all()
+    }
+
+    pub fn offset(&self, offset: IVec3) -> Region {
+        Region {
+            min: self.min + offset,
+            max: self.max + offset,
+        }
+    }
+
+    /// Every chunk the region touches.
+    pub fn chunks(&self) -> impl Iterator<Item = ChunkPos> {
+        let (min, max) = (self.min, self.max);
+        (min.x.div_euclid(16)..=max.x.div_euclid(16)).flat_map(move |x| {
+            (min.z.div_euclid(16)..=max.z.div_euclid(16)).map(move |z| ChunkPos::new(x, z))
+        })
+    }
+
+    /// The positions of the region inside the chunk that are within the chunk's height.
+    fn positions_in(&self, pos: ChunkPos, chunk: &Chunk) -> impl Iterator<Item = ChunkBlockPos> {
+        let min_y = self.min.y.max(i32::from(chunk.min_y));
+        let max_y = self
+            .max
+            .y
+            .min(i32::from(chunk.min_y) + chunk.sections.len() as i32 * 16 - 1);
+        let xs = self.min.x.max(pos.x() * 16)..=self.max.x.min(pos.x() * 16 + 15);
+        let zs = self.min.z.max(pos.z() * 16)..=self.max.z.min(pos.z() * 16 + 15);
+        (min_y..=max_y).flat_map(move |y| {
+            let xs = xs.clone();
+            zs.clone().flat_map(move |z| {
+                xs.clone()
+                    .map(move |x| BlockPos::of(x, y, z).chunk_block_pos())
+            })
+        })
+    }
+}
+
+/// Which of the blocks already in the world an edit is allowed to replace.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum BlockMask {
+    #[default]
+    Any,
+    Only(AHashSet<BlockStateId>),
+    Except(AHashSet<BlockStateId>),
+}
+
+impl BlockMask {
+    pub fn matches(&self, block: BlockStateId) -> bool {
+        match self {
+            BlockMask::Any => true,
+            BlockMask::Only(blocks) => blocks.contains(&block),
+            BlockMask::Except(blocks) => !blocks.contains(&block),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct BlockChange {
+    pos: ChunkBlockPos,
+    before: BlockStateId,
+    after: BlockStateId,
+}
+
+/// What an edit did to one chunk. Block entities are kept for every changed position that had
+/// one, before and after the edit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ChunkChange {
+    pos: ChunkPos,
+    blocks: Vec<BlockChange>,
+    block_entities_before: Vec<BlockEntity>,
+    block_entities_after: Vec<BlockEntity>,
+}
+
+/// Everything a region edit changed, so it can be undone with [`World::undo_region_edit`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegionEdit {
+    dimension: Dimension,
+    chunks: Vec<ChunkChange>,
+}
+
+impl RegionEdit {
+    pub fn dimension(&self) -> Dimension {
+        self.dimension
+    }
+
+    /// How many blocks the edit touched.
+    pub fn block_count(&self) -> usize {
+        self.chunks.iter().map(|chunk| chunk.blocks.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// The blocks whose state changed, which should be sent to any players that have them loaded.
+    pub fn changed_blocks(&self) -> Vec<BlockPos> {
+        self.chunks
+            .iter()
+            .flat_map(|chunk| {
+                chunk
+                    .blocks
+                    .iter()
+                    .filter(|change| change.before != change.after)
+                    .map(|change| chunk.pos.chunk_block(change.pos))
+            })
+            .collect()
+    }
+
+    /// The blocks that carry a block entity after the edit, whose data players should be sent.
+    pub fn changed_block_entities(&self) -> Vec<BlockPos> {
+        self.chunks
+            .iter()
+            .flat_map(|chunk| {
+                chunk
+                    .block_entities_after
+                    .iter()
+                    .map(|block_entity| chunk.pos.chunk_block(block_entity.pos()))
+            })
+            .collect()
+    }
+
+    /// Reverses the edit, so applying the result puts back what was there before.
+    fn inverse(&self) -> RegionEdit {
+        RegionEdit {
+            dimension: self.dimension,
+            chunks: self
+                .chunks
+                .iter()
+                .map(|chunk| ChunkChange {
+                    pos: chunk.pos,
+                    blocks: chunk
+                        .blocks
+                        .iter()
+                        .map(|change| BlockChange {
+                            pos: change.pos,
+                            before: change.after,
+                            after: change.before,
+                        })
+                        .collect(),
+                    block_entities_before: chunk.block_entities_after.clone(),
+                    block_entities_after: chunk.block_entities_before.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// The blocks and block entities to write to get to the state after this edit.
+    fn targets(&self) -> Vec<(ChunkPos, ChunkTargets)> {
+        self.chunks
+            .iter()
+            .map(|chunk| {
+                let targets = ChunkTargets {
+                    blocks: chunk
+                        .blocks
+                        .iter()
+                        .map(|change| (change.pos, change.after))
+                        .collect(),
+                    block_entities: chunk.block_entities_after.clone(),
+                };
+                (chunk.pos, targets)
+            })
+            .collect()
+    }
+}
+
+/// The edits a player can undo and redo, newest last. The oldest edits are forgotten once they
+/// hold more than [`MAX_HISTORY_CHANGES`] blocks between them.
+#[derive(Debug, Default)]
+pub struct EditHistory {
+    undo: VecDeque<RegionEdit>,
+    redo: Vec<RegionEdit>,
+    /// How many blocks the edits in `undo` and `redo` hold.
+    changes: usize,
+}
+
+impl EditHistory {
+    /// Remembers an edit so it can be undone. Anything that was undone can't be redone anymore.
+    ///
+    /// An edit too large to keep clears the history instead, so undoing can't skip over it.
+    pub fn record(&mut self, edit: RegionEdit) {
+        if edit.is_empty() {
+            return;
+        }
+        self.redo.clear();
+        let size = edit.block_count();
+        if size > MAX_HISTORY_CHANGES {
+            self.undo.clear();
+            self.changes = 0;
+            return;
+        }
+        self.undo.push_back(edit);
+        self.changes = self.undo.iter().map(RegionEdit::block_count).sum();
+        while self.changes > MAX_HISTORY_CHANGES {
+            let Some(oldest) = self.undo.pop_front() else {
+                break;
+            };
+            self.changes -= oldest.block_count();
+        }
+    }
+
+    /// Undoes the newest edit. Returns what undoing it changed, or `None` if there was nothing to
+    /// undo.
+    pub fn undo(
+        &mut self,
+        world: &World,
+        pool: &ThreadPool,
+    ) -> Result<Option<RegionEdit>, WorldError> {
+        let Some(edit) = self.undo.pop_back() else {
+            return Ok(None);
+        };
+        match world.undo_region_edit(&edit, pool) {
+            Ok(undone) => {
+                self.redo.push(edit);
+                Ok(Some(undone))
+            }
+            Err(err) => {
+                self.undo.push_back(edit);
+                Err(err)
+            }
+        }
+    }
+
+    /// Redoes the newest undone edit. Returns what redoing it changed, or `None` if there was
+    /// nothing to redo.
+    pub fn redo(
+        &mut self,
+        world: &World,
+        pool: &ThreadPool,
+    ) -> Result<Option<RegionEdit>, WorldError> {
+        let Some(edit) = self.redo.pop() else {
+            return Ok(None);
+        };
+        match world.apply_region_targets(edit.dimension, pool, edit.targets()) {
+            Ok(redone) => {
+                self.undo.push_back(edit);
+                Ok(Some(redone))
+            }
+            Err(err) => {
+                self.redo.push(edit);
+                Err(err)
+            }
+        }
+    }
+}
+
+/// The blocks and block entities to write to a chunk. Later blocks win over earlier ones at the
+/// same position.
+#[derive(Debug, Default)]
+pub(crate) struct ChunkTargets {
+    pub(crate) blocks: Vec<(ChunkBlockPos, BlockStateId)>,
+    pub(crate) block_entities: Vec<BlockEntity>,
+}
+
+impl ChunkTargets {
+    /// Groups a schematic's blocks and block entities by the chunk they land in when its corner is
+    /// at `origin`. Structure voids are left out.
+    pub(crate) fn from_schematic(
+        schematic: &Schematic,
+        origin: BlockPos,
+    ) -> AHashMap<ChunkPos, ChunkTargets> {
+        let structure_void = block!("structure_void");
+        let mut chunks: AHashMap<ChunkPos, ChunkTargets> = AHashMap::new();
+        for (pos, block) in schematic.blocks() {
+            if block == structure_void {
+                continue;
+            }
+            let pos = BlockPos {
+                pos: origin.pos + pos,
+            };
+            chunks
+                .entry(pos.chunk())
+                .or_default()
+                .blocks
+                .push((pos.chunk_block_pos(), block));
+        }
+        for block_entity in &schematic.block_entities {
+            let pos = BlockPos {
+                pos: origin.pos + block_entity.pos,
+            };
+            chunks
+                .entry(pos.chunk())
+                .or_default()
+                .block_entities
+                .push(BlockEntity::with_data(
+                    block_entity.kind,
+                    pos.chunk_block_pos(),
+                    block_entity.data.clone(),
+                ));
+        }
+        chunks
+    }
+
+    /// Writes the targets to the chunk of the given dimension, leaving out anything above or below
+    /// it, and records what was replaced.
+    pub(crate) fn apply(
+        self,
+        pos: ChunkPos,
+        dimension: Dimension,
+        chunk: &mut Chunk,
+    ) -> Result<ChunkChange, WorldError> {
+        let in_chunk = |block_pos: &ChunkBlockPos| chunk.get_section(block_pos.section()).is_some();
+        let block_entities = self
+            .block_entities
+            .into_iter()
+            .filter(|block_entity| in_chunk(&block_entity.pos()))
+            .collect::<Vec<_>>();
+        let entity_positions = block_entities
+            .iter()
+            .map(BlockEntity::pos)
+            .collect::<AHashSet<_>>();
+
+        let mut order = Vec::new();
+        let mut wanted = AHashMap::new();
+        for (block_pos, block) in self.blocks.into_iter().filter(|(pos, _)| in_chunk(pos)) {
+            if wanted.insert(block_pos, block).is_none() {
+                order.push(block_pos);
+            }
+        }
+        for block_pos in &entity_positions {
+            if !wanted.contains_key(block_pos) {
+                wanted.insert(*block_pos, chunk.get_block(*block_pos)?);
+                order.push(*block_pos);
+            }
+        }
+
+        let mut change = ChunkChange {
+            pos,
+            blocks: Vec::new(),
+            block_entities_before: Vec::new(),
+            block_entities_after: Vec::new(),
+        };
+        for block_pos in order {
+            let before = chunk.get_block(block_pos)?;
+            let after = wanted[&block_pos];
+            if before == after && !entity_positions.contains(&block_pos) {
+                continue;
+            }
+            change.blocks.push(BlockChange {
+                pos: block_pos,
+                before,
+                after,
+            });
+            if let Some(block_entity) = chunk.get_block_entity(block_pos) {
+                change.block_entities_before.push(block_entity.clone());
+            }
+        }
+
+        if change
+            .blocks
+            .iter()
+            .any(|block| block.before != block.after)
+        {
+            let mut batch = EditBatch::new(chunk).with_sky_light(dimension.has_sky_light());
+            for block in change
+                .blocks
+                .iter()
+                .filter(|block| block.before != block.after)
+            {
+                batch.set_block(block.pos, block.after);
+            }
+            batch.apply()?;
+        }
+        for block_entity in block_entities {
+            chunk.set_block_entity(block_entity)?;
+        }
+        for block in &change.blocks {
+            if let Some(block_entity) = chunk.get_block_entity(block.pos) {
+                change.block_entities_after.push(block_entity.clone());
+            }
+        }
+        Ok(change)
+    }
+}
+
+type ChunkJob = Box<dyn FnOnce(&Chunk) -> Result<ChunkTargets, WorldError> + Send>;
+
+impl World {
+    /// Sets every block in the region that matches the mask, so [`BlockMask::Any`] fills the
+    /// whole region and [`BlockMask::Only`] replaces some blocks with another.
+    ///
+    /// Like every region edit, this fails without changing anything if one of the chunks doesn't
+    /// exist. A chunk that fails to be edited after that is logged and left out of the edit.
+    pub fn fill_region(
+        &self,
+        region: Region,
+        dimension: Dimension,
+        block: BlockStateId,
+        mask: &BlockMask,
+        pool: &ThreadPool,
+    ) -> Result<RegionEdit, WorldError> {
+        let mask = Arc::new(mask.clone());
+        let jobs = region
+            .chunks()
+            .map(|pos| {
+                let mask = mask.clone();
+                let job: ChunkJob = Box::new(move |chunk: &Chunk| {
+                    let mut targets = ChunkTargets::default();
+                    for block_pos in region.positions_in(pos, chunk) {
+                        let current = chunk.get_block(block_pos)?;
+                        if current != block && mask.matches(current) {
+                            targets.blocks.push((block_pos, block));
+                        }
+                    }
+                    Ok(targets)
+                });
+                (pos, job)
+            })
+            .collect();
+        self.apply_region_jobs(dimension, pool, jobs)
+    }
+
+    /// Pastes a schematic with its corner at `origin`, like [`World::paste_schematic`] but spread
+    /// over the thread pool and recorded so it can be undone.
+    pub fn paste_region(
+        &self,
+        schematic: &Schematic,
+        origin: BlockPos,
+        dimension: Dimension,
+        rotation: Rotation,
+        mirror: Mirror,
+        pool: &ThreadPool,
+    ) -> Result<RegionEdit, WorldError> {
+        let targets = if rotation == Rotation::None && mirror == Mirror::None {
+            ChunkTargets::from_schematic(schematic, origin)
+        } else {
+            ChunkTargets::from_schematic(&schematic.transformed(rotation, mirror), origin)
+        };
+        self.apply_region_targets(dimension, pool, targets.into_iter().collect())
+    }
+
+    /// Moves the blocks in the region by `offset`, leaving air behind. The two regions may
+    /// overlap.
+    pub fn move_region(
+        &self,
+        region: Region,
+        offset: IVec3,
+        dimension: Dimension,
+        pool: &ThreadPool,
+    ) -> Result<RegionEdit, WorldError> {
+        let moved = self.copy_schematic(region.min(), region.max(), dimension)?;
+        let mut targets: AHashMap<ChunkPos, ChunkTargets> = AHashMap::new();
+        for pos in region.chunks() {
+            let chunk = self.load_chunk(pos, dimension)?;
+            targets.entry(pos).or_default().blocks.extend(
+                region
+                    .positions_in(pos, &chunk)
+                    .map(|block_pos| (block_pos, BlockStateId::default())),
+            );
+        }
+        // Added after the air, so they win where the regions overlap
+        for (pos, moved) in ChunkTargets::from_schematic(&moved, region.offset(offset).min()) {
+            let chunk_targets = targets.entry(pos).or_default();
+            chunk_targets.blocks.extend(moved.blocks);
+            chunk_targets.block_entities.extend(moved.block_entities);
+        }
+        self.apply_region_targets(dimension, pool, targets.into_iter().collect())
+    }
+
+    /// Puts back what an edit replaced. Blocks that were changed again since are overwritten.
+    pub fn undo_region_edit(
+        &self,
+        edit: &RegionEdit,
+        pool: &ThreadPool,
+    ) -> Result<RegionEdit, WorldError> {
+        self.apply_region_targets(edit.dimension, pool, edit.inverse().targets())
+    }
+
+    fn apply_region_targets(
+        &self,
+        dimension: Dimension,
+        pool: &ThreadPool,
+        targets: Vec<(ChunkPos, ChunkTargets)>,
+    ) -> Result<RegionEdit, WorldError> {
+        let jobs = targets
+            .into_iter()
+            .map(|(pos, targets)| {
+                let job: ChunkJob = Box::new(move |_: &Chunk| Ok(targets));
+                (pos, job)
+            })
+            .collect();
+        self.apply_region_jobs(dimension, pool, jobs)
+    }
+
+    fn apply_region_jobs(
+        &self,
+        dimension: Dimension,
+        pool: &ThreadPool,
+        jobs: Vec<(ChunkPos, ChunkJob)>,
+    ) -> Result<RegionEdit, WorldError> {
+        for (pos, _) in &jobs {
+            if !self.chunk_exists(*pos, dimension)? {
+                return Err(WorldError::ChunkNotFound);
+            }
+        }
+
+        let mut batch = pool.batch();
+        for (pos, job) in jobs {
+            let world = self.clone();
+            batch.execute(move || {
+                world
+                    .edit_chunk(pos, dimension, |chunk| {
+                        let targets = job(chunk)?;
+                        targets.apply(pos, dimension, chunk)
+                    })
+                    .map(|(change, _)| change)
+            });
+        }
+
+        let mut chunks = Vec::new();
+        for result in batch.wait() {
+            match result {
+                Ok(change) if change.blocks.is_empty() => {}
+                Ok(change) => chunks.push(change),
+                Err(err) => error!("Failed to edit a chunk of a region: {err}"),
+            }
+        }
+        Ok(RegionEdit { dimension, chunks })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_entity::BlockEntityKind;
+    use crate::lighting::{LightEngine, LightType};
+    use ferrumc_storage::memory::MemoryBackend;
+
+    fn test_world() -> World {
+        let world = World::with_backend(MemoryBackend::new()).unwrap();
+        for x in -1..=1 {
+            for z in -1..=1 {
+                world
+                    .save_chunk(
+                        ChunkPos::new(x, z),
+                        Dimension::Overworld,
+                        Arc::new(Chunk::new(Dimension::Overworld.height())),
+                    )
+                    .unwrap();
+            }
+        }
+        world
+    }
+
+    fn block_at(world: &World, x: i32, y: i32, z: i32) -> BlockStateId {
+        world
+            .get_block_and_fetch(BlockPos::of(x, y, z), Dimension::Overworld)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_fill_replace_and_undo() {
+        let world = test_world();
+        let pool = ThreadPool::new();
+        let mut history = EditHistory::default();
+        let region = Region::new(BlockPos::of(-3, 60, -3), BlockPos::of(3, 61, 3));
+
+        let edit = world
+            .fill_region(
+                region,
+                Dimension::Overworld,
+                block!("stone"),
+                &BlockMask::Any,
+                &pool,
+            )
+            .unwrap();
+        assert_eq!(edit.block_count(), 98);
+        assert_eq!(edit.changed_blocks().len(), 98);
+        history.record(edit);
+
+        let edit = world
+            .fill_region(
+                Region::new(BlockPos::of(0, 61, 0), BlockPos::of(5, 61, 0)),
+                Dimension::Overworld,
+                block!("dirt"),
+                &BlockMask::Only(AHashSet::from([block!("stone")])),
+                &pool,
+            )
+            .unwrap();
+        // Only the stone inside the first region is replaced
+        assert_eq!(edit.block_count(), 4);
+        assert_eq!(block_at(&world, 3, 61, 0), block!("dirt"));
+        assert_eq!(block_at(&world, 4, 61, 0), BlockStateId::default());
+        history.record(edit);
+
+        history.undo(&world, &pool).unwrap().unwrap();
+        assert_eq!(block_at(&world, 3, 61, 0), block!("stone"));
+        history.undo(&world, &pool).unwrap().unwrap();
+        assert_eq!(block_at(&world, -3, 60, -3), BlockStateId::default());
+        assert!(history.undo(&world, &pool).unwrap().is_none());
+
+        history.redo(&world, &pool).unwrap().unwrap();
+        assert_eq!(block_at(&world, -3, 60, -3), block!("stone"));
+        assert_eq!(block_at(&world, 3, 61, 0), block!("stone"));
+    }
+
+    #[test]
+    fn test_edits_without_sky_leave_sky_light_dark() {
+        let world = World::with_backend(MemoryBackend::new()).unwrap();
+        let mut chunk = Chunk::new(Dimension::Nether.height());
+        LightEngine::new(&mut chunk).with_sky_light(false).relight();
+        world
+            .save_chunk(ChunkPos::new(0, 0), Dimension::Nether, Arc::new(chunk))
+            .unwrap();
+        let pool = ThreadPool::new();
+        let top = i32::from(Dimension::Nether.height().max_y()) - 1;
+        let sky_light = |x, y, z| {
+            world
+                .load_chunk(ChunkPos::new(0, 0), Dimension::Nether)
+                .unwrap()
+                .get_light(LightType::Sky, ChunkBlockPos::new(x, y as i16, z))
+                .unwrap()
+        };
+
+        // A few blocks are lit one at a time, a whole layer relights the chunk
+        for region in [
+            Region::new(BlockPos::of(0, top, 0), BlockPos::of(1, top, 1)),
+            Region::new(BlockPos::of(0, top - 2, 0), BlockPos::of(15, top - 2, 15)),
+        ] {
+            world
+                .fill_region(
+                    region,
+                    Dimension::Nether,
+                    block!("glass"),
+                    &BlockMask::Any,
+                    &pool,
+                )
+                .unwrap();
+            assert_eq!(sky_light(0, top, 0), 0);
+            assert_eq!(sky_light(5, top - 1, 5), 0);
+        }
+    }
+
+    #[test]
+    fn test_history_is_limited_by_changes() {
+        let edit = |blocks: usize| RegionEdit {
+            dimension: Dimension::Overworld,
+            chunks: vec![ChunkChange {
+                pos: ChunkPos::new(0, 0),
+                blocks: vec![
+                    BlockChange {
+                        pos: ChunkBlockPos::new(0, 0, 0),
+                        before: BlockStateId::default(),
+                        after: block!("stone"),
+                    };
+                    blocks
+                ],
+                block_entities_before: Vec::new(),
+                block_entities_after: Vec::new(),
+            }],
+        };
+        let mut history = EditHistory::default();
+        history.record(edit(1));
+        history.record(edit(MAX_HISTORY_CHANGES / 2));
+        assert_eq!(history.undo.len(), 2);
+        // The oldest edit goes first
+        history.record(edit(MAX_HISTORY_CHANGES / 2));
+        assert_eq!(history.undo.len(), 2);
+        assert_eq!(history.changes, MAX_HISTORY_CHANGES);
+        // Anything too large to keep at all clears the history
+        history.record(edit(MAX_HISTORY_CHANGES + 1));
+        assert!(history.undo.is_empty());
+        assert_eq!(history.changes, 0);
+    }
+
+    #[test]
+    fn test_move_overlapping_with_block_entity() {
+        let world = test_world();
+        let pool = ThreadPool::new();
+        let chest = block!("chest", {facing: "north", r#type: "single", waterlogged: false});
+        world
+            .set_block_and_fetch(BlockPos::of(-1, 64, -1), Dimension::Overworld, chest)
+            .unwrap();
+        world
+            .set_block_and_fetch(
+                BlockPos::of(0, 64, -1),
+                Dimension::Overworld,
+                block!("stone"),
+            )
+            .unwrap();
+
+        let region = Region::new(BlockPos::of(-1, 64, -1), BlockPos::of(0, 64, -1));
+        let edit = world
+            .move_region(region, IVec3::new(1, 0, 1), Dimension::Overworld, &pool)
+            .unwrap();
+        assert_eq!(block_at(&world, -1, 64, -1), BlockStateId::default());
+        assert_eq!(block_at(&world, 0, 64, -1), BlockStateId::default());
+        assert_eq!(block_at(&world, 0, 64, 0), chest);
+        assert_eq!(block_at(&world, 1, 64, 0), block!("stone"));
+        let moved = world
+            .get_block_entity(BlockPos::of(0, 64, 0), Dimension::Overworld)
+            .unwrap()
+            .unwrap();
+        assert_eq!(moved.kind, BlockEntityKind::Chest);
+        assert_eq!(edit.changed_block_entities(), vec![BlockPos::of(0, 64, 0)]);
+
+        world.undo_region_edit(&edit, &pool).unwrap();
+        assert_eq!(block_at(&world, -1, 64, -1), chest);
+        assert_eq!(block_at(&world, 0, 64, -1), block!("stone"));
+        assert_eq!(block_at(&world, 0, 64, 0), BlockStateId::default());
+        assert!(world
+            .get_block_entity(BlockPos::of(-1, 64, -1), Dimension::Overworld)
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn test_missing_chunk_changes_nothing() {
+        let world = test_world();
+        let pool = ThreadPool::new();
+        let region = Region::new(BlockPos::of(0, 64, 0), BlockPos::of(40, 64, 0));
+        assert!(world
+            .fill_region(
+                region,
+                Dimension::Overworld,
+                block!("stone"),
+                &BlockMask::Any,
+                &pool,
+            )
+            .is_err());
+        assert_eq!(block_at(&world, 0, 64, 0), BlockStateId::default());
+    }
+}
@@ -0,0 +1,404 @@
+//! Ticks scheduled for a block or fluid some game ticks from now.
+//!
+//! Vanilla doesn't change most blocks straight away when something next to them changes. Sand
+//! waits two game ticks before falling, water five before flowing on, and a repeater its delay
+//! before passing power along. Those are scheduled ticks, and like vanilla they're stored with the
+//! chunk they're in so they carry on after a restart. Only chunks in memory tick; the ticks of a
+//! chunk that's unloaded wait until it's loaded again.
+//!
+//! Ticks are due at a [`World::game_time`], which is stored with the world as well.
+
+use crate::chunk_format::Chunk;
+use crate::dimension::Dimension;
+use crate::errors::WorldError;
+use crate::pos::{BlockPos, ChunkBlockPos, ChunkPos};
+use crate::World;
+use ahash::{AHashMap, AHashSet};
+use bitcode_derive::{Decode, Encode};
+use deepsize::DeepSizeOf;
+use ferrumc_storage::backend::StorageBackend;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use tracing::debug;
+
+const WORLD_DATA_TABLE: &str = "world";
+const GAME_TIME_KEY: u128 = 0;
+
+/// What runs when a scheduled tick is due.
+#[derive(Encode, Decode, Clone, Copy, DeepSizeOf, Eq, PartialEq, Hash, Debug)]
+pub enum TickKind {
+    /// The block's own behaviour, like sand falling.
+    Block,
+    /// The fluid in the block flowing, see [`World::tick_fluid`].
+    Fluid,
+}
+
+/// A tick waiting to run in a chunk.
+#[derive(Encode, Decode, Clone, Copy, DeepSizeOf, Eq, PartialEq, Debug)]
+pub struct ScheduledTick {
+    pub kind: TickKind,
+    x: u8,
+    y: i16,
+    z: u8,
+    /// The [`World::game_time`] the tick is due at.
+    pub due: u64,
+    /// Ticks due at the same time run lowest priority first, then in the order they were
+    /// scheduled.
+    pub priority: i8,
+}
+
+impl ScheduledTick {
+    pub fn pos(&self) -> ChunkBlockPos {
+        ChunkBlockPos::new(self.x, self.y, self.z)
+    }
+}
+
+/// A tick to schedule with [`World::schedule_ticks`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TickRequest {
+    pub pos: BlockPos,
+    pub kind: TickKind,
+    /// Game ticks from now.
+    pub delay: u32,
+    pub priority: i8,
+}
+
+impl TickRequest {
+    pub fn new(pos: BlockPos, kind: TickKind, delay: u32) -> Self {
+        Self {
+            pos,
+            kind,
+            delay,
+            priority: 0,
+        }
+    }
+
+    pub fn with_priority(mut self, priority: i8) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+pub(crate) fn load_game_time(storage_backend: &dyn StorageBackend) -> Result<u64, WorldError> {
+    if !storage_backend.table_exists(WORLD_DATA_TABLE.to_string())? {
+        return Ok(0);
+    }
+    Ok(storage_backend
+        .get(WORLD_DATA_TABLE.to_string(), GAME_TIME_KEY)?
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u64::from_le_bytes)
+        .unwrap_or(0))
+}
+
+impl World {
+    /// Game ticks since the world was created. It only moves on in
+    /// [`World::run_scheduled_ticks`], and is saved by [`World::sync`].
+    pub fn game_time(&self) -> u64 {
+        self.game_time.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn save_game_time(&self) -> Result<(), WorldError> {
+        if !self
+            .storage_backend
+            .table_exists(WORLD_DATA_TABLE.to_string())?
+        {
+            self.storage_backend
+                .create_table(WORLD_DATA_TABLE.to_string())?;
+        }
+        self.storage_backend.upsert(
+            WORLD_DATA_TABLE.to_string(),
+            GAME_TIME_KEY,
+            self.game_time().to_le_bytes().to_vec(),
+        )?;
+        Ok(())
+    }
+
+    /// Keeps track of whether a chunk that's being put in memory has ticks to run.
+    pub(crate) fn track_scheduled_ticks(&self, pos: ChunkPos, dimension: Dimension, chunk: &Chunk) {
+        match chunk.scheduled_ticks.iter().map(|tick| tick.due).min() {
+            Some(due) => {
+                self.ticking_chunks.insert((pos, dimension), due);
+            }
+            None => {
+                self.ticking_chunks.remove(&(pos, dimension));
+            }
+        }
+    }
+
+    /// Changes the scheduled ticks of a chunk. Only the ticks change, so unlike
+    /// [`World::edit_chunk`] the chunk's revision stays the same and anything cached from its
+    /// blocks is still valid.
+    fn edit_scheduled_ticks<R>(
+        &self,
+        pos: ChunkPos,
+        dimension: Dimension,
+        f: impl FnOnce(&mut Vec<ScheduledTick>) -> R,
+    ) -> Result<R, WorldError> {
+        let _lock = self.chunk_locks.lock(pos, dimension);
+        let before = self.load_chunk(pos, dimension)?;
+        let mut chunk = (*before).clone();
+        let ret = f(&mut chunk.scheduled_ticks);
+        if chunk.scheduled_ticks != before.scheduled_ticks {
+            self.track_scheduled_ticks(pos, dimension, &chunk);
+            let chunk = Arc::new(chunk);
+            self.cache.insert((pos, dimension), chunk.clone());
+            self.dirty_chunks.insert((pos, dimension), chunk);
+        }
+        Ok(ret)
+    }
+
+    /// Schedules ticks, grouped by chunk so each chunk is only changed once. Like vanilla, a tick
+    /// for a block that already has one of the same kind waiting is dropped.
+    pub fn schedule_ticks(
+        &self,
+        dimension: Dimension,
+        ticks: impl IntoIterator<Item = TickRequest>,
+    ) -> Result<(), WorldError> {
+        let now = self.game_time();
+        let mut by_chunk: AHashMap<ChunkPos, Vec<ScheduledTick>> = AHashMap::new();
+        for request in ticks {
+            let pos = request.pos.chunk_block_pos();
+            by_chunk
+                .entry(request.pos.chunk())
+                .or_default()
+                .push(ScheduledTick {
+                    kind: request.kind,
+                    x: pos.x(),
+                    y: pos.y(),
+                    z: pos.z(),
+                    due: now + u64::from(request.delay),
+                    priority: request.priority,
+                });
+        }
+        for (pos, ticks) in by_chunk {
+            self.edit_scheduled_ticks(pos, dimension, |scheduled| {
+                let mut waiting: AHashSet<_> = scheduled
+                    .iter()
+                    .map(|tick| (tick.kind, tick.pos()))
+                    .collect();
+                scheduled.extend(
+                    ticks
+                        .into_iter()
+                        .filter(|tick| waiting.insert((tick.kind, tick.pos()))),
+                );
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Whether a tick of the given kind is waiting for a block.
+    pub fn has_scheduled_tick(
+        &self,
+        pos: BlockPos,
+        dimension: Dimension,
+        kind: TickKind,
+    ) -> Result<bool, WorldError> {
+        let chunk = self.load_chunk(pos.chunk(), dimension)?;
+        let block_pos = pos.chunk_block_pos();
+        Ok(chunk
+            .scheduled_ticks
+            .iter()
+            .any(|tick| tick.kind == kind && tick.pos() == block_pos))
+    }
+
+    /// How many ticks are waiting in the chunks in memory, whether they're due yet or not.
+    pub fn scheduled_tick_count(&self) -> usize {
+        // The cache's eviction listener removes from `ticking_chunks`, so the keys are copied out
+        // before touching the cache rather than holding the map's guard while reading it.
+        let chunks: Vec<_> = self
+            .ticking_chunks
+            .iter()
+            .map(|entry| *entry.key())
+            .collect();
+        chunks
+            .into_iter()
+            .filter_map(|(pos, dimension)| self.get_cached(pos, dimension))
+            .map(|chunk| chunk.scheduled_ticks.len())
+            .sum()
+    }
+
+    /// Moves the game time on by one tick and runs the scheduled ticks that are due, at most
+    /// `budget` of them. Ticks over the budget stay where they are and run first on the next call.
+    /// The blocks around every block a tick changes are updated (see [`World::update_neighbours`]),
+    /// which may schedule more ticks.
+    ///
+    /// Ticks run in order of when they're due, then their priority, then by dimension and chunk,
+    /// so the same world always ticks the same way.
+    ///
+    /// Returns every block that changed, by dimension.
+    pub fn run_scheduled_ticks(&self, budget: usize) -> AHashMap<Dimension, Vec<BlockPos>> {
+        let now = self.game_time.fetch_add(1, Ordering::Relaxed) + 1;
+
+        let ready: Vec<_> = self
+            .ticking_chunks
+            .iter()
+            .filter(|entry| *entry.value() <= now)
+            .map(|entry| *entry.key())
+            .collect();
+        let mut due = Vec::new();
+        for (pos, dimension) in ready {
+            let Some(chunk) = self.get_cached(pos, dimension) else {
+                self.ticking_chunks.remove(&(pos, dimension));
+                continue;
+            };
+            due.extend(
+                chunk
+                    .scheduled_ticks
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, tick)| tick.due <= now)
+                    .map(|(index, tick)| (pos, dimension, index, *tick)),
+            );
+        }
+        due.sort_by_key(|(pos, dimension, index, tick)| {
+            (
+                tick.due,
+                tick.priority,
+                *dimension as u8,
+                pos.x(),
+                pos.z(),
+                *index,
+            )
+        });
+        due.truncate(budget);
+
+        let mut taken: AHashMap<(ChunkPos, Dimension), Vec<ScheduledTick>> = AHashMap::new();
+        for (pos, dimension, _, tick) in &due {
+            taken.entry((*pos, *dimension)).or_default().push(*tick);
+        }
+        for ((pos, dimension), ticks) in taken {
+            let result = self.edit_scheduled_ticks(pos, dimension, |scheduled| {
+                scheduled.retain(|tick| !ticks.contains(tick));
+            });
+            if let Err(err) = result {
+                debug!("Failed to take the due ticks of {pos} in {dimension}: {err}");
+            }
+        }
+
+        let mut changed: AHashMap<Dimension, Vec<BlockPos>> = AHashMap::new();
+        for (pos, dimension, _, tick) in due {
+            let pos = pos.chunk_block(tick.pos());
+            let result = match tick.kind {
+                TickKind::Block => self.tick_block(pos, dimension),
+                TickKind::Fluid => self.tick_fluid(pos, dimension),
+            }
+            .and_then(|mut ticked| {
+                let updated = self.update_neighbours(dimension, &ticked)?;
+                ticked.extend(updated);
+                Ok(ticked)
+            });
+            match result {
+                Ok(ticked) => changed.entry(dimension).or_default().extend(ticked),
+                Err(err) => debug!("Failed to run the {tick:?} at {pos} in {dimension}: {err}"),
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_state_id::BlockStateId;
+    use ferrumc_macros::block;
+    use ferrumc_storage::memory::MemoryBackend;
+
+    fn test_world() -> World {
+        let world = World::with_backend(MemoryBackend::new()).unwrap();
+        for x in -1..=1 {
+            for z in -1..=1 {
+                world
+                    .save_chunk(
+                        ChunkPos::new(x, z),
+                        Dimension::Overworld,
+                        Arc::new(Chunk::new(Dimension::Overworld.height())),
+                    )
+                    .unwrap();
+            }
+        }
+        world
+    }
+
+    #[test]
+    fn test_ticks_run_when_due_and_in_order() {
+        let world = test_world();
+        let first = BlockPos::of(20, 64, 3);
+        let second = BlockPos::of(-5, 64, 3);
+        world
+            .set_block_and_fetch(first, Dimension::Overworld, block!("sand"))
+            .unwrap();
+        world
+            .set_block_and_fetch(second, Dimension::Overworld, block!("sand"))
+            .unwrap();
+        world
+            .schedule_ticks(
+                Dimension::Overworld,
+                [
+                    TickRequest::new(first, TickKind::Block, 3),
+                    TickRequest::new(second, TickKind::Block, 3).with_priority(-1),
+                    // Already waiting, so dropped
+                    TickRequest::new(first, TickKind::Block, 1),
+                ],
+            )
+            .unwrap();
+        assert_eq!(world.scheduled_tick_count(), 2);
+        assert!(world
+            .has_scheduled_tick(first, Dimension::Overworld, TickKind::Block)
+            .unwrap());
+        // Scheduling doesn't count as a change to the chunk's blocks
+        let revision = world.chunk_revision(first.chunk(), Dimension::Overworld);
+        world
+            .schedule_ticks(
+                Dimension::Overworld,
+                [TickRequest::new(first, TickKind::Fluid, 9)],
+            )
+            .unwrap();
+        assert_eq!(
+            world.chunk_revision(first.chunk(), Dimension::Overworld),
+            revision
+        );
+
+        assert!(world.run_scheduled_ticks(usize::MAX).is_empty());
+        assert!(world.run_scheduled_ticks(usize::MAX).is_empty());
+        // The higher priority one runs first when they're over budget
+        let changed = world.run_scheduled_ticks(1);
+        assert!(changed[&Dimension::Overworld].contains(&second));
+        assert_eq!(
+            world
+                .get_block_and_fetch(second, Dimension::Overworld)
+                .unwrap(),
+            BlockStateId::default()
+        );
+        let changed = world.run_scheduled_ticks(1);
+        assert!(changed[&Dimension::Overworld].contains(&first));
+        assert_eq!(world.scheduled_tick_count(), 1);
+    }
+
+    #[test]
+    fn test_ticks_are_stored_with_the_chunk() {
+        let world = test_world();
+        let pos = BlockPos::of(4, 70, 4);
+        world.run_scheduled_ticks(0);
+        world
+            .schedule_ticks(
+                Dimension::Overworld,
+                [TickRequest::new(pos, TickKind::Block, 40)],
+            )
+            .unwrap();
+        world.sync().unwrap();
+        assert_eq!(
+            load_game_time(&*world.storage_backend).unwrap(),
+            world.game_time()
+        );
+
+        // Unloaded chunks don't tick, loading them again picks their ticks back up
+        world.cache.invalidate_all();
+        world.cache.run_pending_tasks();
+        assert_eq!(world.scheduled_tick_count(), 0);
+        world.load_chunk(pos.chunk(), Dimension::Overworld).unwrap();
+        assert_eq!(world.scheduled_tick_count(), 1);
+        let chunk = world.load_chunk(pos.chunk(), Dimension::Overworld).unwrap();
+        assert_eq!(chunk.scheduled_ticks[0].pos(), pos.chunk_block_pos());
+        assert_eq!(chunk.scheduled_ticks[0].due, 41);
+    }
+}
@@ -0,0 +1,1130 @@
+//! Reading and writing builds as schematics, so they can be moved between worlds and servers.
+//!
+//! Three formats are supported: Sponge schematics (`.schem`) versions 2 and 3, as written by
+//! WorldEdit and most other editing tools, and vanilla structure files (`.nbt`), as written by
+//! structure blocks. Blocks are mapped to and from their state strings through [`BLOCK2ID`], and
+//! block entities are kept as raw NBT, see [`crate::block_entity`].
+//!
+//! Schematics are pasted with [`World::paste_schematic`], which can rotate and mirror them, and
+//! regions of the world are saved with [`World::copy_schematic`].
+
+use crate::block_entity::BlockEntityKind;
+use crate::block_state_id::{BlockStateId, BLOCK2ID};
+use crate::chunk_format::DATA_VERSION;
+use crate::dimension::Dimension;
+use crate::edit_batch::EditBatch;
+use crate::errors::WorldError;
+use crate::pos::{BlockPos, ChunkBlockPos, ChunkPos};
+use crate::region_edit::ChunkTargets;
+use crate::vanilla_chunk_format::BlockData;
+use crate::World;
+use ahash::AHashMap;
+use bevy_math::IVec3;
+use ferrumc_macros::{block, NBTDeserialize, NBTSerialize};
+use ferrumc_nbt::{
+    FromNbt, NBTSerializable, NBTSerializeOptions, NbtTape, NbtTapeElement, RawCompound,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write};
+use std::path::Path;
+use tracing::warn;
+
+/// The file formats a [`Schematic`] can be saved in. Reading detects the format by itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SchematicFormat {
+    /// Sponge schematic version 2, for tools that don't read version 3 yet.
+    SpongeV2,
+    /// Sponge schematic version 3, what current versions of WorldEdit write.
+    SpongeV3,
+    /// A vanilla structure file, which structure blocks can load.
+    Structure,
+}
+
+/// The most blocks a schematic read from a file can hold. Sizes come straight from the file, so
+/// without a limit a few bytes could ask for more memory than the server has.
+pub const MAX_FILE_VOLUME: usize = 1 << 26;
+
+/// How far a schematic is turned around the vertical axis when it's pasted, looking from above.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Rotation {
+    #[default]
+    None,
+    Clockwise90,
+    Clockwise180,
+    CounterClockwise90,
+}
+
+impl Rotation {
+    fn quarter_turns(self) -> usize {
+        match self {
+            Rotation::None => 0,
+            Rotation::Clockwise90 => 1,
+            Rotation::Clockwise180 => 2,
+            Rotation::CounterClockwise90 => 3,
+        }
+    }
+}
+
+/// How a schematic is flipped when it's pasted. Mirroring happens before rotating, like it does
+/// for vanilla structures.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Mirror {
+    #[default]
+    None,
+    /// Flips along the z axis, so north and south swap.
+    LeftRight,
+    /// Flips along the x axis, so east and west swap.
+    FrontBack,
+}
+
+/// A block entity in a schematic, positioned relative to the schematic's corner.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SchematicBlockEntity {
+    pub pos: IVec3,
+    pub kind: BlockEntityKind,
+    /// The block entity's tags, without its id and position.
+    pub data: RawCompound,
+}
+
+/// A box of blocks that can be saved to a file and pasted into a world.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Schematic {
+    width: u16,
+    height: u16,
+    length: u16,
+    /// Indexed by `(y * length + z) * width + x`, the order Sponge schematics use.
+    blocks: Vec<BlockStateId>,
+    pub block_entities: Vec<SchematicBlockEntity>,
+    /// Where the schematic was copied from relative to whoever copied it, as WorldEdit records it.
+    /// It's only kept so it survives being loaded and saved again, pasting ignores it.
+    pub offset: IVec3,
+}
+
+impl Schematic {
+    /// An empty schematic of the given size, filled with air.
+    pub fn new(width: u16, height: u16, length: u16) -> Self {
+        Self::filled(width, height, length, BlockStateId::default())
+    }
+
+    fn filled(width: u16, height: u16, length: u16, block: BlockStateId) -> Self {
+        Self {
+            width,
+            height,
+            length,
+            blocks: vec![block; width as usize * height as usize * length as usize],
+            block_entities: Vec::new(),
+            offset: IVec3::ZERO,
+        }
+    }
+
+    /// The size along the x axis.
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    /// The size along the y axis.
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// The size along the z axis.
+    pub fn length(&self) -> u16 {
+        self.length
+    }
+
+    fn index(&self, pos: IVec3) -> Option<usize> {
+        let in_bounds = pos.cmpge(IVec3::ZERO).all()
+            && pos.x < i32::from(self.width)
+            && pos.y < i32::from(self.height)
+            && pos.z < i32::from(self.length);
+        in_bounds.then(|| {
+            ((pos.y as usize * self.length as usize + pos.z as usize) * self.width as usize)
+                + pos.x as usize
+        })
+    }
+
+    fn pos(&self, index: usize) -> IVec3 {
+        let width = self.width as usize;
+        let length = self.length as usize;
+        IVec3::new(
+            (index % width) as i32,
+            (index / (width * length)) as i32,
+            (index / width % length) as i32,
+        )
+    }
+
+    /// The block at a position relative to the schematic's corner, or `None` if it's outside.
+    pub fn get_block(&self, pos: IVec3) -> Option<BlockStateId> {
+        self.index(pos).map(|index| self.blocks[index])
+    }
+
+    pub fn set_block(&mut self, pos: IVec3, block: BlockStateId) -> Result<(), WorldError> {
+        let index = self.index(pos).ok_or_else(|| {
+            WorldError::InvalidSchematic(format!(
+                "{pos} is outside of the {}x{}x{} schematic",
+                self.width, self.height, self.length
+            ))
+        })?;
+        self.blocks[index] = block;
+        Ok(())
+    }
+
+    /// Every block with its position, in storage order.
+    pub fn blocks(&self) -> impl Iterator<Item = (IVec3, BlockStateId)> + '_ {
+        self.blocks
+            .iter()
+            .enumerate()
+            .map(|(index, block)| (self.pos(index), *block))
+    }
+
+    /// A copy of the schematic mirrored and then rotated, with its corner staying at the origin.
+    /// Directional block states, such as stairs or signs, are turned along with their positions.
+    pub fn transformed(&self, rotation: Rotation, mirror: Mirror) -> Schematic {
+        let (width, length) = if rotation.quarter_turns() % 2 == 1 {
+            (self.length, self.width)
+        } else {
+            (self.width, self.length)
+        };
+        let mut transformed =
+            Schematic::filled(width, self.height, length, BlockStateId::default());
+        transformed.offset = self.offset;
+
+        let mut states = AHashMap::new();
+        for (pos, block) in self.blocks() {
+            let block = *states
+                .entry(block)
+                .or_insert_with(|| transform_block(block, rotation, mirror));
+            let index = transformed
+                .index(self.transform_pos(pos, rotation, mirror))
+                .expect("transformed positions stay inside the transformed schematic");
+            transformed.blocks[index] = block;
+        }
+        transformed.block_entities = self
+            .block_entities
+            .iter()
+            .map(|block_entity| SchematicBlockEntity {
+                pos: self.transform_pos(block_entity.pos, rotation, mirror),
+                ..block_entity.clone()
+            })
+            .collect();
+        transformed
+    }
+
+    fn transform_pos(&self, pos: IVec3, rotation: Rotation, mirror: Mirror) -> IVec3 {
+        let width = i32::from(self.width);
+        let length = i32::from(self.length);
+        let (x, z) = match mirror {
+            Mirror::None => (pos.x, pos.z),
+            Mirror::LeftRight => (pos.x, length - 1 - pos.z),
+            Mirror::FrontBack => (width - 1 - pos.x, pos.z),
+        };
+        let (x, z) = match rotation {
+            Rotation::None => (x, z),
+            Rotation::Clockwise90 => (length - 1 - z, x),
+            Rotation::Clockwise180 => (width - 1 - x, length - 1 - z),
+            Rotation::CounterClockwise90 => (z, width - 1 - x),
+        };
+        IVec3::new(x, pos.y, z)
+    }
+
+    /// Reads a schematic in any of the [`SchematicFormat`]s, gzipped or not.
+    pub fn read(bytes: &[u8]) -> Result<Schematic, WorldError> {
+        let mut data = Vec::new();
+        let bytes = if bytes.starts_with(&[0x1f, 0x8b]) {
+            flate2::read::GzDecoder::new(bytes).read_to_end(&mut data)?;
+            data.as_slice()
+        } else {
+            bytes
+        };
+        // The tape panics on anything that isn't a compound, so check before handing it over
+        if bytes.first() != Some(&10) {
+            return Err(WorldError::InvalidSchematic(
+                "The root tag isn't a compound".to_string(),
+            ));
+        }
+
+        let mut tape = NbtTape::new(bytes);
+        tape.parse();
+        let Some((_, root)) = tape.root.as_ref() else {
+            return Err(WorldError::InvalidSchematic("Missing root tag".to_string()));
+        };
+        if let Some(schematic) = root.get("Schematic") {
+            Self::from_sponge_v3(SpongeV3::from_nbt(&tape, schematic)?)
+        } else if root.get("Palette").is_some() {
+            Self::from_sponge_v2(SpongeV2::from_nbt(&tape, root)?)
+        } else if root.get("blocks").is_some() {
+            Self::from_structure(StructureFile::from_nbt(&tape, root)?)
+        } else {
+            Err(WorldError::InvalidSchematic(
+                "Not a Sponge schematic or structure file".to_string(),
+            ))
+        }
+    }
+
+    /// Writes the schematic in the given format, gzipped like the games and tools expect.
+    pub fn write(&self, format: SchematicFormat) -> Result<Vec<u8>, WorldError> {
+        // Sponge sizes are shorts, and reading rejects negative ones
+        let too_big = [self.width, self.height, self.length]
+            .iter()
+            .any(|size| i16::try_from(*size).is_err());
+        if too_big && format != SchematicFormat::Structure {
+            return Err(WorldError::InvalidSchematic(format!(
+                "Sponge schematics can be at most {} blocks along each axis",
+                i16::MAX
+            )));
+        }
+        let mut nbt = Vec::new();
+        match format {
+            SchematicFormat::SpongeV2 => self
+                .to_sponge_v2()
+                .serialize(&mut nbt, &NBTSerializeOptions::WithHeader("Schematic")),
+            SchematicFormat::SpongeV3 => SpongeV3File {
+                schematic: self.to_sponge_v3(),
+            }
+            .serialize(&mut nbt, &NBTSerializeOptions::WithHeader("")),
+            SchematicFormat::Structure => self
+                .to_structure()
+                .serialize(&mut nbt, &NBTSerializeOptions::WithHeader("")),
+        }
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&nbt)?;
+        Ok(encoder.finish()?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Schematic, WorldError> {
+        Self::read(&std::fs::read(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>, format: SchematicFormat) -> Result<(), WorldError> {
+        std::fs::write(path, self.write(format)?)?;
+        Ok(())
+    }
+
+    fn from_sponge_v2(file: SpongeV2) -> Result<Schematic, WorldError> {
+        let mut schematic = Self::from_sponge_blocks(
+            [file.width, file.height, file.length],
+            &file.palette,
+            &file.block_data,
+        )?;
+        schematic.offset = offset(file.offset);
+        for mut data in file.block_entities.unwrap_or_default() {
+            let id = data.get::<String>("Id");
+            let pos = data.get::<Vec<i32>>("Pos");
+            data.remove("Id");
+            data.remove("Pos");
+            schematic.add_block_entity(id, pos, data);
+        }
+        Ok(schematic)
+    }
+
+    fn from_sponge_v3(file: SpongeV3) -> Result<Schematic, WorldError> {
+        let size = [file.width, file.height, file.length];
+        let Some(blocks) = file.blocks else {
+            let [width, height, length] = file_size(size)?;
+            return Ok(Schematic::new(width, height, length));
+        };
+        let mut schematic = Self::from_sponge_blocks(size, &blocks.palette, &blocks.data)?;
+        schematic.offset = offset(file.offset);
+        for block_entity in blocks.block_entities.unwrap_or_default() {
+            let id = block_entity.get::<String>("Id");
+            let pos = block_entity.get::<Vec<i32>>("Pos");
+            let data = block_entity.get::<RawCompound>("Data").unwrap_or_default();
+            schematic.add_block_entity(id, pos, data);
+        }
+        Ok(schematic)
+    }
+
+    fn from_sponge_blocks(
+        [width, height, length]: [i16; 3],
+        palette: &HashMap<String, i32>,
+        data: &[i8],
+    ) -> Result<Schematic, WorldError> {
+        let mut states = vec![BlockStateId::default(); palette.len()];
+        for (state, index) in palette {
+            let slot = usize::try_from(*index)
+                .ok()
+                .and_then(|index| states.get_mut(index))
+                .ok_or_else(|| {
+                    WorldError::InvalidSchematic(format!("Palette index {index} is out of range"))
+                })?;
+            *slot = lookup_block(&BlockData::from_state_string(state));
+        }
+
+        let [width, height, length] = file_size([width, height, length])?;
+        let mut schematic = Schematic::new(width, height, length);
+        let mut bytes = data.iter().map(|byte| *byte as u8);
+        for block in &mut schematic.blocks {
+            let index = read_varint(&mut bytes)
+                .ok_or_else(|| WorldError::InvalidSchematic("Block data ends early".to_string()))?;
+            *block = *states.get(index as usize).ok_or_else(|| {
+                WorldError::InvalidSchematic(format!("Palette index {index} is out of range"))
+            })?;
+        }
+        Ok(schematic)
+    }
+
+    fn from_structure(file: StructureFile) -> Result<Schematic, WorldError> {
+        let [width, height, length] =
+            file_size(<[i32; 3]>::try_from(file.size.0).map_err(|_| {
+                WorldError::InvalidSchematic("The size needs 3 values".to_string())
+            })?)?;
+        let palette = file
+            .palette
+            .or_else(|| {
+                file.palettes
+                    .and_then(|palettes| palettes.into_iter().next())
+            })
+            .unwrap_or_default();
+        let states = palette.iter().map(lookup_block).collect::<Vec<_>>();
+
+        // Positions the structure doesn't list are left alone when it's placed
+        let mut schematic = Schematic::filled(width, height, length, block!("structure_void"));
+        for block in file.blocks {
+            let pos = <[i32; 3]>::try_from(block.pos.0)
+                .map(IVec3::from_array)
+                .map_err(|_| {
+                    WorldError::InvalidSchematic("A position needs 3 values".to_string())
+                })?;
+            let state = *usize::try_from(block.state)
+                .ok()
+                .and_then(|state| states.get(state))
+                .ok_or_else(|| {
+                    WorldError::InvalidSchematic(format!(
+                        "Palette index {} is out of range",
+                        block.state
+                    ))
+                })?;
+            schematic.set_block(pos, state)?;
+            if let Some(mut data) = block.nbt {
+                let id = data.get::<String>("id");
+                for key in ["id", "x", "y", "z"] {
+                    data.remove(key);
+                }
+                schematic.add_block_entity(id, Some(pos.to_array().to_vec()), data);
+            }
+        }
+        Ok(schematic)
+    }
+
+    /// Adds a block entity that was read from a file, leaving out the ones we can't place.
+    fn add_block_entity(&mut self, id: Option<String>, pos: Option<Vec<i32>>, data: RawCompound) {
+        let Some(kind) = id.as_deref().and_then(BlockEntityKind::from_identifier) else {
+            warn!("Skipping unsupported block entity {id:?} in schematic");
+            return;
+        };
+        let Some(pos) = pos
+            .and_then(|pos| <[i32; 3]>::try_from(pos).ok())
+            .map(IVec3::from_array)
+        else {
+            warn!(
+                "Skipping {} without a position in schematic",
+                kind.identifier()
+            );
+            return;
+        };
+        if self.get_block(pos).and_then(BlockEntityKind::for_block) != Some(kind) {
+            warn!(
+                "Skipping {} at {pos} in schematic, the block there doesn't carry it",
+                kind.identifier()
+            );
+            return;
+        }
+        self.block_entities
+            .push(SchematicBlockEntity { pos, kind, data });
+    }
+
+    /// The palette and varint encoded block data Sponge schematics store blocks as.
+    fn to_sponge_blocks(&self) -> (HashMap<String, i32>, Vec<i8>) {
+        let mut indices = AHashMap::new();
+        let mut palette = HashMap::new();
+        let mut data = Vec::with_capacity(self.blocks.len());
+        for block in &self.blocks {
+            let index = *indices.entry(*block).or_insert_with(|| {
+                let index = palette.len() as i32;
+                palette.insert(BlockData::from(*block).to_state_string(), index);
+                index
+            });
+            write_varint(&mut data, index as u32);
+        }
+        (palette, data.into_iter().map(|byte| byte as i8).collect())
+    }
+
+    fn to_sponge_v2(&self) -> SpongeV2 {
+        let (palette, block_data) = self.to_sponge_blocks();
+        let block_entities = self
+            .block_entities
+            .iter()
+            .map(|block_entity| {
+                let mut data = block_entity.data.clone();
+                data.insert("Id", &block_entity.kind.identifier());
+                data.insert("Pos", &block_entity.pos.to_array().to_vec());
+                data
+            })
+            .collect();
+        SpongeV2 {
+            version: 2,
+            data_version: Some(DATA_VERSION),
+            width: self.width as i16,
+            height: self.height as i16,
+            length: self.length as i16,
+            offset: Some(self.offset.to_array().to_vec()),
+            palette_max: Some(palette.len() as i32),
+            palette,
+            block_data,
+            block_entities: Some(block_entities),
+        }
+    }
+
+    fn to_sponge_v3(&self) -> SpongeV3 {
+        let (palette, data) = self.to_sponge_blocks();
+        let block_entities = self
+            .block_entities
+            .iter()
+            .map(|block_entity| {
+                let mut compound = RawCompound::new();
+                compound.insert("Id", &block_entity.kind.identifier());
+                compound.insert("Pos", &block_entity.pos.to_array().to_vec());
+                compound.insert("Data", &block_entity.data);
+                compound
+            })
+            .collect();
+        SpongeV3 {
+            version: 3,
+            data_version: DATA_VERSION,
+            width: self.width as i16,
+            height: self.height as i16,
+            length: self.length as i16,
+            offset: Some(self.offset.to_array().to_vec()),
+            blocks: Some(SpongeV3Blocks {
+                palette,
+                data,
+                block_entities: Some(block_entities),
+            }),
+        }
+    }
+
+    fn to_structure(&self) -> StructureFile {
+        let block_entities = self
+            .block_entities
+            .iter()
+            .map(|block_entity| (block_entity.pos, block_entity))
+            .collect::<AHashMap<_, _>>();
+        let structure_void = block!("structure_void");
+
+        let mut indices = AHashMap::new();
+        let mut palette = Vec::new();
+        let mut blocks = Vec::new();
+        for (pos, block) in self.blocks() {
+            if block == structure_void {
+                continue;
+            }
+            let state = *indices.entry(block).or_insert_with(|| {
+                palette.push(BlockData::from(block));
+                palette.len() as i32 - 1
+            });
+            let nbt = block_entities.get(&pos).map(|block_entity| {
+                let mut data = block_entity.data.clone();
+                data.insert("id", &block_entity.kind.identifier());
+                data
+            });
+            blocks.push(StructureBlock {
+                pos: IntList(pos.to_array().to_vec()),
+                state,
+                nbt,
+            });
+        }
+        StructureFile {
+            data_version: DATA_VERSION,
+            size: IntList(vec![
+                i32::from(self.width),
+                i32::from(self.height),
+                i32::from(self.length),
+            ]),
+            palette: Some(palette),
+            palettes: None,
+            blocks,
+            entities: Some(Vec::new()),
+        }
+    }
+}
+
+/// Checks the size a file gives before anything is allocated for it.
+fn file_size<T>(size: [T; 3]) -> Result<[u16; 3], WorldError>
+where
+    T: Copy + std::fmt::Display,
+    u16: TryFrom<T>,
+{
+    let mut checked = [0; 3];
+    for (checked, size) in checked.iter_mut().zip(size) {
+        *checked = u16::try_from(size)
+            .map_err(|_| WorldError::InvalidSchematic(format!("Invalid size {size}")))?;
+    }
+    let volume = checked.iter().map(|size| *size as usize).product::<usize>();
+    if volume > MAX_FILE_VOLUME {
+        return Err(WorldError::InvalidSchematic(format!(
+            "{volume} blocks is more than the limit of {MAX_FILE_VOLUME}"
+        )));
+    }
+    Ok(checked)
+}
+
+fn offset(offset: Option<Vec<i32>>) -> IVec3 {
+    offset
+        .and_then(|offset| <[i32; 3]>::try_from(offset).ok())
+        .map(IVec3::from_array)
+        .unwrap_or_default()
+}
+
+impl World {
+    /// Pastes a schematic with its corner at `origin`, mirroring and then rotating it first, see
+    /// [`Schematic::transformed`]. Structure voids are skipped, as are blocks that would end up
+    /// above or below the world.
+    ///
+    /// Every chunk the schematic covers is edited through [`World::edit_chunk`], so light is only
+    /// updated inside each chunk.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<BlockPos>)` - Every block whose state changed, which should be sent to any
+    ///   players that have the chunks loaded.
+    /// * `Err(WorldError)` - If a chunk couldn't be loaded or edited. Chunks that were pasted into
+    ///   before the error keep their changes.
+    pub fn paste_schematic(
+        &self,
+        schematic: &Schematic,
+        origin: BlockPos,
+        dimension: Dimension,
+        rotation: Rotation,
+        mirror: Mirror,
+    ) -> Result<Vec<BlockPos>, WorldError> {
+        let transformed;
+        let schematic = if rotation == Rotation::None && mirror == Mirror::None {
+            schematic
+        } else {
+            transformed = schematic.transformed(rotation, mirror);
+            &transformed
+        };
+
+        let mut changed = Vec::new();
+        for (chunk_pos, targets) in ChunkTargets::from_schematic(schematic, origin) {
+            let ChunkTargets {
+                blocks,
+                block_entities,
+            } = targets;
+            let (_, chunk_changed) = self.edit_chunk(chunk_pos, dimension, |chunk| {
+                let in_world = |pos: ChunkBlockPos, chunk: &crate::chunk_format::Chunk| {
+                    chunk.get_section(pos.section()).is_some()
+                };
+                let blocks = blocks
+                    .into_iter()
+                    .filter(|(pos, _)| in_world(*pos, chunk))
+                    .collect::<Vec<_>>();
+                if !blocks.is_empty() {
+                    let mut batch = EditBatch::new(chunk).with_sky_light(dimension.has_sky_light());
+                    for (pos, block) in blocks {
+                        batch.set_block(pos, block);
+                    }
+                    batch.apply()?;
+                }
+                for block_entity in block_entities {
+                    if in_world(block_entity.pos(), chunk) {
+                        chunk.set_block_entity(block_entity)?;
+                    }
+                }
+                Ok(())
+            })?;
+            changed.extend(chunk_changed);
+        }
+        Ok(changed)
+    }
+
+    /// Copies the box between the two corners, inclusive, into a schematic. Every chunk the box
+    /// covers has to exist.
+    pub fn copy_schematic(
+        &self,
+        from: BlockPos,
+        to: BlockPos,
+        dimension: Dimension,
+    ) -> Result<Schematic, WorldError> {
+        let min = from.pos.min(to.pos);
+        let max = from.pos.max(to.pos);
+        let size = (max - min + IVec3::ONE).to_array();
+        let [width, height, length] = size.map(|size| u16::try_from(size).ok());
+        let (Some(width), Some(height), Some(length)) = (width, height, length) else {
+            return Err(WorldError::InvalidSchematic(format!(
+                "A {}x{}x{} region is too large for a schematic",
+                size[0], size[1], size[2]
+            )));
+        };
+
+        let mut schematic = Schematic::new(width, height, length);
+        for chunk_x in min.x.div_euclid(16)..=max.x.div_euclid(16) {
+            for chunk_z in min.z.div_euclid(16)..=max.z.div_euclid(16) {
+                let chunk_pos = ChunkPos::new(chunk_x, chunk_z);
+                let chunk = self.load_chunk(chunk_pos, dimension)?;
+                let xs = min.x.max(chunk_x * 16)..=max.x.min(chunk_x * 16 + 15);
+                let zs = min.z.max(chunk_z * 16)..=max.z.min(chunk_z * 16 + 15);
+                for y in min.y..=max.y {
+                    for z in zs.clone() {
+                        for x in xs.clone() {
+                            let block = chunk.get_block(BlockPos::of(x, y, z).chunk_block_pos())?;
+                            schematic.set_block(IVec3::new(x, y, z) - min, block)?;
+                        }
+                    }
+                }
+                for block_entity in &chunk.block_entities {
+                    let pos = chunk_pos.chunk_block(block_entity.pos()).pos;
+                    if pos.cmpge(min).all() && pos.cmple(max).all() {
+                        schematic.block_entities.push(SchematicBlockEntity {
+                            pos: pos - min,
+                            kind: block_entity.kind,
+                            data: block_entity.data(),
+                        });
+                    }
+                }
+            }
+        }
+        Ok(schematic)
+    }
+}
+
+/// Finds the state for a palette entry, see [`BlockStateId::find`]. Blocks we don't know about
+/// become air.
+fn lookup_block(data: &BlockData) -> BlockStateId {
+    let mut data = data.clone();
+    if !data.name.contains(':') {
+        data.name = format!("minecraft:{}", data.name);
+    }
+    BlockStateId::find(&data).unwrap_or_else(|| {
+        warn!("Unknown block {data:?} in schematic, replacing it with air");
+        BlockStateId::default()
+    })
+}
+
+const DIRECTIONS: [&str; 4] = ["north", "east", "south", "west"];
+
+fn transform_direction(
+    direction: &str,
+    rotation: Rotation,
+    mirror: Mirror,
+) -> Option<&'static str> {
+    let index = DIRECTIONS.iter().position(|known| *known == direction)?;
+    let index = match (mirror, index) {
+        (Mirror::LeftRight, 0 | 2) | (Mirror::FrontBack, 1 | 3) => (index + 2) % 4,
+        _ => index,
+    };
+    Some(DIRECTIONS[(index + rotation.quarter_turns()) % 4])
+}
+
+/// Turns a block state along with the schematic, e.g. a stair facing north that's rotated
+/// clockwise faces east. Properties that hold directions, like `facing` or the sides of a fence,
+/// are remapped, and mirroring swaps left and right.
+fn transform_block(block: BlockStateId, rotation: Rotation, mirror: Mirror) -> BlockStateId {
+    let Some(BlockData {
+        name,
+        properties: Some(properties),
+    }) = block.to_block_data()
+    else {
+        return block;
+    };
+
+    let mut transformed = BTreeMap::new();
+    for (key, value) in properties {
+        let key = transform_direction(&key, rotation, mirror)
+            .map(str::to_string)
+            .unwrap_or(key);
+        let value = match key.as_str() {
+            "facing" | "shape" | "orientation" | "type" | "hinge" => value
+                .split('_')
+                .map(|word| match word {
+                    "left" if mirror != Mirror::None => "right",
+                    "right" if mirror != Mirror::None => "left",
+                    _ => transform_direction(word, rotation, mirror).unwrap_or(word),
+                })
+                .collect::<Vec<_>>()
+                .join("_"),
+            "axis" if rotation.quarter_turns() % 2 == 1 => match value.as_str() {
+                "x" => "z".to_string(),
+                "z" => "x".to_string(),
+                _ => value,
+            },
+            // Sixteen steps counting clockwise from south
+            "rotation" => match value.parse::<i32>() {
+                Ok(steps) => {
+                    let steps = match mirror {
+                        Mirror::None => steps,
+                        Mirror::LeftRight => 8 - steps,
+                        Mirror::FrontBack => 16 - steps,
+                    };
+                    (steps + 4 * rotation.quarter_turns() as i32)
+                        .rem_euclid(16)
+                        .to_string()
+                }
+                Err(_) => value,
+            },
+            _ => value,
+        };
+        transformed.insert(key, value);
+    }
+
+    let mut data = BlockData {
+        name,
+        properties: Some(transformed),
+    };
+    if let Some(id) = BLOCK2ID.get(&data) {
+        return BlockStateId::new(*id as u32);
+    }
+    // Rail shapes only exist with the directions in one order, e.g. `north_east` but not
+    // `east_north`
+    if let Some(shape) = data
+        .properties
+        .as_mut()
+        .and_then(|properties| properties.get_mut("shape"))
+    {
+        if let Some((first, second)) = shape.split_once('_') {
+            *shape = format!("{second}_{first}");
+        }
+    }
+    BLOCK2ID
+        .get(&data)
+        .map_or(block, |id| BlockStateId::new(*id as u32))
+}
+
+fn read_varint(bytes: &mut impl Iterator<Item = u8>) -> Option<u32> {
+    let mut value = 0u32;
+    for shift in (0..35).step_by(7) {
+        let byte = bytes.next()?;
+        value |= u32::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        if value < 0x80 {
+            out.push(value as u8);
+            return;
+        }
+        out.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+}
+
+/// A list of ints. Vanilla structures store positions like this rather than as an int array,
+/// which is what a `Vec<i32>` is written as.
+#[derive(Debug, Clone, Default)]
+struct IntList(Vec<i32>);
+
+impl<'a> FromNbt<'a> for IntList {
+    fn from_nbt(tapes: &NbtTape<'a>, element: &NbtTapeElement<'a>) -> ferrumc_nbt::Result<Self> {
+        Vec::<i32>::from_nbt(tapes, element).map(IntList)
+    }
+}
+
+impl NBTSerializable for IntList {
+    fn serialize<W: Write>(&self, buf: &mut W, options: &NBTSerializeOptions<'_>) {
+        match options {
+            NBTSerializeOptions::None | NBTSerializeOptions::Flatten => {}
+            NBTSerializeOptions::WithHeader(name) => {
+                Self::id().serialize(buf, &NBTSerializeOptions::None);
+                name.serialize(buf, &NBTSerializeOptions::None);
+            }
+            NBTSerializeOptions::Network => {
+                Self::id().serialize(buf, &NBTSerializeOptions::None);
+            }
+        }
+        i32::id().serialize(buf, &NBTSerializeOptions::None);
+        (self.0.len() as i32).serialize(buf, &NBTSerializeOptions::None);
+        for value in &self.0 {
+            value.serialize(buf, &NBTSerializeOptions::None);
+        }
+    }
+
+    async fn serialize_async<W: ferrumc_nbt::tokio::io::AsyncWrite + Unpin>(
+        &self,
+        buf: &mut W,
+        options: &NBTSerializeOptions<'_>,
+    ) {
+        use ferrumc_nbt::tokio::io::AsyncWriteExt;
+
+        let mut data = Vec::new();
+        self.serialize(&mut data, options);
+        buf.write_all(&data)
+            .await
+            .expect("failed to write bytes to writer");
+    }
+
+    fn id() -> u8 {
+        9
+    }
+}
+
+#[derive(NBTSerialize, NBTDeserialize, Debug)]
+struct SpongeV2 {
+    #[nbt(rename = "Version")]
+    version: i32,
+    #[nbt(rename = "DataVersion")]
+    data_version: Option<i32>,
+    #[nbt(rename = "Width")]
+    width: i16,
+    #[nbt(rename = "Height")]
+    height: i16,
+    #[nbt(rename = "Length")]
+    length: i16,
+    #[nbt(rename = "Offset")]
+    offset: Option<Vec<i32>>,
+    #[nbt(rename = "PaletteMax")]
+    palette_max: Option<i32>,
+    #[nbt(rename = "Palette")]
+    palette: HashMap<String, i32>,
+    #[nbt(rename = "BlockData")]
+    block_data: Vec<i8>,
+    #[nbt(rename = "BlockEntities")]
+    block_entities: Option<Vec<RawCompound>>,
+}
+
+#[derive(NBTSerialize, Debug)]
+struct SpongeV3File {
+    #[nbt(rename = "Schematic")]
+    schematic: SpongeV3,
+}
+
+#[derive(NBTSerialize, NBTDeserialize, Debug)]
+struct SpongeV3 {
+    #[nbt(rename = "Version")]
+    version: i32,
+    #[nbt(rename = "DataVersion")]
+    data_version: i32,
+    #[nbt(rename = "Width")]
+    width: i16,
+    #[nbt(rename = "Height")]
+    height: i16,
+    #[nbt(rename = "Length")]
+    length: i16,
+    #[nbt(rename = "Offset")]
+    offset: Option<Vec<i32>>,
+    #[nbt(rename = "Blocks")]
+    blocks: Option<SpongeV3Blocks>,
+}
+
+#[derive(NBTSerialize, NBTDeserialize, Debug)]
+struct SpongeV3Blocks {
+    #[nbt(rename = "Palette")]
+    palette: HashMap<String, i32>,
+    #[nbt(rename = "Data")]
+    data: Vec<i8>,
+    #[nbt(rename = "BlockEntities")]
+    block_entities: Option<Vec<RawCompound>>,
+}
+
+#[derive(NBTSerialize, NBTDeserialize, Debug)]
+struct StructureFile {
+    #[nbt(rename = "DataVersion")]
+    data_version: i32,
+    size: IntList,
+    palette: Option<Vec<BlockData>>,
+    /// Used instead of `palette` by structures with several variants, such as shipwrecks
+    palettes: Option<Vec<Vec<BlockData>>>,
+    blocks: Vec<StructureBlock>,
+    entities: Option<Vec<RawCompound>>,
+}
+
+#[derive(NBTSerialize, NBTDeserialize, Debug)]
+struct StructureBlock {
+    pos: IntList,
+    state: i32,
+    nbt: Option<RawCompound>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_format::Chunk;
+    use ferrumc_storage::memory::MemoryBackend;
+    use std::sync::Arc;
+
+    fn stairs(facing: &str) -> BlockStateId {
+        lookup_block(&BlockData {
+            name: "minecraft:oak_stairs".to_string(),
+            properties: Some(BTreeMap::from(
+                [
+                    ("facing", facing),
+                    ("half", "bottom"),
+                    ("shape", "inner_left"),
+                    ("waterlogged", "false"),
+                ]
+                .map(|(key, value)| (key.to_string(), value.to_string())),
+            )),
+        })
+    }
+
+    fn sample() -> Schematic {
+        let mut schematic = Schematic::new(3, 2, 4);
+        schematic
+            .set_block(IVec3::new(0, 0, 0), block!("stone"))
+            .unwrap();
+        schematic
+            .set_block(IVec3::new(2, 1, 3), stairs("north"))
+            .unwrap();
+        schematic
+            .set_block(
+                IVec3::new(1, 0, 2),
+                block!("chest", {facing: "west", r#type: "single", waterlogged: false}),
+            )
+            .unwrap();
+        let mut data = RawCompound::new();
+        data.insert("CustomName", &"\"Loot\"");
+        schematic.block_entities.push(SchematicBlockEntity {
+            pos: IVec3::new(1, 0, 2),
+            kind: BlockEntityKind::Chest,
+            data,
+        });
+        schematic
+    }
+
+    #[test]
+    fn test_round_trips() {
+        let schematic = sample();
+        for format in [
+            SchematicFormat::SpongeV2,
+            SchematicFormat::SpongeV3,
+            SchematicFormat::Structure,
+        ] {
+            let bytes = schematic.write(format).unwrap();
+            assert_eq!(Schematic::read(&bytes).unwrap(), schematic, "{format:?}");
+        }
+        // Left out properties come from the default state
+        let found = lookup_block(&BlockData::from_state_string(
+            "oak_stairs[facing=north,shape=inner_left]",
+        ))
+        .to_block_data()
+        .unwrap();
+        let mut expected = BlockStateId::new(ferrumc_data::blocks::OAK_STAIRS.default_state_id)
+            .to_block_data()
+            .unwrap();
+        let properties = expected.properties.as_mut().unwrap();
+        properties.insert("facing".to_string(), "north".to_string());
+        properties.insert("shape".to_string(), "inner_left".to_string());
+        assert_eq!(found, expected);
+        assert_eq!(
+            lookup_block(&BlockData::from_state_string("not_a_block")),
+            BlockStateId::default()
+        );
+    }
+
+    #[test]
+    fn test_invalid_sizes_are_rejected() {
+        let structure = |size: Vec<i32>| StructureFile {
+            data_version: DATA_VERSION,
+            size: IntList(size),
+            palette: None,
+            palettes: None,
+            blocks: Vec::new(),
+            entities: None,
+        };
+        for size in [vec![-1, 1, 1], vec![1, 70000, 1], vec![65535, 65535, 65535]] {
+            assert!(matches!(
+                Schematic::from_structure(structure(size)),
+                Err(WorldError::InvalidSchematic(_))
+            ));
+        }
+        let schematic = Schematic::from_structure(structure(vec![2, 3, 4])).unwrap();
+        assert_eq!(
+            [schematic.width(), schematic.height(), schematic.length()],
+            [2, 3, 4]
+        );
+        assert!(Schematic::from_sponge_blocks([-1, 1, 1], &HashMap::new(), &[]).is_err());
+    }
+
+    #[test]
+    fn test_rotate_and_mirror() {
+        let schematic = sample();
+        let rotated = schematic.transformed(Rotation::Clockwise90, Mirror::None);
+        assert_eq!((rotated.width(), rotated.length()), (4, 3));
+        // The north east corner ends up in the south east corner, facing east
+        assert_eq!(rotated.get_block(IVec3::new(0, 1, 2)), Some(stairs("east")));
+        assert_eq!(rotated.block_entities[0].pos, IVec3::new(1, 0, 1));
+        assert_eq!(
+            rotated.get_block(IVec3::new(1, 0, 1)),
+            Some(block!("chest", {facing: "north", r#type: "single", waterlogged: false}))
+        );
+
+        let mut turned = schematic.clone();
+        for _ in 0..4 {
+            turned = turned.transformed(Rotation::Clockwise90, Mirror::None);
+        }
+        assert_eq!(turned, schematic);
+
+        let mirrored = schematic.transformed(Rotation::None, Mirror::LeftRight);
+        let Some(BlockData {
+            properties: Some(properties),
+            ..
+        }) = mirrored
+            .get_block(IVec3::new(2, 1, 0))
+            .unwrap()
+            .to_block_data()
+        else {
+            panic!("stairs have properties");
+        };
+        assert_eq!(properties["facing"], "south");
+        assert_eq!(properties["shape"], "inner_right");
+    }
+
+    #[test]
+    fn test_paste_and_copy_across_chunks() {
+        let world = World::with_backend(MemoryBackend::new()).unwrap();
+        for x in -1..=0 {
+            for z in -1..=0 {
+                world
+                    .save_chunk(
+                        ChunkPos::new(x, z),
+                        Dimension::Overworld,
+                        Arc::new(Chunk::new(Dimension::Overworld.height())),
+                    )
+                    .unwrap();
+            }
+        }
+        let mut schematic = sample();
+        schematic
+            .set_block(IVec3::new(2, 1, 0), block!("structure_void"))
+            .unwrap();
+        let origin = BlockPos::of(-2, 64, -2);
+
+        let changed = world
+            .paste_schematic(
+                &schematic,
+                origin,
+                Dimension::Overworld,
+                Rotation::None,
+                Mirror::None,
+            )
+            .unwrap();
+        assert_eq!(changed.len(), 3);
+        assert_eq!(
+            world
+                .get_block_and_fetch(BlockPos::of(0, 65, 1), Dimension::Overworld)
+                .unwrap(),
+            stairs("north")
+        );
+        let block_entity = world
+            .get_block_entity(BlockPos::of(-1, 64, 0), Dimension::Overworld)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            block_entity.data().get::<String>("CustomName").unwrap(),
+            "\"Loot\""
+        );
+
+        let copied = world
+            .copy_schematic(origin, BlockPos::of(0, 65, 1), Dimension::Overworld)
+            .unwrap();
+        schematic
+            .set_block(IVec3::new(2, 1, 0), BlockStateId::default())
+            .unwrap();
+        assert_eq!(copied, schematic);
+    }
+}
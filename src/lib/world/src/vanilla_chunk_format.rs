@@ -1,6 +1,9 @@
 use bitcode::{Decode, Encode};
 use ferrumc_macros::NBTDeserialize;
 use ferrumc_macros::NBTSerialize;
+use ferrumc_nbt::{
+    FromNbt, NBTSerializable, NBTSerializeOptions, NbtTape, NbtTapeElement, RawCompound,
+};
 use macro_rules_attribute::{apply, attribute_alias};
 use serde_derive::{Deserialize, Serialize};
 use std::collections::BTreeMap;
@@ -45,18 +48,19 @@ pub(crate) struct VanillaChunk {
     #[nbt(rename = "LastUpdate")]
     pub last_update: Option<i64>,
     pub sections: Option<Vec<Section>>,
+    pub block_entities: Option<Vec<VanillaBlockEntity>>,
 }
 
 #[apply(ChunkDerives)]
 #[derive(deepsize::DeepSizeOf)]
 #[nbt(net_encode)]
 pub(crate) struct VanillaHeightmaps {
-    // #[nbt(rename = "MOTION_BLOCKING_NO_LEAVES")]
-    // pub motion_blocking_no_leaves: Option<Vec<i64>>,
+    #[nbt(rename = "MOTION_BLOCKING_NO_LEAVES")]
+    pub motion_blocking_no_leaves: Option<Vec<i64>>,
     #[nbt(rename = "MOTION_BLOCKING")]
     pub motion_blocking: Option<Vec<i64>>,
-    // #[nbt(rename = "OCEAN_FLOOR")]
-    // pub ocean_floor: Option<Vec<i64>>,
+    #[nbt(rename = "OCEAN_FLOOR")]
+    pub ocean_floor: Option<Vec<i64>>,
     #[nbt(rename = "WORLD_SURFACE")]
     pub world_surface: Option<Vec<i64>>,
 }
@@ -77,6 +81,97 @@ pub(crate) struct Starts {}
 #[derive(deepsize::DeepSizeOf)]
 pub(crate) struct References {}
 
+/// A block entity as vanilla saves it, its id and absolute position followed by whatever tags
+/// the block entity type has.
+///
+/// The tags are kept as a network compound in `data` rather than modelled, see
+/// [`crate::block_entity`]. Entries that can't be read are kept with an empty id, so a broken
+/// block entity doesn't take the rest of the chunk down with it.
+#[derive(
+    Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize, deepsize::DeepSizeOf,
+)]
+pub(crate) struct VanillaBlockEntity {
+    pub id: String,
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    pub data: Vec<u8>,
+}
+
+impl<'a> FromNbt<'a> for VanillaBlockEntity {
+    fn from_nbt(tapes: &NbtTape<'a>, element: &NbtTapeElement<'a>) -> ferrumc_nbt::Result<Self> {
+        let int = |key| {
+            element
+                .get(key)
+                .and_then(|value| i32::from_nbt(tapes, value).ok())
+                .unwrap_or_default()
+        };
+        let id = element
+            .get("id")
+            .and_then(|value| String::from_nbt(tapes, value).ok())
+            .unwrap_or_default();
+        let data =
+            RawCompound::from_nbt_excluding(tapes, element, &["id", "x", "y", "z", "keepPacked"])
+                .unwrap_or_default();
+        Ok(Self {
+            id,
+            x: int("x"),
+            y: int("y"),
+            z: int("z"),
+            data: data.to_network_bytes(),
+        })
+    }
+}
+
+impl NBTSerializable for VanillaBlockEntity {
+    fn serialize<W: std::io::Write>(&self, buf: &mut W, options: &NBTSerializeOptions<'_>) {
+        let mut fields = Vec::with_capacity(self.data.len() + 32);
+        self.id
+            .serialize(&mut fields, &NBTSerializeOptions::WithHeader("id"));
+        self.x
+            .serialize(&mut fields, &NBTSerializeOptions::WithHeader("x"));
+        self.y
+            .serialize(&mut fields, &NBTSerializeOptions::WithHeader("y"));
+        self.z
+            .serialize(&mut fields, &NBTSerializeOptions::WithHeader("z"));
+        RawCompound::from_network_bytes(&self.data)
+            .unwrap_or_default()
+            .serialize(&mut fields, &NBTSerializeOptions::Flatten);
+        fields.push(0);
+
+        match options {
+            NBTSerializeOptions::None => {}
+            NBTSerializeOptions::WithHeader(name) => {
+                Self::id().serialize(buf, &NBTSerializeOptions::None);
+                name.serialize(buf, &NBTSerializeOptions::None);
+            }
+            NBTSerializeOptions::Network | NBTSerializeOptions::Flatten => {
+                Self::id().serialize(buf, &NBTSerializeOptions::None);
+            }
+        }
+        buf.write_all(&fields)
+            .expect("failed to write bytes to writer");
+    }
+
+    async fn serialize_async<W: ferrumc_nbt::tokio::io::AsyncWrite + Unpin>(
+        &self,
+        buf: &mut W,
+        options: &NBTSerializeOptions<'_>,
+    ) {
+        use ferrumc_nbt::tokio::io::AsyncWriteExt;
+
+        let mut data = Vec::new();
+        self.serialize(&mut data, options);
+        buf.write_all(&data)
+            .await
+            .expect("failed to write bytes to writer");
+    }
+
+    fn id() -> u8 {
+        RawCompound::id()
+    }
+}
+
 #[apply(ChunkDerives)]
 #[derive(deepsize::DeepSizeOf)]
 pub(crate) struct Section {
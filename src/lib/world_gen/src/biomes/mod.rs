@@ -1 +1,3 @@
+pub(crate) mod nether;
 pub(crate) mod plains;
+pub(crate) mod the_end;
@@ -0,0 +1,92 @@
+use crate::errors::WorldGenError;
+use crate::{BiomeGenerator, NoiseGenerator};
+use ferrumc_macros::block;
+use ferrumc_world::block_state_id::BlockStateId;
+use ferrumc_world::chunk_format::Chunk;
+use ferrumc_world::dimension::Dimension;
+use ferrumc_world::edit_batch::EditBatch;
+use ferrumc_world::pos::{ChunkBlockPos, ChunkColumnPos, ChunkPos};
+
+/// The height of the bedrock ceiling, which is the top of the nether's playable area.
+const CEILING_Y: i16 = 127;
+
+pub(crate) struct NetherWastesBiome;
+
+impl BiomeGenerator for NetherWastesBiome {
+    fn _biome_id(&self) -> u8 {
+        0
+    }
+
+    fn _biome_name(&self) -> String {
+        "nether_wastes".to_string()
+    }
+
+    fn generate_chunk(
+        &self,
+        pos: ChunkPos,
+        noise: &NoiseGenerator,
+    ) -> Result<Chunk, WorldGenError> {
+        let mut chunk = Chunk::new(Dimension::Nether.height());
+        chunk.fill_biome(Dimension::Nether.default_biome());
+        let netherrack = block!("netherrack");
+        let bedrock = block!("bedrock");
+
+        // Everything below the lava sea is solid, and so is the section under the ceiling
+        for section_y in 0..2 {
+            chunk.set_section(section_y, netherrack)?;
+        }
+        chunk.set_section((CEILING_Y >> 4) as i8, netherrack)?;
+
+        let mut batch =
+            EditBatch::new(&mut chunk).with_sky_light(Dimension::Nether.has_sky_light());
+        for x in 0..16 {
+            for z in 0..16 {
+                let column = pos.column_pos(ChunkColumnPos::new(x, z));
+                let height = noise.get_noise(f64::from(column.x()), f64::from(column.z()));
+                let floor = Dimension::Nether.sea_level() as i16 + (height * 16.0) as i16;
+                for y in 32..floor.max(32) {
+                    batch.set_block(ChunkBlockPos::new(x, y, z), netherrack);
+                }
+                for y in floor..32 {
+                    batch.set_block(ChunkBlockPos::new(x, y, z), block!("lava", {level: 0}));
+                }
+                batch.set_block(ChunkBlockPos::new(x, 0, z), bedrock);
+                batch.set_block(ChunkBlockPos::new(x, CEILING_Y, z), bedrock);
+            }
+        }
+        batch.apply()?;
+
+        Ok(chunk)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_has_floor_and_ceiling() {
+        let noise = NoiseGenerator::new(0);
+        let chunk = NetherWastesBiome
+            .generate_chunk(ChunkPos::new(3, -7), &noise)
+            .unwrap();
+        assert_eq!(chunk.min_y, 0);
+        let bedrock = block!("bedrock");
+        for (x, z) in [(0, 0), (5, 11), (15, 15)] {
+            assert_eq!(
+                chunk.get_block(ChunkBlockPos::new(x, 0, z)).unwrap(),
+                bedrock
+            );
+            assert_eq!(
+                chunk
+                    .get_block(ChunkBlockPos::new(x, CEILING_Y, z))
+                    .unwrap(),
+                bedrock
+            );
+            assert_eq!(
+                chunk.get_block(ChunkBlockPos::new(x, 80, z)).unwrap(),
+                BlockStateId::default()
+            );
+        }
+    }
+}
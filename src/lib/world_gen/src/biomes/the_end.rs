@@ -0,0 +1,98 @@
+use crate::errors::WorldGenError;
+use crate::{BiomeGenerator, NoiseGenerator};
+use ferrumc_macros::block;
+use ferrumc_world::block_state_id::BlockStateId;
+use ferrumc_world::chunk_format::Chunk;
+use ferrumc_world::dimension::Dimension;
+use ferrumc_world::edit_batch::EditBatch;
+use ferrumc_world::pos::{ChunkBlockPos, ChunkColumnPos, ChunkPos};
+
+/// The radius of the central island, in blocks.
+const ISLAND_RADIUS: f64 = 96.0;
+/// The y level of the island's surface.
+const ISLAND_TOP: i16 = 60;
+
+pub(crate) struct TheEndBiome;
+
+impl BiomeGenerator for TheEndBiome {
+    fn _biome_id(&self) -> u8 {
+        0
+    }
+
+    fn _biome_name(&self) -> String {
+        "the_end".to_string()
+    }
+
+    fn generate_chunk(
+        &self,
+        pos: ChunkPos,
+        noise: &NoiseGenerator,
+    ) -> Result<Chunk, WorldGenError> {
+        let mut chunk = Chunk::new(Dimension::End.height());
+        chunk.fill_biome(Dimension::End.default_biome());
+        let end_stone = block!("end_stone");
+
+        // Outside of the central island the end is just void
+        let origin = pos.origin();
+        let nearest_x = f64::from(0.clamp(origin.x(), origin.x() + 15));
+        let nearest_z = f64::from(0.clamp(origin.z(), origin.z() + 15));
+        if (nearest_x * nearest_x + nearest_z * nearest_z).sqrt() >= ISLAND_RADIUS {
+            return Ok(chunk);
+        }
+
+        let mut batch = EditBatch::new(&mut chunk).with_sky_light(Dimension::End.has_sky_light());
+        for x in 0..16 {
+            for z in 0..16 {
+                let column = pos.column_pos(ChunkColumnPos::new(x, z));
+                let (global_x, global_z) = (f64::from(column.x()), f64::from(column.z()));
+                let distance = (global_x * global_x + global_z * global_z).sqrt();
+                if distance >= ISLAND_RADIUS {
+                    continue;
+                }
+                let falloff = 1.0 - distance / ISLAND_RADIUS;
+                let roughness = 1.0 + noise.get_noise(global_x, global_z) * 0.25;
+                let depth = (falloff * 48.0 * roughness).max(1.0) as i16;
+                for y in (ISLAND_TOP - depth).max(0)..ISLAND_TOP {
+                    batch.set_block(ChunkBlockPos::new(x, y, z), end_stone);
+                }
+            }
+        }
+        batch.apply()?;
+
+        Ok(chunk)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_island_and_void() {
+        let noise = NoiseGenerator::new(0);
+        let island = TheEndBiome
+            .generate_chunk(ChunkPos::new(0, 0), &noise)
+            .unwrap();
+        assert_eq!(
+            island
+                .get_block(ChunkBlockPos::new(0, ISLAND_TOP - 1, 0))
+                .unwrap(),
+            block!("end_stone")
+        );
+        assert_eq!(
+            island
+                .get_block(ChunkBlockPos::new(0, ISLAND_TOP, 0))
+                .unwrap(),
+            BlockStateId::default()
+        );
+
+        let void = TheEndBiome
+            .generate_chunk(ChunkPos::new(100, 100), &noise)
+            .unwrap();
+        assert!(
+            void.sections
+                .iter()
+                .all(|s| s.block_states.non_air_blocks == 0)
+        );
+    }
+}
@@ -2,7 +2,9 @@ mod biomes;
 pub mod errors;
 
 use crate::errors::WorldGenError;
-use ferrumc_world::{chunk_format::Chunk, pos::ChunkPos};
+use ferrumc_world::{
+    chunk_format::Chunk, dimension::Dimension, lighting::LightEngine, pos::ChunkPos,
+};
 use noise::{Clamp, NoiseFn, OpenSimplex};
 
 /// Trait for generating a biome
@@ -53,13 +55,27 @@ impl WorldGenerator {
         }
     }
 
-    fn get_biome(&self, _pos: ChunkPos) -> Box<dyn BiomeGenerator> {
+    fn get_biome(&self, _pos: ChunkPos, dimension: Dimension) -> Box<dyn BiomeGenerator> {
         // Implement biome selection here
-        Box::new(biomes::plains::PlainsBiome)
+        match dimension {
+            Dimension::Overworld => Box::new(biomes::plains::PlainsBiome),
+            Dimension::Nether => Box::new(biomes::nether::NetherWastesBiome),
+            Dimension::End => Box::new(biomes::the_end::TheEndBiome),
+        }
     }
 
-    pub fn generate_chunk(&self, pos: ChunkPos) -> Result<Chunk, WorldGenError> {
-        let biome = self.get_biome(pos);
-        biome.generate_chunk(pos, &self.noise_generator)
+    /// Generates the chunk at the given position in the given dimension, with its light and
+    /// heightmaps already calculated.
+    pub fn generate_chunk(
+        &self,
+        pos: ChunkPos,
+        dimension: Dimension,
+    ) -> Result<Chunk, WorldGenError> {
+        let biome = self.get_biome(pos, dimension);
+        let mut chunk = biome.generate_chunk(pos, &self.noise_generator)?;
+        LightEngine::new(&mut chunk)
+            .with_sky_light(dimension.has_sky_light())
+            .relight();
+        Ok(chunk)
     }
 }